@@ -10,318 +10,148 @@ extern crate mio;
 #[macro_use]
 extern crate log;
 extern crate openssl_probe;
+extern crate reqwest;
+extern crate url;
 
-use cernan::filter::{
-    DelayFilterConfig, Filter, FlushBoundaryFilterConfig, JSONEncodeFilterConfig,
-    ProgrammableFilterConfig,
-};
-use cernan::matrix;
+use cernan::admin;
+use cernan::config::{Args, Command};
+use cernan::dead_letter::DeadLetters;
+use cernan::filter::Filter;
 use cernan::metric;
 use cernan::sink::Sink;
-use cernan::source::Source;
-use cernan::thread::Stoppable;
+use cernan::source::{GraphiteProtocol, Source, TCPConfig};
+use cernan::thread::{Stoppable, ThreadHandle};
+use cernan::topology::{Kind, Topology};
+use cernan::util;
 use chrono::Utc;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::mem;
+use std::path::PathBuf;
 use std::process;
 use std::str;
+use std::sync::Mutex;
 
-fn populate_forwards(
-    mut top_level_forwards: Option<&mut HashSet<String>>,
-    forwards: &[String],
-    config_path: &str,
-    available_sends: &HashMap<String, hopper::Sender<metric::Event>>,
-    adjacency_matrix: &mut matrix::Adjacency<hopper::Sender<metric::Event>>,
-) {
-    for fwd in forwards {
-        if let Some(tlf) = top_level_forwards.as_mut() {
-            let _ = (*tlf).insert(fwd.clone());
-        }
-
-        match available_sends.get(fwd) {
-            Some(snd) => {
-                trace!("Populating sender from {:?} to {:?}", config_path, fwd);
-                adjacency_matrix.add_asymmetric_edge(
-                    config_path,
-                    &fwd.clone(),
-                    Some(snd.clone()),
-                );
-            }
-            None => {
-                error!(
-                    "Unable to fulfill configured forward: {} => {}",
-                    config_path, fwd
-                );
-                process::exit(0);
-            }
-        }
-    }
-}
-
-fn join_all(workers: HashMap<String, cernan::thread::ThreadHandle>) {
+fn join_all(workers: HashMap<String, ThreadHandle>) {
     for (_worker_id, worker) in workers {
         worker.join();
     }
 }
 
-macro_rules! cfg_conf {
-    ($config:ident) => {
-        $config
-            .config_path
-            .clone()
-            .expect("[INTERNAL ERROR] no config_path")
-    };
+/// The running routing topology
+///
+/// We track the various child threads in order to support graceful shutdown
+/// and reload. There are currently two paths used to communicate shutdown:
+///
+/// 1) A semaphore is used to signal shutdown to sources.  As generates of
+///    events, sources are shutdown first to ensure at least once processing.
+///
+/// 2) metrics::Event::Shutdown is sent to Hopper channels for Filters and
+///    Sinks by their upstreams as they shut down. Shutdown events serve to
+///    bookend queued events, once a Filter or Sink has read as many as it
+///    expects it is safe for it to flush any pending writes and shutdown.
+#[derive(Default)]
+struct Running {
+    topology: Topology,
+    sources: HashMap<String, ThreadHandle>,
+    filters: HashMap<String, ThreadHandle>,
+    sinks: HashMap<String, ThreadHandle>,
+    senders: HashMap<String, hopper::Sender<metric::Event>>,
+    upstreams: HashMap<String, util::Upstreams>,
+    channels: HashMap<String, util::Forwards>,
+    valves: HashMap<String, util::SharedValve>,
+    overflow: HashMap<String, BTreeMap<String, util::Overflow>>,
+    data_directory: PathBuf,
+    flush_timer: Option<ThreadHandle>,
+    admin: admin::SharedState,
 }
 
-#[allow(clippy::cyclomatic_complexity)]
-fn main() {
-    openssl_probe::init_ssl_cert_env_vars();
-
-    let (verbose, config_path) = cernan::config::parse_args();
-
-    let level = match verbose {
-        0 => log::LevelFilter::Error,
-        1 => log::LevelFilter::Warn,
-        2 => log::LevelFilter::Info,
-        3 => log::LevelFilter::Debug,
-        _ => log::LevelFilter::Trace,
-    };
-
-    fern::Dispatch::new()
-        .format(|out, message, record| {
-            out.finish(format_args!(
-                "[{}][{}][{}][{}] {}",
-                record.module_path().unwrap(),
-                record.line().unwrap(),
-                Utc::now().to_rfc3339(),
-                record.level(),
-                message
-            ))
-        })
-        .level(level)
-        .chain(std::io::stdout())
-        .apply()
-        .expect("could not set up logging");
-
-    let mut args = cernan::config::parse_config(&config_path);
-
-    let signal =
-        chan_signal::notify(&[chan_signal::Signal::INT, chan_signal::Signal::TERM]);
+impl Running {
+    /// Move from the running topology to `next`, that of `args`.
+    ///
+    /// Nodes the two topologies share are left running and their hopper
+    /// queues preserved. Those forwarding into a node being restarted are held
+    /// up while it restarts and then send into its replacement.
+    fn apply(&mut self, mut args: Args, next: Topology) {
+        let settings = admin::settings(&args);
+        let data_directory = args.data_directory.clone();
+        let diff = self.topology.diff(&next);
+        self.overflow = mem::replace(&mut args.overflow, HashMap::new());
+        self.data_directory = args.data_directory.clone();
+        for (config_path, delta) in &diff.upstreams {
+            if !diff.start.contains(config_path) {
+                self.upstreams[config_path].adjust(*delta);
+            }
+        }
 
-    info!("cernan - {}", args.version);
+        // The flush timer holds senders into every first-hop filter and sink.
+        // It is restarted last with the senders of the next topology.
+        if let Some(flush_timer) = self.flush_timer.take() {
+            flush_timer.shutdown();
+        }
 
-    // We track the various child threads in order to support graceful shutdown.
-    // There are currently two paths used to communicate shutdown:
-    //
-    // 1) A semaphore is used to signal shutdown to sources.  As generates of
-    //    events, sources are shutdown first to ensure at least once processing.
-    //
-    // 2) metrics::Event::Shutdown is sent to Hopper channels for Filters and Sinks
-    //    after all sources have shutdown.  Shutdown events serve to bookend queued
-    //    events, once read it is safe for these workers to flush any pending writes
-    //    and shutdown.
-    let mut sources: HashMap<String, cernan::thread::ThreadHandle> = HashMap::new();
-    let mut sinks: HashMap<String, cernan::thread::ThreadHandle> = HashMap::new();
-    let mut filters: HashMap<String, cernan::thread::ThreadHandle> = HashMap::new();
-    let mut senders: HashMap<String, hopper::Sender<metric::Event>> = HashMap::new();
-    let mut receivers: HashMap<String, hopper::Receiver<metric::Event>> =
-        HashMap::new();
-    let mut flush_sends = HashSet::new();
-
-    let mut config_topology: HashMap<String, Vec<String>> = HashMap::new();
-    let mut adjacency_matrix =
-        matrix::Adjacency::<hopper::Sender<metric::Event>>::new();
-
-    // We have to build up the mapping from source / filter / sink to its
-    // forwards. We do that here. Once completed we'll have populated:
-    //
-    //  * senders
-    //  * receivers
-    //  * config_topology
-    //
-    // config_topology gives us the whole graph as requested by the user. We'll
-    // use this to pull information from senders / receivers when we start
-    // spinning up threads. We DO NOT check that config_topology has sufficient
-    // information while we build it, otherwise users would have to carefully
-    // order their configuration. That would stink.
-    //
-    // SINKS
-    if let Some(ref config) = args.null {
-        let (send, recv) = hopper::channel_with_explicit_capacity(
-            &config.config_path,
-            &args.data_directory,
-            args.max_hopper_in_memory_bytes,
-            args.max_hopper_queue_bytes,
-            args.max_hopper_queue_files,
-        )
-        .unwrap();
-        senders.insert(config.config_path.clone(), send);
-        receivers.insert(config.config_path.clone(), recv);
-        config_topology.insert(config.config_path.clone(), Default::default());
-    }
-    if let Some(ref config) = args.console {
-        let config_path = cfg_conf!(config);
-        let (send, recv) = hopper::channel_with_explicit_capacity(
-            &config_path,
-            &args.data_directory,
-            args.max_hopper_in_memory_bytes,
-            args.max_hopper_queue_bytes,
-            args.max_hopper_queue_files,
-        )
-        .unwrap();
-        senders.insert(config_path.clone(), send);
-        receivers.insert(config_path.clone(), recv);
-        config_topology.insert(config_path.clone(), Default::default());
-    }
-    if let Some(ref config) = args.wavefront {
-        let config_path = cfg_conf!(config);
-        let (send, recv) = hopper::channel_with_explicit_capacity(
-            &config_path,
-            &args.data_directory,
-            args.max_hopper_in_memory_bytes,
-            args.max_hopper_queue_bytes,
-            args.max_hopper_queue_files,
-        )
-        .unwrap();
-        senders.insert(config_path.clone(), send);
-        receivers.insert(config_path.clone(), recv);
-        config_topology.insert(config_path.clone(), Default::default());
-    }
-    if let Some(ref config) = args.prometheus {
-        let config_path = cfg_conf!(config);
-        let (send, recv) = hopper::channel_with_explicit_capacity(
-            &config_path,
-            &args.data_directory,
-            args.max_hopper_in_memory_bytes,
-            args.max_hopper_queue_bytes,
-            args.max_hopper_queue_files,
-        )
-        .unwrap();
-        senders.insert(config_path.clone(), send);
-        receivers.insert(config_path.clone(), recv);
-        config_topology.insert(config_path.clone(), Default::default());
-    }
-    if let Some(ref config) = args.influxdb {
-        let config_path = cfg_conf!(config);
-        let (send, recv) = hopper::channel_with_explicit_capacity(
-            &config_path,
-            &args.data_directory,
-            args.max_hopper_in_memory_bytes,
-            args.max_hopper_queue_bytes,
-            args.max_hopper_queue_files,
-        )
-        .unwrap();
-        senders.insert(config_path.clone(), send);
-        receivers.insert(config_path.clone(), recv);
-        config_topology.insert(config_path.clone(), Default::default());
-    }
-    if let Some(ref config) = args.native_sink_config {
-        let config_path = cfg_conf!(config);
-        let (send, recv) = hopper::channel_with_explicit_capacity(
-            &config_path,
-            &args.data_directory,
-            args.max_hopper_in_memory_bytes,
-            args.max_hopper_queue_bytes,
-            args.max_hopper_queue_files,
-        )
-        .unwrap();
-        senders.insert(config_path.clone(), send);
-        receivers.insert(config_path.clone(), recv);
-        config_topology.insert(config_path.clone(), Default::default());
-    }
-    if let Some(ref config) = args.elasticsearch {
-        let config_path = cfg_conf!(config);
-        let (send, recv) = hopper::channel_with_explicit_capacity(
-            &config_path,
-            &args.data_directory,
-            args.max_hopper_in_memory_bytes,
-            args.max_hopper_queue_bytes,
-            args.max_hopper_queue_files,
-        )
-        .unwrap();
-        senders.insert(config_path.clone(), send);
-        receivers.insert(config_path.clone(), recv);
-        config_topology.insert(config_path.clone(), Default::default());
-    }
-    if let Some(ref configs) = args.kafkas {
-        for config in configs {
-            let config_path = cfg_conf!(config);
-            let (send, recv) = hopper::channel_with_explicit_capacity(
-                &config_path,
-                &args.data_directory,
-                args.max_hopper_in_memory_bytes,
-                args.max_hopper_queue_bytes,
-                args.max_hopper_queue_files,
-            )
-            .unwrap();
-            senders.insert(config_path.clone(), send);
-            receivers.insert(config_path.clone(), recv);
-            config_topology.insert(config_path.clone(), Default::default());
+        // Nodes forwarding into those being restarted are held up before
+        // anything is shut down, the restarted nodes seeing nothing more from
+        // them.
+        let rewired: Vec<(String, util::Forwards)> = diff
+            .rewire
+            .iter()
+            .map(|config_path| {
+                (config_path.clone(), self.channels[config_path].clone())
+            })
+            .collect();
+        let mut holds: Vec<(&str, util::Hold)> = rewired
+            .iter()
+            .map(|&(ref config_path, ref forwards)| {
+                info!("Holding up {:?}", config_path);
+                (config_path.as_str(), forwards.hold())
+            })
+            .collect();
+
+        let mut old_senders = HashMap::new();
+        for config_path in &diff.stop {
+            if let Some(sender) = self.senders.remove(config_path) {
+                old_senders.insert(config_path.clone(), sender);
+            }
+            self.upstreams.remove(config_path);
+            self.valves.remove(config_path);
+            self.channels.remove(config_path);
         }
-    }
-    // FILTERS
-    if let Some(ref configs) = args.programmable_filters {
-        for (config_path, config) in configs {
-            let (send, recv) = hopper::channel_with_explicit_capacity(
-                config_path,
-                &args.data_directory,
-                args.max_hopper_in_memory_bytes,
-                args.max_hopper_queue_bytes,
-                args.max_hopper_queue_files,
-            )
-            .unwrap();
-            senders.insert(config_path.clone(), send);
-            receivers.insert(config_path.clone(), recv);
-            config_topology.insert(config_path.clone(), config.forwards.clone());
-            adjacency_matrix.add_edges(
-                &config_path.clone(),
-                config.forwards.clone(),
-                None,
-            );
+        for config_path in &diff.stop {
+            if let Some(source) = self.sources.remove(config_path) {
+                info!("Signaling shutdown to {:?}", config_path);
+                source.shutdown();
+            }
         }
-    }
-    if let Some(ref configs) = args.delay_filters {
-        for (config_path, config) in configs {
-            let (send, recv) = hopper::channel_with_explicit_capacity(
-                config_path,
-                &args.data_directory,
-                args.max_hopper_in_memory_bytes,
-                args.max_hopper_queue_bytes,
-                args.max_hopper_queue_files,
-            )
-            .unwrap();
-            senders.insert(config_path.clone(), send);
-            receivers.insert(config_path.clone(), recv);
-            config_topology.insert(config_path.clone(), config.forwards.clone());
-            adjacency_matrix.add_edges(
-                &config_path.clone(),
-                config.forwards.clone(),
-                None,
-            );
+        for (config_path, total) in &diff.inject {
+            let mut chans = util::Channel::new(vec![old_senders[config_path].clone()]);
+            for _ in 0..*total {
+                util::send(&mut chans, metric::Event::Shutdown);
+            }
         }
-    }
-    if let Some(ref configs) = args.json_encode_filters {
-        for (config_path, config) in configs {
-            let (send, recv) = hopper::channel_with_explicit_capacity(
-                config_path,
-                &args.data_directory,
-                args.max_hopper_in_memory_bytes,
-                args.max_hopper_queue_bytes,
-                args.max_hopper_queue_files,
-            )
-            .unwrap();
-            senders.insert(config_path.clone(), send);
-            receivers.insert(config_path.clone(), recv);
-            config_topology.insert(config_path.clone(), config.forwards.clone());
-            adjacency_matrix.add_edges(
-                &config_path.clone(),
-                config.forwards.clone(),
-                None,
-            );
+        for config_path in &diff.stop {
+            if let Some(filter) = self.filters.remove(config_path) {
+                info!("Waiting on shutdown of {:?}", config_path);
+                filter.join();
+            }
         }
-    }
-    if let Some(ref configs) = args.flush_boundary_filters {
-        for (config_path, config) in configs {
+        for config_path in &diff.stop {
+            if let Some(sink) = self.sinks.remove(config_path) {
+                info!("Waiting on shutdown of {:?}", config_path);
+                sink.join();
+            }
+        }
+        drop(old_senders);
+        util::forget_dropped(|config_path| next.get(config_path).is_some());
+
+        // We have to build up the hopper queues of the filters and sinks to
+        // start before anything can send into them. Nodes are started
+        // downstream first: sinks, then filters, then sources.
+        let mut receivers: HashMap<String, hopper::Receiver<metric::Event>> =
+            HashMap::new();
+        for config_path in &diff.start {
+            if next.get(config_path).unwrap().kind == Kind::Source {
+                continue;
+            }
             let (send, recv) = hopper::channel_with_explicit_capacity(
                 config_path,
                 &args.data_directory,
@@ -330,478 +160,544 @@ fn main() {
                 args.max_hopper_queue_files,
             )
             .unwrap();
-            senders.insert(config_path.clone(), send);
+            self.senders.insert(config_path.clone(), send);
             receivers.insert(config_path.clone(), recv);
-            config_topology.insert(config_path.clone(), config.forwards.clone());
-            adjacency_matrix.add_edges(
-                &config_path.clone(),
-                config.forwards.clone(),
-                None,
-            );
+            let expected = next.expected_shutdowns(config_path) as isize
+                + diff.upstreams.get(config_path).cloned().unwrap_or(0);
+            self.upstreams
+                .insert(config_path.clone(), util::Upstreams::new(expected as usize));
         }
-    }
-    // SOURCES
-    //
-    if let Some(ref configs) = args.native_server_config {
-        for (config_path, config) in configs {
-            adjacency_matrix.add_edges(
-                &config_path.clone(),
-                config.forwards.clone(),
-                None,
-            );
+        self.start_sinks(&mut args, &diff.start, &mut receivers);
+        self.start_filters(&mut args, &diff.start, &mut receivers);
+
+        for &mut (config_path, ref mut hold) in &mut holds {
+            let node = next.get(config_path).unwrap();
+            for (idx, forward) in node.forwards.iter().enumerate() {
+                if diff.start.contains(forward) {
+                    hold.replace(idx, self.senders[forward].clone());
+                }
+            }
+            info!("Rewired {:?}", config_path);
         }
-    }
-    {
-        let internal_config = &args.internal;
-        config_topology
-            .insert(cfg_conf!(internal_config), internal_config.forwards.clone());
-        adjacency_matrix.add_edges(
-            &cfg_conf!(internal_config),
-            internal_config.forwards.clone(),
-            None,
+        drop(holds);
+        self.start_sources(&mut args, &diff.start);
+
+        // BACKGROUND
+        //
+        let mut flush_sends = BTreeSet::new();
+        for (_, node) in next.iter() {
+            if node.kind == Kind::Source {
+                flush_sends.extend(node.forwards.iter().cloned());
+            }
+        }
+        let flush_channels =
+            self.channel(&flush_sends.into_iter().collect::<Vec<_>>());
+        self.flush_timer = Some(
+            cernan::source::FlushTimer::new(
+                flush_channels,
+                cernan::source::FlushTimerConfig,
+            )
+            .run(),
         );
+
+        let mut state = self.admin.write().unwrap();
+        state.settings = settings;
+        state.data_directory = data_directory;
+        state.nodes = self
+            .sources
+            .iter()
+            .chain(self.filters.iter())
+            .chain(self.sinks.iter())
+            .map(|(config_path, handle)| {
+                let status = admin::NodeStatus {
+                    alive: Some(handle.liveness()),
+                    valve: self.valves.get(config_path).cloned(),
+                };
+                (config_path.clone(), status)
+            })
+            .collect();
+        state.senders = self
+            .senders
+            .iter()
+            .map(|(config_path, sender)| {
+                (config_path.clone(), Mutex::new(sender.clone()))
+            })
+            .collect();
+        state.topology = next.clone();
+        self.topology = next;
     }
-    if let Some(ref configs) = args.statsds {
-        for (config_path, config) in configs {
-            config_topology.insert(config_path.clone(), config.forwards.clone());
-            adjacency_matrix.add_edges(
-                &config_path.clone(),
-                config.forwards.clone(),
-                None,
-            );
-        }
+
+    /// Collect the senders into the given forwards.
+    fn channel(&self, forwards: &[String]) -> util::Channel {
+        forwards
+            .iter()
+            .map(|fwd| {
+                trace!("Populating sender to {:?}", fwd);
+                self.senders[fwd].clone()
+            })
+            .collect()
     }
-    if let Some(ref configs) = args.graphites {
-        for (config_path, config) in configs {
-            config_topology.insert(config_path.clone(), config.forwards.clone());
-            adjacency_matrix.add_edges(
-                &config_path.clone(),
-                config.forwards.clone(),
-                None,
-            );
-        }
+
+    /// Collect the senders into the forwards of the node `config_path`, with
+    /// the node's taps and overflow policies.
+    fn forwarding(&self, config_path: &str, forwards: &[String]) -> util::Channel {
+        let overflow = self.overflow.get(config_path).cloned().unwrap_or_default();
+        self.channel(forwards).tapped(config_path).overflow(
+            config_path,
+            forwards,
+            &overflow,
+        )
     }
-    if let Some(ref configs) = args.avros {
-        for (config_path, config) in configs {
-            config_topology.insert(config_path.clone(), config.forwards.clone());
-            adjacency_matrix.add_edges(
-                &config_path.clone(),
-                config.forwards.clone(),
-                None,
-            );
-        }
+
+    fn start_sink<S, C>(
+        &mut self,
+        config_path: String,
+        config: C,
+        receivers: &mut HashMap<String, hopper::Receiver<metric::Event>>,
+    ) where
+        S: 'static + Send + Sink<C>,
+        C: 'static + Send + Clone,
+    {
+        let recv = receivers.remove(&config_path).unwrap();
+        let upstreams = self.upstreams[&config_path].clone();
+        let dead_letters = DeadLetters::new(&self.data_directory, &config_path);
+        let sink = S::new(recv, upstreams, config).dead_letter_queue(dead_letters);
+        self.valves.insert(config_path.clone(), sink.valve());
+        self.sinks.insert(config_path, sink.run());
     }
-    if let Some(ref configs) = args.files {
-        for config in configs {
-            let config_path = cfg_conf!(config);
-            config_topology.insert(config_path.clone(), config.forwards.clone());
-            adjacency_matrix.add_edges(
-                &config_path.clone(),
-                config.forwards.clone(),
-                None,
-            );
-        }
+
+    fn start_source<S, C>(
+        &mut self,
+        config_path: String,
+        forwards: &[String],
+        config: C,
+    ) where
+        S: Send + Source<C>,
+        C: 'static + Send + Clone,
+    {
+        let chans = self.forwarding(&config_path, forwards);
+        self.channels.insert(config_path.clone(), chans.forwards());
+        self.sources
+            .insert(config_path, S::new(chans, config).run());
     }
 
-    // Now we validate the topology. We search the outer keys and assert that
-    // for every forward there's another key in the map. If not, invalid.
+    fn start_filter<F, I>(
+        &mut self,
+        config_path: String,
+        forwards: &[String],
+        init: I,
+        receivers: &mut HashMap<String, hopper::Receiver<metric::Event>>,
+    ) where
+        F: Filter,
+        I: 'static + Send + FnOnce() -> F,
     {
-        for (key, forwards) in &config_topology {
-            for forward in forwards {
-                if config_topology.get(forward).is_none() {
-                    error!(
-                        "Unable to fulfill configured forward: {} => {}",
-                        key, forward,
+        let recv = receivers.remove(&config_path).unwrap();
+        let upstreams = self.upstreams[&config_path].clone();
+        let chans = self.forwarding(&config_path, forwards);
+        self.channels.insert(config_path.clone(), chans.forwards());
+        self.filters.insert(
+            config_path,
+            cernan::thread::spawn(move |_poll| {
+                init().run(recv, upstreams, chans);
+            }),
+        );
+    }
+
+    fn start_sinks(
+        &mut self,
+        args: &mut Args,
+        start: &BTreeSet<String>,
+        receivers: &mut HashMap<String, hopper::Receiver<metric::Event>>,
+    ) {
+        macro_rules! start_sinks {
+            ($configs:expr, $sink:ty) => {
+                if let Some(cfgs) = mem::replace(&mut $configs, None) {
+                    for (config_path, config) in cfgs {
+                        if start.contains(&config_path) {
+                            self.start_sink::<$sink, _>(
+                                config_path,
+                                config,
+                                receivers,
+                            );
+                        }
+                    }
+                }
+            };
+        }
+        start_sinks!(args.null, cernan::sink::Null);
+        start_sinks!(args.console, cernan::sink::Console);
+        start_sinks!(args.wavefront, cernan::sink::Wavefront);
+        start_sinks!(args.prometheus, cernan::sink::Prometheus);
+        start_sinks!(
+            args.prometheus_remote_write,
+            cernan::sink::PrometheusRemoteWrite
+        );
+        start_sinks!(args.influxdb, cernan::sink::InfluxDB);
+        start_sinks!(args.native_sink_config, cernan::sink::Native);
+        start_sinks!(args.elasticsearch, cernan::sink::Elasticsearch);
+        if let Some(cfgs) = mem::replace(&mut args.kafkas, None) {
+            for config in cfgs {
+                let config_path = config.config_path.clone().unwrap();
+                if start.contains(&config_path) {
+                    self.start_sink::<cernan::sink::Kafka, _>(
+                        config_path,
+                        config,
+                        receivers,
                     );
-                    process::exit(1);
                 }
             }
         }
     }
 
-    // SINKS
-    //
-    if let Some(config) = mem::replace(&mut args.null, None) {
-        let recv = receivers.remove(&config.config_path).unwrap();
-        let sources = adjacency_matrix.pop_nodes(&config.config_path);
-        sinks.insert(
-            config.config_path.clone(),
-            cernan::sink::Null::new(recv, sources, config).run(),
-        );
-    }
-    if let Some(config) = mem::replace(&mut args.console, None) {
-        let recv = receivers
-            .remove(&config.config_path.clone().unwrap())
-            .unwrap();
-        let sources = adjacency_matrix.pop_nodes(&config.config_path.clone().unwrap());
-        sinks.insert(
-            config.config_path.clone().unwrap(),
-            cernan::sink::Console::new(recv, sources, config).run(),
-        );
-    }
-    if let Some(config) = mem::replace(&mut args.wavefront, None) {
-        let recv = receivers
-            .remove(&config.config_path.clone().unwrap())
-            .unwrap();
-        let sources = adjacency_matrix.pop_nodes(&config.config_path.clone().unwrap());
-        sinks.insert(
-            config.config_path.clone().unwrap(),
-            cernan::sink::Wavefront::new(recv, sources, config).run(),
-        );
-    }
-    if let Some(config) = mem::replace(&mut args.prometheus, None) {
-        let recv = receivers
-            .remove(&config.config_path.clone().unwrap())
-            .unwrap();
-        let sources = adjacency_matrix.pop_nodes(&config.config_path.clone().unwrap());
-        sinks.insert(
-            config.config_path.clone().unwrap(),
-            cernan::sink::Prometheus::new(recv, sources, config).run(),
-        );
-    }
-    if let Some(config) = mem::replace(&mut args.influxdb, None) {
-        let recv = receivers
-            .remove(&config.config_path.clone().unwrap())
-            .unwrap();
-        let sources = adjacency_matrix.pop_nodes(&config.config_path.clone().unwrap());
-        sinks.insert(
-            config.config_path.clone().unwrap(),
-            cernan::sink::InfluxDB::new(recv, sources, config).run(),
-        );
-    }
-    if let Some(config) = mem::replace(&mut args.native_sink_config, None) {
-        let recv = receivers
-            .remove(&config.config_path.clone().unwrap())
-            .unwrap();
-        let sources = adjacency_matrix.pop_nodes(&config.config_path.clone().unwrap());
-        sinks.insert(
-            config.config_path.clone().unwrap(),
-            cernan::sink::Native::new(recv, sources, config).run(),
+    fn start_filters(
+        &mut self,
+        args: &mut Args,
+        start: &BTreeSet<String>,
+        receivers: &mut HashMap<String, hopper::Receiver<metric::Event>>,
+    ) {
+        // Filters are constructed on their own thread, the programmable
+        // filter's lua state may not be sent between them.
+        macro_rules! start_filters {
+            ($configs:expr, $init:expr) => {
+                if let Some(cfg_map) = mem::replace(&mut $configs, None) {
+                    for (config_path, config) in cfg_map {
+                        if start.contains(&config_path) {
+                            let forwards = config.forwards.clone();
+                            self.start_filter(
+                                config_path,
+                                &forwards,
+                                move || $init(config),
+                                receivers,
+                            );
+                        }
+                    }
+                }
+            };
+        }
+        start_filters!(
+            args.programmable_filters,
+            cernan::filter::ProgrammableFilter::new
         );
-    }
-    if let Some(config) = mem::replace(&mut args.elasticsearch, None) {
-        let recv = receivers
-            .remove(&config.config_path.clone().unwrap())
-            .unwrap();
-        let sources = adjacency_matrix.pop_nodes(&config.config_path.clone().unwrap());
-        sinks.insert(
-            config.config_path.clone().unwrap(),
-            cernan::sink::Elasticsearch::new(recv, sources, config).run(),
+        start_filters!(args.delay_filters, |c| cernan::filter::DelayFilter::new(&c));
+        start_filters!(
+            args.json_encode_filters,
+            cernan::filter::JSONEncodeFilter::new
         );
+        start_filters!(args.flush_boundary_filters, |c| {
+            cernan::filter::FlushBoundaryFilter::new(&c)
+        });
     }
-    if let Some(cfgs) = mem::replace(&mut args.kafkas, None) {
-        for config in cfgs {
-            let recv = receivers
-                .remove(&config.config_path.clone().unwrap())
-                .unwrap();
-            let sources =
-                adjacency_matrix.pop_nodes(&config.config_path.clone().unwrap());
-            sinks.insert(
-                config.config_path.clone().unwrap(),
-                cernan::sink::Kafka::new(recv, sources, config).run(),
-            );
-        }
-    }
-
-    // FILTERS
-    //
-    if let Some(cfg_map) = mem::replace(&mut args.programmable_filters, None) {
-        for config in cfg_map.values() {
-            let c: ProgrammableFilterConfig = (*config).clone();
-            let recv = receivers
-                .remove(&config.config_path.clone().unwrap())
-                .unwrap();
-            let config_path = config
-                .config_path
-                .clone()
-                .expect("[INTERNAL ERROR] no config_path");
-            populate_forwards(
-                None,
-                &config.forwards,
-                &config_path,
-                &senders,
-                &mut adjacency_matrix,
-            );
 
-            let sources = adjacency_matrix.filter_nodes(
-                &config.config_path.clone().unwrap(),
-                |&(_k, option_v)| option_v.is_none(),
-            );
-            let downstream_sends = adjacency_matrix.pop_metadata(&config_path);
-            filters.insert(
-                config.config_path.clone().unwrap(),
-                cernan::thread::spawn(move |_poll| {
-                    cernan::filter::ProgrammableFilter::new(c).run(
-                        recv,
-                        sources,
-                        downstream_sends,
+    fn start_sources(&mut self, args: &mut Args, start: &BTreeSet<String>) {
+        macro_rules! start_sources {
+            ($configs:expr, $source:ty) => {
+                if let Some(cfg_map) = mem::replace(&mut $configs, None) {
+                    for (config_path, config) in cfg_map {
+                        if start.contains(&config_path) {
+                            let forwards = config.forwards.clone();
+                            self.start_source::<$source, _>(
+                                config_path,
+                                &forwards,
+                                config,
+                            );
+                        }
+                    }
+                }
+            };
+        }
+        if let Some(cfg_map) = mem::replace(&mut args.native_server_config, None) {
+            for (config_path, config) in cfg_map {
+                if start.contains(&config_path) {
+                    let forwards = config.forwards.clone();
+                    self.start_source::<cernan::source::NativeServer, TCPConfig>(
+                        config_path,
+                        &forwards,
+                        config.into(),
                     );
-                }),
-            );
+                }
+            }
         }
-    };
-
-    if let Some(cfg_map) = mem::replace(&mut args.delay_filters, None) {
-        for config in cfg_map.values() {
-            let c: DelayFilterConfig = (*config).clone();
-            let recv = receivers
-                .remove(&config.config_path.clone().unwrap())
-                .unwrap();
-
-            let config_path = config
-                .config_path
-                .clone()
-                .expect("[INTERNAL ERROR] no config_path");
-            populate_forwards(
-                None,
-                &config.forwards,
-                &config_path,
-                &senders,
-                &mut adjacency_matrix,
+        let internal_config = mem::replace(&mut args.internal, Default::default());
+        let internal_config_path = internal_config.config_path.clone().unwrap();
+        if start.contains(&internal_config_path) {
+            let forwards = internal_config.forwards.clone();
+            self.start_source::<cernan::source::Internal, _>(
+                internal_config_path,
+                &forwards,
+                internal_config,
             );
-
-            let sources = adjacency_matrix.filter_nodes(
-                &config.config_path.clone().unwrap(),
-                |&(_k, option_v)| option_v.is_none(),
-            );
-            let downstream_sends = adjacency_matrix.pop_metadata(&config_path);
-            filters.insert(
-                config.config_path.clone().unwrap(),
-                cernan::thread::spawn(move |_poll| {
-                    cernan::filter::DelayFilter::new(&c).run(
-                        recv,
-                        sources,
-                        downstream_sends,
+        }
+        start_sources!(args.statsds, cernan::source::Statsd);
+        start_sources!(args.influxdbs, cernan::source::InfluxDBServer);
+        start_sources!(args.prometheus_scrapes, cernan::source::PrometheusScrape);
+        start_sources!(
+            args.prometheus_remote_writes,
+            cernan::source::PrometheusRemoteWriteServer
+        );
+        start_sources!(args.syslogs, cernan::source::Syslog);
+        if let Some(cfg_map) = mem::replace(&mut args.graphites, None) {
+            for (config_path, config) in cfg_map {
+                if start.contains(&config_path) {
+                    let forwards = config.forwards.clone();
+                    match config.protocol {
+                        GraphiteProtocol::Plaintext => self
+                            .start_source::<cernan::source::Graphite, TCPConfig>(
+                                config_path,
+                                &forwards,
+                                config.into(),
+                            ),
+                        GraphiteProtocol::Pickle => self
+                            .start_source::<cernan::source::GraphitePickle, TCPConfig>(
+                                config_path,
+                                &forwards,
+                                config.into(),
+                            ),
+                    }
+                }
+            }
+        }
+        start_sources!(args.avros, cernan::source::Avro);
+        if let Some(cfg) = mem::replace(&mut args.files, None) {
+            for config in cfg {
+                let config_path = config.config_path.clone().unwrap();
+                if start.contains(&config_path) {
+                    let forwards = config.forwards.clone();
+                    self.start_source::<cernan::source::FileServer, _>(
+                        config_path,
+                        &forwards,
+                        config,
                     );
-                }),
-            );
+                }
+            }
         }
-    };
-
-    if let Some(cfg_map) = mem::replace(&mut args.json_encode_filters, None) {
-        for config in cfg_map.values() {
-            let c: JSONEncodeFilterConfig = (*config).clone();
-            let recv = receivers
-                .remove(&config.config_path.clone().unwrap())
-                .unwrap();
-
-            let config_path = config
-                .config_path
-                .clone()
-                .expect("[INTERNAL ERROR] no config_path");
-            populate_forwards(
-                None,
-                &config.forwards,
-                &config_path,
-                &senders,
-                &mut adjacency_matrix,
-            );
+    }
 
-            let sources = adjacency_matrix.filter_nodes(
-                &config.config_path.clone().unwrap(),
-                |&(_k, option_v)| option_v.is_none(),
-            );
-            let downstream_sends = adjacency_matrix.pop_metadata(&config_path);
-            filters.insert(
-                config.config_path.clone().unwrap(),
-                cernan::thread::spawn(move |_poll| {
-                    cernan::filter::JSONEncodeFilter::new(c).run(
-                        recv,
-                        sources,
-                        downstream_sends,
-                    );
-                }),
-            );
+    /// Shut down every source, then wait on the filters and sinks to drain.
+    fn shutdown(self) {
+        // Shut down source to quiesce event generation.
+        // During shutdown sources will propgate metric::Event::Shutdown
+        // to all of its downstream consumers.
+        for (id, source_worker) in self.sources {
+            info!("Signaling shutdown to {:?}", id);
+            source_worker.shutdown();
         }
-    };
 
-    if let Some(cfg_map) = mem::replace(&mut args.flush_boundary_filters, None) {
-        for config in cfg_map.values() {
-            let c: FlushBoundaryFilterConfig = (*config).clone();
-            let recv = receivers
-                .remove(&config.config_path.clone().unwrap())
-                .unwrap();
-            let config_path = config
-                .config_path
-                .clone()
-                .expect("[INTERNAL ERROR] no config_path");
-            populate_forwards(
-                None,
-                &config.forwards,
-                &config_path,
-                &senders,
-                &mut adjacency_matrix,
-            );
+        join_all(self.sinks);
+        join_all(self.filters);
+    }
+}
 
-            let sources = adjacency_matrix.filter_nodes(
-                &config.config_path.clone().unwrap(),
-                |&(_k, option_v)| option_v.is_none(),
-            );
-            let downstream_sends = adjacency_matrix.pop_metadata(&config_path);
-            filters.insert(
-                config.config_path.clone().unwrap(),
-                cernan::thread::spawn(move |_poll| {
-                    cernan::filter::FlushBoundaryFilter::new(&c).run(
-                        recv,
-                        sources,
-                        downstream_sends,
-                    );
-                }),
-            );
+/// Re-read the configuration at `config_path` and move the running topology
+/// to the one it describes. An invalid configuration is logged and otherwise
+/// ignored, the running topology is left as it is.
+fn reload(config_path: &str, running: &mut Running) {
+    info!("Reloading configuration from {}", config_path);
+    let args = match cernan::config::try_parse_config(config_path) {
+        Ok(args) => args,
+        Err(errors) => {
+            for e in errors {
+                error!("Unable to reload configuration: {}", e);
+            }
+            return;
         }
     };
+    let next = match validate(&args) {
+        Some(next) => next,
+        None => return,
+    };
+    running.apply(args, next);
+    info!("Reloaded configuration from {}", config_path);
+}
 
-    // SOURCES
-    //
-    if let Some(cfg_map) = mem::replace(&mut args.native_server_config, None) {
-        for (config_path, config) in cfg_map {
-            populate_forwards(
-                Some(&mut flush_sends),
-                &config.forwards,
-                &config_path,
-                &senders,
-                &mut adjacency_matrix,
-            );
+/// Build the topology of `args` and validate it, logging every error. None
+/// is returned if the topology cannot be run.
+fn validate(args: &Args) -> Option<Topology> {
+    let topology = Topology::from_args(args);
+    let mut fatal = false;
+    for e in topology.errors() {
+        if e.is_fatal() {
+            error!("Unable to fulfill configured topology: {}", e);
+            fatal = true;
+        } else {
+            warn!("{}", e);
+        }
+    }
+    if fatal {
+        None
+    } else {
+        Some(topology)
+    }
+}
 
-            let native_server_send = adjacency_matrix.pop_metadata(&config_path);
-            sources.insert(
-                config_path.clone(),
-                cernan::source::NativeServer::new(native_server_send, config.into())
-                    .run(),
-            );
+/// The `check` command: parse and validate the configuration at
+/// `config_path`, reporting every error and warning on stderr. Returns the
+/// exit status, non-zero only if there are errors, as `run` would refuse the
+/// configuration.
+fn check(config_path: &str, dot: bool) -> i32 {
+    let args = match cernan::config::try_parse_config(config_path) {
+        Ok(args) => args,
+        Err(errors) => {
+            for e in errors {
+                eprintln!("error: {}", e);
+            }
+            return 1;
         }
     };
+    let topology = Topology::from_args(&args);
+    let mut fatal = false;
+    for e in topology.errors() {
+        if e.is_fatal() {
+            eprintln!("error: {}: {}", config_path, e);
+            fatal = true;
+        } else {
+            eprintln!("warning: {}: {}", config_path, e);
+        }
+    }
+    if dot {
+        print!("{}", topology.dot());
+    }
+    if fatal {
+        1
+    } else {
+        0
+    }
+}
 
-    let internal_config = mem::replace(&mut args.internal, Default::default());
-    let internal_config_path = internal_config.config_path.clone().unwrap();
-    populate_forwards(
-        Some(&mut flush_sends),
-        &internal_config.forwards,
-        &internal_config_path,
-        &senders,
-        &mut adjacency_matrix,
-    );
-
-    let internal_send = adjacency_matrix.pop_metadata(&internal_config_path);
-    sources.insert(
-        internal_config.config_path.clone().unwrap(),
-        cernan::source::Internal::new(internal_send, internal_config).run(),
-    );
-
-    if let Some(cfg_map) = mem::replace(&mut args.statsds, None) {
-        for (config_path, config) in cfg_map {
-            populate_forwards(
-                Some(&mut flush_sends),
-                &config.forwards,
-                &config_path,
-                &senders,
-                &mut adjacency_matrix,
-            );
-
-            let statsd_sends = adjacency_matrix.pop_metadata(&config_path);
-            sources.insert(
-                config_path.clone(),
-                cernan::source::Statsd::new(statsd_sends, config).run(),
-            );
+/// The `replay` command: ask the cernan running the configuration at
+/// `config_path` to replay the dead letters of `sink` into `into`, through its
+/// admin API. Returns the exit status.
+fn replay(config_path: &str, sink: &str, into: Option<&str>) -> i32 {
+    let args = match cernan::config::try_parse_config(config_path) {
+        Ok(args) => args,
+        Err(errors) => {
+            for e in errors {
+                eprintln!("error: {}", e);
+            }
+            return 1;
         }
     };
-
-    if let Some(cfg_map) = mem::replace(&mut args.graphites, None) {
-        for (config_path, config) in cfg_map {
-            populate_forwards(
-                Some(&mut flush_sends),
-                &config.forwards,
-                &config_path,
-                &senders,
-                &mut adjacency_matrix,
-            );
-
-            let graphite_sends = adjacency_matrix.pop_metadata(&config_path);
-            sources.insert(
-                config_path.clone(),
-                cernan::source::Graphite::new(graphite_sends, config.into()).run(),
-            );
+    let admin = match args.admin {
+        Some(admin) => admin,
+        None => {
+            eprintln!(
+                "error: {}: replay needs [admin] to be configured",
+                config_path
+            );
+            return 1;
         }
     };
-
-    if let Some(cfg_map) = mem::replace(&mut args.avros, None) {
-        for (config_path, config) in cfg_map {
-            populate_forwards(
-                Some(&mut flush_sends),
-                &config.forwards,
-                &config_path,
-                &senders,
-                &mut adjacency_matrix,
-            );
-
-            let avro_sends = adjacency_matrix.pop_metadata(&config_path);
-            sources.insert(
-                config_path.clone(),
-                cernan::source::Avro::new(avro_sends, config).run(),
-            );
+    let base = format!("http://{}:{}/replay/{}", admin.host, admin.port, sink);
+    let url = match into {
+        Some(into) => url::Url::parse_with_params(&base, &[("into", into)]),
+        None => url::Url::parse(&base),
+    };
+    let url = match url {
+        Ok(url) => url,
+        Err(e) => {
+            eprintln!("error: {}: {}", base, e);
+            return 1;
         }
     };
+    match reqwest::Client::new().post(url).send() {
+        Ok(mut resp) => {
+            println!("{}", resp.text().unwrap_or_default());
+            if resp.status().is_success() {
+                0
+            } else {
+                1
+            }
+        }
+        Err(e) => {
+            eprintln!("error: {}", e);
+            1
+        }
+    }
+}
 
-    if let Some(cfg) = mem::replace(&mut args.files, None) {
-        for config in cfg {
-            let config_path = config.config_path.clone().unwrap();
-            populate_forwards(
-                Some(&mut flush_sends),
-                &config.forwards,
-                &config_path,
-                &senders,
-                &mut adjacency_matrix,
-            );
+fn main() {
+    openssl_probe::init_ssl_cert_env_vars();
 
-            let fp_sends = adjacency_matrix.pop_metadata(&config_path);
-            sources.insert(
-                cfg_conf!(config).clone(),
-                cernan::source::FileServer::new(fp_sends, config).run(),
-            );
-        }
+    let (verbose, config_path, command) = cernan::config::parse_args();
+    match command {
+        Command::Check { dot } => process::exit(check(&config_path, dot)),
+        Command::Replay { sink, into } => process::exit(replay(
+            &config_path,
+            &sink,
+            into.as_ref().map(|s| s.as_str()),
+        )),
+        Command::Run => {}
+    }
+
+    let level = match verbose {
+        0 => log::LevelFilter::Error,
+        1 => log::LevelFilter::Warn,
+        2 => log::LevelFilter::Info,
+        3 => log::LevelFilter::Debug,
+        _ => log::LevelFilter::Trace,
     };
 
-    // BACKGROUND
-    //
-    let mut flush_channels = Vec::new();
-    for destination in &flush_sends {
-        match senders.get(destination) {
-            Some(snd) => {
-                flush_channels.push(snd.clone());
-            }
-            None => {
-                error!(
-                    "Unable to fulfill configured top-level flush to {}",
-                    destination
-                );
-                process::exit(0);
+    fern::Dispatch::new()
+        .format(|out, message, record| {
+            out.finish(format_args!(
+                "[{}][{}][{}][{}] {}",
+                record.module_path().unwrap(),
+                record.line().unwrap(),
+                Utc::now().to_rfc3339(),
+                record.level(),
+                message
+            ))
+        })
+        .level(level)
+        .chain(std::io::stdout())
+        .apply()
+        .expect("could not set up logging");
+
+    let args = match cernan::config::try_parse_config(&config_path) {
+        Ok(args) => args,
+        Err(errors) => {
+            for e in errors {
+                error!("Unable to parse configuration: {}", e);
             }
+            process::exit(1);
         }
-    }
+    };
+
+    let signal = chan_signal::notify(&[
+        chan_signal::Signal::INT,
+        chan_signal::Signal::TERM,
+        chan_signal::Signal::HUP,
+    ]);
+
+    info!("cernan - {}", args.version);
 
-    drop(flush_sends);
-    drop(senders);
-    cernan::source::FlushTimer::new(flush_channels, cernan::source::FlushTimerConfig)
-        .run();
+    // Build the topology requested by the user and validate it. We search the
+    // forwards of every node and assert that each is another filter or sink.
+    // If not, invalid. We DO NOT check that the topology is complete while we
+    // build it, otherwise users would have to carefully order their
+    // configuration. That would stink.
+    let topology = match validate(&args) {
+        Some(topology) => topology,
+        None => process::exit(1),
+    };
+    let mut running = Running::default();
+    // The admin API is started once, changes to it are not reloaded.
+    let admin_server = args
+        .admin
+        .as_ref()
+        .map(|config| admin::serve(config, running.admin.clone()));
+    running.apply(args, topology);
 
     cernan::thread::spawn(move |_poll| {
         cernan::time::update_time();
     });
 
-    drop(args);
-    drop(config_topology);
-    drop(receivers);
-
-    signal.recv().unwrap();
-
-    // Shut down source to quiesce event generation.
-    // During shutdown sources will propgate metric::Event::Shutdown
-    // to all of its downstream consumers.
-    for (id, source_worker) in sources {
-        info!("Signaling shutdown to {:?}", id);
-        source_worker.shutdown();
+    // SIGHUP reloads the configuration, anything else shuts us down.
+    while let Some(chan_signal::Signal::HUP) = signal.recv() {
+        reload(&config_path, &mut running);
     }
 
-    join_all(sinks);
-    join_all(filters);
+    running.shutdown();
+    if let Some(server) = admin_server {
+        server.shutdown();
+    }
 }
@@ -14,7 +14,7 @@ pub struct Buckets {
     keys: Vec<u64>,
     values: Vec<Vec<Telemetry>>,
     count: u64,
-    bin_width: i64,
+    bin_width_ns: i64,
 }
 
 impl Default for Buckets {
@@ -34,7 +34,7 @@ impl Default for Buckets {
             keys: Default::default(),
             values: Default::default(),
             count: 0,
-            bin_width: 1,
+            bin_width_ns: time::NANOS_PER_SEC,
         }
     }
 }
@@ -70,8 +70,34 @@ impl Buckets {
     /// considered as happening at the 'same' time if their timestamps are
     /// within N seconds of one another.
     pub fn new(bin_width: i64) -> Buckets {
+        Buckets::new_ns(bin_width.saturating_mul(time::NANOS_PER_SEC))
+    }
+
+    /// Create a new bucket with a `bin_width` measured in nanoseconds.
+    ///
+    /// This is `Buckets::new` for aggregation widths shorter than a second.
+    /// Bins are assigned on the full precision of each `Telemetry` timestamp.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate cernan;
+    ///
+    /// let metric = cernan::metric::Telemetry::new()
+    ///     .name("foo")
+    ///     .value(1.0)
+    ///     .kind(cernan::metric::AggregationMethod::Sum)
+    ///     .harden()
+    ///     .unwrap();
+    /// let mut bucket = cernan::buckets::Buckets::new_ns(250_000_000);
+    /// bucket.add(metric.clone().timestamp_ns(1_000_000_000));
+    /// bucket.add(metric.clone().timestamp_ns(1_100_000_000));
+    /// bucket.add(metric.timestamp_ns(1_300_000_000));
+    /// assert_eq!(2, bucket.count());
+    /// ```
+    pub fn new_ns(bin_width_ns: i64) -> Buckets {
         let mut b = Buckets::default();
-        b.bin_width = bin_width;
+        b.bin_width_ns = bin_width_ns;
         b
     }
 
@@ -108,6 +134,7 @@ impl Buckets {
                 v.swap(0, len - 1);
                 v.truncate(1);
                 v[0].timestamp = time::now();
+                v[0].subsec_nanos = 0;
                 self.count = self.count.saturating_add(1);
             } else {
                 v.clear()
@@ -154,16 +181,16 @@ impl Buckets {
                 self.values.index_mut(hsh_idx)
             }
         };
-        let bin_width = self.bin_width;
+        let bin_width_ns = self.bin_width_ns;
 
-        match hsh.binary_search_by(|probe| probe.within(bin_width, &value)) {
+        match hsh.binary_search_by(|probe| probe.within_ns(bin_width_ns, &value)) {
             Ok(idx) => hsh[idx] += value,
             Err(idx) => {
                 self.count = self.count.saturating_add(1);
                 if value.persist && idx > 0 {
                     let mut cur: Telemetry = hsh[idx - 1]
                         .clone()
-                        .timestamp(value.timestamp)
+                        .timestamp_ns(value.epoch_ns())
                         .thaw()
                         .persist(true)
                         .harden()
@@ -216,7 +243,7 @@ impl Buckets {
 
     #[cfg(test)]
     pub fn bin_width(&self) -> i64 {
-        self.bin_width
+        self.bin_width_ns / time::NANOS_PER_SEC
     }
 }
 
@@ -383,6 +410,45 @@ mod test {
         assert_eq!(3, v.len());
     }
 
+    #[test]
+    fn test_gauge_sub_second_bin_width() {
+        let m0 = Telemetry::new()
+            .name("lO")
+            .value(3.211)
+            .timestamp_ns(645_181_811_100_000_000)
+            .kind(AggregationMethod::Set)
+            .harden()
+            .unwrap();
+        let m1 = Telemetry::new()
+            .name("lO")
+            .value(4.322)
+            .timestamp_ns(645_181_811_400_000_000)
+            .kind(AggregationMethod::Set)
+            .harden()
+            .unwrap();
+        let m2 = Telemetry::new()
+            .name("lO")
+            .value(5.433)
+            .timestamp_ns(645_181_811_600_000_000)
+            .kind(AggregationMethod::Set)
+            .harden()
+            .unwrap();
+
+        let mut bkt = Buckets::new_ns(500_000_000);
+        let id = m0.name_tag_hash();
+
+        bkt.add(m0);
+        bkt.add(m1);
+        bkt.add(m2);
+
+        let v = bkt.get(id).unwrap();
+        assert_eq!(2, v.len());
+        assert_eq!(100_000_000, v[0].subsec_nanos);
+        assert_eq!(Some(4.322), v[0].set());
+        assert_eq!(600_000_000, v[1].subsec_nanos);
+        assert_eq!(Some(5.433), v[1].set());
+    }
+
     #[test]
     fn variable_size_bins() {
         fn inner(bin_width: u16, ms: Vec<Telemetry>) -> TestResult {
@@ -1057,6 +1123,22 @@ mod test {
                                     let r_bins = rhs.into_vec();
                                     assert_eq!(l_bins, r_bins);
                                 }
+                                AggregationMethod::Distinct => {
+                                    assert_eq!(lhs.hyperloglog(), rhs.hyperloglog())
+                                }
+                                AggregationMethod::Sketch => {
+                                    let l_sketch = lhs.sketch().unwrap();
+                                    let r_sketch = rhs.sketch().unwrap();
+                                    assert_eq!(
+                                        l_sketch.positive(),
+                                        r_sketch.positive()
+                                    );
+                                    assert_eq!(
+                                        l_sketch.negative(),
+                                        r_sketch.negative()
+                                    );
+                                    assert_eq!(l_sketch.count(), r_sketch.count());
+                                }
                             }
                         }
                         Err(_) => return TestResult::failed(),
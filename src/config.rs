@@ -3,13 +3,18 @@
 //! Used to parse the argv/config file into a struct that
 //! the server can consume and use as configuration data.
 
+use crate::admin::AdminConfig;
 use crate::metric::TagMap;
-use clap::{App, Arg};
-use std::collections::HashMap;
+use crate::util::Overflow;
+use clap::{App, AppSettings, Arg, SubCommand};
+use glob;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
 use std::env;
 use std::fs::File;
 use std::io::Read;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use toml;
 
 const VERSION: Option<&'static str> = option_env!("CARGO_PKG_VERSION");
@@ -18,14 +23,18 @@ use crate::filter::{
     DelayFilterConfig, FlushBoundaryFilterConfig, JSONEncodeFilterConfig,
     ProgrammableFilterConfig,
 };
+use crate::protocols::influxdb::Precision;
 use crate::sink::wavefront::PadControl;
 use crate::sink::{
-    ConsoleConfig, ElasticsearchConfig, InfluxDBConfig, KafkaConfig, NativeConfig,
-    NullConfig, PrometheusConfig, WavefrontConfig,
+    ConsoleConfig, DeliveryConfig, ElasticsearchConfig, InfluxDBConfig, KafkaConfig,
+    NativeConfig, NullConfig, PrometheusConfig, PrometheusRemoteWriteConfig,
+    WavefrontConfig,
 };
 use crate::source::{
-    flushes_per_second, FileServerConfig, GraphiteConfig, InternalConfig,
-    NativeServerConfig, StatsdConfig, StatsdParseConfig, TCPConfig,
+    flushes_per_second, FileServerConfig, GraphiteConfig, GraphiteProtocol,
+    InfluxDBServerConfig, InfluxDBTransport, InternalConfig, NativeServerConfig,
+    PrometheusRemoteWriteServerConfig, PrometheusScrapeConfig, StatsdConfig,
+    StatsdParseConfig, StatsdTransport, SyslogConfig, SyslogTransport, TCPConfig,
 };
 
 // This stinks and is verbose. Once
@@ -72,6 +81,11 @@ pub struct Args {
     pub flush_interval: u64,
     /// Cernan version string. This is set automatically.
     pub version: String,
+    /// The admin HTTP API, served only if configured. See `admin` for more.
+    pub admin: Option<AdminConfig>,
+    /// The `Overflow` policies of the forwards of sources and filters, by
+    /// config path and then forward. See `util::Overflow` for more.
+    pub overflow: HashMap<String, BTreeMap<String, Overflow>>,
     /// The programmable filters to use in this cernan run. See
     /// `filters::ProgrammableFilter` for more.
     pub programmable_filters: Option<HashMap<String, ProgrammableFilterConfig>>,
@@ -85,19 +99,21 @@ pub struct Args {
     /// `filters::FlushBoundaryFilter` for more.
     pub flush_boundary_filters: Option<HashMap<String, FlushBoundaryFilterConfig>>,
     /// See `sinks::Console` for more.
-    pub console: Option<ConsoleConfig>,
+    pub console: Option<HashMap<String, ConsoleConfig>>,
     /// See `sinks::Null` for more.
-    pub null: Option<NullConfig>,
+    pub null: Option<HashMap<String, NullConfig>>,
     /// See `sinks::Wavefront` for more.
-    pub wavefront: Option<WavefrontConfig>,
+    pub wavefront: Option<HashMap<String, WavefrontConfig>>,
     /// See `sinks::InfluxDB` for more.
-    pub influxdb: Option<InfluxDBConfig>,
+    pub influxdb: Option<HashMap<String, InfluxDBConfig>>,
     /// See `sinks::Native` for more.
-    pub native_sink_config: Option<NativeConfig>,
+    pub native_sink_config: Option<HashMap<String, NativeConfig>>,
     /// See `sinks::Prometheus` for more.
-    pub prometheus: Option<PrometheusConfig>,
+    pub prometheus: Option<HashMap<String, PrometheusConfig>>,
+    /// See `sinks::PrometheusRemoteWrite` for more.
+    pub prometheus_remote_write: Option<HashMap<String, PrometheusRemoteWriteConfig>>,
     /// See `sinks::Elasticsearch` for more.
-    pub elasticsearch: Option<ElasticsearchConfig>,
+    pub elasticsearch: Option<HashMap<String, ElasticsearchConfig>>,
     /// See `sinks::Kafka` for more.
     pub kafkas: Option<Vec<KafkaConfig>>,
     /// See `sources::FileServer` for more.
@@ -106,12 +122,21 @@ pub struct Args {
     pub internal: InternalConfig,
     /// See `sources::Graphite` for more.
     pub graphites: Option<HashMap<String, GraphiteConfig>>,
+    /// See `sources::InfluxDBServer` for more.
+    pub influxdbs: Option<HashMap<String, InfluxDBServerConfig>>,
+    /// See `sources::PrometheusScrape` for more.
+    pub prometheus_scrapes: Option<HashMap<String, PrometheusScrapeConfig>>,
+    /// See `sources::PrometheusRemoteWriteServer` for more.
+    pub prometheus_remote_writes:
+        Option<HashMap<String, PrometheusRemoteWriteServerConfig>>,
     /// See `sources::Avro` for more.
     pub avros: Option<HashMap<String, TCPConfig>>,
     /// See `sources::Native` for more.
     pub native_server_config: Option<HashMap<String, NativeServerConfig>>,
     /// See `sources::Statsd` for more.
     pub statsds: Option<HashMap<String, StatsdConfig>>,
+    /// See `sources::Syslog` for more.
+    pub syslogs: Option<HashMap<String, SyslogConfig>>,
 }
 
 impl Default for Args {
@@ -124,6 +149,8 @@ impl Default for Args {
             scripts_directory: default_scripts_directory(),
             flush_interval: 60 * flushes_per_second(),
             version: default_version(),
+            admin: None,
+            overflow: HashMap::new(),
             // filters
             programmable_filters: None,
             delay_filters: None,
@@ -135,12 +162,17 @@ impl Default for Args {
             wavefront: None,
             influxdb: None,
             prometheus: None,
+            prometheus_remote_write: None,
             native_sink_config: None,
             elasticsearch: None,
             kafkas: None,
             // sources
             statsds: None,
+            syslogs: None,
             graphites: None,
+            influxdbs: None,
+            prometheus_scrapes: None,
+            prometheus_remote_writes: None,
             avros: None,
             native_server_config: None,
             files: None,
@@ -149,53 +181,389 @@ impl Default for Args {
     }
 }
 
+/// The errors found in a configuration. Parsing carries on past an error,
+/// taking the default in place of the offending value, so that every error
+/// is reported at once.
+#[derive(Default)]
+struct Errors(RefCell<Vec<String>>);
+
+impl Errors {
+    /// Record `error`.
+    fn push<S: Into<String>>(&self, error: S) {
+        self.0.borrow_mut().push(error.into());
+    }
+
+    /// Record `error` unless `ok`.
+    fn check(&self, ok: bool, error: &str) {
+        if !ok {
+            self.push(error);
+        }
+    }
+}
+
+/// A value the configuration must have, see `Errors`.
+trait Expect<T> {
+    /// The value, or `T::default()` with `error` recorded if there is none.
+    fn expect_in(self, errors: &Errors, error: &str) -> T;
+}
+
+impl<T: Default> Expect<T> for Option<T> {
+    fn expect_in(self, errors: &Errors, error: &str) -> T {
+        self.unwrap_or_else(|| {
+            errors.push(error);
+            T::default()
+        })
+    }
+}
+
+impl<T: Default, E> Expect<T> for Result<T, E> {
+    fn expect_in(self, errors: &Errors, error: &str) -> T {
+        self.ok().expect_in(errors, error)
+    }
+}
+
+/// The table `value`, the configuration at `config_path`, or None with an
+/// error recorded if it is not a table.
+fn table<'a>(
+    value: &'a toml::Value,
+    config_path: &str,
+    errors: &Errors,
+) -> Option<&'a toml::value::Table> {
+    let tbl = value.as_table();
+    if tbl.is_none() {
+        errors.push(format!("{} must be in table format", config_path));
+    }
+    tbl
+}
+
+/// The `forwards` of a source or filter, an array of config paths.
+fn parse_forwards(fwds: &toml::Value, errors: &Errors) -> Vec<String> {
+    fwds.as_array()
+        .map(Vec::as_slice)
+        .expect_in(errors, "forwards must be an array")
+        .iter()
+        .map(|s| {
+            s.as_str()
+                .expect_in(errors, "forwards must be strings")
+                .to_string()
+        })
+        .collect()
+}
+
+/// Parse the `overflow` of every source and filter, by config path.
+///
+/// A node's `overflow` is either a single policy, applied to all of its
+/// forwards, or a table of policies by forward. Forwards not given a policy
+/// drop newest. See `util::Overflow` for the policies.
+fn parse_overflow(
+    value: &toml::Value,
+    errors: &Errors,
+) -> HashMap<String, BTreeMap<String, Overflow>> {
+    let mut res = HashMap::new();
+    for section in &["sources", "filters"] {
+        let kinds = match value.get(section).and_then(|v| v.as_table()) {
+            Some(kinds) => kinds,
+            None => continue,
+        };
+        for (kind, nodes) in kinds {
+            if *section == "sources" && kind == "internal" {
+                parse_node_overflow("sources.internal", nodes, &mut res, errors);
+                continue;
+            }
+            let nodes = match nodes.as_table() {
+                Some(nodes) => nodes,
+                None => continue,
+            };
+            for (name, node) in nodes {
+                let config_path = if *section == "sources" && kind == "files" {
+                    match node.get("path") {
+                        Some(pth) => format!("sources.files.{}", pth),
+                        None => continue,
+                    }
+                } else {
+                    format!("{}.{}.{}", section, kind, name)
+                };
+                parse_node_overflow(&config_path, node, &mut res, errors);
+            }
+        }
+    }
+    res
+}
+
+fn parse_node_overflow(
+    config_path: &str,
+    node: &toml::Value,
+    res: &mut HashMap<String, BTreeMap<String, Overflow>>,
+    errors: &Errors,
+) {
+    let overflow = match node.get("overflow") {
+        Some(overflow) => overflow,
+        None => return,
+    };
+    let forwards: Vec<&str> = node
+        .get("forwards")
+        .and_then(|fwd| fwd.as_array())
+        .map(|fwd| fwd.iter().filter_map(|f| f.as_str()).collect())
+        .unwrap_or_default();
+    let parse = |policy: &toml::Value| -> Overflow {
+        policy
+            .as_str()
+            .expect_in(errors, "overflow policy must be a string")
+            .parse()
+            .unwrap_or_else(|e| {
+                errors.push(format!("{}.overflow: {}", config_path, e));
+                Overflow::default()
+            })
+    };
+    let policies: BTreeMap<String, Overflow> = match overflow.as_table() {
+        Some(tbl) => tbl
+            .iter()
+            .map(|(forward, policy)| {
+                errors.check(
+                    forwards.contains(&forward.as_str()),
+                    &format!(
+                        "{}.overflow: \"{}\" is not one of its forwards",
+                        config_path, forward
+                    ),
+                );
+                (forward.clone(), parse(policy))
+            })
+            .collect(),
+        None => {
+            let policy = parse(overflow);
+            forwards
+                .iter()
+                .map(|forward| (forward.to_string(), policy.clone()))
+                .collect()
+        }
+    };
+    res.insert(config_path.to_string(), policies);
+}
+
 /// Common utility function for parsing flush_interval and
 /// returning the number of flushes per second represented.
-fn parse_flush_interval(table: &toml::Value, key: &str) -> Option<u64> {
+fn parse_flush_interval(
+    table: &toml::Value,
+    key: &str,
+    errors: &Errors,
+) -> Option<u64> {
     match table.get(key) {
         Some(value) => match value {
             toml::Value::Float(f) => Some((f * flushes_per_second() as f64) as u64),
             toml::Value::Integer(i) => Some(*i as u64 * flushes_per_second()),
-            _ => panic!(
-                "Expected number of seconds for flush_interval config parameter."
-            ),
+            _ => {
+                errors.push(
+                    "Expected number of seconds for flush_interval config parameter.",
+                );
+                None
+            }
         },
         _ => None,
     }
 }
 
+/// Common utility function for parsing the delivery policy of a network sink,
+/// the `delivery` table of the sink at `config_path`. Keys absent from the
+/// table keep their value in `default`.
+fn parse_delivery(
+    snk: &toml::Value,
+    config_path: &str,
+    default: DeliveryConfig,
+    errors: &Errors,
+) -> DeliveryConfig {
+    let tbl = match snk.get("delivery") {
+        Some(tbl) => match table(tbl, &format!("{}.delivery", config_path), errors) {
+            Some(tbl) => tbl,
+            None => return default,
+        },
+        None => return default,
+    };
+    let integer = |key: &str| {
+        tbl.get(key).map(|v| {
+            let i = v.as_integer().expect_in(
+                errors,
+                &format!("could not parse {}.delivery.{}", config_path, key),
+            );
+            errors.check(
+                i >= 0,
+                &format!("{}.delivery.{} must not be negative", config_path, key),
+            );
+            i as u64
+        })
+    };
+    let jitter = tbl
+        .get("jitter")
+        .map(|v| {
+            v.as_float().expect_in(
+                errors,
+                &format!("could not parse {}.delivery.jitter", config_path),
+            )
+        })
+        .unwrap_or(default.jitter);
+    errors.check(
+        jitter >= 0.0 && jitter <= 1.0,
+        &format!("{}.delivery.jitter must be between 0 and 1", config_path),
+    );
+    DeliveryConfig {
+        initial_backoff: integer("initial_backoff").unwrap_or(default.initial_backoff),
+        max_backoff: integer("max_backoff").unwrap_or(default.max_backoff),
+        jitter,
+        max_attempts: integer("max_attempts")
+            .map(|i| i as u32)
+            .or(default.max_attempts),
+        max_elapsed: integer("max_elapsed").or(default.max_elapsed),
+        breaker_threshold: integer("breaker_threshold")
+            .map(|i| i as u32)
+            .or(default.breaker_threshold),
+        breaker_cooldown: integer("breaker_cooldown")
+            .unwrap_or(default.breaker_cooldown),
+    }
+}
+
+/// Common utility function for parsing the instances of a sink.
+///
+/// A sink may be configured once, its keys set directly in `[sinks.<kind>]`,
+/// and is then addressed in forwards as `sinks.<kind>`. Or it may be
+/// configured as any number of named instances, `[sinks.<kind>.<name>]`, each
+/// addressed as `sinks.<kind>.<name>`. `subtables` are the keys of the sink's
+/// own configuration which hold tables, and which are never taken to be
+/// instance names.
+///
+/// The instances are returned keyed by their config path, which is passed to
+/// `parse` along with the instance's table.
+fn parse_sinks<T, F>(
+    sinks: &toml::value::Table,
+    kind: &str,
+    subtables: &[&str],
+    errors: &Errors,
+    mut parse: F,
+) -> Option<HashMap<String, T>>
+where
+    F: FnMut(&toml::Value, &str) -> T,
+{
+    sinks.get(kind).map(|snk| {
+        let mut instances = HashMap::default();
+        let tbl = match table(snk, &format!("sinks.{}", kind), errors) {
+            Some(tbl) => tbl,
+            None => return instances,
+        };
+        let named = !tbl.is_empty()
+            && tbl
+                .iter()
+                .all(|(k, v)| v.is_table() && !subtables.contains(&k.as_str()));
+        if named {
+            for (name, instance) in tbl.iter() {
+                let config_path = format!("sinks.{}.{}", kind, name);
+                let config = parse(instance, &config_path);
+                instances.insert(config_path, config);
+            }
+        } else {
+            let config_path = format!("sinks.{}", kind);
+            let config = parse(snk, &config_path);
+            instances.insert(config_path, config);
+        }
+        instances
+    })
+}
+
+/// What cernan has been asked to do on the command line
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Run the configured routing topology.
+    Run,
+    /// Parse and validate the configuration, then exit. The routing topology
+    /// is printed as a Graphviz digraph if `dot` is set.
+    Check {
+        /// Whether to print the routing topology as Graphviz.
+        dot: bool,
+    },
+    /// Ask the running cernan, through its admin API, to re-inject the dead
+    /// letters of `sink` into `into`, by default the sink itself.
+    Replay {
+        /// The sink whose dead letters to replay.
+        sink: String,
+        /// The filter or sink to replay them into.
+        into: Option<String>,
+    },
+}
+
 /// Parse the cernan configuration arguments
 ///
 /// This function will read the environment arguments and return a minimal
 /// amount of information. This will be paired with a call to `parse_config`.
-pub fn parse_args() -> (u64, String) {
-    let args = App::new("cernan")
-        .version(VERSION.unwrap_or("unknown"))
-        .author("Brian L. Troutwine <blt@postmates.com>")
-        .about("telemetry aggregation and shipping, last up the ladder")
-        .arg(
-            Arg::with_name("config-file")
-                .long("config")
-                .short("C")
-                .value_name("config")
-                .required(true)
-                .help("The config file to feed in.")
-                .takes_value(true),
-        )
-        .arg(
-            Arg::with_name("verbose")
-                .short("v")
-                .multiple(true)
-                .help("Turn on verbose output."),
-        )
-        .get_matches();
+pub fn parse_args() -> (u64, String, Command) {
+    let config_file = Arg::with_name("config-file")
+        .long("config")
+        .short("C")
+        .value_name("config")
+        .required(true)
+        .help("The config file to feed in.")
+        .takes_value(true);
+    let args =
+        App::new("cernan")
+            .version(VERSION.unwrap_or("unknown"))
+            .author("Brian L. Troutwine <blt@postmates.com>")
+            .about("telemetry aggregation and shipping, last up the ladder")
+            .setting(AppSettings::SubcommandsNegateReqs)
+            .arg(config_file.clone())
+            .arg(
+                Arg::with_name("verbose")
+                    .short("v")
+                    .multiple(true)
+                    .help("Turn on verbose output."),
+            )
+            .subcommand(
+                SubCommand::with_name("check")
+                    .about("Validate the config file and exit, non-zero on error")
+                    .arg(config_file.clone())
+                    .arg(
+                        Arg::with_name("dot")
+                            .long("dot")
+                            .help("Print the routing topology as Graphviz."),
+                    ),
+            )
+            .subcommand(
+                SubCommand::with_name("replay")
+                    .about("Replay the dead letters of a sink into the running cernan")
+                    .arg(config_file)
+                    .arg(
+                        Arg::with_name("sink")
+                            .required(true)
+                            .help("The sink whose dead letters to replay."),
+                    )
+                    .arg(Arg::with_name("into").long("into").takes_value(true).help(
+                        "The filter or sink to replay into, by default the sink.",
+                    )),
+            )
+            .get_matches();
 
     let verb = if args.is_present("verbose") {
         args.occurrences_of("verbose")
     } else {
         0
     };
-    (verb, args.value_of("config-file").unwrap().to_string())
+    match args.subcommand() {
+        ("check", Some(check)) => (
+            verb,
+            check.value_of("config-file").unwrap().to_string(),
+            Command::Check {
+                dot: check.is_present("dot"),
+            },
+        ),
+        ("replay", Some(replay)) => (
+            verb,
+            replay.value_of("config-file").unwrap().to_string(),
+            Command::Replay {
+                sink: replay.value_of("sink").unwrap().to_string(),
+                into: replay.value_of("into").map(|into| into.to_string()),
+            },
+        ),
+        _ => (
+            verb,
+            args.value_of("config-file").unwrap().to_string(),
+            Command::Run,
+        ),
+    }
 }
 
 /// Parse the cernan configuration file.
@@ -204,26 +572,328 @@ pub fn parse_args() -> (u64, String) {
 /// format. Examples are also available in this repository under
 /// `examples/configs`.
 pub fn parse_config(filename: &str) -> Args {
-    let mut fp = match File::open(filename) {
-        Err(e) => panic!("Could not open file {} with error {}", filename, e),
-        Ok(fp) => fp,
-    };
+    try_parse_config(filename).unwrap_or_else(|e| panic!("{}", e.join("\n")))
+}
+
+/// Parse the cernan configuration file, as `parse_config`, but return every
+/// error found rather than panic.
+pub fn try_parse_config(filename: &str) -> Result<Args, Vec<String>> {
+    let value = read_config(filename).map_err(|e| vec![e])?;
+    parse_config_value(&value).map_err(|errors| {
+        errors
+            .into_iter()
+            .map(|e| format!("{}: {}", filename, e))
+            .collect()
+    })
+}
+
+/// Read the configuration file `filename` as TOML, merging in any files it
+/// includes. See `interpolate` and `include`.
+fn read_config(filename: &str) -> Result<toml::Value, String> {
+    let mut value = read_toml(Path::new(filename))?;
+    let base = Path::new(filename)
+        .parent()
+        .unwrap_or_else(|| Path::new("."));
+    include(&mut value, base)?;
+    Ok(value)
+}
 
+fn read_toml(path: &Path) -> Result<toml::Value, String> {
     let mut buffer = String::new();
-    fp.read_to_string(&mut buffer).unwrap();
-    parse_config_file(&buffer)
+    File::open(path)
+        .and_then(|mut fp| fp.read_to_string(&mut buffer))
+        .map_err(|e| e.to_string())
+        .and_then(|_| parse_toml(&buffer))
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Merge the files matched by the top-level `include` globs of `value` into
+/// it
+///
+/// Relative globs are taken from `base`, the directory of the including file.
+/// The files matched by each glob are merged in lexical order. An included
+/// file may only configure sources, filters and sinks and may not itself
+/// include anything. It is an error for an included file to configure a node
+/// of the same name as one already configured.
+fn include(value: &mut toml::Value, base: &Path) -> Result<(), String> {
+    let patterns = match value.as_table_mut().and_then(|t| t.remove("include")) {
+        Some(toml::Value::Array(patterns)) => patterns,
+        Some(_) => return Err("include must be an array of globs".to_string()),
+        None => return Ok(()),
+    };
+    for pattern in patterns {
+        let pattern = pattern
+            .as_str()
+            .ok_or_else(|| "include must be an array of globs".to_string())?;
+        let pattern = base.join(pattern);
+        let pattern = pattern.to_string_lossy();
+        let mut paths = glob::glob(&pattern)
+            .map_err(|e| format!("include {}: {}", pattern, e))?
+            .collect::<Result<Vec<PathBuf>, _>>()
+            .map_err(|e| format!("include {}: {}", pattern, e))?;
+        paths.sort();
+        for path in paths {
+            let fragment = read_toml(&path)?;
+            merge(value, fragment)
+                .map_err(|e| format!("{}: {}", path.display(), e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Merge the sources, filters and sinks of an included file into `value`.
+fn merge(value: &mut toml::Value, fragment: toml::Value) -> Result<(), String> {
+    let table = value
+        .as_table_mut()
+        .ok_or_else(|| "config must be a table".to_string())?;
+    let fragment = match fragment {
+        toml::Value::Table(fragment) => fragment,
+        _ => return Err("config must be a table".to_string()),
+    };
+    for (section, kinds) in fragment {
+        if section != "sources" && section != "filters" && section != "sinks" {
+            return Err(format!(
+                "{} may not be set in an included file, only sources, filters and \
+                 sinks",
+                section
+            ));
+        }
+        let kinds = match kinds {
+            toml::Value::Table(kinds) => kinds,
+            _ => return Err(format!("{} must be in table format", section)),
+        };
+        let into = table
+            .entry(section.clone())
+            .or_insert_with(|| toml::Value::Table(toml::value::Table::new()))
+            .as_table_mut()
+            .ok_or_else(|| format!("{} must be in table format", section))?;
+        for (kind, instances) in kinds {
+            let path = format!("{}.{}", section, kind);
+            if !into.contains_key(&kind) {
+                into.insert(kind, instances);
+                continue;
+            }
+            // A sink configured once, its keys set directly in
+            // `[sinks.<kind>]`, is a single node. See `parse_sinks`.
+            let all_tables = |v: &toml::Value| {
+                v.as_table().map_or(false, |t| {
+                    !t.is_empty() && t.values().all(toml::Value::is_table)
+                })
+            };
+            if section == "sinks"
+                && !(all_tables(&instances) && all_tables(&into[&kind]))
+            {
+                return Err(format!("duplicate node {}", path));
+            }
+            let mut existing = match into.remove(&kind).unwrap() {
+                toml::Value::Table(existing) => existing,
+                _ => return Err(format!("{} must be in table format", path)),
+            };
+            let instances = match instances {
+                toml::Value::Table(instances) => instances,
+                _ => return Err(format!("{} must be in table format", path)),
+            };
+            for (name, config) in instances {
+                if existing.contains_key(&name) {
+                    return Err(format!("duplicate node {}.{}", path, name));
+                }
+                existing.insert(name, config);
+            }
+            into.insert(kind, toml::Value::Table(existing));
+        }
+    }
+    Ok(())
+}
+
+/// Interpolate references in the configuration `value`
+///
+/// Once the configuration has been parsed as TOML every `${NAME}` in its
+/// strings is replaced by the value of the environment variable `NAME`,
+/// `${NAME:-default}` by the same or `default` if `NAME` is unset or empty
+/// and `${file:/path}` by the contents of the file at `/path`, less any
+/// trailing newline. `$${` is a literal `${`. What a reference resolves to is
+/// taken as is, quotes, backslashes, newlines and all, and comments are never
+/// looked at.
+///
+/// An error is returned if any reference cannot be resolved.
+pub fn interpolate(value: &mut toml::Value) -> Result<(), String> {
+    interpolate_value(value, "")
+}
+
+fn interpolate_value(value: &mut toml::Value, path: &str) -> Result<(), String> {
+    match *value {
+        toml::Value::String(ref mut string) => {
+            *string =
+                interpolate_str(string).map_err(|e| format!("{}: {}", path, e))?;
+        }
+        toml::Value::Array(ref mut values) => {
+            for (idx, value) in values.iter_mut().enumerate() {
+                interpolate_value(value, &format!("{}[{}]", path, idx))?;
+            }
+        }
+        toml::Value::Table(ref mut table) => {
+            for (key, value) in table.iter_mut() {
+                let path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                interpolate_value(value, &path)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Interpolate the references of a single string.
+fn interpolate_str(string: &str) -> Result<String, String> {
+    let mut res = String::with_capacity(string.len());
+    let mut rest = string;
+    while let Some(start) = rest.find('$') {
+        res.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if rest.starts_with("$${") {
+            res.push_str("${");
+            rest = &rest[3..];
+        } else if rest.starts_with("${") {
+            let end = rest
+                .find('}')
+                .ok_or_else(|| format!("unterminated reference {}", rest))?;
+            res.push_str(&resolve(&rest[2..end])?);
+            rest = &rest[end + 1..];
+        } else {
+            res.push('$');
+            rest = &rest[1..];
+        }
+    }
+    res.push_str(rest);
+    Ok(res)
+}
+
+/// Interpolate the unquoted references of the configuration file `buffer`
+///
+/// TOML has no place for a reference outside of a string, save where it is
+/// the whole of a value, as in `port = ${PORT:-8125}`. Such a reference is
+/// resolved before the file is parsed and must resolve to an integer, float
+/// or boolean, so that it cannot change the structure of the file.
+fn interpolate_unquoted(buffer: &str) -> Result<String, String> {
+    let mut res = String::with_capacity(buffer.len());
+    for (idx, line) in buffer.split('\n').enumerate() {
+        if idx > 0 {
+            res.push('\n');
+        }
+        let eq = match line.find('=') {
+            Some(eq) => eq,
+            None => {
+                res.push_str(line);
+                continue;
+            }
+        };
+        let key = line[..eq].trim();
+        let (value, comment) = match line[eq + 1..].find('#') {
+            Some(hash) => line[eq + 1..].split_at(hash),
+            None => (&line[eq + 1..], ""),
+        };
+        let value = value.trim();
+        let unquoted = !key.is_empty()
+            && !key.starts_with('#')
+            && !key.starts_with('[')
+            && value.starts_with("${")
+            && value.find('}') == Some(value.len() - 1);
+        if !unquoted {
+            res.push_str(line);
+            continue;
+        }
+        let resolved = resolve(&value[2..value.len() - 1])
+            .map_err(|e| format!("line {}: {}", idx + 1, e))?;
+        let resolved = resolved.trim();
+        let literal = i64::from_str(resolved).is_ok()
+            || bool::from_str(resolved).is_ok()
+            || f64::from_str(resolved).map_or(false, f64::is_finite);
+        if !literal {
+            return Err(format!(
+                "line {}: unquoted reference {} is not an integer, float or \
+                 boolean, quote it to use it as a string",
+                idx + 1,
+                value
+            ));
+        }
+        res.push_str(&line[..eq + 1]);
+        res.push(' ');
+        res.push_str(resolved);
+        if !comment.is_empty() {
+            res.push(' ');
+            res.push_str(comment);
+        }
+    }
+    Ok(res)
+}
+
+/// Parse a configuration file held in `buffer`, interpolating its references.
+fn parse_toml(buffer: &str) -> Result<toml::Value, String> {
+    let buffer = interpolate_unquoted(buffer)?;
+    let mut value: toml::Value = toml::from_str(&buffer).map_err(|e| e.to_string())?;
+    interpolate(&mut value)?;
+    Ok(value)
 }
 
+/// Resolve a single reference, the text between `${` and `}`.
+fn resolve(reference: &str) -> Result<String, String> {
+    if reference.starts_with("file:") {
+        let path = &reference[5..];
+        let mut contents = String::new();
+        File::open(path)
+            .and_then(|mut fp| fp.read_to_string(&mut contents))
+            .map_err(|e| format!("could not read file {}: {}", path, e))?;
+        let len = contents.trim_end_matches(|c| c == '\n' || c == '\r').len();
+        contents.truncate(len);
+        return Ok(contents);
+    }
+    let (name, default) = match reference.find(":-") {
+        Some(idx) => (&reference[..idx], Some(&reference[idx + 2..])),
+        None => (reference, None),
+    };
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        return Err(format!("invalid environment variable name {:?}", name));
+    }
+    match (env::var(name), default) {
+        (Ok(ref value), Some(default)) if value.is_empty() => Ok(default.to_string()),
+        (Ok(value), _) => Ok(value),
+        (Err(_), Some(default)) => Ok(default.to_string()),
+        (Err(e), None) => Err(format!("environment variable {}: {}", name, e)),
+    }
+}
+
+/// Parse a configuration held in `buffer`. Included files are not merged.
+#[cfg(test)]
 fn parse_config_file(buffer: &str) -> Args {
+    let value = parse_toml(buffer).unwrap_or_else(|e| panic!("{}", e));
+    parse_config_value(&value).unwrap_or_else(|e| panic!("{}", e.join("\n")))
+}
+
+/// Parse the configuration `value`, returning every error found in it.
+fn parse_config_value(value: &toml::Value) -> Result<Args, Vec<String>> {
+    let errors = Errors::default();
+    let args = parse_config_args(value, &errors);
+    let errors = errors.0.into_inner();
+    if errors.is_empty() {
+        Ok(args)
+    } else {
+        Err(errors)
+    }
+}
+
+fn parse_config_args(value: &toml::Value, errors: &Errors) -> Args {
     let mut args = Args::default();
-    let value: toml::Value =
-        toml::from_str(buffer).expect("could not parse config file");
 
     args.max_hopper_queue_bytes = value
         .get("max-hopper-queue-bytes")
         .map(|s| {
             s.as_integer()
-                .expect("could not parse max-hopper-queue-bytes") as usize
+                .expect_in(errors, "could not parse max-hopper-queue-bytes")
+                as usize
         })
         .unwrap_or(args.max_hopper_queue_bytes);
 
@@ -231,7 +901,8 @@ fn parse_config_file(buffer: &str) -> Args {
         .get("max-hopper-queue-files")
         .map(|s| {
             s.as_integer()
-                .expect("could not parse max-hopper-queue-files") as usize
+                .expect_in(errors, "could not parse max-hopper-queue-files")
+                as usize
         })
         .unwrap_or(args.max_hopper_queue_files);
 
@@ -239,7 +910,7 @@ fn parse_config_file(buffer: &str) -> Args {
         .get("max-hopper-in-memory-bytes")
         .map(|s| {
             s.as_integer()
-                .expect("could not parse max-hopper-in-memory-bytes")
+                .expect_in(errors, "could not parse max-hopper-in-memory-bytes")
                 as usize
         })
         .unwrap_or(args.max_hopper_in_memory_bytes);
@@ -249,7 +920,7 @@ fn parse_config_file(buffer: &str) -> Args {
         .map(|s| {
             let s = s
                 .as_str()
-                .expect("data-directory value must be valid string");
+                .expect_in(errors, "data-directory value must be valid string");
             Path::new(s).to_path_buf()
         })
         .unwrap_or(args.data_directory);
@@ -259,45 +930,95 @@ fn parse_config_file(buffer: &str) -> Args {
         .map(|s| {
             let s = s
                 .as_str()
-                .expect("scripts-directory value must be valid string");
+                .expect_in(errors, "scripts-directory value must be valid string");
             Path::new(s).to_path_buf()
         })
         .unwrap_or(args.scripts_directory);
 
-    args.flush_interval =
-        parse_flush_interval(&value, "flush-interval").unwrap_or(args.flush_interval);
+    args.flush_interval = parse_flush_interval(value, "flush-interval", errors)
+        .unwrap_or(args.flush_interval);
+
+    args.admin = value.get("admin").map(|admin| {
+        let mut res = AdminConfig::default();
+        res.host = admin
+            .get("host")
+            .map(|p| {
+                p.as_str()
+                    .expect_in(errors, "could not parse admin.host")
+                    .to_string()
+            })
+            .unwrap_or(res.host);
+        res.port = admin
+            .get("port")
+            .map(|p| {
+                p.as_integer()
+                    .expect_in(errors, "could not parse admin.port")
+                    as u16
+            })
+            .unwrap_or(res.port);
+        res.valve_closed_threshold = admin
+            .get("valve-closed-threshold")
+            .map(|p| {
+                p.as_integer()
+                    .expect_in(errors, "could not parse admin.valve-closed-threshold")
+                    as u64
+            })
+            .unwrap_or(res.valve_closed_threshold);
+        res.flush_stalled_threshold = admin
+            .get("flush-stalled-threshold")
+            .map(|p| {
+                p.as_integer()
+                    .expect_in(errors, "could not parse admin.flush-stalled-threshold")
+                    as u64
+            })
+            .unwrap_or(res.flush_stalled_threshold);
+        res.max_queue_backlog = admin.get("max-queue-backlog").map(|p| {
+            p.as_integer()
+                .expect_in(errors, "could not parse admin.max-queue-backlog")
+                as u64
+        });
+        res
+    });
+
+    args.overflow = parse_overflow(value, errors);
 
     let global_tags: TagMap = match value.get("tags") {
         Some(tbl) => {
             let mut tags = TagMap::default();
-            let ttbl = tbl.as_table().unwrap();
-            for (k, v) in ttbl.iter() {
+            for (k, v) in table(tbl, "tags", errors).into_iter().flatten() {
                 let val = match v.as_str() {
                     Some(s) => s.to_string(),
                     None => {
-                        let ktbl =
-                            v.as_table().expect("tag must be a string or a table");
+                        let ktbl = match v.as_table() {
+                            Some(ktbl) => ktbl,
+                            None => {
+                                errors.push("tag must be a string or a table");
+                                continue;
+                            }
+                        };
                         if ktbl
                             .get("environment")
                             .map_or(false, |ev| ev.as_bool().unwrap_or(false))
                         {
                             let env_key = ktbl
                                 .get("value")
-                                .expect("must have a value key")
-                                .as_str()
-                                .expect("value key must be string");
+                                .and_then(|v| v.as_str())
+                                .expect_in(errors, "value key must be a string");
                             env::var_os(env_key)
-                                .expect("value could not be read from the environment")
+                                .expect_in(
+                                    errors,
+                                    "value could not be read from the environment",
+                                )
                                 .into_string()
-                                .expect(
+                                .expect_in(
+                                    errors,
                                     "value read from environment is not a rust string",
                                 )
                         } else {
-                            use std::process::exit;
-                            println!(
-                                "environment variable table must have environment / value keys"
+                            errors.push(
+                                "environment variable table must have environment / value keys",
                             );
-                            exit(1);
+                            continue;
                         }
                     }
                 };
@@ -313,19 +1034,16 @@ fn parse_config_file(buffer: &str) -> Args {
     if let Some(filters) = value.get("filters") {
         args.delay_filters = filters.get("delay").map(|fltr| {
             let mut filters: HashMap<String, DelayFilterConfig> = HashMap::new();
-            for (name, tbl) in fltr.as_table().unwrap().iter() {
+            for (name, tbl) in
+                table(fltr, "filters.delay", errors).into_iter().flatten()
+            {
                 match tbl.get("tolerance") {
                     Some(tol) => {
-                        let tolerance =
-                            tol.as_integer().expect("tolerance must be an integer");
+                        let tolerance = tol
+                            .as_integer()
+                            .expect_in(errors, "tolerance must be an integer");
                         let fwds = match tbl.get("forwards") {
-                            Some(fwds) => fwds
-                                .as_array()
-                                .expect("forwards must be an array")
-                                .to_vec()
-                                .iter()
-                                .map(|s| s.as_str().unwrap().to_string())
-                                .collect(),
+                            Some(fwds) => parse_forwards(fwds, errors),
                             None => Vec::new(),
                         };
                         let config_path = format!("filters.delay.{}", name);
@@ -344,22 +1062,19 @@ fn parse_config_file(buffer: &str) -> Args {
 
         args.json_encode_filters = filters.get("json_encode").map(|fltr| {
             let mut filters: HashMap<String, JSONEncodeFilterConfig> = HashMap::new();
-            for (name, tbl) in fltr.as_table().unwrap().iter() {
+            for (name, tbl) in table(fltr, "filters.json_encode", errors)
+                .into_iter()
+                .flatten()
+            {
                 let parse_line = if let Some(parse_line) = tbl.get("parse_line") {
                     parse_line
                         .as_bool()
-                        .expect("could not parse parse_line as boolean")
+                        .expect_in(errors, "could not parse parse_line as boolean")
                 } else {
                     false
                 };
                 let fwds = match tbl.get("forwards") {
-                    Some(fwds) => fwds
-                        .as_array()
-                        .expect("forwards must be an array")
-                        .to_vec()
-                        .iter()
-                        .map(|s| s.as_str().unwrap().to_string())
-                        .collect(),
+                    Some(fwds) => parse_forwards(fwds, errors),
                     None => Vec::new(),
                 };
                 let config_path = format!("filters.json_encode.{}", name);
@@ -377,19 +1092,17 @@ fn parse_config_file(buffer: &str) -> Args {
         args.flush_boundary_filters = filters.get("flush_boundary").map(|fltr| {
             let mut filters: HashMap<String, FlushBoundaryFilterConfig> =
                 HashMap::new();
-            for (name, tbl) in fltr.as_table().unwrap().iter() {
+            for (name, tbl) in table(fltr, "filters.flush_boundary", errors)
+                .into_iter()
+                .flatten()
+            {
                 match tbl.get("tolerance") {
                     Some(tol) => {
-                        let tolerance =
-                            tol.as_integer().expect("tolerance must be an integer");
+                        let tolerance = tol
+                            .as_integer()
+                            .expect_in(errors, "tolerance must be an integer");
                         let fwds = match tbl.get("forwards") {
-                            Some(fwds) => fwds
-                                .as_array()
-                                .expect("forwards must be an array")
-                                .to_vec()
-                                .iter()
-                                .map(|s| s.as_str().unwrap().to_string())
-                                .collect(),
+                            Some(fwds) => parse_forwards(fwds, errors),
                             None => Vec::new(),
                         };
                         let config_path = format!("filters.flush_boundary.{}", name);
@@ -409,18 +1122,17 @@ fn parse_config_file(buffer: &str) -> Args {
         args.programmable_filters = filters.get("programmable").map(|fltr| {
             let mut filters: HashMap<String, ProgrammableFilterConfig> =
                 HashMap::new();
-            for (name, tbl) in fltr.as_table().unwrap().iter() {
+            for (name, tbl) in table(fltr, "filters.programmable", errors)
+                .into_iter()
+                .flatten()
+            {
                 match tbl.get("script") {
                     Some(pth) => {
-                        let path = Path::new(pth.as_str().unwrap());
+                        let path = Path::new(
+                            pth.as_str().expect_in(errors, "script must be a string"),
+                        );
                         let fwds = match tbl.get("forwards") {
-                            Some(fwds) => fwds
-                                .as_array()
-                                .expect("forwards must be an array")
-                                .to_vec()
-                                .iter()
-                                .map(|s| s.as_str().unwrap().to_string())
-                                .collect(),
+                            Some(fwds) => parse_forwards(fwds, errors),
                             None => Vec::new(),
                         };
                         let config_path = format!("filters.programmable.{}", name);
@@ -442,170 +1154,225 @@ fn parse_config_file(buffer: &str) -> Args {
 
     // sinks
     //
-    if let Some(sinks) = value.get("sinks") {
-        let sinks = sinks.as_table().expect("sinks must be in table format");
+    if let Some(sinks) = value
+        .get("sinks")
+        .and_then(|sinks| table(sinks, "sinks", errors))
+    {
+        args.null =
+            parse_sinks(sinks, "null", &[], errors, |_, config_path| NullConfig {
+                config_path: config_path.to_string(),
+            });
 
-        args.null = sinks.get("null").map(|_| NullConfig {
-            config_path: "sinks.null".to_string(),
-        });
+        args.console =
+            parse_sinks(sinks, "console", &[], errors, |snk, config_path| {
+                let mut res = ConsoleConfig::default();
+                res.config_path = Some(config_path.to_string());
 
-        args.console = sinks.get("console").map(|snk| {
-            let mut res = ConsoleConfig::default();
-            res.config_path = Some("sinks.console".to_string());
+                res.bin_width = snk
+                    .get("bin_width")
+                    .map(|bw| {
+                        bw.as_integer().expect_in(
+                            errors,
+                            "could not parse sinks.console.bin_width",
+                        )
+                    })
+                    .unwrap_or(res.bin_width);
 
-            res.bin_width = snk
-                .get("bin_width")
-                .map(|bw| {
-                    bw.as_integer()
-                        .expect("could not parse sinks.console.bin_width")
-                })
-                .unwrap_or(res.bin_width);
+                res.flush_interval =
+                    parse_flush_interval(snk, "flush_interval", errors)
+                        .unwrap_or(args.flush_interval);
+                res.tags = global_tags.clone();
 
-            res.flush_interval = parse_flush_interval(snk, "flush_interval")
-                .unwrap_or(args.flush_interval);
-            res.tags = global_tags.clone();
+                res
+            });
 
-            res
-        });
+        args.wavefront = parse_sinks(
+            sinks,
+            "wavefront",
+            &["delivery", "padding", "percentiles"],
+            errors,
+            |snk, config_path| {
+                let mut res = WavefrontConfig::default();
+                res.config_path = Some(config_path.to_string());
 
-        args.wavefront = sinks.get("wavefront").map(|snk| {
-            let mut res = WavefrontConfig::default();
-            res.config_path = Some("sinks.wavefront".to_string());
-
-            res.pad_control = snk.get("padding")
-                .and_then(|t| t.as_table())
-                .map(|tbl| PadControl {
-                    set: tbl.get("set").map_or(false, |v| {
-                        v.as_bool().expect("could not parse padding.set as boolean")
-                    }),
-                    sum: tbl.get("sum").map_or(false, |v| {
-                        v.as_bool().expect("could not parse padding.sum as boolean")
-                    }),
-                    summarize: tbl.get("summarize").map_or(false, |v| {
-                        v.as_bool()
-                            .expect("could not parse padding.summarize as boolean")
-                    }),
-                    histogram: tbl.get("histogram").map_or(false, |v| {
-                        v.as_bool()
-                            .expect("could not parse padding.histogram as boolean")
-                    }),
-                })
-                .unwrap_or(res.pad_control);
-
-            res.percentiles = snk.get("percentiles")
-                .and_then(|t| t.as_table())
-                .map(|tbl| {
-                    let mut prcnt = Vec::default();
-                    for (k, v) in tbl.iter() {
-                        let v: f64 =
-                            v.as_float().expect("percentile value must be a float");
-                        prcnt.push((k.clone(), v));
-                    }
-                    prcnt
-                })
-                .unwrap_or(res.percentiles);
+                res.pad_control = snk
+                    .get("padding")
+                    .and_then(|t| t.as_table())
+                    .map(|tbl| PadControl {
+                        set: tbl.get("set").map_or(false, |v| {
+                            v.as_bool().expect_in(
+                                errors,
+                                "could not parse padding.set as boolean",
+                            )
+                        }),
+                        sum: tbl.get("sum").map_or(false, |v| {
+                            v.as_bool().expect_in(
+                                errors,
+                                "could not parse padding.sum as boolean",
+                            )
+                        }),
+                        summarize: tbl.get("summarize").map_or(false, |v| {
+                            v.as_bool().expect_in(
+                                errors,
+                                "could not parse padding.summarize as boolean",
+                            )
+                        }),
+                        histogram: tbl.get("histogram").map_or(false, |v| {
+                            v.as_bool().expect_in(
+                                errors,
+                                "could not parse padding.histogram as boolean",
+                            )
+                        }),
+                        distinct: tbl.get("distinct").map_or(false, |v| {
+                            v.as_bool().expect_in(
+                                errors,
+                                "could not parse padding.distinct as boolean",
+                            )
+                        }),
+                    })
+                    .unwrap_or(res.pad_control);
+
+                res.percentiles = snk
+                    .get("percentiles")
+                    .and_then(|t| t.as_table())
+                    .map(|tbl| {
+                        let mut prcnt = Vec::default();
+                        for (k, v) in tbl.iter() {
+                            let v: f64 = v
+                                .as_float()
+                                .expect_in(errors, "percentile value must be a float");
+                            prcnt.push((k.clone(), v));
+                        }
+                        prcnt
+                    })
+                    .unwrap_or(res.percentiles);
 
-            res.port = snk.get("port")
-                .map(|p| {
-                    p.as_integer()
-                        .expect("could not parse sinks.wavefront.port")
-                        as u16
-                })
-                .unwrap_or(res.port);
+                res.port = snk
+                    .get("port")
+                    .map(|p| {
+                        p.as_integer()
+                            .expect_in(errors, "could not parse sinks.wavefront.port")
+                            as u16
+                    })
+                    .unwrap_or(res.port);
 
-            res.age_threshold = snk.get("age_threshold")
-                .map(|p| {
-                    Some(p.as_integer()
-                        .expect("could not parse sinks.wavefront.age_threshold")
-                        as u64)
-                })
-                .unwrap_or(res.age_threshold);
+                res.age_threshold = snk
+                    .get("age_threshold")
+                    .map(|p| {
+                        Some(p.as_integer().expect_in(
+                            errors,
+                            "could not parse sinks.wavefront.age_threshold",
+                        ) as u64)
+                    })
+                    .unwrap_or(res.age_threshold);
 
-            res.host = snk.get("host")
-                .map(|p| {
-                    p.as_str()
-                        .expect("could not parse sinks.wavefront.host")
-                        .to_string()
-                })
-                .unwrap_or(res.host);
+                res.host = snk
+                    .get("host")
+                    .map(|p| {
+                        p.as_str()
+                            .expect_in(errors, "could not parse sinks.wavefront.host")
+                            .to_string()
+                    })
+                    .unwrap_or(res.host);
 
-            res.bin_width = snk.get("bin_width")
-                .map(|bw| {
-                    bw.as_integer()
-                        .expect("could not parse sinks.wavefront.bin_width")
-                })
-                .unwrap_or(res.bin_width);
+                res.bin_width = snk
+                    .get("bin_width")
+                    .map(|bw| {
+                        bw.as_integer().expect_in(
+                            errors,
+                            "could not parse sinks.wavefront.bin_width",
+                        )
+                    })
+                    .unwrap_or(res.bin_width);
 
-            res.flush_interval = parse_flush_interval(snk, "flush_interval").unwrap_or(args.flush_interval);
-            if res.bin_width > (res.flush_interval as i64) {
-                warn!("bin_width > flush_interval. bin_width will be effectively flush_interval due to flush behaviour.")
-            }
+                res.flush_interval =
+                    parse_flush_interval(snk, "flush_interval", errors)
+                        .unwrap_or(args.flush_interval);
+                if res.bin_width > (res.flush_interval as i64) {
+                    warn!("bin_width > flush_interval. bin_width will be effectively flush_interval due to flush behaviour.")
+                }
 
-            res.tags = global_tags.clone();
+                res.delivery = parse_delivery(snk, config_path, res.delivery, errors);
+                res.tags = global_tags.clone();
 
-            res
-        });
+                res
+            },
+        );
 
-        args.influxdb = sinks.get("influxdb").map(|snk| {
-            let mut res = InfluxDBConfig::default();
-            res.config_path = Some("sinks.influxdb".to_string());
+        args.influxdb = parse_sinks(
+            sinks,
+            "influxdb",
+            &["delivery"],
+            errors,
+            |snk, config_path| {
+                let mut res = InfluxDBConfig::default();
+                res.config_path = Some(config_path.to_string());
 
-            res.port = snk
-                .get("port")
-                .map(|p| {
-                    p.as_integer().expect("could not parse sinks.influxdb.port") as u16
-                })
-                .unwrap_or(res.port);
-
-            res.secure = snk
-                .get("secure")
-                .map(|p| p.as_bool().expect("could not parse sinks.influxdb.secure"))
-                .unwrap_or(res.secure);
-
-            res.host = snk
-                .get("host")
-                .map(|p| {
-                    p.as_str()
-                        .expect("could not parse sinks.influxdb.host")
-                        .to_string()
-                })
-                .unwrap_or(res.host);
+                res.port = snk
+                    .get("port")
+                    .map(|p| {
+                        p.as_integer()
+                            .expect_in(errors, "could not parse sinks.influxdb.port")
+                            as u16
+                    })
+                    .unwrap_or(res.port);
 
-            res.db = snk
-                .get("db")
-                .map(|p| {
-                    p.as_str()
-                        .expect("could not parse sinks.influxdb.db")
-                        .to_string()
-                })
-                .unwrap_or(res.db);
+                res.secure = snk
+                    .get("secure")
+                    .map(|p| {
+                        p.as_bool()
+                            .expect_in(errors, "could not parse sinks.influxdb.secure")
+                    })
+                    .unwrap_or(res.secure);
+
+                res.host = snk
+                    .get("host")
+                    .map(|p| {
+                        p.as_str()
+                            .expect_in(errors, "could not parse sinks.influxdb.host")
+                            .to_string()
+                    })
+                    .unwrap_or(res.host);
 
-            res.flush_interval = parse_flush_interval(snk, "flush_interval")
-                .unwrap_or(args.flush_interval);
-            res.tags = global_tags.clone();
+                res.db = snk
+                    .get("db")
+                    .map(|p| {
+                        p.as_str()
+                            .expect_in(errors, "could not parse sinks.influxdb.db")
+                            .to_string()
+                    })
+                    .unwrap_or(res.db);
 
-            res
-        });
+                res.flush_interval =
+                    parse_flush_interval(snk, "flush_interval", errors)
+                        .unwrap_or(args.flush_interval);
+                res.delivery = parse_delivery(snk, config_path, res.delivery, errors);
+                res.tags = global_tags.clone();
+
+                res
+            },
+        );
 
         args.prometheus =
-            sinks.get("prometheus").map(|snk| {
+            parse_sinks(sinks, "prometheus", &[], errors, |snk, config_path| {
                 let mut res = PrometheusConfig::default();
-                res.config_path = Some("sinks.prometheus".to_string());
+                res.config_path = Some(config_path.to_string());
 
-                res.age_threshold =
-                    snk.get("age_threshold")
-                        .map(|p| {
-                            Some(p.as_integer().expect(
-                                "could not parse sinks.prometheus.age_threshold",
-                            ) as u64)
-                        })
-                        .unwrap_or(res.age_threshold);
+                res.age_threshold = snk
+                    .get("age_threshold")
+                    .map(|p| {
+                        Some(p.as_integer().expect_in(
+                            errors,
+                            "could not parse sinks.prometheus.age_threshold",
+                        ) as u64)
+                    })
+                    .unwrap_or(res.age_threshold);
 
                 res.port = snk
                     .get("port")
                     .map(|p| {
                         p.as_integer()
-                            .expect("could not parse sinks.prometheus.port")
+                            .expect_in(errors, "could not parse sinks.prometheus.port")
                             as u16
                     })
                     .unwrap_or(res.port);
@@ -614,7 +1381,7 @@ fn parse_config_file(buffer: &str) -> Args {
                     .get("host")
                     .map(|p| {
                         p.as_str()
-                            .expect("could not parse sinks.prometheus.host")
+                            .expect_in(errors, "could not parse sinks.prometheus.host")
                             .to_string()
                     })
                     .unwrap_or(res.host);
@@ -622,7 +1389,8 @@ fn parse_config_file(buffer: &str) -> Args {
                 res.capacity_in_seconds = snk
                     .get("capacity_in_seconds")
                     .map(|p| {
-                        p.as_integer().expect(
+                        p.as_integer().expect_in(
+                            errors,
                             "could not parse sinks.prometheus.capacity_in_seconds",
                         ) as usize
                     })
@@ -633,107 +1401,189 @@ fn parse_config_file(buffer: &str) -> Args {
                 res
             });
 
-        args.elasticsearch = sinks.get("elasticsearch").map(|snk| {
-            let mut res = ElasticsearchConfig::default();
-            res.config_path = Some("sinks.elasticsearch".to_string());
+        args.prometheus_remote_write = parse_sinks(
+            sinks,
+            "prometheus_remote_write",
+            &["delivery"],
+            errors,
+            |snk, config_path| {
+                let mut res = PrometheusRemoteWriteConfig::default();
+                res.config_path = Some(config_path.to_string());
+
+                res.url =
+                    snk.get("url")
+                        .map(|p| {
+                            p.as_str()
+                            .expect_in(errors,
+                                "could not parse sinks.prometheus_remote_write.url",
+                            )
+                            .to_string()
+                        })
+                        .unwrap_or(res.url);
+
+                res.max_samples_per_send = snk
+                    .get("max_samples_per_send")
+                    .map(|p| {
+                        p.as_integer().expect_in(errors,
+                            "could not parse sinks.prometheus_remote_write.max_samples_per_send",
+                        ) as usize
+                    })
+                    .unwrap_or(res.max_samples_per_send);
+                errors.check(
+                    res.max_samples_per_send > 0,
+                    "sinks.prometheus_remote_write.max_samples_per_send must be at least 1",
+                );
+
+                res.timeout =
+                    snk.get("timeout")
+                        .map(|p| {
+                            p.as_integer().expect_in(errors,
+                            "could not parse sinks.prometheus_remote_write.timeout",
+                        ) as u64
+                        })
+                        .unwrap_or(res.timeout);
 
-            res.delivery_attempt_limit = snk
-                .get("delivery_attempt_limit")
-                .map(|p| {
-                    p.as_integer().expect(
+                res.flush_interval =
+                    parse_flush_interval(snk, "flush_interval", errors)
+                        .unwrap_or(args.flush_interval);
+                res.tags = global_tags.clone();
+                res.delivery = parse_delivery(snk, config_path, res.delivery, errors);
+
+                res
+            },
+        );
+
+        args.elasticsearch = parse_sinks(
+            sinks,
+            "elasticsearch",
+            &["delivery"],
+            errors,
+            |snk, config_path| {
+                let mut res = ElasticsearchConfig::default();
+                res.config_path = Some(config_path.to_string());
+
+                res.delivery_attempt_limit = snk
+                    .get("delivery_attempt_limit")
+                    .map(|p| {
+                        p.as_integer().expect_in(errors,
                         "could not parse sinks.elasticsearch.delivery_attempt_limit",
                     ) as u8
-                })
-                .unwrap_or(res.delivery_attempt_limit);
-
-            res.port = snk
-                .get("port")
-                .map(|p| {
-                    p.as_integer()
-                        .expect("could not parse sinks.elasticsearch.port")
-                        as usize
-                })
-                .unwrap_or(res.port);
+                    })
+                    .unwrap_or(res.delivery_attempt_limit);
 
-            res.host = snk
-                .get("host")
-                .map(|p| {
-                    p.as_str()
-                        .expect("could not parse sinks.elasticsearch.host")
-                        .to_string()
-                })
-                .unwrap_or(res.host);
+                res.port = snk
+                    .get("port")
+                    .map(|p| {
+                        p.as_integer().expect_in(
+                            errors,
+                            "could not parse sinks.elasticsearch.port",
+                        ) as usize
+                    })
+                    .unwrap_or(res.port);
 
-            res.index_prefix = snk
-                .get("index-prefix")
-                .map(|p| {
-                    Some(
+                res.host = snk
+                    .get("host")
+                    .map(|p| {
                         p.as_str()
-                            .expect("could not parse sinks.elasticsearch.index-prefix")
-                            .to_string(),
-                    )
-                })
-                .unwrap_or(res.index_prefix);
-
-            res.secure = snk
-                .get("secure")
-                .map(|bw| {
-                    bw.as_bool()
-                        .expect("could not parse sinks.elasticsearch.secure")
-                })
-                .unwrap_or(res.secure);
+                            .expect_in(
+                                errors,
+                                "could not parse sinks.elasticsearch.host",
+                            )
+                            .to_string()
+                    })
+                    .unwrap_or(res.host);
 
-            res.index_type = snk
-                .get("index_type")
-                .map(|bw| {
-                    bw.as_str()
-                        .expect("could not parse sinks.elasticsearch.index_type")
-                        .to_string()
-                })
-                .unwrap_or(res.index_type);
+                res.index_prefix = snk
+                    .get("index-prefix")
+                    .map(|p| {
+                        Some(
+                            p.as_str()
+                                .expect_in(
+                                    errors,
+                                    "could not parse sinks.elasticsearch.index-prefix",
+                                )
+                                .to_string(),
+                        )
+                    })
+                    .unwrap_or(res.index_prefix);
+
+                res.secure = snk
+                    .get("secure")
+                    .map(|bw| {
+                        bw.as_bool().expect_in(
+                            errors,
+                            "could not parse sinks.elasticsearch.secure",
+                        )
+                    })
+                    .unwrap_or(res.secure);
+
+                res.index_type = snk
+                    .get("index_type")
+                    .map(|bw| {
+                        bw.as_str()
+                            .expect_in(
+                                errors,
+                                "could not parse sinks.elasticsearch.index_type",
+                            )
+                            .to_string()
+                    })
+                    .unwrap_or(res.index_type);
 
-            res.flush_interval = parse_flush_interval(snk, "flush_interval")
-                .unwrap_or(args.flush_interval);
-            res.tags = global_tags.clone();
+                res.flush_interval =
+                    parse_flush_interval(snk, "flush_interval", errors)
+                        .unwrap_or(args.flush_interval);
+                res.delivery = parse_delivery(snk, config_path, res.delivery, errors);
+                res.tags = global_tags.clone();
 
-            res
-        });
+                res
+            },
+        );
 
-        args.native_sink_config = sinks.get("native").map(|snk| {
-            let mut res = NativeConfig::default();
-            res.config_path = Some("sinks.native".to_string());
+        args.native_sink_config = parse_sinks(
+            sinks,
+            "native",
+            &["delivery"],
+            errors,
+            |snk, config_path| {
+                let mut res = NativeConfig::default();
+                res.config_path = Some(config_path.to_string());
 
-            res.port = snk
-                .get("port")
-                .map(|p| {
-                    p.as_integer().expect("could not parse sinks.native.port") as u16
-                })
-                .unwrap_or(res.port);
+                res.port = snk
+                    .get("port")
+                    .map(|p| {
+                        p.as_integer()
+                            .expect_in(errors, "could not parse sinks.native.port")
+                            as u16
+                    })
+                    .unwrap_or(res.port);
 
-            res.host = snk
-                .get("host")
-                .map(|p| {
-                    p.as_str()
-                        .expect("could not parse sinks.native.host")
-                        .to_string()
-                })
-                .unwrap_or(res.host);
+                res.host = snk
+                    .get("host")
+                    .map(|p| {
+                        p.as_str()
+                            .expect_in(errors, "could not parse sinks.native.host")
+                            .to_string()
+                    })
+                    .unwrap_or(res.host);
 
-            res.flush_interval = parse_flush_interval(snk, "flush_interval")
-                .unwrap_or(args.flush_interval);
-            res.tags = global_tags.clone();
+                res.flush_interval =
+                    parse_flush_interval(snk, "flush_interval", errors)
+                        .unwrap_or(args.flush_interval);
+                res.delivery = parse_delivery(snk, config_path, res.delivery, errors);
+                res.tags = global_tags.clone();
 
-            res
-        });
+                res
+            },
+        );
 
         args.kafkas = sinks.get("kafka").map(|snk| {
             let mut kafkas = Vec::new();
-            for (name, tbl) in snk.as_table().unwrap().iter() {
+            for (name, tbl) in table(snk, "sinks.kafka", errors).into_iter().flatten() {
                 let is_enabled = tbl
                     .get("enabled")
                     .unwrap_or(&toml::Value::Boolean(true))
                     .as_bool()
-                    .expect("must be a bool");
+                    .expect_in(errors, "must be a bool");
                 if !is_enabled {
                     continue;
                 }
@@ -744,7 +1594,7 @@ fn parse_config_file(buffer: &str) -> Args {
 
                 let topic = tbl
                     .get("topic")
-                    .map(|x| x.as_str().expect("topic must be a string").to_string());
+                    .map(|x| x.as_str().expect_in(errors, "topic must be a string").to_string());
                 if topic.is_none() {
                     warn!(
                         "kafka sink {} skipped as it does not provide a topic!",
@@ -756,7 +1606,7 @@ fn parse_config_file(buffer: &str) -> Args {
 
                 let brokers = tbl.get("brokers").map(|x| {
                     x.as_str()
-                        .expect("brokers must be a comma-separated list of host or host:port")
+                        .expect_in(errors, "brokers must be a comma-separated list of host or host:port")
                         .to_string()
                 });
                 if brokers.is_none() {
@@ -771,11 +1621,12 @@ fn parse_config_file(buffer: &str) -> Args {
                 // Allow configuration of librdkafka producer with a sub-table.
                 // Sooo many options:
                 // https://github.com/edenhill/librdkafka/blob/master/CONFIGURATION.md
-                res.rdkafka_config = tbl.get("librdkafka").map(|x| {
-                    let tbl = x
-                        .as_table()
-                        .expect("librdkafka configuration should be a table");
-                    let mut map = HashMap::new();
+                let librdkafka = &format!("{}.librdkafka", config_path);
+                res.rdkafka_config = tbl
+                    .get("librdkafka")
+                    .and_then(|x| table(x, librdkafka, errors))
+                    .map(|tbl| {
+                    let mut map = BTreeMap::new();
                     for (key, value) in tbl.iter() {
                         match *value {
                             toml::Value::Integer(i) => map.insert(key.clone(), format!("{}", i)),
@@ -797,12 +1648,12 @@ fn parse_config_file(buffer: &str) -> Args {
                 });
 
                 res.flush_interval =
-                    parse_flush_interval(tbl, "flush_interval").unwrap_or(args.flush_interval);
+                    parse_flush_interval(tbl, "flush_interval", errors).unwrap_or(args.flush_interval);
                 res.max_message_bytes = tbl
                     .get("max_message_bytes")
                     .map(|fi| {
                         fi.as_integer()
-                            .expect("could not parse sinks.kafka.max_message_bytes")
+                            .expect_in(errors, "could not parse sinks.kafka.max_message_bytes")
                             as usize
                     })
                     .unwrap_or(res.max_message_bytes);
@@ -815,28 +1666,31 @@ fn parse_config_file(buffer: &str) -> Args {
 
     // sources
     //
-    if let Some(sources) = value.get("sources") {
-        let sources = sources.as_table().expect("sources must be in table format");
-
+    if let Some(sources) = value
+        .get("sources")
+        .and_then(|sources| table(sources, "sources", errors))
+    {
         args.files = sources.get("files").map(|src| {
             let mut files = Vec::new();
-            for tbl in src.as_table().unwrap().values() {
+            for tbl in table(src, "sources.files", errors)
+                .into_iter()
+                .flat_map(|tbl| tbl.values())
+            {
                 match tbl.get("path") {
                     Some(pth) => {
                         let mut fl = FileServerConfig::default();
-                        fl.path = Some(Path::new(pth.as_str().unwrap()).to_path_buf());
+                        fl.path = Some(
+                            Path::new(
+                                pth.as_str()
+                                    .expect_in(errors, "path must be a string"),
+                            )
+                            .to_path_buf(),
+                        );
                         fl.config_path = Some(format!("sources.files.{}", pth));
 
                         fl.forwards = tbl
                             .get("forwards")
-                            .map(|fwd| {
-                                fwd.as_array()
-                                    .expect("forwards must be an array")
-                                    .to_vec()
-                                    .iter()
-                                    .map(|s| s.as_str().unwrap().to_string())
-                                    .collect()
-                            })
+                            .map(|fwd| parse_forwards(fwd, errors))
                             .unwrap_or(fl.forwards);
 
                         // NOTE The table lookup will return an i64 but we
@@ -851,9 +1705,10 @@ fn parse_config_file(buffer: &str) -> Args {
                         fl.max_read_bytes = tbl
                             .get("max_read_bytes")
                             .map(|mrl| {
-                                mrl.as_integer()
-                                    .expect("could not parse max_read_bytes")
-                                    as usize
+                                mrl.as_integer().expect_in(
+                                    errors,
+                                    "could not parse max_read_bytes",
+                                ) as usize
                             })
                             .unwrap_or(fl.max_read_bytes);
 
@@ -867,20 +1722,64 @@ fn parse_config_file(buffer: &str) -> Args {
 
         args.statsds = sources.get("statsd").map(|src| {
             let mut statsds = HashMap::default();
-            for (name, tbl) in src.as_table().unwrap().iter() {
+            for (name, tbl) in table(src, "sources.statsd", errors).into_iter().flatten() {
                 let is_enabled = tbl
                     .get("enabled")
                     .unwrap_or(&toml::Value::Boolean(true))
                     .as_bool()
-                    .expect("must be a bool");
+                    .expect_in(errors, "must be a bool");
                 if is_enabled {
                     let mut res = StatsdConfig::default();
                     res.config_path = Some(name.clone());
 
+                    res.transport = tbl
+                        .get("transport")
+                        .map(|t| {
+                            match t.as_str().expect_in(errors, "could not parse statsd transport")
+                            {
+                                "udp" => StatsdTransport::Udp,
+                                "tcp" => StatsdTransport::Tcp,
+                                "unix_datagram" => StatsdTransport::UnixDatagram,
+                                "unix_stream" => StatsdTransport::UnixStream,
+                                other => {
+                                    errors.push(format!(
+                                        "unknown statsd transport {}, expected udp, tcp, unix_datagram or unix_stream",
+                                        other
+                                    ));
+                                    res.transport
+                                }
+                            }
+                        })
+                        .unwrap_or(res.transport);
+
+                    res.path = tbl.get("path").map(|p| {
+                        PathBuf::from(
+                            p.as_str().expect_in(errors, "could not parse statsd path"),
+                        )
+                    });
+                    match res.transport {
+                        StatsdTransport::UnixDatagram | StatsdTransport::UnixStream => {
+                            errors.check(
+                                res.path.is_some(),
+                                "unix statsd transports must have a path",
+                            );
+                        }
+                        StatsdTransport::Udp | StatsdTransport::Tcp => {}
+                    }
+
+                    res.workers = tbl
+                        .get("workers")
+                        .map(|w| {
+                            w.as_integer().expect_in(errors, "could not parse statsd workers")
+                                as usize
+                        })
+                        .unwrap_or(res.workers);
+                    errors.check(res.workers > 0, "statsd workers must be at least 1");
+
                     res.port = tbl
                         .get("port")
                         .map(|p| {
-                            p.as_integer().expect("could not parse statsd port") as u16
+                            p.as_integer().expect_in(errors, "could not parse statsd port") as u16
                         })
                         .unwrap_or(res.port);
 
@@ -888,53 +1787,67 @@ fn parse_config_file(buffer: &str) -> Args {
                         .get("host")
                         .map(|p| {
                             p.as_str()
-                                .expect("could not parse statsd host")
+                                .expect_in(errors, "could not parse statsd host")
                                 .to_string()
                         })
                         .unwrap_or(res.host);
 
                     res.forwards = tbl
                         .get("forwards")
-                        .map(|fwd| {
-                            fwd.as_array()
-                                .expect("forwards must be an array")
-                                .to_vec()
-                                .iter()
-                                .map(|s| s.as_str().unwrap().to_string())
-                                .collect()
-                        })
+                        .map(|fwd| parse_forwards(fwd, errors))
                         .unwrap_or(res.forwards);
 
                     res.parse_config = tbl
                         .get("mapping")
                         .map(|cfg| {
                             let mut masks = Vec::new();
-                            for (_, tbl) in
-                                cfg.as_table().expect("mapping must be a table").iter()
-                            {
+                            let mut sketch_masks = Vec::new();
+                            let mappings = table(cfg, "mapping", errors);
+                            for tbl in mappings.into_iter().flat_map(|tbl| tbl.values()) {
                                 if let Some(mask) = tbl.get("mask") {
-                                    let re = ::regex::Regex::new(
-                                        mask.as_str().expect("mask must be a string"),
-                                    )
-                                    .expect("mask is not a valid regex");
+                                    let re = match ::regex::Regex::new(
+                                        mask.as_str().expect_in(errors, "mask must be a string"),
+                                    ) {
+                                        Ok(re) => re,
+                                        Err(e) => {
+                                            errors.push(format!(
+                                                "mask is not a valid regex: {}",
+                                                e
+                                            ));
+                                            continue;
+                                        }
+                                    };
                                     if let Some(bnds) = tbl.get("bounds") {
                                         let bounds = bnds
                                             .as_array()
-                                            .expect("bounds must be an array")
-                                            .to_vec()
+                                            .map(Vec::as_slice)
+                                            .expect_in(errors, "bounds must be an array")
                                             .iter()
-                                            .map(|v| v.as_float().unwrap())
+                                            .map(|v| {
+                                                v.as_float()
+                                                    .expect_in(errors, "bounds must be floats")
+                                            })
                                             .collect();
                                         masks.insert(0, (re, bounds));
+                                    } else if let Some(acc) =
+                                        tbl.get("relative_accuracy")
+                                    {
+                                        let accuracy = acc
+                                            .as_float()
+                                            .expect_in(errors, "relative_accuracy must be a float");
+                                        sketch_masks.insert(0, (re, accuracy));
                                     } else {
-                                        panic!("mapping must have bounds");
+                                        errors.push(
+                                            "mapping must have bounds or relative_accuracy",
+                                        );
                                     }
                                 } else {
-                                    panic!("mapping must have a mask");
+                                    errors.push("mapping must have a mask");
                                 }
                             }
                             let mut parse_config = StatsdParseConfig::default();
                             parse_config.histogram_masks = masks;
+                            parse_config.sketch_masks = sketch_masks;
 
                             parse_config
                         })
@@ -944,14 +1857,16 @@ fn parse_config_file(buffer: &str) -> Args {
                         .get("summarize_error_bound")
                         .map(|p| {
                             p.as_float()
-                                .expect("summarize_error_bound must be a flaot")
+                                .expect_in(errors, "summarize_error_bound must be a flaot")
                         })
                         .unwrap_or(0.01);
 
                     res.parse_config.summarize_error_bound = error_bound;
 
-                    assert!(res.config_path.is_some());
-                    assert!(!res.forwards.is_empty());
+                    errors.check(
+                        !res.forwards.is_empty(),
+                        &format!("sources.statsd.{} must have forwards", name),
+                    );
 
                     statsds.insert(format!("sources.statsd.{}", name), res);
                 }
@@ -959,49 +1874,374 @@ fn parse_config_file(buffer: &str) -> Args {
             statsds
         });
 
-        args.graphites = sources.get("graphite").map(|src| {
-            let mut graphites = HashMap::default();
-            for (name, tbl) in src.as_table().unwrap().iter() {
+        args.influxdbs = sources.get("influxdb").map(|src| {
+            let mut influxdbs = HashMap::default();
+            for (name, tbl) in table(src, "sources.influxdb", errors).into_iter().flatten() {
                 let is_enabled = tbl
                     .get("enabled")
                     .unwrap_or(&toml::Value::Boolean(true))
                     .as_bool()
-                    .expect("must be a bool");
+                    .expect_in(errors, "must be a bool");
                 if is_enabled {
-                    let mut res = GraphiteConfig::default();
+                    let mut res = InfluxDBServerConfig::default();
                     res.config_path = Some(name.clone());
 
+                    res.transport = tbl
+                        .get("transport")
+                        .map(|t| {
+                            match t.as_str().expect_in(errors, "could not parse influxdb transport")
+                            {
+                                "udp" => InfluxDBTransport::Udp,
+                                "tcp" => InfluxDBTransport::Tcp,
+                                "http" => InfluxDBTransport::Http,
+                                other => {
+                                    errors.push(format!(
+                                        "unknown influxdb transport {}, expected udp, tcp or http",
+                                        other
+                                    ));
+                                    res.transport
+                                }
+                            }
+                        })
+                        .unwrap_or(res.transport);
+
+                    // InfluxDB conventionally takes its UDP listener on 8089,
+                    // HTTP on 8086.
+                    let default_port = match res.transport {
+                        InfluxDBTransport::Http => res.port,
+                        InfluxDBTransport::Udp | InfluxDBTransport::Tcp => 8089,
+                    };
                     res.port = tbl
                         .get("port")
                         .map(|p| {
-                            p.as_integer().expect("could not parse graphite port")
+                            p.as_integer().expect_in(errors, "could not parse influxdb port")
                                 as u16
                         })
-                        .unwrap_or(res.port);
+                        .unwrap_or(default_port);
+
+                    res.workers = tbl
+                        .get("workers")
+                        .map(|w| {
+                            w.as_integer().expect_in(errors, "could not parse influxdb workers")
+                                as usize
+                        })
+                        .unwrap_or(res.workers);
+                    errors.check(res.workers > 0, "influxdb workers must be at least 1");
 
                     res.host = tbl
                         .get("host")
                         .map(|p| {
                             p.as_str()
-                                .expect("could not parse graphite host")
+                                .expect_in(errors, "could not parse influxdb host")
                                 .to_string()
                         })
                         .unwrap_or(res.host);
 
+                    res.precision = tbl
+                        .get("precision")
+                        .map(|p| {
+                            let p = p.as_str().expect_in(errors, "could not parse influxdb precision");
+                            Precision::parse(p).unwrap_or_else(|| {
+                                errors.push(format!("unknown influxdb precision {}", p));
+                                res.precision
+                            })
+                        })
+                        .unwrap_or(res.precision);
+
                     res.forwards = tbl
                         .get("forwards")
-                        .map(|fwd| {
-                            fwd.as_array()
-                                .expect("forwards must be an array")
-                                .to_vec()
+                        .map(|fwd| parse_forwards(fwd, errors))
+                        .unwrap_or(res.forwards);
+
+                    errors.check(
+                        !res.forwards.is_empty(),
+                        &format!("sources.influxdb.{} must have forwards", name),
+                    );
+
+                    influxdbs.insert(format!("sources.influxdb.{}", name), res);
+                }
+            }
+            influxdbs
+        });
+
+        args.prometheus_scrapes = sources.get("prometheus").map(|src| {
+            let mut scrapes = HashMap::default();
+            for (name, tbl) in table(src, "sources.prometheus", errors)
+                .into_iter()
+                .flatten()
+            {
+                let is_enabled = tbl
+                    .get("enabled")
+                    .unwrap_or(&toml::Value::Boolean(true))
+                    .as_bool()
+                    .expect_in(errors, "must be a bool");
+                if is_enabled {
+                    let mut res = PrometheusScrapeConfig::default();
+                    res.config_path = Some(name.clone());
+
+                    res.targets = tbl
+                        .get("targets")
+                        .map(|targets| {
+                            targets
+                                .as_array()
+                                .map(Vec::as_slice)
+                                .expect_in(errors, "targets must be an array")
                                 .iter()
-                                .map(|s| s.as_str().unwrap().to_string())
+                                .map(|t| {
+                                    t.as_str()
+                                        .expect_in(errors, "targets must be strings")
+                                        .to_string()
+                                })
                                 .collect()
                         })
+                        .unwrap_or(res.targets);
+
+                    res.scrape_interval = tbl
+                        .get("scrape_interval")
+                        .map(|i| {
+                            i.as_integer().expect_in(
+                                errors,
+                                "could not parse prometheus scrape_interval",
+                            ) as u64
+                        })
+                        .unwrap_or(res.scrape_interval);
+
+                    res.scrape_timeout = tbl
+                        .get("scrape_timeout")
+                        .map(|i| {
+                            i.as_integer().expect_in(
+                                errors,
+                                "could not parse prometheus scrape_timeout",
+                            ) as u64
+                        })
+                        .unwrap_or(res.scrape_timeout);
+
+                    res.forwards = tbl
+                        .get("forwards")
+                        .map(|fwd| parse_forwards(fwd, errors))
+                        .unwrap_or(res.forwards);
+
+                    errors.check(
+                        !res.forwards.is_empty(),
+                        &format!("sources.prometheus.{} must have forwards", name),
+                    );
+                    errors.check(
+                        !res.targets.is_empty(),
+                        "prometheus sources must have targets",
+                    );
+                    errors.check(
+                        res.scrape_interval > 0,
+                        "prometheus scrape_interval must be at least 1",
+                    );
+
+                    scrapes.insert(format!("sources.prometheus.{}", name), res);
+                }
+            }
+            scrapes
+        });
+
+        args.prometheus_remote_writes =
+            sources.get("prometheus_remote_write").map(|src| {
+                let mut receivers = HashMap::default();
+                for (name, tbl) in table(src, "sources.prometheus_remote_write", errors).into_iter().flatten() {
+                    let is_enabled = tbl
+                        .get("enabled")
+                        .unwrap_or(&toml::Value::Boolean(true))
+                        .as_bool()
+                        .expect_in(errors, "must be a bool");
+                    if is_enabled {
+                        let mut res = PrometheusRemoteWriteServerConfig::default();
+                        res.config_path = Some(name.clone());
+
+                        res.port = tbl
+                            .get("port")
+                            .map(|p| {
+                                p.as_integer().expect_in(errors,
+                                    "could not parse prometheus_remote_write port",
+                                ) as u16
+                            })
+                            .unwrap_or(res.port);
+
+                        res.host = tbl
+                            .get("host")
+                            .map(|p| {
+                                p.as_str()
+                                    .expect_in(errors,
+                                        "could not parse prometheus_remote_write host",
+                                    )
+                                    .to_string()
+                            })
+                            .unwrap_or(res.host);
+
+                        res.path = tbl
+                            .get("path")
+                            .map(|p| {
+                                p.as_str()
+                                    .expect_in(errors,
+                                        "could not parse prometheus_remote_write path",
+                                    )
+                                    .to_string()
+                            })
+                            .unwrap_or(res.path);
+
+                        res.forwards = tbl
+                            .get("forwards")
+                            .map(|fwd| parse_forwards(fwd, errors))
+                            .unwrap_or(res.forwards);
+
+                        errors.check(
+                            !res.forwards.is_empty(),
+                            &format!("sources.prometheus_remote_write.{} must have forwards", name),
+                        );
+
+                        receivers.insert(
+                            format!("sources.prometheus_remote_write.{}", name),
+                            res,
+                        );
+                    }
+                }
+                receivers
+            });
+
+        args.syslogs = sources.get("syslog").map(|src| {
+            let mut syslogs = HashMap::default();
+            for (name, tbl) in table(src, "sources.syslog", errors).into_iter().flatten() {
+                let is_enabled = tbl
+                    .get("enabled")
+                    .unwrap_or(&toml::Value::Boolean(true))
+                    .as_bool()
+                    .expect_in(errors, "must be a bool");
+                if is_enabled {
+                    let mut res = SyslogConfig::default();
+                    res.config_path = Some(name.clone());
+
+                    res.transport = tbl
+                        .get("transport")
+                        .map(|t| {
+                            match t.as_str().expect_in(errors, "could not parse syslog transport")
+                            {
+                                "udp" => SyslogTransport::Udp,
+                                "tcp" => SyslogTransport::Tcp,
+                                "unix_datagram" => SyslogTransport::UnixDatagram,
+                                "unix_stream" => SyslogTransport::UnixStream,
+                                other => {
+                                    errors.push(format!(
+                                        "unknown syslog transport {}, expected udp, tcp, unix_datagram or unix_stream",
+                                        other
+                                    ));
+                                    res.transport
+                                }
+                            }
+                        })
+                        .unwrap_or(res.transport);
+
+                    res.path = tbl.get("path").map(|p| {
+                        PathBuf::from(p.as_str().expect_in(errors, "could not parse syslog path"))
+                    });
+                    match res.transport {
+                        SyslogTransport::UnixDatagram | SyslogTransport::UnixStream => {
+                            errors.check(
+                                res.path.is_some(),
+                                "unix syslog transports must have a path",
+                            );
+                        }
+                        SyslogTransport::Udp | SyslogTransport::Tcp => {}
+                    }
+
+                    res.port = tbl
+                        .get("port")
+                        .map(|p| {
+                            p.as_integer().expect_in(errors, "could not parse syslog port") as u16
+                        })
+                        .unwrap_or(res.port);
+
+                    res.host = tbl
+                        .get("host")
+                        .map(|p| {
+                            p.as_str()
+                                .expect_in(errors, "could not parse syslog host")
+                                .to_string()
+                        })
+                        .unwrap_or(res.host);
+
+                    res.forwards = tbl
+                        .get("forwards")
+                        .map(|fwd| parse_forwards(fwd, errors))
+                        .unwrap_or(res.forwards);
+
+                    errors.check(
+                        !res.forwards.is_empty(),
+                        &format!("sources.syslog.{} must have forwards", name),
+                    );
+
+                    syslogs.insert(format!("sources.syslog.{}", name), res);
+                }
+            }
+            syslogs
+        });
+
+        args.graphites = sources.get("graphite").map(|src| {
+            let mut graphites = HashMap::default();
+            for (name, tbl) in table(src, "sources.graphite", errors).into_iter().flatten() {
+                let is_enabled = tbl
+                    .get("enabled")
+                    .unwrap_or(&toml::Value::Boolean(true))
+                    .as_bool()
+                    .expect_in(errors, "must be a bool");
+                if is_enabled {
+                    let mut res = GraphiteConfig::default();
+                    res.config_path = Some(name.clone());
+
+                    res.protocol = tbl
+                        .get("protocol")
+                        .map(|p| {
+                            match p.as_str().expect_in(errors, "could not parse graphite protocol")
+                            {
+                                "plaintext" => GraphiteProtocol::Plaintext,
+                                "pickle" => GraphiteProtocol::Pickle,
+                                other => {
+                                    errors.push(format!(
+                                        "unknown graphite protocol {}, expected plaintext or pickle",
+                                        other
+                                    ));
+                                    res.protocol
+                                }
+                            }
+                        })
+                        .unwrap_or(res.protocol);
+
+                    // Carbon conventionally listens for pickle on the port
+                    // after plaintext.
+                    let default_port = match res.protocol {
+                        GraphiteProtocol::Plaintext => res.port,
+                        GraphiteProtocol::Pickle => 2004,
+                    };
+                    res.port = tbl
+                        .get("port")
+                        .map(|p| {
+                            p.as_integer().expect_in(errors, "could not parse graphite port")
+                                as u16
+                        })
+                        .unwrap_or(default_port);
+
+                    res.host = tbl
+                        .get("host")
+                        .map(|p| {
+                            p.as_str()
+                                .expect_in(errors, "could not parse graphite host")
+                                .to_string()
+                        })
+                        .unwrap_or(res.host);
+
+                    res.forwards = tbl
+                        .get("forwards")
+                        .map(|fwd| parse_forwards(fwd, errors))
                         .unwrap_or(res.forwards);
 
-                    assert!(res.config_path.is_some());
-                    assert!(!res.forwards.is_empty());
+                    errors.check(
+                        !res.forwards.is_empty(),
+                        &format!("sources.graphite.{} must have forwards", name),
+                    );
 
                     graphites.insert(format!("sources.graphite.{}", name), res);
                 }
@@ -1011,12 +2251,13 @@ fn parse_config_file(buffer: &str) -> Args {
 
         args.avros = sources.get("avro").map(|src| {
             let mut avros = HashMap::default();
-            for (name, tbl) in src.as_table().unwrap().iter() {
+            for (name, tbl) in table(src, "sources.avro", errors).into_iter().flatten()
+            {
                 let is_enabled = tbl
                     .get("enabled")
                     .unwrap_or(&toml::Value::Boolean(true))
                     .as_bool()
-                    .expect("must be a bool");
+                    .expect_in(errors, "must be a bool");
                 if is_enabled {
                     let mut res = TCPConfig::default();
                     res.config_path = Some(name.clone());
@@ -1027,31 +2268,30 @@ fn parse_config_file(buffer: &str) -> Args {
                     res.port = tbl
                         .get("port")
                         .map(|p| {
-                            p.as_integer().expect("could not parse avro port") as u16
+                            p.as_integer()
+                                .expect_in(errors, "could not parse avro port")
+                                as u16
                         })
                         .unwrap_or(2002);
 
                     res.host = tbl
                         .get("host")
                         .map(|p| {
-                            p.as_str().expect("could not parse avro host").to_string()
+                            p.as_str()
+                                .expect_in(errors, "could not parse avro host")
+                                .to_string()
                         })
                         .unwrap_or(res.host);
 
                     res.forwards = tbl
                         .get("forwards")
-                        .map(|fwd| {
-                            fwd.as_array()
-                                .expect("forwards must be an array")
-                                .to_vec()
-                                .iter()
-                                .map(|s| s.as_str().unwrap().to_string())
-                                .collect()
-                        })
+                        .map(|fwd| parse_forwards(fwd, errors))
                         .unwrap_or(res.forwards);
 
-                    assert!(res.config_path.is_some());
-                    assert!(!res.forwards.is_empty());
+                    errors.check(
+                        !res.forwards.is_empty(),
+                        &format!("sources.avro.{} must have forwards", name),
+                    );
 
                     avros.insert(format!("sources.avro.{}", name), res);
                 }
@@ -1061,12 +2301,14 @@ fn parse_config_file(buffer: &str) -> Args {
 
         args.native_server_config = sources.get("native").map(|src| {
             let mut native_server_config = HashMap::default();
-            for (name, tbl) in src.as_table().unwrap().iter() {
+            for (name, tbl) in
+                table(src, "sources.native", errors).into_iter().flatten()
+            {
                 let is_enabled = tbl
                     .get("enabled")
                     .unwrap_or(&toml::Value::Boolean(true))
                     .as_bool()
-                    .expect("must be a bool");
+                    .expect_in(errors, "must be a bool");
                 if is_enabled {
                     let mut res = NativeServerConfig::default();
                     res.config_path = Some(format!("sources.native.{}", name));
@@ -1074,31 +2316,26 @@ fn parse_config_file(buffer: &str) -> Args {
                     res.port = tbl
                         .get("port")
                         .map(|p| {
-                            p.as_integer().expect("could not parse native port") as u16
+                            p.as_integer()
+                                .expect_in(errors, "could not parse native port")
+                                as u16
                         })
                         .unwrap_or(res.port);
 
                     res.ip = tbl
                         .get("ip")
                         .map(|p| {
-                            p.as_str().expect("could not parse native ip").to_string()
+                            p.as_str()
+                                .expect_in(errors, "could not parse native ip")
+                                .to_string()
                         })
                         .unwrap_or(res.ip);
 
                     res.forwards = tbl
                         .get("forwards")
-                        .map(|fwd| {
-                            fwd.as_array()
-                                .expect("forwards must be an array")
-                                .to_vec()
-                                .iter()
-                                .map(|s| s.as_str().unwrap().to_string())
-                                .collect()
-                        })
+                        .map(|fwd| parse_forwards(fwd, errors))
                         .unwrap_or(res.forwards);
 
-                    assert!(res.config_path.is_some());
-
                     native_server_config
                         .insert(format!("sources.native.{}", name), res);
                 }
@@ -1114,14 +2351,7 @@ fn parse_config_file(buffer: &str) -> Args {
 
                 res.forwards = src
                     .get("forwards")
-                    .map(|fwd| {
-                        fwd.as_array()
-                            .expect("forwards must be an array")
-                            .to_vec()
-                            .iter()
-                            .map(|s| s.as_str().unwrap().to_string())
-                            .collect()
-                    })
+                    .map(|fwd| parse_forwards(fwd, errors))
                     .unwrap_or(res.forwards);
 
                 res
@@ -1140,6 +2370,244 @@ mod test {
     // use rusoto::Region;
     // use std::path::{Path, PathBuf};
 
+    #[test]
+    fn config_try_parse_config() {
+        use std::io::Write;
+        let dir = tempdir::TempDir::new("cernan_config").unwrap();
+
+        let good = dir.path().join("good.toml");
+        File::create(&good)
+            .unwrap()
+            .write_all(b"data-directory = \"/foo/bar\"\n")
+            .unwrap();
+        let args = try_parse_config(good.to_str().unwrap()).unwrap();
+        assert_eq!(args.data_directory, Path::new("/foo/bar").to_path_buf());
+
+        let bad = dir.path().join("bad.toml");
+        File::create(&bad)
+            .unwrap()
+            .write_all(b"[sinks.influxdb]\nport = \"eighty\"\nsecure = 1\n")
+            .unwrap();
+        let err = try_parse_config(bad.to_str().unwrap()).unwrap_err();
+        assert_eq!(err.len(), 2);
+        assert!(err.iter().all(|e| e.starts_with(bad.to_str().unwrap())));
+        assert!(err.iter().any(|e| e.contains("sinks.influxdb.port")));
+        assert!(err.iter().any(|e| e.contains("sinks.influxdb.secure")));
+
+        let missing = dir.path().join("missing.toml");
+        assert!(try_parse_config(missing.to_str().unwrap()).is_err());
+    }
+
+    #[test]
+    fn config_interpolate() {
+        use std::io::Write;
+        let dir = tempdir::TempDir::new("cernan_interpolate").unwrap();
+        let secret = dir.path().join("secret");
+        File::create(&secret)
+            .unwrap()
+            .write_all(b"hunter2\n")
+            .unwrap();
+        let password = dir.path().join("password");
+        File::create(&password)
+            .unwrap()
+            .write_all(b"a \"quoted\" \\ pass\nword\n")
+            .unwrap();
+        env::set_var("CERNAN_TEST_INTERPOLATE_HOST", "example.com");
+        env::set_var("CERNAN_TEST_INTERPOLATE_EMPTY", "");
+        env::remove_var("CERNAN_TEST_INTERPOLATE_UNSET");
+
+        let config = format!(
+            r#"
+# ${{CERNAN_TEST_INTERPOLATE_UNSET}} is left alone in comments
+[sinks.influxdb]
+host = "${{CERNAN_TEST_INTERPOLATE_HOST}}"
+port = ${{CERNAN_TEST_INTERPOLATE_UNSET:-8089}} # ${{CERNAN_TEST_INTERPOLATE_UNSET}}
+db = "${{CERNAN_TEST_INTERPOLATE_EMPTY:-cernan}}"
+
+[tags]
+secret = "${{file:{}}}"
+password = "${{file:{}}}"
+literal = "$${{HOME}} costs $5"
+"#,
+            secret.display(),
+            password.display()
+        );
+        let args = parse_config_file(&config);
+        let influxdb = args.influxdb.unwrap().remove("sinks.influxdb").unwrap();
+        assert_eq!(influxdb.host, "example.com");
+        assert_eq!(influxdb.port, 8089);
+        assert_eq!(influxdb.db, "cernan");
+        assert_eq!(influxdb.tags.get("secret"), Some(&"hunter2".to_string()));
+        assert_eq!(
+            influxdb.tags.get("password"),
+            Some(&"a \"quoted\" \\ pass\nword".to_string())
+        );
+        assert_eq!(
+            influxdb.tags.get("literal"),
+            Some(&"${HOME} costs $5".to_string())
+        );
+
+        let interpolated = |s: &str| interpolate(&mut toml::Value::String(s.into()));
+        assert!(interpolated("${CERNAN_TEST_INTERPOLATE_UNSET}").is_err());
+        assert!(interpolated("${CERNAN_TEST_INTERPOLATE_HOST").is_err());
+        assert!(interpolated("${}").is_err());
+        assert!(interpolated("${file:/does/not/exist}").is_err());
+        assert!(parse_toml("port = ${CERNAN_TEST_INTERPOLATE_UNSET}").is_err());
+        // An unquoted reference may not smuggle in anything but a literal.
+        assert!(parse_toml("port = ${CERNAN_TEST_INTERPOLATE_HOST}").is_err());
+    }
+
+    #[test]
+    fn config_include() {
+        use std::io::Write;
+        let dir = tempdir::TempDir::new("cernan_include").unwrap();
+        let confd = dir.path().join("conf.d");
+        std::fs::create_dir(&confd).unwrap();
+        let write = |path: &Path, contents: &str| {
+            File::create(path)
+                .unwrap()
+                .write_all(contents.as_bytes())
+                .unwrap();
+        };
+
+        let main = dir.path().join("cernan.toml");
+        write(
+            &main,
+            r#"
+include = ["conf.d/*.toml"]
+
+[sources]
+  [sources.statsd.primary]
+  port = 8125
+  forwards = ["sinks.console"]
+
+[sinks]
+  [sinks.console]
+"#,
+        );
+        write(
+            &confd.join("a.toml"),
+            r#"
+[sources.statsd.team_a]
+port = 8126
+forwards = ["sinks.null"]
+
+[sinks.null]
+"#,
+        );
+        write(
+            &confd.join("b.toml"),
+            r#"
+[sources.graphite.team_b]
+port = 2004
+forwards = ["sinks.null"]
+"#,
+        );
+        let args = try_parse_config(main.to_str().unwrap()).unwrap();
+        let statsds = args.statsds.unwrap();
+        assert!(statsds.contains_key("sources.statsd.primary"));
+        assert!(statsds.contains_key("sources.statsd.team_a"));
+        assert!(args
+            .graphites
+            .unwrap()
+            .contains_key("sources.graphite.team_b"));
+        assert!(args.null.unwrap().contains_key("sinks.null"));
+        assert!(args.console.unwrap().contains_key("sinks.console"));
+
+        write(
+            &confd.join("c.toml"),
+            r#"
+[sources.statsd.primary]
+port = 8127
+"#,
+        );
+        let err = try_parse_config(main.to_str().unwrap()).unwrap_err();
+        assert!(err[0].contains("c.toml"));
+        assert!(err[0].contains("duplicate node sources.statsd.primary"));
+        std::fs::remove_file(confd.join("c.toml")).unwrap();
+
+        write(&confd.join("d.toml"), "[sinks.console]\nbin_width = 2\n");
+        let err = try_parse_config(main.to_str().unwrap()).unwrap_err();
+        assert!(err[0].contains("duplicate node sinks.console"));
+        std::fs::remove_file(confd.join("d.toml")).unwrap();
+
+        write(&confd.join("e.toml"), "data-directory = \"/foo\"\n");
+        assert!(try_parse_config(main.to_str().unwrap()).is_err());
+    }
+
+    #[test]
+    fn config_file_admin() {
+        let args = parse_config_file("");
+        assert!(args.admin.is_none());
+
+        let config = r#"
+[admin]
+port = 9000
+flush-stalled-threshold = 600
+max-queue-backlog = 1048576
+"#;
+        let admin = parse_config_file(config).admin.unwrap();
+        assert_eq!(admin.host, "localhost");
+        assert_eq!(admin.port, 9000);
+        assert_eq!(admin.valve_closed_threshold, 60);
+        assert_eq!(admin.flush_stalled_threshold, 600);
+        assert_eq!(admin.max_queue_backlog, Some(1_048_576));
+    }
+
+    #[test]
+    fn config_file_overflow() {
+        use crate::metric::{Event, LogLine, Telemetry};
+        use crate::util::EventClass;
+
+        let config = r#"
+[sources]
+  [sources.internal]
+  forwards = ["sinks.console"]
+  overflow = "block"
+
+  [sources.statsd.primary]
+  forwards = ["sinks.console", "sinks.null"]
+  overflow = { "sinks.null" = "shed:log, raw" }
+
+  [sources.files.foo]
+  path = "/tmp/foo.log"
+  forwards = ["sinks.console"]
+
+[filters]
+  [filters.delay.two_seconds]
+  tolerance = 2
+  forwards = ["sinks.console"]
+  overflow = "drop-newest"
+"#;
+        let args = parse_config_file(config);
+        assert_eq!(args.overflow.len(), 3);
+        assert_eq!(
+            args.overflow["sources.internal"]["sinks.console"],
+            Overflow::Block
+        );
+        let primary = &args.overflow["sources.statsd.primary"];
+        assert!(primary.get("sinks.console").is_none());
+        assert_eq!(
+            primary["sinks.null"],
+            Overflow::Shed(vec![EventClass::Log, EventClass::Raw])
+        );
+        assert_eq!(
+            args.overflow["filters.delay.two_seconds"]["sinks.console"],
+            Overflow::DropNewest
+        );
+
+        let telem = Event::new_telemetry(
+            Telemetry::new().name("a").value(1.0).harden().unwrap(),
+        );
+        let line = Event::new_log(LogLine::new("a", "hello"));
+        assert!(!primary["sinks.null"].drops(&telem));
+        assert!(primary["sinks.null"].drops(&line));
+        assert!(!primary["sinks.null"].drops(&Event::Shutdown));
+
+        assert!("shed:metrics".parse::<Overflow>().is_err());
+        assert!("drop-oldest".parse::<Overflow>().is_err());
+    }
+
     #[test]
     fn config_file_data_directory() {
         let config = r#"
@@ -1256,7 +2724,11 @@ scripts-directory = "/foo/bar"
         let args = parse_config_file(config);
 
         assert!(args.elasticsearch.is_some());
-        let es = args.elasticsearch.unwrap();
+        let es = args
+            .elasticsearch
+            .unwrap()
+            .remove("sinks.elasticsearch")
+            .unwrap();
 
         assert_eq!(es.port, 1234);
         assert_eq!(es.host, "example.com");
@@ -1288,7 +2760,7 @@ scripts-directory = "/foo/bar"
         assert!(args.kafkas.is_some());
         let kafkas = args.kafkas.unwrap();
         assert_eq!(kafkas.len(), 1);
-        let expected_librdkafka_config: HashMap<String, String> = vec![
+        let expected_librdkafka_config: BTreeMap<String, String> = vec![
             (String::from("setting.one"), String::from("1")),
             (String::from("setting.two"), String::from("2")),
             (String::from("setting.three"), String::from("three")),
@@ -1381,7 +2853,11 @@ scripts-directory = "/foo/bar"
         let args = parse_config_file(config);
 
         assert!(args.native_sink_config.is_some());
-        let native_sink_config = args.native_sink_config.unwrap();
+        let native_sink_config = args
+            .native_sink_config
+            .unwrap()
+            .remove("sinks.native")
+            .unwrap();
         assert_eq!(native_sink_config.host, String::from("foo.example.com"));
         assert_eq!(native_sink_config.port, 1972);
         assert_eq!(
@@ -1415,6 +2891,254 @@ scripts-directory = "/foo/bar"
         );
     }
 
+    #[test]
+    fn config_statsd_sources_transports() {
+        let config = r#"
+[sources]
+  [sources.statsd.udp]
+  forwards = ["sinks.console"]
+
+  [sources.statsd.tcp]
+  transport = "tcp"
+  port = 8126
+  forwards = ["sinks.console"]
+
+  [sources.statsd.dgram]
+  transport = "unix_datagram"
+  path = "/var/run/cernan/statsd.sock"
+  forwards = ["sinks.console"]
+
+  [sources.statsd.stream]
+  transport = "unix_stream"
+  path = "/var/run/cernan/statsd_stream.sock"
+  forwards = ["sinks.console"]
+"#;
+
+        let args = parse_config_file(config);
+
+        assert!(args.statsds.is_some());
+        let statsds = args.statsds.unwrap();
+
+        let udp = statsds.get("sources.statsd.udp").unwrap();
+        assert_eq!(udp.transport, StatsdTransport::Udp);
+        assert_eq!(udp.port, 8125);
+        assert_eq!(udp.path, None);
+
+        let tcp = statsds.get("sources.statsd.tcp").unwrap();
+        assert_eq!(tcp.transport, StatsdTransport::Tcp);
+        assert_eq!(tcp.port, 8126);
+
+        let dgram = statsds.get("sources.statsd.dgram").unwrap();
+        assert_eq!(dgram.transport, StatsdTransport::UnixDatagram);
+        assert_eq!(
+            dgram.path,
+            Some(PathBuf::from("/var/run/cernan/statsd.sock"))
+        );
+
+        let stream = statsds.get("sources.statsd.stream").unwrap();
+        assert_eq!(stream.transport, StatsdTransport::UnixStream);
+        assert_eq!(
+            stream.path,
+            Some(PathBuf::from("/var/run/cernan/statsd_stream.sock"))
+        );
+    }
+
+    #[test]
+    fn config_statsd_sources_workers() {
+        let config = r#"
+[sources]
+  [sources.statsd.single]
+  forwards = ["sinks.console"]
+
+  [sources.statsd.parallel]
+  port = 8126
+  workers = 4
+  forwards = ["sinks.console"]
+"#;
+
+        let args = parse_config_file(config);
+
+        assert!(args.statsds.is_some());
+        let statsds = args.statsds.unwrap();
+
+        let single = statsds.get("sources.statsd.single").unwrap();
+        assert_eq!(single.workers, 1);
+
+        let parallel = statsds.get("sources.statsd.parallel").unwrap();
+        assert_eq!(parallel.workers, 4);
+    }
+
+    #[test]
+    fn config_statsd_sources_unix_requires_path() {
+        let config = r#"
+[sources]
+  [sources.statsd.dgram]
+  transport = "unix_datagram"
+  forwards = ["sinks.console"]
+"#;
+
+        let value = parse_toml(config).unwrap();
+        let err = parse_config_value(&value).unwrap_err();
+        assert_eq!(
+            err,
+            vec!["unix statsd transports must have a path".to_string()]
+        );
+    }
+
+    #[test]
+    fn config_influxdb_sources() {
+        let config = r#"
+[sources]
+  [sources.influxdb.http]
+  forwards = ["sinks.console"]
+
+  [sources.influxdb.udp]
+  transport = "udp"
+  precision = "s"
+  workers = 2
+  forwards = ["sinks.console"]
+
+  [sources.influxdb.tcp]
+  transport = "tcp"
+  host = "0.0.0.0"
+  port = 9000
+  forwards = ["sinks.console"]
+"#;
+
+        let args = parse_config_file(config);
+
+        assert!(args.influxdbs.is_some());
+        let influxdbs = args.influxdbs.unwrap();
+
+        let http = influxdbs.get("sources.influxdb.http").unwrap();
+        assert_eq!(http.transport, InfluxDBTransport::Http);
+        assert_eq!(http.host, "localhost");
+        assert_eq!(http.port, 8086);
+        assert_eq!(http.precision, Precision::Nanoseconds);
+        assert_eq!(http.workers, 1);
+
+        let udp = influxdbs.get("sources.influxdb.udp").unwrap();
+        assert_eq!(udp.transport, InfluxDBTransport::Udp);
+        assert_eq!(udp.port, 8089);
+        assert_eq!(udp.precision, Precision::Seconds);
+        assert_eq!(udp.workers, 2);
+
+        let tcp = influxdbs.get("sources.influxdb.tcp").unwrap();
+        assert_eq!(tcp.transport, InfluxDBTransport::Tcp);
+        assert_eq!(tcp.host, "0.0.0.0");
+        assert_eq!(tcp.port, 9000);
+    }
+
+    #[test]
+    fn config_prometheus_sources() {
+        let config = r#"
+[sources]
+  [sources.prometheus.node]
+  targets = ["http://localhost:9100/metrics", "http://10.1.1.2:9100/metrics"]
+  scrape_interval = 15
+  forwards = ["sinks.console"]
+
+  [sources.prometheus.app]
+  targets = ["http://localhost:8080/metrics"]
+  forwards = ["sinks.console"]
+"#;
+
+        let args = parse_config_file(config);
+
+        assert!(args.prometheus_scrapes.is_some());
+        let scrapes = args.prometheus_scrapes.unwrap();
+
+        let node = scrapes.get("sources.prometheus.node").unwrap();
+        assert_eq!(
+            node.targets,
+            vec![
+                "http://localhost:9100/metrics".to_string(),
+                "http://10.1.1.2:9100/metrics".to_string()
+            ]
+        );
+        assert_eq!(node.scrape_interval, 15);
+        assert_eq!(node.scrape_timeout, 10);
+
+        let app = scrapes.get("sources.prometheus.app").unwrap();
+        assert_eq!(app.scrape_interval, 60);
+        assert_eq!(app.forwards, vec!["sinks.console".to_string()]);
+    }
+
+    #[test]
+    fn config_prometheus_remote_write_sources() {
+        let config = r#"
+[sources]
+  [sources.prometheus_remote_write.edge]
+  host = "0.0.0.0"
+  port = 9999
+  path = "/receive"
+  forwards = ["sinks.console"]
+
+  [sources.prometheus_remote_write.defaults]
+  forwards = ["sinks.console"]
+"#;
+
+        let args = parse_config_file(config);
+
+        assert!(args.prometheus_remote_writes.is_some());
+        let receivers = args.prometheus_remote_writes.unwrap();
+
+        let edge = receivers
+            .get("sources.prometheus_remote_write.edge")
+            .unwrap();
+        assert_eq!(edge.host, "0.0.0.0");
+        assert_eq!(edge.port, 9999);
+        assert_eq!(edge.path, "/receive");
+        assert_eq!(edge.config_path, Some("edge".to_string()));
+
+        let defaults = receivers
+            .get("sources.prometheus_remote_write.defaults")
+            .unwrap();
+        assert_eq!(defaults.host, "localhost");
+        assert_eq!(defaults.port, 9201);
+        assert_eq!(defaults.path, "/api/v1/write");
+    }
+
+    #[test]
+    fn config_syslog_sources() {
+        let config = r#"
+[sources]
+  [sources.syslog.network]
+  forwards = ["sinks.console"]
+
+  [sources.syslog.relay]
+  transport = "tcp"
+  host = "0.0.0.0"
+  port = 601
+  forwards = ["sinks.console"]
+
+  [sources.syslog.devlog]
+  transport = "unix_datagram"
+  path = "/dev/log"
+  forwards = ["sinks.console"]
+"#;
+
+        let args = parse_config_file(config);
+
+        assert!(args.syslogs.is_some());
+        let syslogs = args.syslogs.unwrap();
+
+        let network = syslogs.get("sources.syslog.network").unwrap();
+        assert_eq!(network.transport, SyslogTransport::Udp);
+        assert_eq!(network.host, "localhost");
+        assert_eq!(network.port, 514);
+        assert_eq!(network.forwards, vec!["sinks.console".to_string()]);
+
+        let relay = syslogs.get("sources.syslog.relay").unwrap();
+        assert_eq!(relay.transport, SyslogTransport::Tcp);
+        assert_eq!(relay.host, "0.0.0.0");
+        assert_eq!(relay.port, 601);
+
+        let devlog = syslogs.get("sources.syslog.devlog").unwrap();
+        assert_eq!(devlog.transport, SyslogTransport::UnixDatagram);
+        assert_eq!(devlog.path, Some(PathBuf::from("/dev/log")));
+    }
+
     #[test]
     fn config_statsd_sources_histogram_mappings() {
         let config = r#"
@@ -1457,6 +3181,39 @@ scripts-directory = "/foo/bar"
         );
     }
 
+    #[test]
+    fn config_statsd_sources_sketch_mapping() {
+        let config = r#"
+[sources]
+  [sources.statsd.primary]
+  enabled = true
+  forwards = ["sinks.null"]
+
+  [sources.statsd.primary.mapping]
+  [sources.statsd.primary.mapping.foo]
+  mask = "foo.*"
+  bounds = [0.0, 1.0, 10.0]
+
+  [sources.statsd.primary.mapping.latency]
+  mask = ".*latency$"
+  relative_accuracy = 0.005
+ "#;
+
+        let args = parse_config_file(config);
+
+        assert!(args.statsds.is_some());
+        let statsds = args.statsds.unwrap();
+
+        let config0 = statsds.get("sources.statsd.primary").unwrap();
+        assert_eq!(config0.parse_config.histogram_masks.len(), 1);
+        assert_eq!(config0.parse_config.sketch_masks.len(), 1);
+        assert_eq!(
+            config0.parse_config.sketch_masks[0].0.as_str(),
+            ".*latency$"
+        );
+        assert_eq!(config0.parse_config.sketch_masks[0].1, 0.005);
+    }
+
     #[test]
     fn config_statsd_sources_error_bound() {
         let config = r#"
@@ -1563,6 +3320,41 @@ scripts-directory = "/foo/bar"
         assert_eq!(config1.forwards, vec!["sinks.wavefront".to_string()]);
     }
 
+    #[test]
+    fn config_graphite_sources_pickle() {
+        let config = r#"
+[sources]
+  [sources.graphite.plain]
+  forwards = ["sinks.null"]
+
+  [sources.graphite.pickled]
+  protocol = "pickle"
+  forwards = ["sinks.null"]
+
+  [sources.graphite.pickled_port]
+  protocol = "pickle"
+  port = 2104
+  forwards = ["sinks.null"]
+"#;
+
+        let args = parse_config_file(config);
+
+        let graphites = args.graphites.unwrap();
+        assert_eq!(graphites.len(), 3);
+
+        let config0 = graphites.get("sources.graphite.plain").unwrap();
+        assert_eq!(config0.protocol, GraphiteProtocol::Plaintext);
+        assert_eq!(config0.port, 2003);
+
+        let config1 = graphites.get("sources.graphite.pickled").unwrap();
+        assert_eq!(config1.protocol, GraphiteProtocol::Pickle);
+        assert_eq!(config1.port, 2004);
+
+        let config2 = graphites.get("sources.graphite.pickled_port").unwrap();
+        assert_eq!(config2.protocol, GraphiteProtocol::Pickle);
+        assert_eq!(config2.port, 2104);
+    }
+
     #[test]
     fn config_avro_sources_style() {
         let config = r#"
@@ -1738,7 +3530,7 @@ scripts-directory = "/foo/bar"
         let args = parse_config_file(config);
 
         assert!(args.wavefront.is_some());
-        let wavefront = args.wavefront.unwrap();
+        let wavefront = args.wavefront.unwrap().remove("sinks.wavefront").unwrap();
         assert_eq!(wavefront.host, String::from("example.com"));
         assert_eq!(wavefront.port, 3131);
         assert_eq!(wavefront.bin_width, 9);
@@ -1764,7 +3556,7 @@ scripts-directory = "/foo/bar"
         let args = parse_config_file(config);
 
         assert!(args.wavefront.is_some());
-        let wavefront = args.wavefront.unwrap();
+        let wavefront = args.wavefront.unwrap().remove("sinks.wavefront").unwrap();
         assert_eq!(wavefront.host, String::from("example.com"));
         assert_eq!(wavefront.port, 3131);
         assert_eq!(wavefront.bin_width, 9);
@@ -1790,12 +3582,13 @@ scripts-directory = "/foo/bar"
       sum = false
       summarize = false
       histogram = true
+      distinct = true
     "#;
 
         let args = parse_config_file(config);
 
         assert!(args.wavefront.is_some());
-        let wavefront = args.wavefront.unwrap();
+        let wavefront = args.wavefront.unwrap().remove("sinks.wavefront").unwrap();
         assert_eq!(wavefront.host, String::from("example.com"));
         assert_eq!(wavefront.port, 3131);
         assert_eq!(wavefront.bin_width, 9);
@@ -1804,6 +3597,53 @@ scripts-directory = "/foo/bar"
         assert_eq!(wavefront.pad_control.sum, false);
         assert_eq!(wavefront.pad_control.summarize, false);
         assert_eq!(wavefront.pad_control.histogram, true);
+        assert_eq!(wavefront.pad_control.distinct, true);
+    }
+
+    #[test]
+    fn config_file_wavefront_named_sinks() {
+        let config = r#"
+    [sinks]
+      [sinks.wavefront.prod]
+      host = "prod.example.com"
+      bin_width = 9
+
+      [sinks.wavefront.staging]
+      host = "staging.example.com"
+
+        [sinks.wavefront.staging.padding]
+        set = true
+
+      [sinks.console.debug]
+    "#;
+
+        let args = parse_config_file(config);
+
+        let console = args.console.unwrap();
+        assert_eq!(console.len(), 1);
+        assert_eq!(
+            console["sinks.console.debug"].config_path,
+            Some("sinks.console.debug".to_string())
+        );
+
+        let mut wavefronts = args.wavefront.unwrap();
+        assert_eq!(wavefronts.len(), 2);
+
+        let prod = wavefronts.remove("sinks.wavefront.prod").unwrap();
+        assert_eq!(prod.config_path, Some("sinks.wavefront.prod".to_string()));
+        assert_eq!(prod.host, String::from("prod.example.com"));
+        assert_eq!(prod.bin_width, 9);
+        assert_eq!(prod.pad_control.set, false);
+
+        let staging = wavefronts.remove("sinks.wavefront.staging").unwrap();
+        assert_eq!(
+            staging.config_path,
+            Some("sinks.wavefront.staging".to_string())
+        );
+        assert_eq!(staging.host, String::from("staging.example.com"));
+        assert_eq!(staging.bin_width, WavefrontConfig::default().bin_width);
+        assert_eq!(staging.pad_control.set, true);
+        assert_eq!(staging.flush_interval, args.flush_interval);
     }
 
     #[test]
@@ -1821,7 +3661,7 @@ scripts-directory = "/foo/bar"
         let args = parse_config_file(config);
 
         assert!(args.influxdb.is_some());
-        let influxdb = args.influxdb.unwrap();
+        let influxdb = args.influxdb.unwrap().remove("sinks.influxdb").unwrap();
         assert_eq!(influxdb.host, String::from("example.com"));
         assert_eq!(influxdb.db, String::from("postmates"));
         assert_eq!(influxdb.port, 3131);
@@ -1829,6 +3669,64 @@ scripts-directory = "/foo/bar"
         assert_eq!(influxdb.secure, true);
     }
 
+    #[test]
+    fn config_file_sink_delivery() {
+        let config = r#"
+    [sinks]
+      [sinks.influxdb]
+      host = "example.com"
+        [sinks.influxdb.delivery]
+        initial_backoff = 10
+        max_backoff = 10000
+        jitter = 0.25
+        max_attempts = 5
+        max_elapsed = 30000
+        breaker_threshold = 3
+        breaker_cooldown = 60
+      [sinks.native.primary]
+      host = "example.com"
+        [sinks.native.primary.delivery]
+        max_attempts = 2
+      [sinks.elasticsearch]
+      host = "example.com"
+    "#;
+
+        let args = parse_config_file(config);
+
+        let influxdb = args.influxdb.unwrap().remove("sinks.influxdb").unwrap();
+        assert_eq!(
+            influxdb.delivery,
+            DeliveryConfig {
+                initial_backoff: 10,
+                max_backoff: 10000,
+                jitter: 0.25,
+                max_attempts: Some(5),
+                max_elapsed: Some(30000),
+                breaker_threshold: Some(3),
+                breaker_cooldown: 60,
+            }
+        );
+        let native = args
+            .native_sink_config
+            .unwrap()
+            .remove("sinks.native.primary")
+            .unwrap();
+        assert_eq!(
+            native.delivery,
+            DeliveryConfig {
+                max_attempts: Some(2),
+                ..DeliveryConfig::default()
+            }
+        );
+        let elasticsearch = args
+            .elasticsearch
+            .unwrap()
+            .remove("sinks.elasticsearch")
+            .unwrap();
+        assert_eq!(elasticsearch.delivery.max_attempts, Some(1));
+        assert_eq!(elasticsearch.delivery.breaker_threshold, None);
+    }
+
     #[test]
     fn config_file_prometheus_sinks_style() {
         let config = r#"
@@ -1841,12 +3739,46 @@ scripts-directory = "/foo/bar"
         let args = parse_config_file(config);
 
         assert!(args.prometheus.is_some());
-        let prometheus = args.prometheus.unwrap();
+        let prometheus = args.prometheus.unwrap().remove("sinks.prometheus").unwrap();
         assert_eq!(prometheus.host, String::from("example.com"));
         assert_eq!(prometheus.port, 3131);
         assert_eq!(prometheus.capacity_in_seconds, 600);
     }
 
+    #[test]
+    fn config_file_prometheus_remote_write_sinks_style() {
+        let config = r#"
+    [sinks]
+      [sinks.prometheus_remote_write]
+      url = "https://cortex.example.com/api/v1/push"
+      max_samples_per_send = 1000
+      flush_interval = 15
+        [sinks.prometheus_remote_write.delivery]
+        max_attempts = 4
+    "#;
+
+        let args = parse_config_file(config);
+
+        assert!(args.prometheus_remote_write.is_some());
+        let remote_write = args
+            .prometheus_remote_write
+            .unwrap()
+            .remove("sinks.prometheus_remote_write")
+            .unwrap();
+        assert_eq!(
+            remote_write.url,
+            String::from("https://cortex.example.com/api/v1/push")
+        );
+        assert_eq!(remote_write.delivery.max_attempts, Some(4));
+        assert_eq!(remote_write.max_samples_per_send, 1000);
+        assert_eq!(remote_write.timeout, 30);
+        assert_eq!(remote_write.flush_interval, 15 * flushes_per_second());
+        assert_eq!(
+            remote_write.config_path,
+            Some("sinks.prometheus_remote_write".to_string())
+        );
+    }
+
     #[test]
     fn config_file_prometheus_explicit_summary_capacity() {
         let config = r#"
@@ -1860,7 +3792,7 @@ scripts-directory = "/foo/bar"
         let args = parse_config_file(config);
 
         assert!(args.prometheus.is_some());
-        let prometheus = args.prometheus.unwrap();
+        let prometheus = args.prometheus.unwrap().remove("sinks.prometheus").unwrap();
         assert_eq!(prometheus.host, String::from("example.com"));
         assert_eq!(prometheus.port, 3131);
         assert_eq!(prometheus.capacity_in_seconds, 50);
@@ -1877,7 +3809,7 @@ scripts-directory = "/foo/bar"
         let args = parse_config_file(config);
 
         assert!(args.console.is_some());
-        let console = args.console.unwrap();
+        let console = args.console.unwrap().remove("sinks.console").unwrap();
         assert_eq!(console.bin_width, 9);
         assert_eq!(console.flush_interval, args.flush_interval); // default
     }
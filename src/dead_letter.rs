@@ -0,0 +1,222 @@
+//! Dead letters, events sinks have given up on
+//!
+//! A sink that permanently fails to deliver an event hands it back, with a
+//! reason, through `sink::Sink::dead_letters`. Each sink's dead letters are
+//! appended to a file under `data_directory/dead-letters`, one JSON object
+//! per line, where they may be inspected and later replayed into a filter or
+//! sink of the running topology with `cernan replay`.
+
+use crate::metric::Event;
+use crate::time;
+use serde_json;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Total dead letters written.
+pub static DEAD_LETTER_WRITTEN: AtomicUsize = AtomicUsize::new(0);
+/// Total dead letters that could not be written and were lost.
+pub static DEAD_LETTER_LOST: AtomicUsize = AtomicUsize::new(0);
+
+/// An event a sink permanently failed to deliver.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeadLetter {
+    /// The config path of the sink that gave up on the event.
+    pub sink: String,
+    /// Why the sink gave up.
+    pub reason: String,
+    /// When the sink gave up, in seconds since the Unix epoch.
+    pub time: i64,
+    /// The event itself.
+    pub event: Event,
+}
+
+/// A `DeadLetter` as written to disk. JSON objects may only have string keys
+/// and so the metadata of raw events is written apart, as a list of pairs.
+#[derive(Serialize, Deserialize)]
+struct Record {
+    sink: String,
+    reason: String,
+    time: i64,
+    event: Event,
+    #[serde(default)]
+    metadata: Option<Vec<(Vec<u8>, Vec<u8>)>>,
+}
+
+impl From<DeadLetter> for Record {
+    fn from(letter: DeadLetter) -> Record {
+        let mut event = letter.event;
+        let metadata = match event {
+            Event::Raw {
+                ref mut metadata, ..
+            } => metadata.take().map(|m| m.into_iter().collect()),
+            _ => None,
+        };
+        Record {
+            sink: letter.sink,
+            reason: letter.reason,
+            time: letter.time,
+            event,
+            metadata,
+        }
+    }
+}
+
+impl From<Record> for DeadLetter {
+    fn from(record: Record) -> DeadLetter {
+        let mut event = record.event;
+        if let Event::Raw {
+            ref mut metadata, ..
+        } = event
+        {
+            *metadata = record.metadata.map(|m| m.into_iter().collect());
+        }
+        DeadLetter {
+            sink: record.sink,
+            reason: record.reason,
+            time: record.time,
+            event,
+        }
+    }
+}
+
+/// The path of the dead letters of the sink `config_path`.
+pub fn path(data_directory: &Path, config_path: &str) -> PathBuf {
+    data_directory
+        .join("dead-letters")
+        .join(format!("{}.json", config_path))
+}
+
+/// The dead-letter queue of a single sink.
+#[derive(Debug, Clone)]
+pub struct DeadLetters {
+    sink: String,
+    path: PathBuf,
+}
+
+impl DeadLetters {
+    /// Create the dead-letter queue of the sink `config_path`, kept under
+    /// `data_directory`.
+    pub fn new(data_directory: &Path, config_path: &str) -> DeadLetters {
+        DeadLetters {
+            sink: config_path.to_string(),
+            path: path(data_directory, config_path),
+        }
+    }
+
+    /// Append `letters`, events and the reason each was given up on, to the
+    /// queue. Should the queue not be writable the letters are lost, and
+    /// counted in `DEAD_LETTER_LOST`.
+    pub fn write(&self, letters: Vec<(Event, String)>) {
+        if letters.is_empty() {
+            return;
+        }
+        let total = letters.len();
+        if let Err(e) = self.append(letters) {
+            error!(
+                "Unable to write {} dead letters to {:?}: {}",
+                total, self.path, e
+            );
+            DEAD_LETTER_LOST.fetch_add(total, Ordering::Relaxed);
+        }
+    }
+
+    fn append(&self, letters: Vec<(Event, String)>) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut fp = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        let now = time::now();
+        let total = letters.len();
+        let mut buffer = String::new();
+        for (event, reason) in letters {
+            let letter = DeadLetter {
+                sink: self.sink.clone(),
+                reason,
+                time: now,
+                event,
+            };
+            buffer.push_str(&serde_json::to_string(&Record::from(letter))?);
+            buffer.push('\n');
+        }
+        fp.write_all(buffer.as_bytes())?;
+        DEAD_LETTER_WRITTEN.fetch_add(total, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+/// Read the dead letters at `path`. Lines that do not parse are skipped.
+pub fn read(path: &Path) -> io::Result<Vec<DeadLetter>> {
+    let fp = File::open(path)?;
+    let mut letters = Vec::new();
+    for line in BufReader::new(fp).lines() {
+        let line = line?;
+        match serde_json::from_str::<Record>(&line) {
+            Ok(record) => letters.push(record.into()),
+            Err(e) => warn!("Skipping unreadable dead letter in {:?}: {}", path, e),
+        }
+    }
+    Ok(letters)
+}
+
+/// Take every dead letter of the sink `config_path`, emptying its queue.
+/// Letters written while this runs are kept for the next take.
+pub fn take(data_directory: &Path, config_path: &str) -> io::Result<Vec<DeadLetter>> {
+    let path = path(data_directory, config_path);
+    let taken = path.with_extension("json.taken");
+    // A previous take may have failed part way, its letters come first.
+    let mut letters = if taken.exists() {
+        read(&taken)?
+    } else {
+        Vec::new()
+    };
+    match fs::rename(&path, &taken) {
+        Ok(()) => letters.extend(read(&taken)?),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    if taken.exists() {
+        fs::remove_file(&taken)?;
+    }
+    Ok(letters)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::metric::{Encoding, LogLine};
+    use std::iter;
+    use tempdir::TempDir;
+
+    #[test]
+    fn test_write_and_take() {
+        let dir = TempDir::new("cernan_dead_letter").unwrap();
+        let queue = DeadLetters::new(dir.path(), "sinks.elasticsearch");
+        assert!(take(dir.path(), "sinks.elasticsearch").unwrap().is_empty());
+
+        let line = Event::new_log(LogLine::new("a", "hello"));
+        let raw = Event::Raw {
+            order_by: 1024,
+            encoding: Encoding::Raw,
+            bytes: vec![1, 2, 3],
+            metadata: Some(iter::once((b"k".to_vec(), vec![1, 2, 3])).collect()),
+            connection_id: None,
+        };
+        queue.write(vec![(line.clone(), "rejected".into())]);
+        queue.write(vec![(raw.clone(), "unrecoverable".into())]);
+        let letters = read(&path(dir.path(), "sinks.elasticsearch")).unwrap();
+        assert_eq!(letters.len(), 2);
+
+        let letters = take(dir.path(), "sinks.elasticsearch").unwrap();
+        assert_eq!(letters.len(), 2);
+        assert_eq!(letters[0].sink, "sinks.elasticsearch");
+        assert_eq!(letters[0].reason, "rejected");
+        assert_eq!(letters[0].event, line);
+        assert_eq!(letters[1].event, raw);
+        assert!(take(dir.path(), "sinks.elasticsearch").unwrap().is_empty());
+    }
+}
@@ -72,7 +72,8 @@ impl filter::Filter for JSONEncodeFilter {
     ) -> Result<(), filter::FilterError> {
         match event {
             metric::Event::Log(log) => {
-                let naive_time = NaiveDateTime::from_timestamp(log.time, 0);
+                let naive_time =
+                    NaiveDateTime::from_timestamp(log.time, log.subsec_nanos);
                 let utc_time: DateTime<Utc> = DateTime::from_utc(naive_time, Utc);
                 let metadata = json_to_object(json!({
                     "time": utc_time.to_rfc3339(),
@@ -189,6 +190,7 @@ mod test {
                     path: "testpath".to_string(),
                     value: "{\"bad\": \"do not parse\"}".to_string(),
                     time: 946684800,
+                    subsec_nanos: 0,
                     tags: Default::default(),
                     fields: Default::default(),
                 })
@@ -202,6 +204,30 @@ mod test {
         );
     }
 
+    #[test]
+    fn sub_second_time() {
+        // Test the time keeps the LogLine's sub-second precision
+        assert_eq!(
+            process_event(
+                false,
+                metric::Event::new_log(metric::LogLine {
+                    path: "testpath".to_string(),
+                    value: "line".to_string(),
+                    time: 946684800,
+                    subsec_nanos: 123_000_000,
+                    tags: Default::default(),
+                    fields: Default::default(),
+                })
+            ),
+            json!({
+                "path": "testpath",
+                "message": "line",
+                "time": "2000-01-01T00:00:00.123+00:00",
+                "tags": {},
+            })
+        );
+    }
+
     #[test]
     fn parsable_line_parsing_on() {
         // Test we do parse a line if parsing is on
@@ -212,6 +238,7 @@ mod test {
                     path: "testpath".to_string(),
                     value: "{\"good\": \"do parse\"}".to_string(),
                     time: 946684800,
+                    subsec_nanos: 0,
                     tags: Default::default(),
                     fields: Default::default(),
                 })
@@ -235,6 +262,7 @@ mod test {
                     path: "testpath".to_string(),
                     value: "this is not json".to_string(),
                     time: 946684800,
+                    subsec_nanos: 0,
                     tags: Default::default(),
                     fields: Default::default(),
                 })
@@ -258,6 +286,7 @@ mod test {
                     path: "testpath".to_string(),
                     value: "[123, \"not an object\"]".to_string(),
                     time: 946684800,
+                    subsec_nanos: 0,
                     tags: Default::default(),
                     fields: Default::default(),
                 })
@@ -69,7 +69,7 @@ pub trait Filter {
     fn run(
         &mut self,
         recv: hopper::Receiver<metric::Event>,
-        sources: Vec<String>,
+        upstreams: util::Upstreams,
         mut chans: util::Channel,
     ) {
         let mut attempts = 0;
@@ -83,10 +83,10 @@ pub trait Filter {
                 Some(metric::Event::Shutdown) => {
                     util::send(&mut chans, metric::Event::Shutdown);
                     total_shutdowns += 1;
-                    if total_shutdowns >= sources.len() {
+                    if total_shutdowns >= upstreams.expected() {
                         trace!(
-                            "Received shutdown from every configured source: {:?}",
-                            sources
+                            "Received shutdown from every configured source: {}",
+                            total_shutdowns
                         );
                         return;
                     }
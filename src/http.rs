@@ -9,10 +9,15 @@ use std;
 pub type Request = tiny_http::Request;
 /// HTTP response. Alias of `tiny_http::Response`.
 pub type Response<'a> = tiny_http::Response<&'a [u8]>;
+/// HTTP response with its body read from `R`, chunked if its length is not
+/// given. Alias of `tiny_http::Response`.
+pub type StreamingResponse<R> = tiny_http::Response<R>;
 /// HTTP header. Alias of `tiny_http::Header`.
 pub type Header = tiny_http::Header;
 /// HTTP header field. Alias of `tiny_http::HeaderField`.
 pub type HeaderField = tiny_http::HeaderField;
+/// HTTP method. Alias of `tiny_http::Method`.
+pub type Method = tiny_http::Method;
 /// HTTP status code. Alias of `tiny_http::StatusCode`.
 pub type StatusCode = tiny_http::StatusCode;
 
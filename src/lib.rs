@@ -46,6 +46,7 @@ extern crate serde_avro;
 #[macro_use]
 extern crate serde_json;
 extern crate slab;
+extern crate snap;
 extern crate toml;
 extern crate url;
 extern crate uuid;
@@ -62,16 +63,19 @@ extern crate serde_derive;
 #[cfg(test)]
 extern crate quickcheck;
 
+pub mod admin;
 pub mod buckets;
 pub mod config;
 pub mod constants;
+pub mod dead_letter;
 pub mod filter;
 pub mod http;
-pub mod matrix;
 pub mod metric;
 pub mod protocols;
 pub mod sink;
 pub mod source;
+pub mod tap;
 pub mod thread;
 pub mod time;
+pub mod topology;
 pub mod util;
@@ -0,0 +1,508 @@
+//! A mergeable quantile sketch with relative error guarantees.
+//!
+//! This is the DDSketch of Masson, Rim and Lee. Values are counted in buckets
+//! whose bounds grow geometrically, so that any quantile is answered within a
+//! fixed relative error of the true value. Unlike `quantiles::CKMS`, whose
+//! error is in rank, merging sketches does not compromise that guarantee: the
+//! merge of two sketches is the sketch of the union of their streams. This
+//! makes the sketch suitable for aggregation across many hops of cernan.
+
+use std::collections::BTreeMap;
+use std::f64;
+
+/// The default relative accuracy of a sketch's quantiles
+pub const DEFAULT_RELATIVE_ACCURACY: f64 = 0.01;
+/// The largest number of buckets kept for values of either sign
+///
+/// Should this be exceeded the buckets of smallest magnitude are collapsed
+/// together, giving up accuracy near zero to keep the tails accurate. At the
+/// default accuracy 2048 buckets cover some eighteen orders of magnitude.
+pub const MAX_BUCKETS: usize = 2048;
+/// Values of lesser magnitude than this are counted as zero
+pub const MIN_INDEXABLE: f64 = 1e-9;
+
+/// A DDSketch
+///
+/// The bucket of a positive value `v` is `ceil(log(v) / log(gamma))` where
+/// `gamma` is `(1 + relative_accuracy) / (1 - relative_accuracy)`. Negative
+/// values are bucketed by their magnitude into a store of their own.
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct DDSketch {
+    relative_accuracy: f64,
+    positive: BTreeMap<i32, u64>,
+    negative: BTreeMap<i32, u64>,
+    zero_count: u64,
+    count: u64,
+    sum: f64,
+    min: f64,
+    max: f64,
+}
+
+impl Default for DDSketch {
+    fn default() -> DDSketch {
+        DDSketch::new(DEFAULT_RELATIVE_ACCURACY).unwrap()
+    }
+}
+
+impl DDSketch {
+    /// Create an empty DDSketch
+    ///
+    /// None is returned if the relative accuracy is not strictly between zero
+    /// and one.
+    pub fn new(relative_accuracy: f64) -> Option<DDSketch> {
+        if !(relative_accuracy > 0.0 && relative_accuracy < 1.0) {
+            return None;
+        }
+        Some(DDSketch {
+            relative_accuracy,
+            positive: BTreeMap::new(),
+            negative: BTreeMap::new(),
+            zero_count: 0,
+            count: 0,
+            sum: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        })
+    }
+
+    /// Create a DDSketch from the buckets of another
+    ///
+    /// This is the inverse of the accessors `positive`, `negative`,
+    /// `zero_count`, `sum`, `min` and `max`. None is returned if the relative
+    /// accuracy is not valid.
+    pub fn from_buckets(
+        relative_accuracy: f64,
+        positive: Vec<(i32, u64)>,
+        negative: Vec<(i32, u64)>,
+        zero_count: u64,
+        sum: f64,
+        min: f64,
+        max: f64,
+    ) -> Option<DDSketch> {
+        let mut sketch = DDSketch::new(relative_accuracy)?;
+        // The buckets come from peers, so their counts are saturated rather
+        // than trusted not to overflow.
+        for (index, count) in positive {
+            let bucket = sketch.positive.entry(index).or_insert(0);
+            *bucket = bucket.saturating_add(count);
+            sketch.count = sketch.count.saturating_add(count);
+        }
+        for (index, count) in negative {
+            let bucket = sketch.negative.entry(index).or_insert(0);
+            *bucket = bucket.saturating_add(count);
+            sketch.count = sketch.count.saturating_add(count);
+        }
+        sketch.zero_count = zero_count;
+        sketch.count = sketch.count.saturating_add(zero_count);
+        if sketch.count > 0 {
+            sketch.sum = sum;
+            sketch.min = min;
+            sketch.max = max;
+        }
+        sketch.collapse();
+        Some(sketch)
+    }
+
+    fn gamma(&self) -> f64 {
+        (1.0 + self.relative_accuracy) / (1.0 - self.relative_accuracy)
+    }
+
+    fn index(&self, magnitude: f64) -> i32 {
+        (magnitude.ln() / self.gamma().ln()).ceil() as i32
+    }
+
+    /// The value reported for a bucket, within the relative accuracy of every
+    /// value counted in it
+    fn value(&self, index: i32) -> f64 {
+        let gamma = self.gamma();
+        2.0 * gamma.powi(index) / (gamma + 1.0)
+    }
+
+    /// The relative accuracy of the DDSketch
+    pub fn relative_accuracy(&self) -> f64 {
+        self.relative_accuracy
+    }
+
+    /// The buckets of positive values, by index
+    pub fn positive(&self) -> &BTreeMap<i32, u64> {
+        &self.positive
+    }
+
+    /// The buckets of negative values, by the index of their magnitude
+    pub fn negative(&self) -> &BTreeMap<i32, u64> {
+        &self.negative
+    }
+
+    /// The number of values counted as zero
+    pub fn zero_count(&self) -> u64 {
+        self.zero_count
+    }
+
+    /// The number of values inserted
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// The sum of values inserted
+    pub fn sum(&self) -> f64 {
+        self.sum
+    }
+
+    /// The least value inserted, None if empty
+    pub fn min(&self) -> Option<f64> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.min)
+        }
+    }
+
+    /// The greatest value inserted, None if empty
+    pub fn max(&self) -> Option<f64> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.max)
+        }
+    }
+
+    /// Determine if no values have been inserted
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Insert a value
+    ///
+    /// NaN has no place in the order of values and is ignored.
+    pub fn insert(&mut self, value: f64) {
+        self.insert_n(value, 1)
+    }
+
+    fn insert_n(&mut self, value: f64, count: u64) {
+        if value.is_nan() || count == 0 {
+            return;
+        }
+        if value > MIN_INDEXABLE {
+            let index = self.index(value);
+            let bucket = self.positive.entry(index).or_insert(0);
+            *bucket = bucket.saturating_add(count);
+        } else if value < -MIN_INDEXABLE {
+            let index = self.index(-value);
+            let bucket = self.negative.entry(index).or_insert(0);
+            *bucket = bucket.saturating_add(count);
+        } else {
+            self.zero_count = self.zero_count.saturating_add(count);
+        }
+        self.count = self.count.saturating_add(count);
+        self.sum += value * count as f64;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.collapse();
+    }
+
+    /// Merge another DDSketch into this one
+    ///
+    /// The result is the sketch of the union of both streams. If the relative
+    /// accuracies differ the buckets of `other` are re-inserted at this
+    /// sketch's accuracy, adding that error to the error of `other`.
+    pub fn merge(&mut self, other: &DDSketch) {
+        if other.is_empty() {
+            return;
+        }
+        if other.relative_accuracy == self.relative_accuracy {
+            for (index, count) in &other.positive {
+                let bucket = self.positive.entry(*index).or_insert(0);
+                *bucket = bucket.saturating_add(*count);
+            }
+            for (index, count) in &other.negative {
+                let bucket = self.negative.entry(*index).or_insert(0);
+                *bucket = bucket.saturating_add(*count);
+            }
+            self.zero_count = self.zero_count.saturating_add(other.zero_count);
+            self.count = self.count.saturating_add(other.count);
+            self.sum += other.sum;
+            self.min = self.min.min(other.min);
+            self.max = self.max.max(other.max);
+            self.collapse();
+        } else {
+            let (sum, min, max) = (self.sum, self.min, self.max);
+            for (index, count) in &other.positive {
+                self.insert_n(other.value(*index), *count);
+            }
+            for (index, count) in &other.negative {
+                self.insert_n(-other.value(*index), *count);
+            }
+            self.insert_n(0.0, other.zero_count);
+            // The re-inserted bucket values stand in for the originals, whose
+            // sum and extrema are known exactly.
+            self.sum = sum + other.sum;
+            self.min = min.min(other.min);
+            self.max = max.max(other.max);
+        }
+    }
+
+    /// Collapse the buckets of smallest magnitude until each store holds no
+    /// more than `MAX_BUCKETS`.
+    fn collapse(&mut self) {
+        for store in &mut [&mut self.positive, &mut self.negative] {
+            while store.len() > MAX_BUCKETS {
+                let lowest = *store.keys().next().unwrap();
+                let count = store.remove(&lowest).unwrap();
+                let next = *store.keys().next().unwrap();
+                let bucket = store.get_mut(&next).unwrap();
+                *bucket = bucket.saturating_add(count);
+            }
+        }
+    }
+
+    /// Query the DDSketch for the value at quantile `q`
+    ///
+    /// The quantile is clamped to `[0, 1]`. The result is within the relative
+    /// accuracy of the true value at that rank, or None if the sketch is
+    /// empty. The rank is that of `q * (count - 1)`, rounded down.
+    pub fn query(&self, q: f64) -> Option<f64> {
+        if self.is_empty() {
+            return None;
+        }
+        let q = q.max(0.0).min(1.0);
+        let rank = (q * (self.count - 1) as f64) as u64;
+        // The extrema are known exactly.
+        if rank == 0 {
+            return Some(self.min);
+        }
+        if rank == self.count - 1 {
+            return Some(self.max);
+        }
+        let mut seen = 0;
+        for (index, count) in self.negative.iter().rev() {
+            seen += count;
+            if seen > rank {
+                return Some(self.clamp(-self.value(*index)));
+            }
+        }
+        seen += self.zero_count;
+        if seen > rank {
+            return Some(self.clamp(0.0));
+        }
+        for (index, count) in &self.positive {
+            seen += count;
+            if seen > rank {
+                return Some(self.clamp(self.value(*index)));
+            }
+        }
+        Some(self.max)
+    }
+
+    fn clamp(&self, value: f64) -> f64 {
+        value.max(self.min).min(self.max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck::{QuickCheck, TestResult};
+
+    fn exact(values: &mut Vec<f64>, q: f64) -> f64 {
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let rank = (q * (values.len() - 1) as f64) as usize;
+        values[rank]
+    }
+
+    fn within_accuracy(estimate: f64, actual: f64, accuracy: f64) -> bool {
+        // Slack is given for rounding at bucket bounds and for values counted
+        // as zero.
+        (estimate - actual).abs() <= actual.abs() * (accuracy + 1e-9) + 1e-9
+    }
+
+    #[test]
+    fn test_empty() {
+        let sketch = DDSketch::default();
+        assert!(sketch.is_empty());
+        assert_eq!(None, sketch.query(0.5));
+        assert_eq!(None, sketch.min());
+        assert_eq!(None, sketch.max());
+    }
+
+    #[test]
+    fn test_invalid_accuracy() {
+        assert!(DDSketch::new(0.0).is_none());
+        assert!(DDSketch::new(1.0).is_none());
+        assert!(DDSketch::new(f64::NAN).is_none());
+    }
+
+    #[test]
+    fn test_extrema_are_exact() {
+        let mut sketch = DDSketch::default();
+        for v in &[-12.5, 0.0, 3.0, 1_000.25] {
+            sketch.insert(*v);
+        }
+        assert_eq!(Some(-12.5), sketch.query(0.0));
+        assert_eq!(Some(1_000.25), sketch.query(1.0));
+        assert_eq!(4, sketch.count());
+        assert_eq!(990.75, sketch.sum());
+    }
+
+    #[test]
+    fn test_query_within_accuracy() {
+        fn inner(mut values: Vec<f64>, q: f64) -> TestResult {
+            if values.is_empty() || q.is_nan() || values.iter().any(|v| v.is_nan()) {
+                return TestResult::discard();
+            }
+            let q = q.abs().fract();
+            let mut sketch = DDSketch::default();
+            for v in &values {
+                sketch.insert(*v);
+            }
+            let estimate = sketch.query(q).unwrap();
+            let actual = exact(&mut values, q);
+            assert!(
+                within_accuracy(estimate, actual, DEFAULT_RELATIVE_ACCURACY),
+                "{} ~ {}",
+                estimate,
+                actual
+            );
+            TestResult::passed()
+        }
+        QuickCheck::new()
+            .tests(1000)
+            .max_tests(10000)
+            .quickcheck(inner as fn(Vec<f64>, f64) -> TestResult);
+    }
+
+    #[test]
+    fn test_merge_is_union() {
+        fn inner(lhs: Vec<f64>, rhs: Vec<f64>) -> TestResult {
+            if lhs.iter().chain(rhs.iter()).any(|v| v.is_nan()) {
+                return TestResult::discard();
+            }
+            let mut lhs_sketch = DDSketch::default();
+            let mut rhs_sketch = DDSketch::default();
+            let mut union_sketch = DDSketch::default();
+            for v in &lhs {
+                lhs_sketch.insert(*v);
+                union_sketch.insert(*v);
+            }
+            for v in &rhs {
+                rhs_sketch.insert(*v);
+                union_sketch.insert(*v);
+            }
+            lhs_sketch.merge(&rhs_sketch);
+            assert_eq!(union_sketch.positive(), lhs_sketch.positive());
+            assert_eq!(union_sketch.negative(), lhs_sketch.negative());
+            assert_eq!(union_sketch.zero_count(), lhs_sketch.zero_count());
+            assert_eq!(union_sketch.count(), lhs_sketch.count());
+            assert_eq!(union_sketch.min(), lhs_sketch.min());
+            assert_eq!(union_sketch.max(), lhs_sketch.max());
+            TestResult::passed()
+        }
+        QuickCheck::new()
+            .tests(100)
+            .max_tests(1000)
+            .quickcheck(inner as fn(Vec<f64>, Vec<f64>) -> TestResult);
+    }
+
+    #[test]
+    fn test_tail_accurate_after_many_merges() {
+        let mut values = Vec::new();
+        let mut central = DDSketch::default();
+        for instance in 0..50 {
+            let mut sketch = DDSketch::default();
+            for i in 0..1_000 {
+                let v = f64::from(instance * 1_000 + i) * 0.37;
+                sketch.insert(v);
+                values.push(v);
+            }
+            central.merge(&sketch);
+        }
+        for q in &[0.5, 0.99, 0.999] {
+            let actual = exact(&mut values, *q);
+            let estimate = central.query(*q).unwrap();
+            assert!(
+                within_accuracy(estimate, actual, DEFAULT_RELATIVE_ACCURACY),
+                "{}: {} ~ {}",
+                q,
+                estimate,
+                actual
+            );
+        }
+    }
+
+    #[test]
+    fn test_merge_differing_accuracy() {
+        let mut coarse = DDSketch::new(0.05).unwrap();
+        let mut fine = DDSketch::new(0.01).unwrap();
+        for i in 1..1_001 {
+            coarse.insert(f64::from(i));
+            fine.insert(-f64::from(i));
+        }
+        coarse.merge(&fine);
+        assert_eq!(0.05, coarse.relative_accuracy());
+        assert_eq!(2_000, coarse.count());
+        assert!(coarse.sum().abs() < 1e-6);
+        assert_eq!(Some(-1_000.0), coarse.min());
+        assert_eq!(Some(1_000.0), coarse.max());
+    }
+
+    #[test]
+    fn test_collapse_bounds_buckets() {
+        let mut sketch = DDSketch::default();
+        let mut v = 1e-8;
+        while v < 1e300 {
+            sketch.insert(v);
+            v *= 1.05;
+        }
+        assert!(sketch.positive().len() <= MAX_BUCKETS);
+        assert_eq!(sketch.max(), sketch.query(1.0));
+    }
+
+    #[test]
+    fn test_from_buckets() {
+        let mut sketch = DDSketch::default();
+        for v in &[-1.0, 0.0, 2.0, 3.5, 3.5] {
+            sketch.insert(*v);
+        }
+        let res = DDSketch::from_buckets(
+            sketch.relative_accuracy(),
+            sketch.positive().iter().map(|(k, v)| (*k, *v)).collect(),
+            sketch.negative().iter().map(|(k, v)| (*k, *v)).collect(),
+            sketch.zero_count(),
+            sketch.sum(),
+            sketch.min().unwrap(),
+            sketch.max().unwrap(),
+        )
+        .unwrap();
+        assert_eq!(sketch, res);
+        assert!(
+            DDSketch::from_buckets(1.5, vec![], vec![], 0, 0.0, 0.0, 0.0).is_none()
+        );
+    }
+
+    #[test]
+    fn test_from_buckets_saturates() {
+        let mut sketch = DDSketch::from_buckets(
+            0.01,
+            vec![(1, u64::max_value()), (1, 1)],
+            vec![(1, u64::max_value())],
+            u64::max_value(),
+            0.0,
+            -1.0,
+            1.0,
+        )
+        .unwrap();
+        assert_eq!(sketch.positive()[&1], u64::max_value());
+        assert_eq!(sketch.count(), u64::max_value());
+        let other = sketch.clone();
+        sketch.merge(&other);
+        assert_eq!(sketch.zero_count(), u64::max_value());
+        assert_eq!(sketch.count(), u64::max_value());
+
+        // Sketches of differing accuracy merge by re-insertion, which must
+        // saturate just the same.
+        let mut coarse = DDSketch::new(0.02).unwrap();
+        coarse.merge(&sketch);
+        coarse.merge(&sketch);
+        assert_eq!(coarse.zero_count(), u64::max_value());
+        assert_eq!(coarse.count(), u64::max_value());
+    }
+}
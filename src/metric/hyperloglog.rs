@@ -0,0 +1,313 @@
+//! A mergeable estimator of the number of distinct members in a stream.
+//!
+//! This is the HyperLogLog of Flajolet et al, with the small range correction
+//! of the original paper. Members are hashed with seahash, which is stable
+//! across platforms and processes, so that sketches built in separate cernan
+//! instances may be merged without loss.
+
+use seahash;
+
+/// The default precision. 2**12 registers gives a standard error of about
+/// 1.6%.
+pub const DEFAULT_PRECISION: u8 = 12;
+/// The smallest supported precision
+pub const MIN_PRECISION: u8 = 4;
+/// The largest supported precision
+pub const MAX_PRECISION: u8 = 18;
+
+/// A HyperLogLog sketch
+///
+/// Each register holds the longest run of leading zeros -- plus one -- seen in
+/// the hashes of members routed to it. The number of registers is two raised
+/// to the sketch's precision.
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct HyperLogLog {
+    precision: u8,
+    registers: Vec<u8>,
+}
+
+impl Default for HyperLogLog {
+    fn default() -> HyperLogLog {
+        HyperLogLog::new(DEFAULT_PRECISION)
+    }
+}
+
+impl HyperLogLog {
+    /// Create an empty HyperLogLog with `2**precision` registers
+    ///
+    /// The precision is clamped to between `MIN_PRECISION` and
+    /// `MAX_PRECISION`.
+    pub fn new(precision: u8) -> HyperLogLog {
+        let precision = precision.max(MIN_PRECISION).min(MAX_PRECISION);
+        HyperLogLog {
+            precision,
+            registers: vec![0; 1 << precision],
+        }
+    }
+
+    /// Create a HyperLogLog from the registers of another
+    ///
+    /// This is the inverse of `HyperLogLog::registers`. None is returned if the
+    /// number of registers is not a power of two supported as a precision, or
+    /// if any register holds a run of zeros longer than a hash allows.
+    pub fn from_registers(registers: Vec<u8>) -> Option<HyperLogLog> {
+        let len = registers.len();
+        if !len.is_power_of_two() {
+            return None;
+        }
+        let precision = len.trailing_zeros() as u8;
+        if !(MIN_PRECISION..=MAX_PRECISION).contains(&precision) {
+            return None;
+        }
+        if registers.iter().any(|r| *r > max_rho(precision)) {
+            return None;
+        }
+        Some(HyperLogLog {
+            precision,
+            registers,
+        })
+    }
+
+    /// The registers of the HyperLogLog, suitable for transmission
+    pub fn registers(&self) -> &[u8] {
+        &self.registers
+    }
+
+    /// The precision of the HyperLogLog
+    pub fn precision(&self) -> u8 {
+        self.precision
+    }
+
+    /// Insert a member, given as raw bytes
+    pub fn insert_bytes(&mut self, member: &[u8]) {
+        self.insert_hash(seahash::hash(member))
+    }
+
+    /// Insert a numeric member
+    ///
+    /// Numbers are hashed by value, so that `0.0` and `-0.0` are the same
+    /// member.
+    pub fn insert(&mut self, member: f64) {
+        let member = if member == 0.0 { 0.0 } else { member };
+        let bits = member.to_bits();
+        let mut bytes = [0; 8];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = (bits >> (8 * i)) as u8;
+        }
+        self.insert_bytes(&bytes)
+    }
+
+    fn insert_hash(&mut self, hash: u64) {
+        let p = u32::from(self.precision);
+        let idx = (hash >> (64 - p)) as usize;
+        // The sentinel bit bounds the run of zeros in the remaining 64 - p
+        // bits.
+        let rest = (hash << p) | (1 << (p - 1));
+        let rho = (rest.leading_zeros() + 1) as u8;
+        if rho > self.registers[idx] {
+            self.registers[idx] = rho;
+        }
+    }
+
+    /// Merge another HyperLogLog into this one
+    ///
+    /// The result estimates the distinct members of the union of both
+    /// streams. If the precisions differ the result takes the lesser of the
+    /// two.
+    pub fn merge(&mut self, other: &HyperLogLog) {
+        if other.precision < self.precision {
+            *self = self.fold(other.precision);
+        }
+        if other.precision > self.precision {
+            let other = other.fold(self.precision);
+            self.merge_registers(&other.registers);
+        } else {
+            self.merge_registers(&other.registers);
+        }
+    }
+
+    fn merge_registers(&mut self, registers: &[u8]) {
+        for (lhs, rhs) in self.registers.iter_mut().zip(registers) {
+            if *rhs > *lhs {
+                *lhs = *rhs;
+            }
+        }
+    }
+
+    /// Reduce the HyperLogLog to a lesser precision
+    ///
+    /// The index bits dropped from each register are the leading bits of the
+    /// hash tail at the lesser precision, so they are folded into the run of
+    /// zeros.
+    fn fold(&self, precision: u8) -> HyperLogLog {
+        let mut res = HyperLogLog::new(precision);
+        let shift = u32::from(self.precision - res.precision);
+        for (idx, &val) in self.registers.iter().enumerate() {
+            if val == 0 {
+                continue;
+            }
+            let dropped = (idx & ((1 << shift) - 1)) as u32;
+            let rho = if dropped == 0 {
+                val + shift as u8
+            } else {
+                (dropped.leading_zeros() - (32 - shift) + 1) as u8
+            };
+            let slot = &mut res.registers[idx >> shift];
+            if rho > *slot {
+                *slot = rho;
+            }
+        }
+        res
+    }
+
+    /// Estimate the number of distinct members inserted
+    pub fn estimate(&self) -> f64 {
+        let m = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+        let mut sum = 0.0;
+        let mut zeros: u32 = 0;
+        for &val in &self.registers {
+            sum += 2f64.powi(-i32::from(val));
+            if val == 0 {
+                zeros += 1;
+            }
+        }
+        let raw = alpha * m * m / sum;
+        if raw <= 2.5 * m && zeros > 0 {
+            (m * (m / f64::from(zeros)).ln()).round()
+        } else {
+            raw.round()
+        }
+    }
+
+    /// Determine if no members have been inserted
+    pub fn is_empty(&self) -> bool {
+        self.registers.iter().all(|r| *r == 0)
+    }
+}
+
+/// The greatest register value at `precision`: the run of zeros in the 64 -
+/// precision bits of hash tail, plus one.
+fn max_rho(precision: u8) -> u8 {
+    64 - precision + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck::{QuickCheck, TestResult};
+
+    fn within_error(estimate: f64, actual: usize) -> bool {
+        // Four standard errors at the default precision.
+        (estimate - actual as f64).abs() <= (actual as f64 * 0.065).max(1.0)
+    }
+
+    #[test]
+    fn test_empty() {
+        let hll = HyperLogLog::default();
+        assert!(hll.is_empty());
+        assert_eq!(0.0, hll.estimate());
+    }
+
+    #[test]
+    fn test_duplicates_are_counted_once() {
+        let mut hll = HyperLogLog::default();
+        for _ in 0..1000 {
+            hll.insert_bytes(b"user_id_1234");
+        }
+        assert_eq!(1.0, hll.estimate());
+
+        let mut hll = HyperLogLog::default();
+        hll.insert(0.0);
+        hll.insert(-0.0);
+        assert_eq!(1.0, hll.estimate());
+    }
+
+    #[test]
+    fn test_estimate_within_error() {
+        for &n in &[10, 1_000, 100_000] {
+            let mut hll = HyperLogLog::default();
+            for i in 0..n {
+                hll.insert(i as f64);
+            }
+            assert!(
+                within_error(hll.estimate(), n),
+                "{} ~ {}",
+                hll.estimate(),
+                n
+            );
+        }
+    }
+
+    #[test]
+    fn test_merge_is_union() {
+        fn inner(lhs: Vec<u16>, rhs: Vec<u16>) -> TestResult {
+            let mut lhs_hll = HyperLogLog::default();
+            let mut rhs_hll = HyperLogLog::default();
+            let mut union_hll = HyperLogLog::default();
+            for x in &lhs {
+                lhs_hll.insert(f64::from(*x));
+                union_hll.insert(f64::from(*x));
+            }
+            for x in &rhs {
+                rhs_hll.insert(f64::from(*x));
+                union_hll.insert(f64::from(*x));
+            }
+            lhs_hll.merge(&rhs_hll);
+            assert_eq!(union_hll, lhs_hll);
+            TestResult::passed()
+        }
+        QuickCheck::new()
+            .tests(100)
+            .max_tests(1000)
+            .quickcheck(inner as fn(Vec<u16>, Vec<u16>) -> TestResult);
+    }
+
+    #[test]
+    fn test_merge_lesser_precision() {
+        let mut fine = HyperLogLog::new(14);
+        let mut coarse = HyperLogLog::new(10);
+        let mut expected = HyperLogLog::new(10);
+        for i in 0..5_000 {
+            fine.insert(i as f64);
+            expected.insert(i as f64);
+        }
+        for i in 5_000..10_000 {
+            coarse.insert(i as f64);
+            expected.insert(i as f64);
+        }
+        fine.merge(&coarse);
+        assert_eq!(10, fine.precision());
+        assert_eq!(expected, fine);
+    }
+
+    #[test]
+    fn test_from_registers() {
+        let mut hll = HyperLogLog::default();
+        hll.insert_bytes(b"foo");
+        let res = HyperLogLog::from_registers(hll.registers().to_vec()).unwrap();
+        assert_eq!(hll, res);
+        assert!(HyperLogLog::from_registers(vec![0; 100]).is_none());
+        assert!(HyperLogLog::from_registers(vec![0; 8]).is_none());
+
+        let mut registers = vec![0; 1 << MIN_PRECISION];
+        registers[0] = max_rho(MIN_PRECISION);
+        assert!(HyperLogLog::from_registers(registers.clone()).is_some());
+        registers[0] += 1;
+        assert!(HyperLogLog::from_registers(registers).is_none());
+    }
+
+    #[test]
+    fn test_fold_max_registers() {
+        let registers = vec![max_rho(MAX_PRECISION); 1 << MAX_PRECISION];
+        let hll = HyperLogLog::from_registers(registers).unwrap();
+        let mut res = HyperLogLog::new(MIN_PRECISION);
+        res.merge(&hll);
+        assert!(res.registers().iter().all(|r| *r <= max_rho(MIN_PRECISION)));
+    }
+}
@@ -7,6 +7,9 @@ use std::collections::HashSet;
 pub struct LogLine {
     /// The time that this `LogLine` occupies, in the units of time::now()
     pub time: i64,
+    /// The nanoseconds past `time` at which this `LogLine` happened
+    #[serde(default)]
+    pub subsec_nanos: u32,
     /// The path that this `LogLine` originated from. May be a unix path or not,
     /// depending on origin.
     pub path: String,
@@ -30,10 +33,12 @@ impl LogLine {
     where
         S: Into<String>,
     {
+        let (time, subsec_nanos) = time::now_parts();
         LogLine {
             path: path.into(),
             value: value.into(),
-            time: time::now(),
+            time: time,
+            subsec_nanos: subsec_nanos,
             tags: Default::default(),
             fields: Default::default(),
         }
@@ -54,9 +59,38 @@ impl LogLine {
     /// ```
     pub fn time(mut self, time: i64) -> LogLine {
         self.time = time;
+        self.subsec_nanos = 0;
         self
     }
 
+    /// Set the time of the Logline in nanoseconds since the Unix Epoch
+    ///
+    /// # Examples
+    /// ```
+    /// use cernan::metric::LogLine;
+    ///
+    /// let l = LogLine::new("some_path", "value").time_ns(101_000_000_042);
+    /// assert_eq!(l.time, 101);
+    /// assert_eq!(l.subsec_nanos, 42);
+    /// assert_eq!(l.epoch_ms(), 101_000);
+    /// ```
+    pub fn time_ns(mut self, ns: i64) -> LogLine {
+        let (time, subsec_nanos) = time::from_nanos(ns);
+        self.time = time;
+        self.subsec_nanos = subsec_nanos;
+        self
+    }
+
+    /// Return the time of the LogLine in milliseconds since the Unix Epoch
+    pub fn epoch_ms(&self) -> i64 {
+        time::to_millis(self.time, self.subsec_nanos)
+    }
+
+    /// Return the time of the LogLine in nanoseconds since the Unix Epoch
+    pub fn epoch_ns(&self) -> i64 {
+        time::to_nanos(self.time, self.subsec_nanos)
+    }
+
     /// Insert a new field into LogLine
     ///
     /// Fields are distinct from tags. A 'field' is related to data that has
@@ -2,12 +2,16 @@
 //! over, plus related metadata. The main show here is
 //! `metric::Event`. Everything branches down from that.
 mod ackbag;
+mod ddsketch;
 mod event;
+mod hyperloglog;
 mod logline;
 mod telemetry;
 
 pub use self::ackbag::global_ack_bag;
+pub use self::ddsketch::DDSketch;
 pub use self::event::{Encoding, Event, Metadata};
+pub use self::hyperloglog::HyperLogLog;
 pub use self::logline::LogLine;
 #[cfg(test)]
 pub use self::telemetry::Value;
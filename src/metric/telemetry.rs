@@ -1,3 +1,5 @@
+use crate::metric::ddsketch::{self, DDSketch};
+use crate::metric::hyperloglog::HyperLogLog;
 use crate::metric::{cmp_tagmap, TagIter, TagMap};
 use crate::time;
 use quantiles::ckms::CKMS;
@@ -31,6 +33,14 @@ pub enum AggregationMethod {
     /// is that of `quantiles::Histogram`, unequal bins with a preference for
     /// write over read speed.
     Histogram,
+    /// Estimate the number of distinct values in the `Telemetry` stream. The
+    /// method is a HyperLogLog, which is cheap to store and merges across time
+    /// bins and cernan instances without loss.
+    Distinct,
+    /// Produce a quantile query structure over the `Telemetry` stream with
+    /// relative error. The method is a DDSketch which, unlike the CKMS of
+    /// `Summarize`, keeps its error bound when merged across cernan instances.
+    Sketch,
 }
 
 /// DO NOT USE - PUBLIC FOR TESTING ONLY
@@ -44,16 +54,22 @@ pub enum Value {
     Histogram(Histogram<f64>),
     /// DO NOT USE - PUBLIC FOR TESTING ONLY
     Quantiles { ckms: CKMS<f64>, sum: f64 },
+    /// DO NOT USE - PUBLIC FOR TESTING ONLY
+    Distinct(HyperLogLog),
+    /// DO NOT USE - PUBLIC FOR TESTING ONLY
+    Sketch(DDSketch),
 }
 
 pub struct SoftTelemetry {
     name: Option<String>,
     initial_value: Option<f64>,
+    initial_member: Option<String>,
     thawed_value: Option<Value>,
     kind: Option<AggregationMethod>,
-    error: Option<f64>,       // only needed for Summarize
+    error: Option<f64>,       // only needed for Summarize or Sketch
     bounds: Option<Vec<f64>>, // only needed for Histogram
     timestamp: Option<i64>,
+    subsec_nanos: Option<u32>,
     tags: Option<TagMap>,
     persist: Option<bool>,
     override_count: Option<u64>,
@@ -74,6 +90,9 @@ pub struct Telemetry {
     tags: Option<TagMap>,
     /// The time of Telemetry, measured in seconds.
     pub timestamp: i64,
+    /// The nanoseconds past `timestamp` at which the Telemetry happened.
+    #[serde(default)]
+    pub subsec_nanos: u32,
     override_count: Option<u64>,
     override_sample_sum: Option<f64>,
 }
@@ -105,6 +124,14 @@ impl Add for Value {
                     sum += x;
                     Value::Quantiles { ckms, sum }
                 }
+                Value::Distinct(mut y) => {
+                    y.insert(x);
+                    Value::Distinct(y)
+                }
+                Value::Sketch(mut y) => {
+                    y.insert(x);
+                    Value::Sketch(y)
+                }
             },
             Value::Histogram(mut x) => match rhs {
                 Value::Set(y) => Value::Set(y),
@@ -114,6 +141,8 @@ impl Add for Value {
                     Value::Histogram(x)
                 }
                 Value::Quantiles { ckms, sum } => Value::Quantiles { ckms, sum },
+                Value::Distinct(y) => Value::Distinct(y),
+                Value::Sketch(y) => Value::Sketch(y),
             },
             Value::Quantiles { mut ckms, mut sum } => match rhs {
                 Value::Set(y) => Value::Set(y),
@@ -132,6 +161,40 @@ impl Add for Value {
                     sum += rhs_sum;
                     Value::Quantiles { ckms, sum }
                 }
+                Value::Distinct(mut y) => {
+                    for v in ckms.into_vec() {
+                        y.insert(v);
+                    }
+                    Value::Distinct(y)
+                }
+                Value::Sketch(mut y) => {
+                    for v in ckms.into_vec() {
+                        y.insert(v);
+                    }
+                    Value::Sketch(y)
+                }
+            },
+            Value::Distinct(mut x) => match rhs {
+                Value::Set(y) => Value::Set(y),
+                Value::Sum(y) => Value::Sum(x.estimate() + y),
+                Value::Histogram(y) => Value::Histogram(y),
+                Value::Quantiles { ckms, sum } => Value::Quantiles { ckms, sum },
+                Value::Distinct(y) => {
+                    x.merge(&y);
+                    Value::Distinct(x)
+                }
+                Value::Sketch(y) => Value::Sketch(y),
+            },
+            Value::Sketch(mut x) => match rhs {
+                Value::Set(y) => Value::Set(y),
+                Value::Sum(y) => Value::Sum(x.sum() + y),
+                Value::Histogram(y) => Value::Histogram(y),
+                Value::Quantiles { ckms, sum } => Value::Quantiles { ckms, sum },
+                Value::Distinct(y) => Value::Distinct(y),
+                Value::Sketch(y) => {
+                    x.merge(&y);
+                    Value::Sketch(x)
+                }
             },
         }
     }
@@ -145,6 +208,8 @@ impl Value {
             (&Value::Sum(_), &Value::Sum(_)) => true,
             (&Value::Histogram(_), &Value::Histogram(_)) => true,
             (&Value::Quantiles { .. }, &Value::Quantiles { .. }) => true,
+            (&Value::Distinct(_), &Value::Distinct(_)) => true,
+            (&Value::Sketch(_), &Value::Sketch(_)) => true,
             _ => false,
         }
     }
@@ -166,6 +231,28 @@ impl Value {
     pub fn query(&self, prcnt: f64) -> Option<f64> {
         match *self {
             Value::Quantiles { ref ckms, .. } => ckms.query(prcnt).map(|x| x.1),
+            Value::Sketch(ref sketch) => sketch.query(prcnt),
+            _ => None,
+        }
+    }
+
+    pub fn sketch(&self) -> Option<&DDSketch> {
+        match *self {
+            Value::Sketch(ref sketch) => Some(sketch),
+            _ => None,
+        }
+    }
+
+    pub fn distinct(&self) -> Option<f64> {
+        match *self {
+            Value::Distinct(ref hll) => Some(hll.estimate()),
+            _ => None,
+        }
+    }
+
+    pub fn hyperloglog(&self) -> Option<&HyperLogLog> {
+        match *self {
+            Value::Distinct(ref hll) => Some(hll),
             _ => None,
         }
     }
@@ -182,12 +269,14 @@ impl Value {
             Value::Set(_) | Value::Sum(_) => 1,
             Value::Histogram(ref histo) => histo.count(),
             Value::Quantiles { ref ckms, .. } => ckms.count(),
+            Value::Distinct(ref hll) => hll.estimate() as usize,
+            Value::Sketch(ref sketch) => sketch.count() as usize,
         }
     }
 
     pub fn mean(&self) -> f64 {
         match *self {
-            Value::Set(_) | Value::Sum(_) => 1.0,
+            Value::Set(_) | Value::Sum(_) | Value::Distinct(_) => 1.0,
             Value::Histogram(ref histo) => {
                 if let Some(sum) = histo.sum() {
                     let count = histo.count();
@@ -198,6 +287,13 @@ impl Value {
                 }
             }
             Value::Quantiles { ref ckms, .. } => ckms.cma().unwrap_or(0.0),
+            Value::Sketch(ref sketch) => {
+                if sketch.is_empty() {
+                    0.0
+                } else {
+                    sketch.sum() / (sketch.count() as f64)
+                }
+            }
         }
     }
 
@@ -207,6 +303,8 @@ impl Value {
             Value::Sum(_) => AggregationMethod::Sum,
             Value::Histogram(_) => AggregationMethod::Histogram,
             Value::Quantiles { .. } => AggregationMethod::Summarize,
+            Value::Distinct(_) => AggregationMethod::Distinct,
+            Value::Sketch(_) => AggregationMethod::Sketch,
         }
     }
 
@@ -234,7 +332,9 @@ impl PartialOrd for Telemetry {
     fn partial_cmp(&self, other: &Telemetry) -> Option<cmp::Ordering> {
         match self.name.partial_cmp(&other.name) {
             Some(cmp::Ordering::Equal) => {
-                match self.timestamp.partial_cmp(&other.timestamp) {
+                match (self.timestamp, self.subsec_nanos)
+                    .partial_cmp(&(other.timestamp, other.subsec_nanos))
+                {
                     Some(cmp::Ordering::Equal) => cmp_tagmap(&self.tags, &other.tags),
                     other => other,
                 }
@@ -252,6 +352,7 @@ impl Default for Telemetry {
             persist: false,
             tags: None,
             timestamp: time::now(),
+            subsec_nanos: 0,
             override_sample_sum: None,
             override_count: None,
         }
@@ -290,10 +391,66 @@ impl SoftTelemetry {
     /// but we've got to know where to start.
     pub fn value(mut self, val: f64) -> SoftTelemetry {
         self.thawed_value = None;
+        self.initial_member = None;
         self.initial_value = Some(val);
         self
     }
 
+    /// Set the initial member of a Distinct Telemetry
+    ///
+    /// Members need not be numeric. A member that parses as a number is the
+    /// same member as that number given through `value`. It is an error to set
+    /// this if the aggregation method is not AggregationMethod::Distinct.
+    pub fn member<S>(mut self, member: S) -> SoftTelemetry
+    where
+        S: Into<String>,
+    {
+        let member = member.into();
+        if let Ok(val) = member.parse::<f64>() {
+            return self.value(val);
+        }
+        self.thawed_value = None;
+        self.initial_value = None;
+        self.initial_member = Some(member);
+        self
+    }
+
+    /// Set the HyperLogLog of a Distinct Telemetry
+    ///
+    /// This replaces any value previously set and sets the aggregation method
+    /// to AggregationMethod::Distinct.
+    pub fn hyperloglog(mut self, hll: HyperLogLog) -> SoftTelemetry {
+        self.initial_value = None;
+        self.initial_member = None;
+        self.thawed_value = Some(Value::Distinct(hll));
+        self.kind = Some(AggregationMethod::Distinct);
+        self
+    }
+
+    /// Set the DDSketch of a Sketch Telemetry
+    ///
+    /// This replaces any value previously set and sets the aggregation method
+    /// to AggregationMethod::Sketch.
+    pub fn sketch(mut self, sketch: DDSketch) -> SoftTelemetry {
+        self.initial_value = None;
+        self.initial_member = None;
+        self.thawed_value = Some(Value::Sketch(sketch));
+        self.kind = Some(AggregationMethod::Sketch);
+        self
+    }
+
+    /// Set the Histogram of a Histogram Telemetry
+    ///
+    /// This replaces any value previously set and sets the aggregation method
+    /// to AggregationMethod::Histogram.
+    pub fn histogram(mut self, histo: Histogram<f64>) -> SoftTelemetry {
+        self.initial_value = None;
+        self.initial_member = None;
+        self.thawed_value = Some(Value::Histogram(histo));
+        self.kind = Some(AggregationMethod::Histogram);
+        self
+    }
+
     /// Set the kind of Telemetry aggregation
     ///
     /// Telemetry provide different views into the stored data. The kind
@@ -306,7 +463,8 @@ impl SoftTelemetry {
     /// Set the error for quantile calculation
     ///
     /// This is only necessary if the kind has been set to
-    /// AggregationMethod::Summarize. It is an error to set this if the
+    /// AggregationMethod::Summarize or AggregationMethod::Sketch, for which it
+    /// is the relative accuracy of quantiles. It is an error to set this if the
     /// aggregation method is not as previously specified.
     pub fn error(mut self, error: f64) -> SoftTelemetry {
         self.error = Some(error);
@@ -341,6 +499,18 @@ impl SoftTelemetry {
     /// to have happened.
     pub fn timestamp(mut self, ts: i64) -> SoftTelemetry {
         self.timestamp = Some(ts);
+        self.subsec_nanos = None;
+        self
+    }
+
+    /// Set the timestamp of the Telemetry in nanoseconds
+    ///
+    /// This is the instant of time in nanoseconds since the Unix Epoch that the
+    /// Telemetry is considered to have happened.
+    pub fn timestamp_ns(mut self, ns: i64) -> SoftTelemetry {
+        let (secs, subsec_nanos) = time::from_nanos(ns);
+        self.timestamp = Some(secs);
+        self.subsec_nanos = Some(subsec_nanos);
         self
     }
 
@@ -367,7 +537,10 @@ impl SoftTelemetry {
         if self.initial_value.is_some() && self.thawed_value.is_some() {
             return Err(Error::CannotHaveTwoValues);
         }
-        if self.initial_value.is_none() && self.thawed_value.is_none() {
+        if self.initial_value.is_none()
+            && self.initial_member.is_none()
+            && self.thawed_value.is_none()
+        {
             return Err(Error::NoValue);
         }
         let name = if let Some(name) = self.name {
@@ -385,11 +558,15 @@ impl SoftTelemetry {
         } else {
             time::now()
         };
+        let subsec_nanos = self.subsec_nanos.unwrap_or(0);
         let persist = if let Some(persist) = self.persist {
             persist
         } else {
             false
         };
+        if self.initial_member.is_some() && kind != AggregationMethod::Distinct {
+            return Err(Error::CannotSetMember);
+        }
         match kind {
             AggregationMethod::Summarize => {
                 if self.bounds.is_some() {
@@ -418,6 +595,7 @@ impl SoftTelemetry {
                     persist: persist,
                     tags: self.tags,
                     timestamp: timestamp,
+                    subsec_nanos: subsec_nanos,
                     override_count: self.override_count,
                     override_sample_sum: self.override_sample_sum,
                 })
@@ -446,6 +624,7 @@ impl SoftTelemetry {
                     persist: persist,
                     tags: self.tags,
                     timestamp: timestamp,
+                    subsec_nanos: subsec_nanos,
                     override_sample_sum: None,
                     override_count: None,
                 })
@@ -468,6 +647,7 @@ impl SoftTelemetry {
                     persist: persist,
                     tags: self.tags,
                     timestamp: timestamp,
+                    subsec_nanos: subsec_nanos,
                     override_sample_sum: None,
                     override_count: None,
                 })
@@ -490,6 +670,70 @@ impl SoftTelemetry {
                     persist: persist,
                     tags: self.tags,
                     timestamp: timestamp,
+                    subsec_nanos: subsec_nanos,
+                    override_sample_sum: None,
+                    override_count: None,
+                })
+            }
+            AggregationMethod::Sketch => {
+                if self.bounds.is_some() {
+                    return Err(Error::CannotSetBounds);
+                }
+                let accuracy =
+                    self.error.unwrap_or(ddsketch::DEFAULT_RELATIVE_ACCURACY);
+                let value = match (self.initial_value, self.thawed_value) {
+                    (Some(iv), None) => {
+                        let mut sketch = match DDSketch::new(accuracy) {
+                            Some(sketch) => sketch,
+                            None => return Err(Error::SketchAccuracyOutOfRange),
+                        };
+                        sketch.insert(iv);
+                        Value::Sketch(sketch)
+                    }
+                    (None, Some(tv)) => tv,
+                    _ => unreachable!(),
+                };
+                Ok(Telemetry {
+                    name: name,
+                    value: Some(value),
+                    persist: persist,
+                    tags: self.tags,
+                    timestamp: timestamp,
+                    subsec_nanos: subsec_nanos,
+                    override_sample_sum: self.override_sample_sum,
+                    override_count: self.override_count,
+                })
+            }
+            AggregationMethod::Distinct => {
+                if self.error.is_some() {
+                    return Err(Error::CannotSetError);
+                }
+                if self.bounds.is_some() {
+                    return Err(Error::CannotSetBounds);
+                }
+                let value =
+                    match (self.initial_value, self.initial_member, self.thawed_value)
+                    {
+                        (Some(iv), None, None) => {
+                            let mut hll = HyperLogLog::default();
+                            hll.insert(iv);
+                            Value::Distinct(hll)
+                        }
+                        (None, Some(im), None) => {
+                            let mut hll = HyperLogLog::default();
+                            hll.insert_bytes(im.as_bytes());
+                            Value::Distinct(hll)
+                        }
+                        (None, None, Some(tv)) => tv,
+                        _ => unreachable!(),
+                    };
+                Ok(Telemetry {
+                    name: name,
+                    value: Some(value),
+                    persist: persist,
+                    tags: self.tags,
+                    timestamp: timestamp,
+                    subsec_nanos: subsec_nanos,
                     override_sample_sum: None,
                     override_count: None,
                 })
@@ -503,9 +747,11 @@ pub enum Error {
     CannotHaveTwoValues,
     CannotSetBounds,
     CannotSetError,
+    CannotSetMember,
     NoInitialValue,
     NoName,
     NoValue,
+    SketchAccuracyOutOfRange,
     SummarizeErrorTooLarge,
 }
 
@@ -532,11 +778,13 @@ impl Telemetry {
         SoftTelemetry {
             name: None,
             initial_value: None,
+            initial_member: None,
             thawed_value: None,
             kind: None,
             error: None,
             bounds: None,
             timestamp: None,
+            subsec_nanos: None,
             tags: None,
             persist: None,
             override_sample_sum: None,
@@ -555,11 +803,13 @@ impl Telemetry {
         SoftTelemetry {
             name: Some(self.name),
             initial_value: None,
+            initial_member: None,
             thawed_value: Some(self.value.unwrap()),
             kind: Some(kind),
             error: None,
             bounds: None,
             timestamp: Some(self.timestamp),
+            subsec_nanos: Some(self.subsec_nanos),
             tags: self.tags,
             persist: Some(self.persist),
             override_count: None,
@@ -699,6 +949,12 @@ impl Telemetry {
                 ckms.insert(value);
                 *sum += value;
             }
+            Some(Value::Distinct(ref mut hll)) => {
+                hll.insert(value);
+            }
+            Some(Value::Sketch(ref mut sketch)) => {
+                sketch.insert(value);
+            }
             None => unreachable!(),
         }
         self
@@ -722,7 +978,8 @@ impl Telemetry {
         }
     }
 
-    /// Query a CKMS for a percentile, return None if not SUMMARIZE
+    /// Query a CKMS or DDSketch for a percentile, return None if not SUMMARIZE
+    /// or SKETCH
     pub fn query(&self, prcnt: f64) -> Option<f64> {
         if let Some(ref v) = self.value {
             v.query(prcnt)
@@ -731,6 +988,33 @@ impl Telemetry {
         }
     }
 
+    /// Estimate the distinct values of a DISTINCT, None otherwise
+    pub fn distinct(&self) -> Option<f64> {
+        if let Some(ref v) = self.value {
+            v.distinct()
+        } else {
+            None
+        }
+    }
+
+    /// Retrieve the HyperLogLog of a DISTINCT, None if not DISTINCT
+    pub fn hyperloglog(&self) -> Option<&HyperLogLog> {
+        if let Some(ref v) = self.value {
+            v.hyperloglog()
+        } else {
+            None
+        }
+    }
+
+    /// Retrieve the DDSketch of a SKETCH, None if not SKETCH
+    pub fn sketch(&self) -> Option<&DDSketch> {
+        if let Some(ref v) = self.value {
+            v.sketch()
+        } else {
+            None
+        }
+    }
+
     /// Retrieve the bins of a BIN, None if not BIN
     pub fn bins(&self) -> Option<Iter<f64>> {
         if let Some(ref v) = self.value {
@@ -743,8 +1027,14 @@ impl Telemetry {
     /// Sum of all samples inserted into this Telemetry
     pub fn samples_sum(&self) -> Option<f64> {
         match self.value {
-            Some(Value::Set(_)) | Some(Value::Sum(_)) | None => None,
+            Some(Value::Set(_))
+            | Some(Value::Sum(_))
+            | Some(Value::Distinct(_))
+            | None => None,
             Some(Value::Histogram(ref histo)) => histo.sum(),
+            Some(Value::Sketch(ref sketch)) => {
+                self.override_sample_sum.or_else(|| Some(sketch.sum()))
+            }
             Some(Value::Quantiles { sum, .. }) => {
                 if self.override_sample_sum.is_some() {
                     self.override_sample_sum
@@ -784,6 +1074,8 @@ impl Telemetry {
             Some(Value::Sum(x)) => Some(x),
             Some(Value::Quantiles { ref ckms, .. }) => ckms.query(1.0).map(|x| x.1),
             Some(Value::Histogram(ref histo)) => histo.sum(),
+            Some(Value::Distinct(ref hll)) => Some(hll.estimate()),
+            Some(Value::Sketch(ref sketch)) => sketch.query(1.0),
             None => unreachable!(),
         }
     }
@@ -804,6 +1096,9 @@ impl Telemetry {
                 .iter()
                 .map(|x| x.1 as f64)
                 .collect(),
+            Some(Value::Distinct(ref hll)) => vec![hll.estimate()],
+            // A DDSketch keeps only counts of values by bucket, not the values.
+            Some(Value::Sketch(_)) => Vec::new(),
             None => unreachable!(),
         }
     }
@@ -812,13 +1107,25 @@ impl Telemetry {
     ///
     /// This method determines if 1. two telemetry are 'alike' by name, tags and
     /// aggregation and 2. if their timestamps are within one span bound of one
-    /// another.
+    /// another. The span is measured in seconds.
     pub fn within(&self, span: i64, other: &Telemetry) -> cmp::Ordering {
+        self.within_ns(span.saturating_mul(time::NANOS_PER_SEC), other)
+    }
+
+    /// Determine if two Telemetry are within one nanosecond 'span' of one
+    /// another.
+    ///
+    /// This is `Telemetry::within` at full timestamp precision, allowing for
+    /// spans shorter than a second.
+    pub fn within_ns(&self, span: i64, other: &Telemetry) -> cmp::Ordering {
         match self.name.partial_cmp(&other.name) {
             Some(cmp::Ordering::Equal) => match cmp_tagmap(&self.tags, &other.tags) {
                 Some(cmp::Ordering::Equal) => {
-                    let lhs_bin = self.timestamp / span;
-                    let rhs_bin = other.timestamp / span;
+                    // Widened so that a seconds-sized span over far-off
+                    // timestamps can't overflow.
+                    let span = i128::from(span);
+                    let lhs_bin = self.epoch_ns_wide() / span;
+                    let rhs_bin = other.epoch_ns_wide() / span;
                     lhs_bin.cmp(&rhs_bin)
                 }
                 other => other.unwrap(),
@@ -833,6 +1140,8 @@ impl Telemetry {
             Some(Value::Set(x)) | Some(Value::Sum(x)) => x == 0.0,
             Some(Value::Histogram(ref histo)) => histo.count() == 0,
             Some(Value::Quantiles { ref ckms, .. }) => ckms.count() == 0,
+            Some(Value::Distinct(ref hll)) => hll.is_empty(),
+            Some(Value::Sketch(ref sketch)) => sketch.is_empty(),
             None => unreachable!(),
         }
     }
@@ -892,6 +1201,16 @@ impl Telemetry {
         self.kind() == AggregationMethod::Summarize
     }
 
+    /// Returns true if aggregation method is DISTINCT
+    pub fn is_distinct(&self) -> bool {
+        self.kind() == AggregationMethod::Distinct
+    }
+
+    /// Returns true if aggregation method is SKETCH
+    pub fn is_sketch(&self) -> bool {
+        self.kind() == AggregationMethod::Sketch
+    }
+
     #[cfg(test)]
     pub fn priv_value(&self) -> Value {
         self.value.clone().unwrap()
@@ -919,8 +1238,54 @@ impl Telemetry {
     /// ```
     pub fn timestamp(mut self, time: i64) -> Telemetry {
         self.timestamp = time;
+        self.subsec_nanos = 0;
         self
     }
+
+    /// Adjust Telemetry time in nanoseconds
+    ///
+    /// This sets the metric time to the specified value, taken to be UTC
+    /// nanoseconds since the Unix Epoch.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cernan::metric::Telemetry;
+    ///
+    /// let m = Telemetry::new()
+    ///     .name("foo")
+    ///     .value(1.1)
+    ///     .harden()
+    ///     .unwrap()
+    ///     .timestamp_ns(10_101_000_000_042);
+    ///
+    /// assert_eq!(10101, m.timestamp);
+    /// assert_eq!(42, m.subsec_nanos);
+    /// assert_eq!(10_101_000, m.epoch_ms());
+    /// ```
+    pub fn timestamp_ns(mut self, ns: i64) -> Telemetry {
+        let (secs, subsec_nanos) = time::from_nanos(ns);
+        self.timestamp = secs;
+        self.subsec_nanos = subsec_nanos;
+        self
+    }
+
+    /// Return the Telemetry time in UTC milliseconds since the Unix Epoch
+    pub fn epoch_ms(&self) -> i64 {
+        time::to_millis(self.timestamp, self.subsec_nanos)
+    }
+
+    /// Return the Telemetry time in UTC nanoseconds since the Unix Epoch
+    ///
+    /// Times past the year 2262 saturate, see `time::to_nanos`.
+    pub fn epoch_ns(&self) -> i64 {
+        time::to_nanos(self.timestamp, self.subsec_nanos)
+    }
+
+    fn epoch_ns_wide(&self) -> i128 {
+        i128::from(self.timestamp) * i128::from(time::NANOS_PER_SEC)
+            + i128::from(self.subsec_nanos)
+    }
 }
 
 #[cfg(test)]
@@ -1064,11 +1429,13 @@ mod tests {
         where
             G: Gen,
         {
-            let i: usize = g.gen_range(0, 4);
+            let i: usize = g.gen_range(0, 6);
             match i {
                 0 => AggregationMethod::Sum,
                 1 => AggregationMethod::Set,
                 2 => AggregationMethod::Summarize,
+                3 => AggregationMethod::Distinct,
+                4 => AggregationMethod::Sketch,
                 _ => AggregationMethod::Histogram,
             }
         }
@@ -1090,6 +1457,8 @@ mod tests {
                 AggregationMethod::Set => mb.kind(AggregationMethod::Set),
                 AggregationMethod::Sum => mb.kind(AggregationMethod::Sum),
                 AggregationMethod::Summarize => mb.kind(AggregationMethod::Summarize),
+                AggregationMethod::Distinct => mb.kind(AggregationMethod::Distinct),
+                AggregationMethod::Sketch => mb.kind(AggregationMethod::Sketch),
                 AggregationMethod::Histogram => mb
                     .kind(AggregationMethod::Histogram)
                     .bounds(vec![1.0, 10.0, 100.0, 1000.0]),
@@ -1132,6 +1501,22 @@ mod tests {
             .quickcheck(inner as fn(i64, Telemetry, Telemetry) -> TestResult);
     }
 
+    #[test]
+    fn test_metric_within_ns() {
+        let lhs = Telemetry::new()
+            .name("foo")
+            .value(1.0)
+            .timestamp_ns(10_100_000_000)
+            .harden()
+            .unwrap();
+        let rhs = lhs.clone().timestamp_ns(10_600_000_000);
+
+        assert_eq!(cmp::Ordering::Equal, lhs.within(1, &rhs));
+        assert_eq!(cmp::Ordering::Less, lhs.within_ns(500_000_000, &rhs));
+        assert_eq!(cmp::Ordering::Greater, rhs.within_ns(500_000_000, &lhs));
+        assert_eq!(Some(cmp::Ordering::Less), lhs.partial_cmp(&rhs));
+    }
+
     #[test]
     fn test_metric_add_assign() {
         fn inner(lhs: f64, rhs: f64, kind: AggregationMethod) -> TestResult {
@@ -1154,8 +1539,16 @@ mod tests {
                 let expected = match kind {
                     AggregationMethod::Set => rhs,
                     AggregationMethod::Sum => lhs + rhs,
-                    AggregationMethod::Summarize => lhs.max(rhs),
+                    AggregationMethod::Summarize | AggregationMethod::Sketch => {
+                        lhs.max(rhs)
+                    }
                     AggregationMethod::Histogram => lhs + rhs,
+                    AggregationMethod::Distinct => {
+                        let mut hll = HyperLogLog::default();
+                        hll.insert(lhs);
+                        hll.insert(rhs);
+                        hll.estimate()
+                    }
                 };
                 // println!("VAL: {:?} | EXPECTED: {:?}", val, expected);
                 match val.partial_cmp(&expected) {
@@ -1184,6 +1577,65 @@ mod tests {
             .quickcheck(inner as fn(f64, f64, AggregationMethod) -> TestResult);
     }
 
+    #[test]
+    fn test_distinct_members() {
+        let lhs = Telemetry::new()
+            .name("users")
+            .member("alice")
+            .kind(AggregationMethod::Distinct)
+            .harden()
+            .unwrap();
+        let numeric = Telemetry::new()
+            .name("users")
+            .member("1234")
+            .kind(AggregationMethod::Distinct)
+            .harden()
+            .unwrap();
+        let mut res = lhs.clone();
+        res += lhs.clone();
+        assert_eq!(Some(1.0), res.distinct());
+        assert_eq!(
+            numeric,
+            numeric.clone().thaw().value(1234.0).harden().unwrap()
+        );
+
+        assert!(Telemetry::new()
+            .name("users")
+            .member("alice")
+            .kind(AggregationMethod::Set)
+            .harden()
+            .is_err());
+    }
+
+    #[test]
+    fn test_sketch_quantiles() {
+        let mut m = Telemetry::new()
+            .name("latency")
+            .value(1.0)
+            .kind(AggregationMethod::Sketch)
+            .error(0.02)
+            .harden()
+            .unwrap();
+        for i in 2..101 {
+            m = m.insert(f64::from(i));
+        }
+        assert!(m.is_sketch());
+        assert_eq!(0.02, m.sketch().unwrap().relative_accuracy());
+        assert_eq!(100, m.count());
+        assert_eq!(Some(5050.0), m.samples_sum());
+        assert_eq!(Some(100.0), m.query(1.0));
+        let p99 = m.query(0.99).unwrap();
+        assert!((p99 - 99.0).abs() <= 99.0 * 0.02, "{}", p99);
+
+        assert!(Telemetry::new()
+            .name("latency")
+            .value(1.0)
+            .kind(AggregationMethod::Sketch)
+            .error(1.5)
+            .harden()
+            .is_err());
+    }
+
     #[test]
     fn test_negative_timer() {
         let m = Telemetry::new()
@@ -2,20 +2,71 @@
 //! protocol for telemetry.
 
 use crate::metric::{AggregationMethod, Telemetry};
+use crate::protocols::pickle;
 use std::str::FromStr;
 use std::sync;
 
+/// Determine if a tag name is valid per the Graphite 1.1 rules.
+///
+/// Tag names must be at least one character long and may not contain any of
+/// `;!^=`.
+fn valid_tag_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.contains(|c: char| c == ';' || c == '!' || c == '^' || c == '=')
+}
+
+/// Determine if a tag value is valid per the Graphite 1.1 rules.
+///
+/// Tag values must be at least one character long, may not contain `;` and
+/// may not begin with `~`.
+fn valid_tag_value(value: &str) -> bool {
+    !value.is_empty() && !value.contains(';') && !value.starts_with('~')
+}
+
+/// Split a possibly tagged graphite path into its name and tags.
+///
+/// A tagged path has the form `name;tag=value;tag2=value2`, as introduced in
+/// Graphite 1.1. Returns None if the name is empty or any tag is invalid.
+fn parse_path(path: &str) -> Option<(&str, Vec<(&str, &str)>)> {
+    let mut segments = path.split(';');
+    let name = segments.next().unwrap();
+    if name.is_empty() {
+        return None;
+    }
+    let mut tags = Vec::new();
+    for segment in segments {
+        match segment.find('=') {
+            Some(eq_idx) => {
+                let (key, value) = (&segment[..eq_idx], &segment[(eq_idx + 1)..]);
+                if !valid_tag_name(key) || !valid_tag_value(value) {
+                    return None;
+                }
+                tags.push((key, value));
+            }
+            None => return None,
+        }
+    }
+    Some((name, tags))
+}
+
 /// Parse a string for graphite data into a `metric::Telemetry` if possible.
+///
+/// Both plain and Graphite 1.1 tagged paths are understood. Tags in a tagged
+/// path are overlaid onto the `Telemetry` tag map.
 pub fn parse_graphite(
     source: &str,
     res: &mut Vec<Telemetry>,
     metric: &sync::Arc<Option<Telemetry>>,
 ) -> bool {
     let mut iter = source.split_whitespace();
-    while let Some(name) = iter.next() {
+    while let Some(path) = iter.next() {
         match iter.next() {
             Some(val) => match iter.next() {
                 Some(time) => {
+                    let (name, tags) = match parse_path(path) {
+                        Some(parsed) => parsed,
+                        None => return false,
+                    };
                     let parsed_val = match f64::from_str(val) {
                         Ok(f) => f,
                         Err(_) => return false,
@@ -27,16 +78,18 @@ pub fn parse_graphite(
                     let metric = sync::Arc::make_mut(&mut sync::Arc::clone(metric))
                         .take()
                         .unwrap();
-                    res.push(
-                        metric
-                            .thaw()
-                            .name(name)
-                            .value(parsed_val)
-                            .kind(AggregationMethod::Set)
-                            .timestamp(parsed_time)
-                            .harden()
-                            .unwrap(),
-                    );
+                    let mut metric = metric
+                        .thaw()
+                        .name(name)
+                        .value(parsed_val)
+                        .kind(AggregationMethod::Set)
+                        .timestamp(parsed_time)
+                        .harden()
+                        .unwrap();
+                    for (key, value) in tags {
+                        metric = metric.overlay_tag(key, value);
+                    }
+                    res.push(metric);
                 }
                 None => return false,
             },
@@ -46,10 +99,75 @@ pub fn parse_graphite(
     !res.is_empty()
 }
 
+/// Parse a Carbon pickle payload into `metric::Telemetry` if possible.
+///
+/// The payload is a single pickled list of `(path, (timestamp, value))`
+/// tuples, without the four byte length prefix that frames it on the
+/// wire. Paths may be tagged, as in `parse_graphite`. Timestamps may be
+/// integers or floats, values may be numbers or numeric strings.
+pub fn parse_graphite_pickle(
+    payload: &[u8],
+    res: &mut Vec<Telemetry>,
+    metric: &sync::Arc<Option<Telemetry>>,
+) -> bool {
+    let points = match pickle::loads(payload) {
+        Ok(pickle::Value::List(points)) => points,
+        Ok(_) => return false,
+        Err(e) => {
+            trace!("Unable to unpickle graphite payload: {:?}", e);
+            return false;
+        }
+    };
+    for point in points {
+        let (path, datapoint) = match point {
+            pickle::Value::Tuple(mut pair) | pickle::Value::List(mut pair) => {
+                if pair.len() != 2 {
+                    return false;
+                }
+                let datapoint = pair.pop().unwrap();
+                (pair.pop().unwrap(), datapoint)
+            }
+            _ => return false,
+        };
+        let (time, val) = match datapoint {
+            pickle::Value::Tuple(ref pair) | pickle::Value::List(ref pair) => {
+                if pair.len() != 2 {
+                    return false;
+                }
+                match (pair[0].as_f64(), pair[1].as_f64()) {
+                    (Some(time), Some(val)) => (time as i64, val),
+                    _ => return false,
+                }
+            }
+            _ => return false,
+        };
+        let (name, tags) = match path.as_str().and_then(parse_path) {
+            Some(parsed) => parsed,
+            None => return false,
+        };
+        let metric = sync::Arc::make_mut(&mut sync::Arc::clone(metric))
+            .take()
+            .unwrap();
+        let mut metric = metric
+            .thaw()
+            .name(name)
+            .value(val)
+            .kind(AggregationMethod::Set)
+            .timestamp(time)
+            .harden()
+            .unwrap();
+        for (key, value) in tags {
+            metric = metric.overlay_tag(key, value);
+        }
+        res.push(metric);
+    }
+    !res.is_empty()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::metric::{AggregationMethod, Telemetry};
+    use crate::metric::{AggregationMethod, TagMap, Telemetry};
     use chrono::{TimeZone, Utc};
     use std::sync;
 
@@ -90,4 +208,90 @@ mod tests {
         assert_eq!(res[5].value(), Some(6.0));
         assert_eq!(res[5].timestamp, Utc.timestamp(606, 0).timestamp());
     }
+
+    #[test]
+    fn test_parse_graphite_tagged() {
+        let pyld = "cpu.load;host=a;dc=b 1.2 1500000000\nmem.free 3 1500000001\n";
+        let mut res = Vec::new();
+        let metric = sync::Arc::new(Some(Telemetry::default()));
+        assert!(parse_graphite(pyld, &mut res, &metric));
+        let defaults = TagMap::default();
+
+        assert_eq!(res[0].kind(), AggregationMethod::Set);
+        assert_eq!(res[0].name, "cpu.load");
+        assert_eq!(res[0].value(), Some(1.2));
+        assert_eq!(res[0].timestamp, 1_500_000_000);
+        assert_eq!(
+            Some(&"a".to_string()),
+            res[0].get_from_tags("host", &defaults)
+        );
+        assert_eq!(
+            Some(&"b".to_string()),
+            res[0].get_from_tags("dc", &defaults)
+        );
+
+        assert_eq!(res[1].name, "mem.free");
+        assert_eq!(res[1].tags(&defaults).count(), 0);
+    }
+
+    #[test]
+    fn test_parse_graphite_invalid_tags() {
+        let invalid = vec![
+            ";host=a 1 101",
+            "cpu;host 1 101",
+            "cpu;=a 1 101",
+            "cpu;host= 1 101",
+            "cpu;ho!st=a 1 101",
+            "cpu;ho^st=a 1 101",
+            "cpu;host=~a 1 101",
+        ];
+        let metric = sync::Arc::new(Some(Telemetry::default()));
+        for input in invalid.iter() {
+            assert!(!parse_graphite(*input, &mut Vec::new(), &metric));
+        }
+    }
+
+    #[test]
+    fn test_parse_graphite_pickle() {
+        // pickle.dumps([('a.b', (1500000000, 1.5)),
+        //               ('c;host=d', (1500000001.0, '2'))], protocol=2)
+        let pyld = b"\x80\x02]q\x00(X\x03\x00\x00\x00a.bq\x01J\x00\x2f\x68\x59G?\xf8\x00\x00\x00\x00\x00\x00\x86q\x02\x86q\x03X\x08\x00\x00\x00c;host=dq\x04GA\xd6\x5a\x0b\xc0\x40\x00\x00X\x01\x00\x00\x002q\x05\x86q\x06\x86q\x07e.";
+        let mut res = Vec::new();
+        let metric = sync::Arc::new(Some(Telemetry::default()));
+        assert!(parse_graphite_pickle(&pyld[..], &mut res, &metric));
+        let defaults = TagMap::default();
+
+        assert_eq!(res[0].kind(), AggregationMethod::Set);
+        assert_eq!(res[0].name, "a.b");
+        assert_eq!(res[0].value(), Some(1.5));
+        assert_eq!(res[0].timestamp, 1_500_000_000);
+
+        assert_eq!(res[1].kind(), AggregationMethod::Set);
+        assert_eq!(res[1].name, "c");
+        assert_eq!(res[1].value(), Some(2.0));
+        assert_eq!(res[1].timestamp, 1_500_000_001);
+        assert_eq!(
+            Some(&"d".to_string()),
+            res[1].get_from_tags("host", &defaults)
+        );
+    }
+
+    #[test]
+    fn test_parse_graphite_pickle_invalid() {
+        let metric = sync::Arc::new(Some(Telemetry::default()));
+        // not a list
+        assert!(!parse_graphite_pickle(b"K\x01.", &mut Vec::new(), &metric));
+        // list of bare values
+        assert!(!parse_graphite_pickle(
+            b"\x80\x02]q\x00K\x01a.",
+            &mut Vec::new(),
+            &metric
+        ));
+        // truncated
+        assert!(!parse_graphite_pickle(
+            b"\x80\x02]q\x00(",
+            &mut Vec::new(),
+            &metric
+        ));
+    }
 }
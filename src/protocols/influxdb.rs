@@ -0,0 +1,369 @@
+//! Handle the InfluxDB line protocol.
+//!
+//! Each line has the form
+//!
+//! ```text
+//! measurement[,tag=value...] field=value[,field=value...] [timestamp]
+//! ```
+//!
+//! Each numeric or boolean field becomes one `Telemetry`, named
+//! `measurement.field`. String fields have no `Telemetry` representation and
+//! are skipped.
+
+use crate::metric::{AggregationMethod, Telemetry};
+use std::str::FromStr;
+use std::sync;
+
+/// The precision of line protocol timestamps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Precision {
+    /// Nanoseconds since the Unix epoch, the line protocol default.
+    Nanoseconds,
+    /// Microseconds since the Unix epoch.
+    Microseconds,
+    /// Milliseconds since the Unix epoch.
+    Milliseconds,
+    /// Seconds since the Unix epoch.
+    Seconds,
+    /// Minutes since the Unix epoch.
+    Minutes,
+    /// Hours since the Unix epoch.
+    Hours,
+}
+
+impl Default for Precision {
+    fn default() -> Precision {
+        Precision::Nanoseconds
+    }
+}
+
+impl Precision {
+    /// Parse a precision as given in the InfluxDB `precision` parameter.
+    pub fn parse(precision: &str) -> Option<Precision> {
+        match precision {
+            "n" | "ns" => Some(Precision::Nanoseconds),
+            "u" | "us" | "µ" | "µs" => Some(Precision::Microseconds),
+            "ms" => Some(Precision::Milliseconds),
+            "s" => Some(Precision::Seconds),
+            "m" => Some(Precision::Minutes),
+            "h" => Some(Precision::Hours),
+            _ => None,
+        }
+    }
+
+    /// Convert a timestamp of this precision to nanoseconds.
+    fn to_nanoseconds(self, timestamp: i64) -> i64 {
+        match self {
+            Precision::Nanoseconds => timestamp,
+            Precision::Microseconds => timestamp.saturating_mul(1_000),
+            Precision::Milliseconds => timestamp.saturating_mul(1_000_000),
+            Precision::Seconds => timestamp.saturating_mul(1_000_000_000),
+            Precision::Minutes => timestamp.saturating_mul(60_000_000_000),
+            Precision::Hours => timestamp.saturating_mul(3_600_000_000_000),
+        }
+    }
+}
+
+/// Split `s` on every `sep` not escaped by a backslash. If `quotes` is set,
+/// `sep` inside double quotes is not split on either.
+fn split_unescaped(s: &str, sep: char, quotes: bool) -> Vec<&str> {
+    let mut res = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+    let mut quoted = false;
+    for (idx, c) in s.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if quotes && c == '"' {
+            quoted = !quoted;
+        } else if c == sep && !quoted {
+            res.push(&s[start..idx]);
+            start = idx + c.len_utf8();
+        }
+    }
+    res.push(&s[start..]);
+    res
+}
+
+/// Split `s` at the first `sep` not escaped by a backslash.
+fn split_once_unescaped(s: &str, sep: char) -> Option<(&str, &str)> {
+    let mut escaped = false;
+    for (idx, c) in s.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == sep {
+            return Some((&s[..idx], &s[idx + c.len_utf8()..]));
+        }
+    }
+    None
+}
+
+/// Remove the backslash escapes from a measurement, tag key, tag value or
+/// field key.
+fn unescape(s: &str) -> String {
+    let mut res = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.peek() {
+                Some(&next)
+                    if next == ',' || next == '=' || next == ' ' || next == '\\' =>
+                {
+                    res.push(next);
+                    let _ = chars.next();
+                }
+                _ => res.push(c),
+            }
+        } else {
+            res.push(c);
+        }
+    }
+    res
+}
+
+/// Parse a field value. `Ok(None)` is returned for string fields, which
+/// cannot be represented as `Telemetry`.
+fn parse_field_value(val: &str) -> Result<Option<f64>, ()> {
+    if val.len() >= 2 && val.starts_with('"') && val.ends_with('"') {
+        return Ok(None);
+    }
+    match val {
+        "t" | "T" | "true" | "True" | "TRUE" => return Ok(Some(1.0)),
+        "f" | "F" | "false" | "False" | "FALSE" => return Ok(Some(0.0)),
+        _ => {}
+    }
+    if val.ends_with('i') {
+        i64::from_str(&val[..val.len() - 1])
+            .map(|v| Some(v as f64))
+            .map_err(|_| ())
+    } else if val.ends_with('u') {
+        u64::from_str(&val[..val.len() - 1])
+            .map(|v| Some(v as f64))
+            .map_err(|_| ())
+    } else {
+        f64::from_str(val).map(Some).map_err(|_| ())
+    }
+}
+
+fn parse_line(
+    line: &str,
+    precision: Precision,
+    res: &mut Vec<Telemetry>,
+    metric: &sync::Arc<Option<Telemetry>>,
+) -> bool {
+    let sections = split_unescaped(line, ' ', true);
+    let (key, fields, timestamp) = match sections.as_slice() {
+        [key, fields] => (*key, *fields, None),
+        [key, fields, timestamp] => (*key, *fields, Some(*timestamp)),
+        _ => return false,
+    };
+
+    let mut key_parts = split_unescaped(key, ',', false).into_iter();
+    let measurement = match key_parts.next() {
+        Some(m) if !m.is_empty() => unescape(m),
+        _ => return false,
+    };
+    let mut tags = Vec::new();
+    for tag in key_parts {
+        match split_once_unescaped(tag, '=') {
+            Some((k, v)) if !k.is_empty() && !v.is_empty() => {
+                tags.push((unescape(k), unescape(v)))
+            }
+            _ => return false,
+        }
+    }
+
+    let timestamp = match timestamp {
+        Some(ts) => match i64::from_str(ts) {
+            Ok(ts) => Some(precision.to_nanoseconds(ts)),
+            Err(_) => return false,
+        },
+        None => None,
+    };
+
+    let mut parsed_fields = 0;
+    for field in split_unescaped(fields, ',', true) {
+        let (field_key, field_val) = match split_once_unescaped(field, '=') {
+            Some((k, v)) if !k.is_empty() && !v.is_empty() => (k, v),
+            _ => return false,
+        };
+        parsed_fields += 1;
+        let value = match parse_field_value(field_val) {
+            Ok(Some(v)) => v,
+            Ok(None) => continue,
+            Err(()) => return false,
+        };
+        let template = sync::Arc::make_mut(&mut sync::Arc::clone(metric))
+            .take()
+            .unwrap();
+        let mut telem = template
+            .thaw()
+            .name(format!("{}.{}", measurement, unescape(field_key)))
+            .value(value)
+            .kind(AggregationMethod::Set);
+        if let Some(ts) = timestamp {
+            telem = telem.timestamp_ns(ts);
+        }
+        let mut telem = telem.harden().unwrap();
+        for &(ref k, ref v) in &tags {
+            telem = telem.overlay_tag(k.as_str(), v.as_str());
+        }
+        res.push(telem);
+    }
+    parsed_fields > 0
+}
+
+/// Parse a payload of InfluxDB line protocol into `metric::Telemetry` if
+/// possible.
+///
+/// Timestamps are interpreted with the given `precision`. Lines without a
+/// timestamp take the time of the `metric` template. Blank lines and comments
+/// are skipped. If any line is malformed the whole payload is rejected.
+pub fn parse_influxdb(
+    source: &str,
+    precision: Precision,
+    res: &mut Vec<Telemetry>,
+    metric: &sync::Arc<Option<Telemetry>>,
+) -> bool {
+    let mut lines = 0;
+    for line in source.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        lines += 1;
+        if !parse_line(line, precision, res, metric) {
+            return false;
+        }
+    }
+    lines > 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metric::TagMap;
+
+    fn parse(source: &str, precision: Precision) -> Option<Vec<Telemetry>> {
+        let metric = sync::Arc::new(Some(Telemetry::default()));
+        let mut res = Vec::new();
+        if parse_influxdb(source, precision, &mut res, &metric) {
+            Some(res)
+        } else {
+            None
+        }
+    }
+
+    #[test]
+    fn test_parse_influxdb_fields_and_tags() {
+        let mut res = parse(
+            "cpu,host=serverA,region=us-west usage_idle=98.5,usage_user=1i,up=true 1465839830100400200\n",
+            Precision::Nanoseconds,
+        )
+        .unwrap();
+        assert_eq!(res.len(), 3);
+
+        let defaults = TagMap::default();
+        assert_eq!(res[0].name, "cpu.usage_idle");
+        assert_eq!(res[0].set(), Some(98.5));
+        assert_eq!(res[0].timestamp, 1465839830);
+        assert_eq!(res[0].subsec_nanos, 100400200);
+        assert_eq!(res[0].kind(), AggregationMethod::Set);
+        assert_eq!(
+            res[0].get_from_tags("host", &defaults),
+            Some(&"serverA".to_string())
+        );
+        assert_eq!(
+            res[0].get_from_tags("region", &defaults),
+            Some(&"us-west".to_string())
+        );
+        assert_eq!(res[1].name, "cpu.usage_user");
+        assert_eq!(res[1].set(), Some(1.0));
+        assert_eq!(res[2].name, "cpu.up");
+        assert_eq!(res[2].set(), Some(1.0));
+        assert_eq!(
+            res[2].get_from_tags("host", &defaults),
+            Some(&"serverA".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_influxdb_escapes_and_strings() {
+        let mut res = parse(
+            "disk\\ io,path=/var/lib,label=a\\,b\\=c msg=\"hello, world\",read\\ bytes=512u",
+            Precision::Seconds,
+        )
+        .unwrap();
+        assert_eq!(res.len(), 1);
+        let defaults = TagMap::default();
+        assert_eq!(res[0].name, "disk io.read bytes");
+        assert_eq!(res[0].set(), Some(512.0));
+        assert_eq!(
+            res[0].get_from_tags("label", &defaults),
+            Some(&"a,b=c".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_influxdb_precision() {
+        let cases = [
+            ("1465839830100400200", Precision::Nanoseconds, 100400200),
+            ("1465839830100400", Precision::Microseconds, 100400000),
+            ("1465839830100", Precision::Milliseconds, 100000000),
+            ("1465839830", Precision::Seconds, 0),
+        ];
+        for &(ts, precision, subsec_nanos) in &cases {
+            let res = parse(&format!("m v=1 {}", ts), precision).unwrap();
+            assert_eq!(res[0].timestamp, 1465839830);
+            assert_eq!(res[0].subsec_nanos, subsec_nanos);
+        }
+        let res = parse("m v=1 24430663", Precision::Minutes).unwrap();
+        assert_eq!(res[0].timestamp, 1465839780);
+        let res = parse("m v=1 407177", Precision::Hours).unwrap();
+        assert_eq!(res[0].timestamp, 1465837200);
+
+        assert_eq!(Precision::parse("ms"), Some(Precision::Milliseconds));
+        assert_eq!(Precision::parse("u"), Some(Precision::Microseconds));
+        assert_eq!(Precision::parse("d"), None);
+    }
+
+    #[test]
+    fn test_parse_influxdb_multiple_lines() {
+        let res = parse(
+            "# a comment\n\nmem free=10i 1\nmem used=20i 2\n",
+            Precision::Seconds,
+        )
+        .unwrap();
+        assert_eq!(res.len(), 2);
+        assert_eq!(res[0].name, "mem.free");
+        assert_eq!(res[0].timestamp, 1);
+        assert_eq!(res[1].name, "mem.used");
+        assert_eq!(res[1].timestamp, 2);
+    }
+
+    #[test]
+    fn test_parse_influxdb_invalid() {
+        for source in &[
+            "",
+            "measurement_only",
+            ",tag=v f=1",
+            "m,tag f=1",
+            "m f=",
+            "m f=1 notatime",
+            "m f=abc",
+            "m f=1i2",
+            "m f=1 1 extra",
+            "m v=1\nbroken",
+        ] {
+            assert!(
+                parse(source, Precision::Nanoseconds).is_none(),
+                "{}",
+                source
+            );
+        }
+    }
+}
@@ -4,6 +4,11 @@
 #![allow(renamed_and_removed_lints)]
 
 pub mod graphite;
+pub mod influxdb;
 pub mod native;
+pub mod pickle;
 pub mod prometheus;
+pub mod prometheus_remote;
+pub mod prometheus_text;
 pub mod statsd;
+pub mod syslog;
@@ -609,6 +609,8 @@ pub struct Telemetry {
     pub metadata: ::std::collections::HashMap<::std::string::String, ::std::string::String>,
     timestamp_ms: ::std::option::Option<i64>,
     bin_bounds: ::std::vec::Vec<f64>,
+    distinct_registers: ::protobuf::SingularField<::std::vec::Vec<u8>>,
+    sketch: ::protobuf::SingularPtrField<Sketch>,
     // special fields
     unknown_fields: ::protobuf::UnknownFields,
     cached_size: ::protobuf::CachedSize,
@@ -855,10 +857,100 @@ impl Telemetry {
     fn mut_bin_bounds_for_reflect(&mut self) -> &mut ::std::vec::Vec<f64> {
         &mut self.bin_bounds
     }
+
+    // optional bytes distinct_registers = 8;
+
+    pub fn clear_distinct_registers(&mut self) {
+        self.distinct_registers.clear();
+    }
+
+    pub fn has_distinct_registers(&self) -> bool {
+        self.distinct_registers.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_distinct_registers(&mut self, v: ::std::vec::Vec<u8>) {
+        self.distinct_registers = ::protobuf::SingularField::some(v);
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_distinct_registers(&mut self) -> &mut ::std::vec::Vec<u8> {
+        if self.distinct_registers.is_none() {
+            self.distinct_registers.set_default();
+        }
+        self.distinct_registers.as_mut().unwrap()
+    }
+
+    // Take field
+    pub fn take_distinct_registers(&mut self) -> ::std::vec::Vec<u8> {
+        self.distinct_registers.take().unwrap_or_else(|| ::std::vec::Vec::new())
+    }
+
+    pub fn get_distinct_registers(&self) -> &[u8] {
+        match self.distinct_registers.as_ref() {
+            Some(v) => &v,
+            None => &[],
+        }
+    }
+
+    fn get_distinct_registers_for_reflect(&self) -> &::protobuf::SingularField<::std::vec::Vec<u8>> {
+        &self.distinct_registers
+    }
+
+    fn mut_distinct_registers_for_reflect(&mut self) -> &mut ::protobuf::SingularField<::std::vec::Vec<u8>> {
+        &mut self.distinct_registers
+    }
+
+    // optional .com.postmates.cernan.Sketch sketch = 9;
+
+    pub fn clear_sketch(&mut self) {
+        self.sketch.clear();
+    }
+
+    pub fn has_sketch(&self) -> bool {
+        self.sketch.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_sketch(&mut self, v: Sketch) {
+        self.sketch = ::protobuf::SingularPtrField::some(v);
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_sketch(&mut self) -> &mut Sketch {
+        if self.sketch.is_none() {
+            self.sketch.set_default();
+        }
+        self.sketch.as_mut().unwrap()
+    }
+
+    // Take field
+    pub fn take_sketch(&mut self) -> Sketch {
+        self.sketch.take().unwrap_or_else(|| Sketch::new())
+    }
+
+    pub fn get_sketch(&self) -> &Sketch {
+        self.sketch.as_ref().unwrap_or_else(|| Sketch::default_instance())
+    }
+
+    fn get_sketch_for_reflect(&self) -> &::protobuf::SingularPtrField<Sketch> {
+        &self.sketch
+    }
+
+    fn mut_sketch_for_reflect(&mut self) -> &mut ::protobuf::SingularPtrField<Sketch> {
+        &mut self.sketch
+    }
 }
 
 impl ::protobuf::Message for Telemetry {
     fn is_initialized(&self) -> bool {
+        for v in &self.sketch {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
         true
     }
 
@@ -899,6 +991,12 @@ impl ::protobuf::Message for Telemetry {
                 7 => {
                     ::protobuf::rt::read_repeated_double_into(wire_type, is, &mut self.bin_bounds)?;
                 },
+                8 => {
+                    ::protobuf::rt::read_singular_bytes_into(wire_type, is, &mut self.distinct_registers)?;
+                },
+                9 => {
+                    ::protobuf::rt::read_singular_message_into(wire_type, is, &mut self.sketch)?;
+                },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
                 },
@@ -928,6 +1026,13 @@ impl ::protobuf::Message for Telemetry {
             my_size += ::protobuf::rt::value_size(6, v, ::protobuf::wire_format::WireTypeVarint);
         }
         my_size += 9 * self.bin_bounds.len() as u32;
+        if let Some(ref v) = self.distinct_registers.as_ref() {
+            my_size += ::protobuf::rt::bytes_size(8, &v);
+        }
+        if let Some(ref v) = self.sketch.as_ref() {
+            let len = v.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
@@ -958,6 +1063,14 @@ impl ::protobuf::Message for Telemetry {
         for v in &self.bin_bounds {
             os.write_double(7, *v)?;
         };
+        if let Some(ref v) = self.distinct_registers.as_ref() {
+            os.write_bytes(8, &v)?;
+        }
+        if let Some(ref v) = self.sketch.as_ref() {
+            os.write_tag(9, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -1037,6 +1150,16 @@ impl ::protobuf::MessageStatic for Telemetry {
                     Telemetry::get_bin_bounds_for_reflect,
                     Telemetry::mut_bin_bounds_for_reflect,
                 ));
+                fields.push(::protobuf::reflect::accessor::make_singular_field_accessor::<_, ::protobuf::types::ProtobufTypeBytes>(
+                    "distinct_registers",
+                    Telemetry::get_distinct_registers_for_reflect,
+                    Telemetry::mut_distinct_registers_for_reflect,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_singular_ptr_field_accessor::<_, ::protobuf::types::ProtobufTypeMessage<Sketch>>(
+                    "sketch",
+                    Telemetry::get_sketch_for_reflect,
+                    Telemetry::mut_sketch_for_reflect,
+                ));
                 ::protobuf::reflect::MessageDescriptor::new::<Telemetry>(
                     "Telemetry",
                     fields,
@@ -1056,6 +1179,8 @@ impl ::protobuf::Clear for Telemetry {
         self.clear_metadata();
         self.clear_timestamp_ms();
         self.clear_bin_bounds();
+        self.clear_distinct_registers();
+        self.clear_sketch();
         self.unknown_fields.clear();
     }
 }
@@ -1072,12 +1197,591 @@ impl ::protobuf::reflect::ProtobufValue for Telemetry {
     }
 }
 
+#[derive(PartialEq,Clone,Default)]
+pub struct Sketch {
+    // message fields
+    relative_accuracy: ::std::option::Option<f64>,
+    positive_indexes: ::std::vec::Vec<i32>,
+    positive_counts: ::std::vec::Vec<u64>,
+    negative_indexes: ::std::vec::Vec<i32>,
+    negative_counts: ::std::vec::Vec<u64>,
+    zero_count: ::std::option::Option<u64>,
+    sum: ::std::option::Option<f64>,
+    min: ::std::option::Option<f64>,
+    max: ::std::option::Option<f64>,
+    // special fields
+    unknown_fields: ::protobuf::UnknownFields,
+    cached_size: ::protobuf::CachedSize,
+}
+
+// see codegen.rs for the explanation why impl Sync explicitly
+unsafe impl ::std::marker::Sync for Sketch {}
+
+impl Sketch {
+    pub fn new() -> Sketch {
+        ::std::default::Default::default()
+    }
+
+    pub fn default_instance() -> &'static Sketch {
+        static mut instance: ::protobuf::lazy::Lazy<Sketch> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const Sketch,
+        };
+        unsafe {
+            instance.get(Sketch::new)
+        }
+    }
+
+    // optional double relative_accuracy = 1;
+
+    pub fn clear_relative_accuracy(&mut self) {
+        self.relative_accuracy = ::std::option::Option::None;
+    }
+
+    pub fn has_relative_accuracy(&self) -> bool {
+        self.relative_accuracy.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_relative_accuracy(&mut self, v: f64) {
+        self.relative_accuracy = ::std::option::Option::Some(v);
+    }
+
+    pub fn get_relative_accuracy(&self) -> f64 {
+        self.relative_accuracy.unwrap_or(0.)
+    }
+
+    fn get_relative_accuracy_for_reflect(&self) -> &::std::option::Option<f64> {
+        &self.relative_accuracy
+    }
+
+    fn mut_relative_accuracy_for_reflect(&mut self) -> &mut ::std::option::Option<f64> {
+        &mut self.relative_accuracy
+    }
+
+    // repeated sint32 positive_indexes = 2;
+
+    pub fn clear_positive_indexes(&mut self) {
+        self.positive_indexes.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_positive_indexes(&mut self, v: ::std::vec::Vec<i32>) {
+        self.positive_indexes = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_positive_indexes(&mut self) -> &mut ::std::vec::Vec<i32> {
+        &mut self.positive_indexes
+    }
+
+    // Take field
+    pub fn take_positive_indexes(&mut self) -> ::std::vec::Vec<i32> {
+        ::std::mem::replace(&mut self.positive_indexes, ::std::vec::Vec::new())
+    }
+
+    pub fn get_positive_indexes(&self) -> &[i32] {
+        &self.positive_indexes
+    }
+
+    fn get_positive_indexes_for_reflect(&self) -> &::std::vec::Vec<i32> {
+        &self.positive_indexes
+    }
+
+    fn mut_positive_indexes_for_reflect(&mut self) -> &mut ::std::vec::Vec<i32> {
+        &mut self.positive_indexes
+    }
+
+    // repeated uint64 positive_counts = 3;
+
+    pub fn clear_positive_counts(&mut self) {
+        self.positive_counts.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_positive_counts(&mut self, v: ::std::vec::Vec<u64>) {
+        self.positive_counts = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_positive_counts(&mut self) -> &mut ::std::vec::Vec<u64> {
+        &mut self.positive_counts
+    }
+
+    // Take field
+    pub fn take_positive_counts(&mut self) -> ::std::vec::Vec<u64> {
+        ::std::mem::replace(&mut self.positive_counts, ::std::vec::Vec::new())
+    }
+
+    pub fn get_positive_counts(&self) -> &[u64] {
+        &self.positive_counts
+    }
+
+    fn get_positive_counts_for_reflect(&self) -> &::std::vec::Vec<u64> {
+        &self.positive_counts
+    }
+
+    fn mut_positive_counts_for_reflect(&mut self) -> &mut ::std::vec::Vec<u64> {
+        &mut self.positive_counts
+    }
+
+    // repeated sint32 negative_indexes = 4;
+
+    pub fn clear_negative_indexes(&mut self) {
+        self.negative_indexes.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_negative_indexes(&mut self, v: ::std::vec::Vec<i32>) {
+        self.negative_indexes = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_negative_indexes(&mut self) -> &mut ::std::vec::Vec<i32> {
+        &mut self.negative_indexes
+    }
+
+    // Take field
+    pub fn take_negative_indexes(&mut self) -> ::std::vec::Vec<i32> {
+        ::std::mem::replace(&mut self.negative_indexes, ::std::vec::Vec::new())
+    }
+
+    pub fn get_negative_indexes(&self) -> &[i32] {
+        &self.negative_indexes
+    }
+
+    fn get_negative_indexes_for_reflect(&self) -> &::std::vec::Vec<i32> {
+        &self.negative_indexes
+    }
+
+    fn mut_negative_indexes_for_reflect(&mut self) -> &mut ::std::vec::Vec<i32> {
+        &mut self.negative_indexes
+    }
+
+    // repeated uint64 negative_counts = 5;
+
+    pub fn clear_negative_counts(&mut self) {
+        self.negative_counts.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_negative_counts(&mut self, v: ::std::vec::Vec<u64>) {
+        self.negative_counts = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_negative_counts(&mut self) -> &mut ::std::vec::Vec<u64> {
+        &mut self.negative_counts
+    }
+
+    // Take field
+    pub fn take_negative_counts(&mut self) -> ::std::vec::Vec<u64> {
+        ::std::mem::replace(&mut self.negative_counts, ::std::vec::Vec::new())
+    }
+
+    pub fn get_negative_counts(&self) -> &[u64] {
+        &self.negative_counts
+    }
+
+    fn get_negative_counts_for_reflect(&self) -> &::std::vec::Vec<u64> {
+        &self.negative_counts
+    }
+
+    fn mut_negative_counts_for_reflect(&mut self) -> &mut ::std::vec::Vec<u64> {
+        &mut self.negative_counts
+    }
+
+    // optional uint64 zero_count = 6;
+
+    pub fn clear_zero_count(&mut self) {
+        self.zero_count = ::std::option::Option::None;
+    }
+
+    pub fn has_zero_count(&self) -> bool {
+        self.zero_count.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_zero_count(&mut self, v: u64) {
+        self.zero_count = ::std::option::Option::Some(v);
+    }
+
+    pub fn get_zero_count(&self) -> u64 {
+        self.zero_count.unwrap_or(0)
+    }
+
+    fn get_zero_count_for_reflect(&self) -> &::std::option::Option<u64> {
+        &self.zero_count
+    }
+
+    fn mut_zero_count_for_reflect(&mut self) -> &mut ::std::option::Option<u64> {
+        &mut self.zero_count
+    }
+
+    // optional double sum = 7;
+
+    pub fn clear_sum(&mut self) {
+        self.sum = ::std::option::Option::None;
+    }
+
+    pub fn has_sum(&self) -> bool {
+        self.sum.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_sum(&mut self, v: f64) {
+        self.sum = ::std::option::Option::Some(v);
+    }
+
+    pub fn get_sum(&self) -> f64 {
+        self.sum.unwrap_or(0.)
+    }
+
+    fn get_sum_for_reflect(&self) -> &::std::option::Option<f64> {
+        &self.sum
+    }
+
+    fn mut_sum_for_reflect(&mut self) -> &mut ::std::option::Option<f64> {
+        &mut self.sum
+    }
+
+    // optional double min = 8;
+
+    pub fn clear_min(&mut self) {
+        self.min = ::std::option::Option::None;
+    }
+
+    pub fn has_min(&self) -> bool {
+        self.min.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_min(&mut self, v: f64) {
+        self.min = ::std::option::Option::Some(v);
+    }
+
+    pub fn get_min(&self) -> f64 {
+        self.min.unwrap_or(0.)
+    }
+
+    fn get_min_for_reflect(&self) -> &::std::option::Option<f64> {
+        &self.min
+    }
+
+    fn mut_min_for_reflect(&mut self) -> &mut ::std::option::Option<f64> {
+        &mut self.min
+    }
+
+    // optional double max = 9;
+
+    pub fn clear_max(&mut self) {
+        self.max = ::std::option::Option::None;
+    }
+
+    pub fn has_max(&self) -> bool {
+        self.max.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_max(&mut self, v: f64) {
+        self.max = ::std::option::Option::Some(v);
+    }
+
+    pub fn get_max(&self) -> f64 {
+        self.max.unwrap_or(0.)
+    }
+
+    fn get_max_for_reflect(&self) -> &::std::option::Option<f64> {
+        &self.max
+    }
+
+    fn mut_max_for_reflect(&mut self) -> &mut ::std::option::Option<f64> {
+        &mut self.max
+    }
+}
+
+impl ::protobuf::Message for Sketch {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeFixed64 {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_double()?;
+                    self.relative_accuracy = ::std::option::Option::Some(tmp);
+                },
+                2 => {
+                    ::protobuf::rt::read_repeated_sint32_into(wire_type, is, &mut self.positive_indexes)?;
+                },
+                3 => {
+                    ::protobuf::rt::read_repeated_uint64_into(wire_type, is, &mut self.positive_counts)?;
+                },
+                4 => {
+                    ::protobuf::rt::read_repeated_sint32_into(wire_type, is, &mut self.negative_indexes)?;
+                },
+                5 => {
+                    ::protobuf::rt::read_repeated_uint64_into(wire_type, is, &mut self.negative_counts)?;
+                },
+                6 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.zero_count = ::std::option::Option::Some(tmp);
+                },
+                7 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeFixed64 {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_double()?;
+                    self.sum = ::std::option::Option::Some(tmp);
+                },
+                8 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeFixed64 {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_double()?;
+                    self.min = ::std::option::Option::Some(tmp);
+                },
+                9 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeFixed64 {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_double()?;
+                    self.max = ::std::option::Option::Some(tmp);
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if let Some(v) = self.relative_accuracy {
+            my_size += 9;
+        }
+        if !self.positive_indexes.is_empty() {
+            my_size += ::protobuf::rt::vec_packed_varint_zigzag_size(2, &self.positive_indexes);
+        }
+        if !self.positive_counts.is_empty() {
+            my_size += ::protobuf::rt::vec_packed_varint_size(3, &self.positive_counts);
+        }
+        if !self.negative_indexes.is_empty() {
+            my_size += ::protobuf::rt::vec_packed_varint_zigzag_size(4, &self.negative_indexes);
+        }
+        if !self.negative_counts.is_empty() {
+            my_size += ::protobuf::rt::vec_packed_varint_size(5, &self.negative_counts);
+        }
+        if let Some(v) = self.zero_count {
+            my_size += ::protobuf::rt::value_size(6, v, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(v) = self.sum {
+            my_size += 9;
+        }
+        if let Some(v) = self.min {
+            my_size += 9;
+        }
+        if let Some(v) = self.max {
+            my_size += 9;
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream) -> ::protobuf::ProtobufResult<()> {
+        if let Some(v) = self.relative_accuracy {
+            os.write_double(1, v)?;
+        }
+        if !self.positive_indexes.is_empty() {
+            os.write_tag(2, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            // TODO: Data size is computed again, it should be cached
+            os.write_raw_varint32(::protobuf::rt::vec_packed_varint_zigzag_data_size(&self.positive_indexes))?;
+            for v in &self.positive_indexes {
+                os.write_sint32_no_tag(*v)?;
+            };
+        }
+        if !self.positive_counts.is_empty() {
+            os.write_tag(3, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            // TODO: Data size is computed again, it should be cached
+            os.write_raw_varint32(::protobuf::rt::vec_packed_varint_data_size(&self.positive_counts))?;
+            for v in &self.positive_counts {
+                os.write_uint64_no_tag(*v)?;
+            };
+        }
+        if !self.negative_indexes.is_empty() {
+            os.write_tag(4, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            // TODO: Data size is computed again, it should be cached
+            os.write_raw_varint32(::protobuf::rt::vec_packed_varint_zigzag_data_size(&self.negative_indexes))?;
+            for v in &self.negative_indexes {
+                os.write_sint32_no_tag(*v)?;
+            };
+        }
+        if !self.negative_counts.is_empty() {
+            os.write_tag(5, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            // TODO: Data size is computed again, it should be cached
+            os.write_raw_varint32(::protobuf::rt::vec_packed_varint_data_size(&self.negative_counts))?;
+            for v in &self.negative_counts {
+                os.write_uint64_no_tag(*v)?;
+            };
+        }
+        if let Some(v) = self.zero_count {
+            os.write_uint64(6, v)?;
+        }
+        if let Some(v) = self.sum {
+            os.write_double(7, v)?;
+        }
+        if let Some(v) = self.min {
+            os.write_double(8, v)?;
+        }
+        if let Some(v) = self.max {
+            os.write_double(9, v)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &::std::any::Any {
+        self as &::std::any::Any
+    }
+    fn as_any_mut(&mut self) -> &mut ::std::any::Any {
+        self as &mut ::std::any::Any
+    }
+    fn into_any(self: Box<Self>) -> ::std::boxed::Box<::std::any::Any> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        ::protobuf::MessageStatic::descriptor_static(None::<Self>)
+    }
+}
+
+impl ::protobuf::MessageStatic for Sketch {
+    fn new() -> Sketch {
+        Sketch::new()
+    }
+
+    fn descriptor_static(_: ::std::option::Option<Sketch>) -> &'static ::protobuf::reflect::MessageDescriptor {
+        static mut descriptor: ::protobuf::lazy::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const ::protobuf::reflect::MessageDescriptor,
+        };
+        unsafe {
+            descriptor.get(|| {
+                let mut fields = ::std::vec::Vec::new();
+                fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeDouble>(
+                    "relative_accuracy",
+                    Sketch::get_relative_accuracy_for_reflect,
+                    Sketch::mut_relative_accuracy_for_reflect,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_vec_accessor::<_, ::protobuf::types::ProtobufTypeSint32>(
+                    "positive_indexes",
+                    Sketch::get_positive_indexes_for_reflect,
+                    Sketch::mut_positive_indexes_for_reflect,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_vec_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                    "positive_counts",
+                    Sketch::get_positive_counts_for_reflect,
+                    Sketch::mut_positive_counts_for_reflect,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_vec_accessor::<_, ::protobuf::types::ProtobufTypeSint32>(
+                    "negative_indexes",
+                    Sketch::get_negative_indexes_for_reflect,
+                    Sketch::mut_negative_indexes_for_reflect,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_vec_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                    "negative_counts",
+                    Sketch::get_negative_counts_for_reflect,
+                    Sketch::mut_negative_counts_for_reflect,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                    "zero_count",
+                    Sketch::get_zero_count_for_reflect,
+                    Sketch::mut_zero_count_for_reflect,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeDouble>(
+                    "sum",
+                    Sketch::get_sum_for_reflect,
+                    Sketch::mut_sum_for_reflect,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeDouble>(
+                    "min",
+                    Sketch::get_min_for_reflect,
+                    Sketch::mut_min_for_reflect,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_option_accessor::<_, ::protobuf::types::ProtobufTypeDouble>(
+                    "max",
+                    Sketch::get_max_for_reflect,
+                    Sketch::mut_max_for_reflect,
+                ));
+                ::protobuf::reflect::MessageDescriptor::new::<Sketch>(
+                    "Sketch",
+                    fields,
+                    file_descriptor_proto()
+                )
+            })
+        }
+    }
+}
+
+impl ::protobuf::Clear for Sketch {
+    fn clear(&mut self) {
+        self.clear_relative_accuracy();
+        self.clear_positive_indexes();
+        self.clear_positive_counts();
+        self.clear_negative_indexes();
+        self.clear_negative_counts();
+        self.clear_zero_count();
+        self.clear_sum();
+        self.clear_min();
+        self.clear_max();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for Sketch {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for Sketch {
+    fn as_ref(&self) -> ::protobuf::reflect::ProtobufValueRef {
+        ::protobuf::reflect::ProtobufValueRef::Message(self)
+    }
+}
+
 #[derive(Clone,PartialEq,Eq,Debug,Hash)]
 pub enum AggregationMethod {
     SUM = 1,
     SET = 2,
     SUMMARIZE = 3,
     BIN = 4,
+    DISTINCT = 5,
+    SKETCH = 6,
 }
 
 impl ::protobuf::ProtobufEnum for AggregationMethod {
@@ -1091,6 +1795,8 @@ impl ::protobuf::ProtobufEnum for AggregationMethod {
             2 => ::std::option::Option::Some(AggregationMethod::SET),
             3 => ::std::option::Option::Some(AggregationMethod::SUMMARIZE),
             4 => ::std::option::Option::Some(AggregationMethod::BIN),
+            5 => ::std::option::Option::Some(AggregationMethod::DISTINCT),
+            6 => ::std::option::Option::Some(AggregationMethod::SKETCH),
             _ => ::std::option::Option::None
         }
     }
@@ -1101,6 +1807,8 @@ impl ::protobuf::ProtobufEnum for AggregationMethod {
             AggregationMethod::SET,
             AggregationMethod::SUMMARIZE,
             AggregationMethod::BIN,
+            AggregationMethod::DISTINCT,
+            AggregationMethod::SKETCH,
         ];
         values
     }
@@ -1137,164 +1845,175 @@ static file_descriptor_proto_data: &'static [u8] = b"\
     .LogLine.MetadataEntryR\x08metadata\x12!\n\x0ctimestamp_ms\x18\x04\x20\
     \x01(\x03R\x0btimestampMs\x1a;\n\rMetadataEntry\x12\x10\n\x03key\x18\x01\
     \x20\x01(\tR\x03key\x12\x14\n\x05value\x18\x02\x20\x01(\tR\x05value:\x02\
-    8\x01\"\xf8\x02\n\tTelemetry\x12\x12\n\x04name\x18\x01\x20\x01(\tR\x04na\
+    8\x01\"\xdd\x03\n\tTelemetry\x12\x12\n\x04name\x18\x01\x20\x01(\tR\x04na\
     me\x12\x1c\n\x07samples\x18\x02\x20\x03(\x01R\x07samplesB\x02\x10\x01\
     \x12#\n\tpersisted\x18\x03\x20\x01(\x08:\x05falseR\tpersisted\x12J\n\x06\
     method\x18\x04\x20\x01(\x0e2'.com.postmates.cernan.AggregationMethod:\tS\
     UMMARIZER\x06method\x12I\n\x08metadata\x18\x05\x20\x03(\x0b2-.com.postma\
     tes.cernan.Telemetry.MetadataEntryR\x08metadata\x12!\n\x0ctimestamp_ms\
     \x18\x06\x20\x01(\x03R\x0btimestampMs\x12\x1d\n\nbin_bounds\x18\x07\x20\
-    \x03(\x01R\tbinBounds\x1a;\n\rMetadataEntry\x12\x10\n\x03key\x18\x01\x20\
-    \x01(\tR\x03key\x12\x14\n\x05value\x18\x02\x20\x01(\tR\x05value:\x028\
-    \x01*=\n\x11AggregationMethod\x12\x07\n\x03SUM\x10\x01\x12\x07\n\x03SET\
-    \x10\x02\x12\r\n\tSUMMARIZE\x10\x03\x12\x07\n\x03BIN\x10\x04B\x16\n\x14c\
-    om.postmates.cernanJ\xae(\n\x06\x12\x04\x20\0c\x01\n\xa0\x0c\n\x01\x0c\
-    \x12\x03\x20\0\x12\x1a\xe3\x03\n\x20Welcome!\n\n\x20This\x20file\x20defi\
-    nes\x20the\x20protocol\x20that\x20cernan\x20speaks\x20natively.\x20We\
-    \x20hope\x20that\x20it's\n\x20a\x20relatively\x20straightforward\x20prot\
-    ocol\x20to\x20implement.\x20Cernan's\x20native\x20transport\n\x20is\x20T\
-    CP.\x20We\x20require\x20that\x20all\x20on-wire\x20payloads\x20have\x20th\
-    e\x20following\x20form:\n\n\x20\x20\x20\x20\x20[------------------------\
-    --------|~~~~~~~~~~\x20.\x20.\x20.\x20~~~~~~~~~~~~]\n\x20\x20\x20\x20\
-    \x20^\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\
-    \x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20^\n\x20\
-    \x20\x20\x20\x20u32,\x20payload\x20length\x20in\x20bytes\x20\x20\x20\x20\
-    \x20protobuf\x20payload,\x20of\x20prefix\x20len\n\n\x20The\x20protobuf\
-    \x20payload\x20conforms\x20to\x20the\x20following\x20definition.\n2\xaf\
-    \x08\x20Copyright\x202016,\x20Postmates\x20Inc.\n\n\x20Permission\x20is\
-    \x20hereby\x20granted,\x20free\x20of\x20charge,\x20to\x20any\x20person\
-    \x20obtaining\x20a\x20copy\n\x20of\x20this\x20software\x20and\x20associa\
-    ted\x20documentation\x20files\x20(the\x20\"Software\"),\x20to\x20deal\n\
-    \x20in\x20the\x20Software\x20without\x20restriction,\x20including\x20wit\
-    hout\x20limitation\x20the\x20rights\n\x20to\x20use,\x20copy,\x20modify,\
-    \x20merge,\x20publish,\x20distribute,\x20sublicense,\x20and/or\x20sell\n\
-    \x20copies\x20of\x20the\x20Software,\x20and\x20to\x20permit\x20persons\
-    \x20to\x20whom\x20the\x20Software\x20is\n\x20furnished\x20to\x20do\x20so\
-    ,\x20subject\x20to\x20the\x20following\x20conditions:\n\n\x20The\x20abov\
-    e\x20copyright\x20notice\x20and\x20this\x20permission\x20notice\x20shall\
-    \x20be\x20included\x20in\n\x20all\x20copies\x20or\x20substantial\x20port\
-    ions\x20of\x20the\x20Software.\n\n\x20THE\x20SOFTWARE\x20IS\x20PROVIDED\
-    \x20\"AS\x20IS\",\x20WITHOUT\x20WARRANTY\x20OF\x20ANY\x20KIND,\x20EXPRES\
-    S\x20OR\n\x20IMPLIED,\x20INCLUDING\x20BUT\x20NOT\x20LIMITED\x20TO\x20THE\
-    \x20WARRANTIES\x20OF\x20MERCHANTABILITY,\n\x20FITNESS\x20FOR\x20A\x20PAR\
-    TICULAR\x20PURPOSE\x20AND\x20NONINFRINGEMENT.\x20IN\x20NO\x20EVENT\x20SH\
-    ALL\x20THE\n\x20AUTHORS\x20OR\x20COPYRIGHT\x20HOLDERS\x20BE\x20LIABLE\
-    \x20FOR\x20ANY\x20CLAIM,\x20DAMAGES\x20OR\x20OTHER\n\x20LIABILITY,\x20WH\
-    ETHER\x20IN\x20AN\x20ACTION\x20OF\x20CONTRACT,\x20TORT\x20OR\x20OTHERWIS\
-    E,\x20ARISING\x20FROM,\n\x20OUT\x20OF\x20OR\x20IN\x20CONNECTION\x20WITH\
-    \x20THE\x20SOFTWARE\x20OR\x20THE\x20USE\x20OR\x20OTHER\x20DEALINGS\x20IN\
-    \x20THE\n\x20SOFTWARE.\n\n\x08\n\x01\x02\x12\x03\"\x08\x1c\n\x08\n\x01\
-    \x08\x12\x03#\0-\n\x0b\n\x04\x08\xe7\x07\0\x12\x03#\0-\n\x0c\n\x05\x08\
-    \xe7\x07\0\x02\x12\x03#\x07\x13\n\r\n\x06\x08\xe7\x07\0\x02\0\x12\x03#\
-    \x07\x13\n\x0e\n\x07\x08\xe7\x07\0\x02\0\x01\x12\x03#\x07\x13\n\x0c\n\
-    \x05\x08\xe7\x07\0\x07\x12\x03#\x16,\n\xb0\x01\n\x02\x04\0\x12\x04)\0,\
-    \x01\x1a\xa3\x01\x20'Payload'\x20-\x20the\x20top-level\x20structure\x20i\
-    n\x20each\x20on-wire\x20payload\n\n\x20Payload\x20is\x20a\x20container\
-    \x20for\x20repeated\x20Telemetry\x20and\x20LogLines.\x20There's\x20not\
-    \x20much\n\x20more\x20to\x20it\x20than\x20that.\n\n\n\n\x03\x04\0\x01\
-    \x12\x03)\x08\x0f\n\x0b\n\x04\x04\0\x02\0\x12\x03*\x02\x20\n\x0c\n\x05\
-    \x04\0\x02\0\x04\x12\x03*\x02\n\n\x0c\n\x05\x04\0\x02\0\x06\x12\x03*\x0b\
-    \x14\n\x0c\n\x05\x04\0\x02\0\x01\x12\x03*\x15\x1b\n\x0c\n\x05\x04\0\x02\
-    \0\x03\x12\x03*\x1e\x1f\n\x0b\n\x04\x04\0\x02\x01\x12\x03+\x02\x1d\n\x0c\
-    \n\x05\x04\0\x02\x01\x04\x12\x03+\x02\n\n\x0c\n\x05\x04\0\x02\x01\x06\
-    \x12\x03+\x0b\x12\n\x0c\n\x05\x04\0\x02\x01\x01\x12\x03+\x13\x18\n\x0c\n\
-    \x05\x04\0\x02\x01\x03\x12\x03+\x1b\x1c\n\x99\x02\n\x02\x04\x01\x12\x044\
-    \09\x01\x1a\x8c\x02\x20'LogLine'\x20-\x20a\x20bit\x20of\x20unstructure\
-    \x20text\n\n\x20One\x20of\x20cernan's\x20gigs\x20is\x20picking\x20up\x20\
-    logs\x20from\x20disk\x20and\x20transforming\x20them\n\x20in-flight,\x20s\
-    hipping\x20them\x20off.\x20This\x20structure\x20allows\x20you\x20to\x20s\
-    hip\x20lines\n\x20directly\x20via\x20the\x20native\x20protocol\x20withou\
-    t\x20having\x20to\x20round-trip\x20through\x20disk\n\x20first.\n\n\n\n\
-    \x03\x04\x01\x01\x12\x034\x08\x0f\n0\n\x04\x04\x01\x02\0\x12\x035\x02\
-    \x1b\"#\x20unique\x20'location'\x20of\x20the\x20log\x20line\n\n\x0c\n\
-    \x05\x04\x01\x02\0\x04\x12\x035\x02\n\n\x0c\n\x05\x04\x01\x02\0\x05\x12\
-    \x035\x0b\x11\n\x0c\n\x05\x04\x01\x02\0\x01\x12\x035\x12\x16\n\x0c\n\x05\
-    \x04\x01\x02\0\x03\x12\x035\x19\x1a\n\x1e\n\x04\x04\x01\x02\x01\x12\x036\
-    \x02\x1c\"\x11\x20the\x20line\x20itself\n\n\x0c\n\x05\x04\x01\x02\x01\
-    \x04\x12\x036\x02\n\n\x0c\n\x05\x04\x01\x02\x01\x05\x12\x036\x0b\x11\n\
-    \x0c\n\x05\x04\x01\x02\x01\x01\x12\x036\x12\x17\n\x0c\n\x05\x04\x01\x02\
-    \x01\x03\x12\x036\x1a\x1b\n,\n\x04\x04\x01\x02\x02\x12\x037\x02#\"\x1f\
-    \x20associated\x20key/value\x20metadata\n\n\r\n\x05\x04\x01\x02\x02\x04\
-    \x12\x047\x026\x1c\n\x0c\n\x05\x04\x01\x02\x02\x06\x12\x037\x02\x15\n\
-    \x0c\n\x05\x04\x01\x02\x02\x01\x12\x037\x16\x1e\n\x0c\n\x05\x04\x01\x02\
-    \x02\x03\x12\x037!\"\n0\n\x04\x04\x01\x02\x03\x12\x038\x02\"\"#\x20milli\
-    seconds\x20since\x20the\x20Unix\x20epoch\n\n\x0c\n\x05\x04\x01\x02\x03\
-    \x04\x12\x038\x02\n\n\x0c\n\x05\x04\x01\x02\x03\x05\x12\x038\x0b\x10\n\
-    \x0c\n\x05\x04\x01\x02\x03\x01\x12\x038\x11\x1d\n\x0c\n\x05\x04\x01\x02\
-    \x03\x03\x12\x038\x20!\n\x8f\x04\n\x02\x04\x02\x12\x04D\0L\x01\x1a\x82\
-    \x04\x20'Telemetry'\x20-\x20a\x20numeric\x20measure\x20of\x20a\x20thing\
-    \n\n\x20Cernan's\x20slightly\x20more\x20complicated\x20gig\x20is\x20its\
-    \x20'telemetry'\n\x20subsystem.\x20Telemetry\x20is\x20defined\x20as\x20a\
-    \x20name\x20and\x20time\x20associated\x20collection\x20of\n\x20measureme\
-    nts.\x20In\x20the\x20structure\x20we\x20refer\x20to\x20these\x20measurem\
-    ents\x20as\n\x20'samples'.\x20The\x20Telemetry\x20structure\x20makes\x20\
-    is\x20possible\x20to\x20associate\x20multiple\n\x20samples\x20in\x20a\
-    \x20single\x20millisecond\x20time\x20window.\x20Cernan\x20will\x20build\
-    \x20a\x20quantile\n\x20structure\x20over\x20these\x20samples\x20but\x20y\
-    ou\x20may\x20further\x20choose\x20aggregation\n\x20interpretations\x20by\
-    \x20setting\x20AggregationMethod.\n\n\n\n\x03\x04\x02\x01\x12\x03D\x08\
-    \x11\n/\n\x04\x04\x02\x02\0\x12\x03E\x02\x1b\"\"\x20the\x20unique\x20nam\
-    e\x20of\x20the\x20telemetry\n\n\x0c\n\x05\x04\x02\x02\0\x04\x12\x03E\x02\
-    \n\n\x0c\n\x05\x04\x02\x02\0\x05\x12\x03E\x0b\x11\n\x0c\n\x05\x04\x02\
-    \x02\0\x01\x12\x03E\x12\x16\n\x0c\n\x05\x04\x02\x02\0\x03\x12\x03E\x19\
-    \x1a\n8\n\x04\x04\x02\x02\x01\x12\x03F\x020\"+\x20telemetry\x20samples\
-    \x20present\x20in\x20timestamp_ms\n\n\x0c\n\x05\x04\x02\x02\x01\x04\x12\
-    \x03F\x02\n\n\x0c\n\x05\x04\x02\x02\x01\x05\x12\x03F\x0b\x11\n\x0c\n\x05\
-    \x04\x02\x02\x01\x01\x12\x03F\x12\x19\n\x0c\n\x05\x04\x02\x02\x01\x03\
-    \x12\x03F\x1c\x1d\n\x0c\n\x05\x04\x02\x02\x01\x08\x12\x03F\x1e/\n\x0f\n\
-    \x08\x04\x02\x02\x01\x08\xe7\x07\0\x12\x03F\x20-\n\x10\n\t\x04\x02\x02\
-    \x01\x08\xe7\x07\0\x02\x12\x03F\x20&\n\x11\n\n\x04\x02\x02\x01\x08\xe7\
-    \x07\0\x02\0\x12\x03F\x20&\n\x12\n\x0b\x04\x02\x02\x01\x08\xe7\x07\0\x02\
-    \0\x01\x12\x03F\x20&\n\x10\n\t\x04\x02\x02\x01\x08\xe7\x07\0\x03\x12\x03\
-    F)-\n1\n\x04\x04\x02\x02\x02\x12\x03G\x022\"$\x20persist\x20metric\x20ac\
-    ross\x20time\x20windows\n\n\x0c\n\x05\x04\x02\x02\x02\x04\x12\x03G\x02\n\
-    \n\x0c\n\x05\x04\x02\x02\x02\x05\x12\x03G\x0b\x0f\n\x0c\n\x05\x04\x02\
-    \x02\x02\x01\x12\x03G\x10\x19\n\x0c\n\x05\x04\x02\x02\x02\x03\x12\x03G\
-    \x1c\x1d\n\x0c\n\x05\x04\x02\x02\x02\x08\x12\x03G\x1e1\n\x0c\n\x05\x04\
-    \x02\x02\x02\x07\x12\x03G*/\n\x18\n\x04\x04\x02\x02\x03\x12\x03H\x02@\"\
-    \x0b\x20see\x20below\n\n\x0c\n\x05\x04\x02\x02\x03\x04\x12\x03H\x02\n\n\
-    \x0c\n\x05\x04\x02\x02\x03\x06\x12\x03H\x0b\x1c\n\x0c\n\x05\x04\x02\x02\
-    \x03\x01\x12\x03H\x1d#\n\x0c\n\x05\x04\x02\x02\x03\x03\x12\x03H&'\n\x0c\
-    \n\x05\x04\x02\x02\x03\x08\x12\x03H(?\n\x0c\n\x05\x04\x02\x02\x03\x07\
-    \x12\x03H4=\n,\n\x04\x04\x02\x02\x04\x12\x03I\x02#\"\x1f\x20associated\
-    \x20key/value\x20metadata\n\n\r\n\x05\x04\x02\x02\x04\x04\x12\x04I\x02H@\
-    \n\x0c\n\x05\x04\x02\x02\x04\x06\x12\x03I\x02\x15\n\x0c\n\x05\x04\x02\
-    \x02\x04\x01\x12\x03I\x16\x1e\n\x0c\n\x05\x04\x02\x02\x04\x03\x12\x03I!\
-    \"\n0\n\x04\x04\x02\x02\x05\x12\x03J\x02\"\"#\x20milliseconds\x20since\
-    \x20the\x20Unix\x20epoch\n\n\x0c\n\x05\x04\x02\x02\x05\x04\x12\x03J\x02\
-    \n\n\x0c\n\x05\x04\x02\x02\x05\x05\x12\x03J\x0b\x10\n\x0c\n\x05\x04\x02\
-    \x02\x05\x01\x12\x03J\x11\x1d\n\x0c\n\x05\x04\x02\x02\x05\x03\x12\x03J\
-    \x20!\n)\n\x04\x04\x02\x02\x06\x12\x03K\x02!\"\x1c\x20BIN\x20inclusive\
-    \x20upper\x20bounds\n\n\x0c\n\x05\x04\x02\x02\x06\x04\x12\x03K\x02\n\n\
-    \x0c\n\x05\x04\x02\x02\x06\x05\x12\x03K\x0b\x11\n\x0c\n\x05\x04\x02\x02\
-    \x06\x01\x12\x03K\x12\x1c\n\x0c\n\x05\x04\x02\x02\x06\x03\x12\x03K\x1f\
-    \x20\n\xde\x03\n\x02\x05\0\x12\x04V\0c\x01\x1a\xd1\x03\x20'AggregationMe\
-    thod'\x20-\x20an\x20interpretation\x20signal\n\n\x20Cernan\x20maintains\
-    \x20quantile\x20summaries\x20for\x20all\x20Telemetry\x20samples.\x20Not\
-    \x20all\x20sinks\n\x20are\x20capable\x20of\x20interpreting\x20summaries\
-    \x20natively.\x20Cernan\x20allows\x20the\x20client\x20to\n\x20set\x20pre\
-    ferred\x20aggregations\x20over\x20the\x20summaries\x20for\x20reporting\
-    \x20to\x20'flat'\n\x20sinks.\x20Sinks\x20are\x20allows\x20to\x20ignore\
-    \x20AggregationMethod\x20at\x20their\n\x20convenience.\x20Additionally,\
-    \x20aggregation\x20time\x20windows\x20may\x20be\x20configured\n\x20per-s\
-    ink\x20and\x20are\x20not\x20controllable\x20through\x20the\x20protocol.\
-    \n\n\n\n\x03\x05\0\x01\x12\x03V\x05\x16\n^\n\x04\x05\0\x02\0\x12\x03Y\
-    \x02\n\x1aQ\x20SUM\x20keeps\x20a\x20sum\x20of\x20samples.\x20This\x20is\
-    \x20often\x20interpreted\x20as\x20a\n\x20per-window\x20counter.\n\n\x0c\
-    \n\x05\x05\0\x02\0\x01\x12\x03Y\x02\x05\n\x0c\n\x05\x05\0\x02\0\x02\x12\
-    \x03Y\x08\t\nU\n\x04\x05\0\x02\x01\x12\x03\\\x02\n\x1aH\x20SET\x20preser\
-    ves\x20the\x20last\x20sample\x20set\x20into\x20the\x20Telemetry\x20per\
-    \x20time\n\x20window.\n\n\x0c\n\x05\x05\0\x02\x01\x01\x12\x03\\\x02\x05\
-    \n\x0c\n\x05\x05\0\x02\x01\x02\x12\x03\\\x08\t\nz\n\x04\x05\0\x02\x02\
-    \x12\x03_\x02\x10\x1am\x20SUMMARIZE\x20produces\x20a\x20quantile\x20summ\
-    ary\x20of\x20the\x20input\x20samples\x20per\x20time\n\x20window.\x20This\
-    \x20is\x20the\x20default\x20behaviour.\n\n\x0c\n\x05\x05\0\x02\x02\x01\
-    \x12\x03_\x02\x0b\n\x0c\n\x05\x05\0\x02\x02\x02\x12\x03_\x0e\x0f\n\x84\
-    \x01\n\x04\x05\0\x02\x03\x12\x03b\x02\n\x1aw\x20BIN\x20produces\x20a\x20\
-    histogram\x20summary\x20of\x20the\x20input\x20samples\x20per\x20time\x20\
-    window.\x20The\n\x20user\x20will\x20specify\x20the\x20bins'\x20upper\x20\
-    bounds.\n\n\x0c\n\x05\x05\0\x02\x03\x01\x12\x03b\x02\x05\n\x0c\n\x05\x05\
-    \0\x02\x03\x02\x12\x03b\x08\t\
+    \x03(\x01R\tbinBounds\x12-\n\x12distinct_registers\x18\x08\x20\x01(\x0cR\
+    \x11distinctRegisters\x124\n\x06sketch\x18\t\x20\x01(\x0b2\x1c.com.postm\
+    ates.cernan.SketchR\x06sketch\x1a;\n\rMetadataEntry\x12\x10\n\x03key\x18\
+    \x01\x20\x01(\tR\x03key\x12\x14\n\x05value\x18\x02\x20\x01(\tR\x05value:\
+    \x028\x01\"\xc2\x02\n\x06Sketch\x12+\n\x11relative_accuracy\x18\x01\x20\
+    \x01(\x01R\x10relativeAccuracy\x12-\n\x10positive_indexes\x18\x02\x20\
+    \x03(\x11R\x0fpositiveIndexesB\x02\x10\x01\x12+\n\x0fpositive_counts\x18\
+    \x03\x20\x03(\x04R\x0epositiveCountsB\x02\x10\x01\x12-\n\x10negative_ind\
+    exes\x18\x04\x20\x03(\x11R\x0fnegativeIndexesB\x02\x10\x01\x12+\n\x0fneg\
+    ative_counts\x18\x05\x20\x03(\x04R\x0enegativeCountsB\x02\x10\x01\x12\
+    \x1d\n\nzero_count\x18\x06\x20\x01(\x04R\tzeroCount\x12\x10\n\x03sum\x18\
+    \x07\x20\x01(\x01R\x03sum\x12\x10\n\x03min\x18\x08\x20\x01(\x01R\x03min\
+    \x12\x10\n\x03max\x18\t\x20\x01(\x01R\x03max*W\n\x11AggregationMethod\
+    \x12\x07\n\x03SUM\x10\x01\x12\x07\n\x03SET\x10\x02\x12\r\n\tSUMMARIZE\
+    \x10\x03\x12\x07\n\x03BIN\x10\x04\x12\x0c\n\x08DISTINCT\x10\x05\x12\n\n\
+    \x06SKETCH\x10\x06B\x16\n\x14com.postmates.cernanJ\xae(\n\x06\x12\x04\
+    \x20\0c\x01\n\xa0\x0c\n\x01\x0c\x12\x03\x20\0\x12\x1a\xe3\x03\n\x20Welco\
+    me!\n\n\x20This\x20file\x20defines\x20the\x20protocol\x20that\x20cernan\
+    \x20speaks\x20natively.\x20We\x20hope\x20that\x20it's\n\x20a\x20relative\
+    ly\x20straightforward\x20protocol\x20to\x20implement.\x20Cernan's\x20nat\
+    ive\x20transport\n\x20is\x20TCP.\x20We\x20require\x20that\x20all\x20on-w\
+    ire\x20payloads\x20have\x20the\x20following\x20form:\n\n\x20\x20\x20\x20\
+    \x20[--------------------------------|~~~~~~~~~~\x20.\x20.\x20.\x20~~~~~\
+    ~~~~~~~]\n\x20\x20\x20\x20\x20^\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\
+    \x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\
+    \x20\x20\x20\x20^\n\x20\x20\x20\x20\x20u32,\x20payload\x20length\x20in\
+    \x20bytes\x20\x20\x20\x20\x20protobuf\x20payload,\x20of\x20prefix\x20len\
+    \n\n\x20The\x20protobuf\x20payload\x20conforms\x20to\x20the\x20following\
+    \x20definition.\n2\xaf\x08\x20Copyright\x202016,\x20Postmates\x20Inc.\n\
+    \n\x20Permission\x20is\x20hereby\x20granted,\x20free\x20of\x20charge,\
+    \x20to\x20any\x20person\x20obtaining\x20a\x20copy\n\x20of\x20this\x20sof\
+    tware\x20and\x20associated\x20documentation\x20files\x20(the\x20\"Softwa\
+    re\"),\x20to\x20deal\n\x20in\x20the\x20Software\x20without\x20restrictio\
+    n,\x20including\x20without\x20limitation\x20the\x20rights\n\x20to\x20use\
+    ,\x20copy,\x20modify,\x20merge,\x20publish,\x20distribute,\x20sublicense\
+    ,\x20and/or\x20sell\n\x20copies\x20of\x20the\x20Software,\x20and\x20to\
+    \x20permit\x20persons\x20to\x20whom\x20the\x20Software\x20is\n\x20furnis\
+    hed\x20to\x20do\x20so,\x20subject\x20to\x20the\x20following\x20condition\
+    s:\n\n\x20The\x20above\x20copyright\x20notice\x20and\x20this\x20permissi\
+    on\x20notice\x20shall\x20be\x20included\x20in\n\x20all\x20copies\x20or\
+    \x20substantial\x20portions\x20of\x20the\x20Software.\n\n\x20THE\x20SOFT\
+    WARE\x20IS\x20PROVIDED\x20\"AS\x20IS\",\x20WITHOUT\x20WARRANTY\x20OF\x20\
+    ANY\x20KIND,\x20EXPRESS\x20OR\n\x20IMPLIED,\x20INCLUDING\x20BUT\x20NOT\
+    \x20LIMITED\x20TO\x20THE\x20WARRANTIES\x20OF\x20MERCHANTABILITY,\n\x20FI\
+    TNESS\x20FOR\x20A\x20PARTICULAR\x20PURPOSE\x20AND\x20NONINFRINGEMENT.\
+    \x20IN\x20NO\x20EVENT\x20SHALL\x20THE\n\x20AUTHORS\x20OR\x20COPYRIGHT\
+    \x20HOLDERS\x20BE\x20LIABLE\x20FOR\x20ANY\x20CLAIM,\x20DAMAGES\x20OR\x20\
+    OTHER\n\x20LIABILITY,\x20WHETHER\x20IN\x20AN\x20ACTION\x20OF\x20CONTRACT\
+    ,\x20TORT\x20OR\x20OTHERWISE,\x20ARISING\x20FROM,\n\x20OUT\x20OF\x20OR\
+    \x20IN\x20CONNECTION\x20WITH\x20THE\x20SOFTWARE\x20OR\x20THE\x20USE\x20O\
+    R\x20OTHER\x20DEALINGS\x20IN\x20THE\n\x20SOFTWARE.\n\n\x08\n\x01\x02\x12\
+    \x03\"\x08\x1c\n\x08\n\x01\x08\x12\x03#\0-\n\x0b\n\x04\x08\xe7\x07\0\x12\
+    \x03#\0-\n\x0c\n\x05\x08\xe7\x07\0\x02\x12\x03#\x07\x13\n\r\n\x06\x08\
+    \xe7\x07\0\x02\0\x12\x03#\x07\x13\n\x0e\n\x07\x08\xe7\x07\0\x02\0\x01\
+    \x12\x03#\x07\x13\n\x0c\n\x05\x08\xe7\x07\0\x07\x12\x03#\x16,\n\xb0\x01\
+    \n\x02\x04\0\x12\x04)\0,\x01\x1a\xa3\x01\x20'Payload'\x20-\x20the\x20top\
+    -level\x20structure\x20in\x20each\x20on-wire\x20payload\n\n\x20Payload\
+    \x20is\x20a\x20container\x20for\x20repeated\x20Telemetry\x20and\x20LogLi\
+    nes.\x20There's\x20not\x20much\n\x20more\x20to\x20it\x20than\x20that.\n\
+    \n\n\n\x03\x04\0\x01\x12\x03)\x08\x0f\n\x0b\n\x04\x04\0\x02\0\x12\x03*\
+    \x02\x20\n\x0c\n\x05\x04\0\x02\0\x04\x12\x03*\x02\n\n\x0c\n\x05\x04\0\
+    \x02\0\x06\x12\x03*\x0b\x14\n\x0c\n\x05\x04\0\x02\0\x01\x12\x03*\x15\x1b\
+    \n\x0c\n\x05\x04\0\x02\0\x03\x12\x03*\x1e\x1f\n\x0b\n\x04\x04\0\x02\x01\
+    \x12\x03+\x02\x1d\n\x0c\n\x05\x04\0\x02\x01\x04\x12\x03+\x02\n\n\x0c\n\
+    \x05\x04\0\x02\x01\x06\x12\x03+\x0b\x12\n\x0c\n\x05\x04\0\x02\x01\x01\
+    \x12\x03+\x13\x18\n\x0c\n\x05\x04\0\x02\x01\x03\x12\x03+\x1b\x1c\n\x99\
+    \x02\n\x02\x04\x01\x12\x044\09\x01\x1a\x8c\x02\x20'LogLine'\x20-\x20a\
+    \x20bit\x20of\x20unstructure\x20text\n\n\x20One\x20of\x20cernan's\x20gig\
+    s\x20is\x20picking\x20up\x20logs\x20from\x20disk\x20and\x20transforming\
+    \x20them\n\x20in-flight,\x20shipping\x20them\x20off.\x20This\x20structur\
+    e\x20allows\x20you\x20to\x20ship\x20lines\n\x20directly\x20via\x20the\
+    \x20native\x20protocol\x20without\x20having\x20to\x20round-trip\x20throu\
+    gh\x20disk\n\x20first.\n\n\n\n\x03\x04\x01\x01\x12\x034\x08\x0f\n0\n\x04\
+    \x04\x01\x02\0\x12\x035\x02\x1b\"#\x20unique\x20'location'\x20of\x20the\
+    \x20log\x20line\n\n\x0c\n\x05\x04\x01\x02\0\x04\x12\x035\x02\n\n\x0c\n\
+    \x05\x04\x01\x02\0\x05\x12\x035\x0b\x11\n\x0c\n\x05\x04\x01\x02\0\x01\
+    \x12\x035\x12\x16\n\x0c\n\x05\x04\x01\x02\0\x03\x12\x035\x19\x1a\n\x1e\n\
+    \x04\x04\x01\x02\x01\x12\x036\x02\x1c\"\x11\x20the\x20line\x20itself\n\n\
+    \x0c\n\x05\x04\x01\x02\x01\x04\x12\x036\x02\n\n\x0c\n\x05\x04\x01\x02\
+    \x01\x05\x12\x036\x0b\x11\n\x0c\n\x05\x04\x01\x02\x01\x01\x12\x036\x12\
+    \x17\n\x0c\n\x05\x04\x01\x02\x01\x03\x12\x036\x1a\x1b\n,\n\x04\x04\x01\
+    \x02\x02\x12\x037\x02#\"\x1f\x20associated\x20key/value\x20metadata\n\n\
+    \r\n\x05\x04\x01\x02\x02\x04\x12\x047\x026\x1c\n\x0c\n\x05\x04\x01\x02\
+    \x02\x06\x12\x037\x02\x15\n\x0c\n\x05\x04\x01\x02\x02\x01\x12\x037\x16\
+    \x1e\n\x0c\n\x05\x04\x01\x02\x02\x03\x12\x037!\"\n0\n\x04\x04\x01\x02\
+    \x03\x12\x038\x02\"\"#\x20milliseconds\x20since\x20the\x20Unix\x20epoch\
+    \n\n\x0c\n\x05\x04\x01\x02\x03\x04\x12\x038\x02\n\n\x0c\n\x05\x04\x01\
+    \x02\x03\x05\x12\x038\x0b\x10\n\x0c\n\x05\x04\x01\x02\x03\x01\x12\x038\
+    \x11\x1d\n\x0c\n\x05\x04\x01\x02\x03\x03\x12\x038\x20!\n\x8f\x04\n\x02\
+    \x04\x02\x12\x04D\0L\x01\x1a\x82\x04\x20'Telemetry'\x20-\x20a\x20numeric\
+    \x20measure\x20of\x20a\x20thing\n\n\x20Cernan's\x20slightly\x20more\x20c\
+    omplicated\x20gig\x20is\x20its\x20'telemetry'\n\x20subsystem.\x20Telemet\
+    ry\x20is\x20defined\x20as\x20a\x20name\x20and\x20time\x20associated\x20c\
+    ollection\x20of\n\x20measurements.\x20In\x20the\x20structure\x20we\x20re\
+    fer\x20to\x20these\x20measurements\x20as\n\x20'samples'.\x20The\x20Telem\
+    etry\x20structure\x20makes\x20is\x20possible\x20to\x20associate\x20multi\
+    ple\n\x20samples\x20in\x20a\x20single\x20millisecond\x20time\x20window.\
+    \x20Cernan\x20will\x20build\x20a\x20quantile\n\x20structure\x20over\x20t\
+    hese\x20samples\x20but\x20you\x20may\x20further\x20choose\x20aggregation\
+    \n\x20interpretations\x20by\x20setting\x20AggregationMethod.\n\n\n\n\x03\
+    \x04\x02\x01\x12\x03D\x08\x11\n/\n\x04\x04\x02\x02\0\x12\x03E\x02\x1b\"\
+    \"\x20the\x20unique\x20name\x20of\x20the\x20telemetry\n\n\x0c\n\x05\x04\
+    \x02\x02\0\x04\x12\x03E\x02\n\n\x0c\n\x05\x04\x02\x02\0\x05\x12\x03E\x0b\
+    \x11\n\x0c\n\x05\x04\x02\x02\0\x01\x12\x03E\x12\x16\n\x0c\n\x05\x04\x02\
+    \x02\0\x03\x12\x03E\x19\x1a\n8\n\x04\x04\x02\x02\x01\x12\x03F\x020\"+\
+    \x20telemetry\x20samples\x20present\x20in\x20timestamp_ms\n\n\x0c\n\x05\
+    \x04\x02\x02\x01\x04\x12\x03F\x02\n\n\x0c\n\x05\x04\x02\x02\x01\x05\x12\
+    \x03F\x0b\x11\n\x0c\n\x05\x04\x02\x02\x01\x01\x12\x03F\x12\x19\n\x0c\n\
+    \x05\x04\x02\x02\x01\x03\x12\x03F\x1c\x1d\n\x0c\n\x05\x04\x02\x02\x01\
+    \x08\x12\x03F\x1e/\n\x0f\n\x08\x04\x02\x02\x01\x08\xe7\x07\0\x12\x03F\
+    \x20-\n\x10\n\t\x04\x02\x02\x01\x08\xe7\x07\0\x02\x12\x03F\x20&\n\x11\n\
+    \n\x04\x02\x02\x01\x08\xe7\x07\0\x02\0\x12\x03F\x20&\n\x12\n\x0b\x04\x02\
+    \x02\x01\x08\xe7\x07\0\x02\0\x01\x12\x03F\x20&\n\x10\n\t\x04\x02\x02\x01\
+    \x08\xe7\x07\0\x03\x12\x03F)-\n1\n\x04\x04\x02\x02\x02\x12\x03G\x022\"$\
+    \x20persist\x20metric\x20across\x20time\x20windows\n\n\x0c\n\x05\x04\x02\
+    \x02\x02\x04\x12\x03G\x02\n\n\x0c\n\x05\x04\x02\x02\x02\x05\x12\x03G\x0b\
+    \x0f\n\x0c\n\x05\x04\x02\x02\x02\x01\x12\x03G\x10\x19\n\x0c\n\x05\x04\
+    \x02\x02\x02\x03\x12\x03G\x1c\x1d\n\x0c\n\x05\x04\x02\x02\x02\x08\x12\
+    \x03G\x1e1\n\x0c\n\x05\x04\x02\x02\x02\x07\x12\x03G*/\n\x18\n\x04\x04\
+    \x02\x02\x03\x12\x03H\x02@\"\x0b\x20see\x20below\n\n\x0c\n\x05\x04\x02\
+    \x02\x03\x04\x12\x03H\x02\n\n\x0c\n\x05\x04\x02\x02\x03\x06\x12\x03H\x0b\
+    \x1c\n\x0c\n\x05\x04\x02\x02\x03\x01\x12\x03H\x1d#\n\x0c\n\x05\x04\x02\
+    \x02\x03\x03\x12\x03H&'\n\x0c\n\x05\x04\x02\x02\x03\x08\x12\x03H(?\n\x0c\
+    \n\x05\x04\x02\x02\x03\x07\x12\x03H4=\n,\n\x04\x04\x02\x02\x04\x12\x03I\
+    \x02#\"\x1f\x20associated\x20key/value\x20metadata\n\n\r\n\x05\x04\x02\
+    \x02\x04\x04\x12\x04I\x02H@\n\x0c\n\x05\x04\x02\x02\x04\x06\x12\x03I\x02\
+    \x15\n\x0c\n\x05\x04\x02\x02\x04\x01\x12\x03I\x16\x1e\n\x0c\n\x05\x04\
+    \x02\x02\x04\x03\x12\x03I!\"\n0\n\x04\x04\x02\x02\x05\x12\x03J\x02\"\"#\
+    \x20milliseconds\x20since\x20the\x20Unix\x20epoch\n\n\x0c\n\x05\x04\x02\
+    \x02\x05\x04\x12\x03J\x02\n\n\x0c\n\x05\x04\x02\x02\x05\x05\x12\x03J\x0b\
+    \x10\n\x0c\n\x05\x04\x02\x02\x05\x01\x12\x03J\x11\x1d\n\x0c\n\x05\x04\
+    \x02\x02\x05\x03\x12\x03J\x20!\n)\n\x04\x04\x02\x02\x06\x12\x03K\x02!\"\
+    \x1c\x20BIN\x20inclusive\x20upper\x20bounds\n\n\x0c\n\x05\x04\x02\x02\
+    \x06\x04\x12\x03K\x02\n\n\x0c\n\x05\x04\x02\x02\x06\x05\x12\x03K\x0b\x11\
+    \n\x0c\n\x05\x04\x02\x02\x06\x01\x12\x03K\x12\x1c\n\x0c\n\x05\x04\x02\
+    \x02\x06\x03\x12\x03K\x1f\x20\n\xde\x03\n\x02\x05\0\x12\x04V\0c\x01\x1a\
+    \xd1\x03\x20'AggregationMethod'\x20-\x20an\x20interpretation\x20signal\n\
+    \n\x20Cernan\x20maintains\x20quantile\x20summaries\x20for\x20all\x20Tele\
+    metry\x20samples.\x20Not\x20all\x20sinks\n\x20are\x20capable\x20of\x20in\
+    terpreting\x20summaries\x20natively.\x20Cernan\x20allows\x20the\x20clien\
+    t\x20to\n\x20set\x20preferred\x20aggregations\x20over\x20the\x20summarie\
+    s\x20for\x20reporting\x20to\x20'flat'\n\x20sinks.\x20Sinks\x20are\x20all\
+    ows\x20to\x20ignore\x20AggregationMethod\x20at\x20their\n\x20convenience\
+    .\x20Additionally,\x20aggregation\x20time\x20windows\x20may\x20be\x20con\
+    figured\n\x20per-sink\x20and\x20are\x20not\x20controllable\x20through\
+    \x20the\x20protocol.\n\n\n\n\x03\x05\0\x01\x12\x03V\x05\x16\n^\n\x04\x05\
+    \0\x02\0\x12\x03Y\x02\n\x1aQ\x20SUM\x20keeps\x20a\x20sum\x20of\x20sample\
+    s.\x20This\x20is\x20often\x20interpreted\x20as\x20a\n\x20per-window\x20c\
+    ounter.\n\n\x0c\n\x05\x05\0\x02\0\x01\x12\x03Y\x02\x05\n\x0c\n\x05\x05\0\
+    \x02\0\x02\x12\x03Y\x08\t\nU\n\x04\x05\0\x02\x01\x12\x03\\\x02\n\x1aH\
+    \x20SET\x20preserves\x20the\x20last\x20sample\x20set\x20into\x20the\x20T\
+    elemetry\x20per\x20time\n\x20window.\n\n\x0c\n\x05\x05\0\x02\x01\x01\x12\
+    \x03\\\x02\x05\n\x0c\n\x05\x05\0\x02\x01\x02\x12\x03\\\x08\t\nz\n\x04\
+    \x05\0\x02\x02\x12\x03_\x02\x10\x1am\x20SUMMARIZE\x20produces\x20a\x20qu\
+    antile\x20summary\x20of\x20the\x20input\x20samples\x20per\x20time\n\x20w\
+    indow.\x20This\x20is\x20the\x20default\x20behaviour.\n\n\x0c\n\x05\x05\0\
+    \x02\x02\x01\x12\x03_\x02\x0b\n\x0c\n\x05\x05\0\x02\x02\x02\x12\x03_\x0e\
+    \x0f\n\x84\x01\n\x04\x05\0\x02\x03\x12\x03b\x02\n\x1aw\x20BIN\x20produce\
+    s\x20a\x20histogram\x20summary\x20of\x20the\x20input\x20samples\x20per\
+    \x20time\x20window.\x20The\n\x20user\x20will\x20specify\x20the\x20bins'\
+    \x20upper\x20bounds.\n\n\x0c\n\x05\x05\0\x02\x03\x01\x12\x03b\x02\x05\n\
+    \x0c\n\x05\x05\0\x02\x03\x02\x12\x03b\x08\t\
 ";
 
 static mut file_descriptor_proto_lazy: ::protobuf::lazy::Lazy<::protobuf::descriptor::FileDescriptorProto> = ::protobuf::lazy::Lazy {
@@ -0,0 +1,541 @@
+//! A restricted decoder for Python's pickle serialization format.
+//!
+//! Pickle is a small stack-based virtual machine. Arbitrary pickles may import
+//! and call Python objects, which we have no interest in supporting. This
+//! module understands only the opcodes needed to build plain data -- numbers,
+//! strings, lists and tuples -- across pickle protocols 0 through 4. This is
+//! sufficient for senders like Carbon's pickle protocol. Any other opcode is
+//! rejected.
+//!
+//! Memoized values are stored by copy. A list that is memoized and then
+//! appended to will be retrieved from the memo as it was at memoization time.
+//! Senders of plain data do not refer back to such lists.
+//!
+//! Copies are deep, so that a few bytes of DUP and APPEND could otherwise grow
+//! a value exponentially. The values copied by DUP, PUT and GET are counted
+//! against `MAX_COPIED`, and values may nest no deeper than `MAX_DEPTH`. A
+//! pickle exceeding either is rejected.
+
+use crate::util;
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use std::str;
+use std::str::FromStr;
+
+/// The most values, counting every nested value, that DUP, PUT and GET may
+/// copy while decoding a single pickle
+pub const MAX_COPIED: usize = 1 << 20;
+/// The deepest lists and tuples may nest in a decoded pickle
+pub const MAX_DEPTH: usize = 64;
+
+/// A decoded pickle value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// Python's `None`
+    None,
+    /// A boolean
+    Bool(bool),
+    /// An integer. Python longs wider than 64 bits are not supported.
+    Int(i64),
+    /// A floating point number
+    Float(f64),
+    /// A unicode string
+    String(String),
+    /// A byte string, as sent by Python 2 `str` or Python 3 `bytes`
+    Bytes(Vec<u8>),
+    /// A list
+    List(Vec<Value>),
+    /// A tuple
+    Tuple(Vec<Value>),
+}
+
+impl Value {
+    /// Interpret the value as a number, if possible.
+    ///
+    /// Integers, floats and strings holding a number are accepted.
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Value::Int(i) => Some(i as f64),
+            Value::Float(f) => Some(f),
+            Value::String(_) | Value::Bytes(_) => {
+                self.as_str().and_then(|s| f64::from_str(s.trim()).ok())
+            }
+            _ => None,
+        }
+    }
+
+    /// Interpret the value as a string, if possible.
+    ///
+    /// Unicode strings and UTF-8 byte strings are accepted.
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Value::String(ref s) => Some(s),
+            Value::Bytes(ref b) => str::from_utf8(b).ok(),
+            _ => None,
+        }
+    }
+}
+
+/// Errors that may occur while decoding a pickle.
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// The pickle ended before the STOP opcode or mid-argument.
+    Truncated,
+    /// The pickle used an opcode this module does not support.
+    UnsupportedOpcode(u8),
+    /// An opcode required more values than were on the stack.
+    StackUnderflow,
+    /// An opcode argument could not be interpreted.
+    InvalidArgument,
+    /// A GET referred to a memo slot that was never PUT.
+    MissingMemo,
+    /// The pickle copied more than `MAX_COPIED` values or nested deeper than
+    /// `MAX_DEPTH`.
+    TooLarge,
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.buf.len() - self.pos < len {
+            return Err(Error::Truncated);
+        }
+        let res = &self.buf[self.pos..(self.pos + len)];
+        self.pos += len;
+        Ok(res)
+    }
+
+    fn byte(&mut self) -> Result<u8, Error> {
+        self.bytes(1).map(|b| b[0])
+    }
+
+    fn line(&mut self) -> Result<&'a str, Error> {
+        let rest = &self.buf[self.pos..];
+        match rest.iter().position(|b| *b == b'\n') {
+            Some(idx) => {
+                self.pos += idx + 1;
+                str::from_utf8(&rest[..idx]).map_err(|_| Error::InvalidArgument)
+            }
+            None => Err(Error::Truncated),
+        }
+    }
+
+    fn length(&mut self, width: usize) -> Result<usize, Error> {
+        let bytes = self.bytes(width)?;
+        Ok(match width {
+            1 => bytes[0] as usize,
+            4 => LittleEndian::read_u32(bytes) as usize,
+            _ => LittleEndian::read_u64(bytes) as usize,
+        })
+    }
+}
+
+/// The number of values in `val`, itself included
+fn size(val: &Value) -> usize {
+    match *val {
+        Value::List(ref items) | Value::Tuple(ref items) => {
+            items.iter().fold(1, |acc, item| acc + size(item))
+        }
+        _ => 1,
+    }
+}
+
+/// The depth to which lists and tuples nest in `val`, zero for a scalar
+fn depth(val: &Value) -> usize {
+    match *val {
+        Value::List(ref items) | Value::Tuple(ref items) => {
+            1 + items.iter().map(depth).max().unwrap_or(0)
+        }
+        _ => 0,
+    }
+}
+
+/// Copy `val`, counting its values against the `copied` so far.
+fn copy(val: &Value, copied: &mut usize) -> Result<Value, Error> {
+    *copied += size(val);
+    if *copied > MAX_COPIED {
+        return Err(Error::TooLarge);
+    }
+    Ok(val.clone())
+}
+
+/// Check that `items` may be nested in a list or tuple.
+fn nestable(items: &[Value]) -> Result<(), Error> {
+    if items.iter().any(|item| depth(item) >= MAX_DEPTH) {
+        return Err(Error::TooLarge);
+    }
+    Ok(())
+}
+
+fn pop(stack: &mut Vec<Value>) -> Result<Value, Error> {
+    stack.pop().ok_or(Error::StackUnderflow)
+}
+
+fn pop_mark(
+    stack: &mut Vec<Value>,
+    marks: &mut Vec<usize>,
+) -> Result<Vec<Value>, Error> {
+    let mark = marks.pop().ok_or(Error::StackUnderflow)?;
+    if mark > stack.len() {
+        return Err(Error::StackUnderflow);
+    }
+    Ok(stack.split_off(mark))
+}
+
+fn extend_list(stack: &mut Vec<Value>, items: Vec<Value>) -> Result<(), Error> {
+    nestable(&items)?;
+    match stack.last_mut() {
+        Some(Value::List(ref mut list)) => {
+            list.extend(items);
+            Ok(())
+        }
+        Some(_) => Err(Error::InvalidArgument),
+        None => Err(Error::StackUnderflow),
+    }
+}
+
+fn parse_int(line: &str) -> Result<Value, Error> {
+    // Protocol 0 encodes booleans as the special integers 00 and 01.
+    match line {
+        "00" => Ok(Value::Bool(false)),
+        "01" => Ok(Value::Bool(true)),
+        _ => i64::from_str(line.trim_end_matches('L'))
+            .map(Value::Int)
+            .map_err(|_| Error::InvalidArgument),
+    }
+}
+
+fn parse_long1(bytes: &[u8]) -> Result<Value, Error> {
+    if bytes.is_empty() {
+        return Ok(Value::Int(0));
+    }
+    if bytes.len() > 8 {
+        return Err(Error::InvalidArgument);
+    }
+    // Little-endian two's complement, sign extended from the last byte.
+    let fill = if bytes[bytes.len() - 1] & 0x80 != 0 {
+        0xff
+    } else {
+        0x00
+    };
+    let mut buf = [fill; 8];
+    buf[..bytes.len()].copy_from_slice(bytes);
+    Ok(Value::Int(LittleEndian::read_i64(&buf)))
+}
+
+fn parse_quoted(line: &str) -> Result<Value, Error> {
+    let line = line.trim();
+    if line.len() >= 2
+        && ((line.starts_with('\'') && line.ends_with('\''))
+            || (line.starts_with('"') && line.ends_with('"')))
+    {
+        Ok(Value::Bytes(line[1..(line.len() - 1)].as_bytes().to_vec()))
+    } else {
+        Err(Error::InvalidArgument)
+    }
+}
+
+fn utf8(bytes: &[u8]) -> Result<Value, Error> {
+    str::from_utf8(bytes)
+        .map(|s| Value::String(s.to_string()))
+        .map_err(|_| Error::InvalidArgument)
+}
+
+/// Decode a single pickle from `buf`.
+///
+/// Decoding stops at the first STOP opcode, returning the value on top of the
+/// stack. Trailing bytes after STOP are ignored.
+pub fn loads(buf: &[u8]) -> Result<Value, Error> {
+    let mut rdr = Reader { buf: buf, pos: 0 };
+    let mut stack: Vec<Value> = Vec::new();
+    let mut marks: Vec<usize> = Vec::new();
+    let mut memo: util::HashMap<usize, Value> = Default::default();
+    let mut copied: usize = 0;
+
+    loop {
+        let opcode = rdr.byte()?;
+        match opcode {
+            // PROTO
+            0x80 => {
+                let _ = rdr.byte()?;
+            }
+            // FRAME
+            0x95 => {
+                let _ = rdr.bytes(8)?;
+            }
+            // STOP
+            b'.' => return pop(&mut stack),
+            // MARK
+            b'(' => marks.push(stack.len()),
+            // POP
+            b'0' => {
+                let _ = pop(&mut stack)?;
+            }
+            // POP_MARK
+            b'1' => {
+                let _ = pop_mark(&mut stack, &mut marks)?;
+            }
+            // DUP
+            b'2' => {
+                let top = stack.last().ok_or(Error::StackUnderflow)?;
+                let top = copy(top, &mut copied)?;
+                stack.push(top);
+            }
+            // NONE
+            b'N' => stack.push(Value::None),
+            // NEWTRUE, NEWFALSE
+            0x88 => stack.push(Value::Bool(true)),
+            0x89 => stack.push(Value::Bool(false)),
+            // INT, LONG
+            b'I' | b'L' => {
+                let line = rdr.line()?;
+                stack.push(parse_int(line)?);
+            }
+            // BININT
+            b'J' => {
+                let val = LittleEndian::read_i32(rdr.bytes(4)?);
+                stack.push(Value::Int(i64::from(val)));
+            }
+            // BININT1
+            b'K' => {
+                let val = rdr.byte()?;
+                stack.push(Value::Int(i64::from(val)));
+            }
+            // BININT2
+            b'M' => {
+                let val = LittleEndian::read_u16(rdr.bytes(2)?);
+                stack.push(Value::Int(i64::from(val)));
+            }
+            // LONG1
+            0x8a => {
+                let len = rdr.length(1)?;
+                stack.push(parse_long1(rdr.bytes(len)?)?);
+            }
+            // FLOAT
+            b'F' => {
+                let line = rdr.line()?;
+                let val = f64::from_str(line).map_err(|_| Error::InvalidArgument)?;
+                stack.push(Value::Float(val));
+            }
+            // BINFLOAT
+            b'G' => {
+                let val = BigEndian::read_f64(rdr.bytes(8)?);
+                stack.push(Value::Float(val));
+            }
+            // STRING
+            b'S' => {
+                let line = rdr.line()?;
+                stack.push(parse_quoted(line)?);
+            }
+            // BINSTRING, BINBYTES
+            b'T' | b'B' => {
+                let len = rdr.length(4)?;
+                stack.push(Value::Bytes(rdr.bytes(len)?.to_vec()));
+            }
+            // SHORT_BINSTRING, SHORT_BINBYTES
+            b'U' | b'C' => {
+                let len = rdr.length(1)?;
+                stack.push(Value::Bytes(rdr.bytes(len)?.to_vec()));
+            }
+            // UNICODE
+            b'V' => {
+                let line = rdr.line()?;
+                stack.push(Value::String(line.to_string()));
+            }
+            // BINUNICODE
+            b'X' => {
+                let len = rdr.length(4)?;
+                stack.push(utf8(rdr.bytes(len)?)?);
+            }
+            // SHORT_BINUNICODE
+            0x8c => {
+                let len = rdr.length(1)?;
+                stack.push(utf8(rdr.bytes(len)?)?);
+            }
+            // BINUNICODE8
+            0x8d => {
+                let len = rdr.length(8)?;
+                stack.push(utf8(rdr.bytes(len)?)?);
+            }
+            // EMPTY_LIST
+            b']' => stack.push(Value::List(Vec::new())),
+            // LIST
+            b'l' => {
+                let items = pop_mark(&mut stack, &mut marks)?;
+                nestable(&items)?;
+                stack.push(Value::List(items));
+            }
+            // APPEND
+            b'a' => {
+                let item = pop(&mut stack)?;
+                extend_list(&mut stack, vec![item])?;
+            }
+            // APPENDS
+            b'e' => {
+                let items = pop_mark(&mut stack, &mut marks)?;
+                extend_list(&mut stack, items)?;
+            }
+            // EMPTY_TUPLE
+            b')' => stack.push(Value::Tuple(Vec::new())),
+            // TUPLE
+            b't' => {
+                let items = pop_mark(&mut stack, &mut marks)?;
+                nestable(&items)?;
+                stack.push(Value::Tuple(items));
+            }
+            // TUPLE1, TUPLE2, TUPLE3
+            0x85 | 0x86 | 0x87 => {
+                let len = (opcode - 0x84) as usize;
+                if stack.len() < len {
+                    return Err(Error::StackUnderflow);
+                }
+                let at = stack.len() - len;
+                let items = stack.split_off(at);
+                nestable(&items)?;
+                stack.push(Value::Tuple(items));
+            }
+            // PUT
+            b'p' => {
+                let idx = usize::from_str(rdr.line()?)
+                    .map_err(|_| Error::InvalidArgument)?;
+                let top = stack.last().ok_or(Error::StackUnderflow)?;
+                memo.insert(idx, copy(top, &mut copied)?);
+            }
+            // BINPUT, LONG_BINPUT
+            b'q' | b'r' => {
+                let idx = rdr.length(if opcode == b'q' { 1 } else { 4 })?;
+                let top = stack.last().ok_or(Error::StackUnderflow)?;
+                memo.insert(idx, copy(top, &mut copied)?);
+            }
+            // MEMOIZE
+            0x94 => {
+                let idx = memo.len();
+                let top = stack.last().ok_or(Error::StackUnderflow)?;
+                memo.insert(idx, copy(top, &mut copied)?);
+            }
+            // GET
+            b'g' => {
+                let idx = usize::from_str(rdr.line()?)
+                    .map_err(|_| Error::InvalidArgument)?;
+                let val = memo.get(&idx).ok_or(Error::MissingMemo)?;
+                let val = copy(val, &mut copied)?;
+                stack.push(val);
+            }
+            // BINGET, LONG_BINGET
+            b'h' | b'j' => {
+                let idx = rdr.length(if opcode == b'h' { 1 } else { 4 })?;
+                let val = memo.get(&idx).ok_or(Error::MissingMemo)?;
+                let val = copy(val, &mut copied)?;
+                stack.push(val);
+            }
+            _ => return Err(Error::UnsupportedOpcode(opcode)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loads_protocol_0() {
+        // pickle.dumps([('a.b', (1500000000, 1.5))], protocol=0) from Python 2
+        let pyld = b"(lp0\n(S'a.b'\np1\n(I1500000000\nF1.5\ntp2\ntp3\na.";
+        assert_eq!(
+            loads(pyld),
+            Ok(Value::List(vec![Value::Tuple(vec![
+                Value::Bytes(b"a.b".to_vec()),
+                Value::Tuple(vec![Value::Int(1_500_000_000), Value::Float(1.5)]),
+            ])]))
+        );
+    }
+
+    #[test]
+    fn test_loads_protocol_2() {
+        // pickle.dumps([(u'a', (1, 2.0)), (u'b', (3, -1))], protocol=2)
+        let pyld = b"\x80\x02]q\x00(X\x01\x00\x00\x00aq\x01K\x01G@\x00\x00\x00\x00\x00\x00\x00\x86q\x02\x86q\x03X\x01\x00\x00\x00bq\x04K\x03J\xff\xff\xff\xff\x86q\x05\x86q\x06e.";
+        assert_eq!(
+            loads(&pyld[..]),
+            Ok(Value::List(vec![
+                Value::Tuple(vec![
+                    Value::String("a".to_string()),
+                    Value::Tuple(vec![Value::Int(1), Value::Float(2.0)]),
+                ]),
+                Value::Tuple(vec![
+                    Value::String("b".to_string()),
+                    Value::Tuple(vec![Value::Int(3), Value::Int(-1)]),
+                ]),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_loads_protocol_4() {
+        // pickle.dumps([('a', (1, 2.0))], protocol=4)
+        let pyld = b"\x80\x04\x95\x17\x00\x00\x00\x00\x00\x00\x00]\x94\x8c\x01a\x94K\x01G@\x00\x00\x00\x00\x00\x00\x00\x86\x94\x86\x94a.";
+        assert_eq!(
+            loads(&pyld[..]),
+            Ok(Value::List(vec![Value::Tuple(vec![
+                Value::String("a".to_string()),
+                Value::Tuple(vec![Value::Int(1), Value::Float(2.0)]),
+            ])]))
+        );
+    }
+
+    #[test]
+    fn test_loads_long1() {
+        assert_eq!(loads(b"\x8a\x02\x00\x80."), Ok(Value::Int(-32768)));
+        assert_eq!(loads(b"\x8a\x03\x00\x80\x00."), Ok(Value::Int(32768)));
+        assert_eq!(loads(b"\x8a\x01\xff."), Ok(Value::Int(-1)));
+        assert_eq!(loads(b"\x8a\x00."), Ok(Value::Int(0)));
+    }
+
+    #[test]
+    fn test_loads_rejects_globals() {
+        // cos\nsystem\n -- the classic arbitrary code execution pickle
+        assert_eq!(
+            loads(b"cos\nsystem\n(S'ls'\ntR."),
+            Err(Error::UnsupportedOpcode(b'c'))
+        );
+    }
+
+    #[test]
+    fn test_loads_truncated() {
+        assert_eq!(loads(b"\x80\x02]q\x00(X\x01\x00"), Err(Error::Truncated));
+        assert_eq!(loads(b"]"), Err(Error::Truncated));
+    }
+
+    #[test]
+    fn test_loads_bounds_copies() {
+        // EMPTY_LIST, then DUP and APPEND, doubling the list each time
+        let mut pyld = b"]".to_vec();
+        for _ in 0..64 {
+            pyld.extend_from_slice(b"2a");
+        }
+        pyld.push(b'.');
+        assert_eq!(loads(&pyld), Err(Error::TooLarge));
+
+        // EMPTY_LIST, BINPUT, then BINGET repeatedly
+        let mut pyld = b"(]q\x00".to_vec();
+        for _ in 0..32 {
+            pyld.extend_from_slice(b"h\x00a2q\x00");
+        }
+        pyld.extend_from_slice(b"t.");
+        assert_eq!(loads(&pyld), Err(Error::TooLarge));
+    }
+
+    #[test]
+    fn test_loads_bounds_depth() {
+        let nest = |n: usize| {
+            let mut pyld = vec![b']'; n];
+            pyld.extend(vec![b'a'; n - 1]);
+            pyld.push(b'.');
+            pyld
+        };
+        assert!(loads(&nest(MAX_DEPTH)).is_ok());
+        assert_eq!(loads(&nest(MAX_DEPTH + 1)), Err(Error::TooLarge));
+    }
+}
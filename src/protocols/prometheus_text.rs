@@ -0,0 +1,331 @@
+//! Parse the Prometheus text exposition format, version 0.0.4.
+//!
+//! This is the format served from the `/metrics` endpoint of most Prometheus
+//! instrumented applications, and by `sink::Prometheus`. The parse is into
+//! metric families, samples grouped by the family their `# TYPE` line
+//! declared. Samples with no `# TYPE` are placed in untyped families of their
+//! own.
+
+use std::f64;
+use std::str::FromStr;
+
+/// The type of a Prometheus metric family.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MetricKind {
+    /// A monotonically increasing count.
+    Counter,
+    /// A value which may go up or down.
+    Gauge,
+    /// Cumulative `_bucket` counts, with `_sum` and `_count`.
+    Histogram,
+    /// Quantiles, with `_sum` and `_count`.
+    Summary,
+    /// A value of no declared type.
+    Untyped,
+}
+
+/// A single sample line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    /// The name of the sample, including any `_bucket`, `_sum` or `_count`
+    /// suffix.
+    pub name: String,
+    /// The labels of the sample, in order of appearance.
+    pub labels: Vec<(String, String)>,
+    /// The value of the sample.
+    pub value: f64,
+    /// The timestamp of the sample in milliseconds since the Unix epoch, if
+    /// one was given.
+    pub timestamp_ms: Option<i64>,
+}
+
+/// A metric family, the samples sharing a `# TYPE` declaration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricFamily {
+    /// The name of the family.
+    pub name: String,
+    /// The declared type of the family.
+    pub kind: MetricKind,
+    /// The samples of the family, in order of appearance.
+    pub samples: Vec<Sample>,
+}
+
+/// Errors that may arise while parsing the text exposition format.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// A `# TYPE` line named an unknown type.
+    UnknownType(String),
+    /// A sample line could not be parsed. The line number is given.
+    InvalidSample(usize),
+}
+
+fn valid_metric_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' || c == ':' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+}
+
+fn parse_value(val: &str) -> Option<f64> {
+    match val {
+        "+Inf" | "Inf" => Some(f64::INFINITY),
+        "-Inf" => Some(f64::NEG_INFINITY),
+        "NaN" => Some(f64::NAN),
+        _ => f64::from_str(val).ok(),
+    }
+}
+
+/// Parse the `{...}` label set at the start of `s`, returning the labels and
+/// the remainder of `s`.
+fn parse_labels(s: &str) -> Option<(Vec<(String, String)>, &str)> {
+    let mut labels = Vec::new();
+    let mut rest = s[1..].trim_start();
+    loop {
+        if rest.starts_with('}') {
+            return Some((labels, &rest[1..]));
+        }
+        let eq = rest.find('=')?;
+        let name = rest[..eq].trim();
+        if !valid_metric_name(name) || name.contains(':') {
+            return None;
+        }
+        rest = rest[eq + 1..].trim_start();
+        if !rest.starts_with('"') {
+            return None;
+        }
+        let mut value = String::new();
+        let mut escaped = false;
+        let mut end = None;
+        for (idx, c) in rest[1..].char_indices() {
+            if escaped {
+                match c {
+                    'n' => value.push('\n'),
+                    '"' | '\\' => value.push(c),
+                    _ => {
+                        value.push('\\');
+                        value.push(c);
+                    }
+                }
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                end = Some(idx + 2);
+                break;
+            } else {
+                value.push(c);
+            }
+        }
+        rest = rest[end?..].trim_start();
+        labels.push((name.to_string(), value));
+        if rest.starts_with(',') {
+            rest = rest[1..].trim_start();
+        } else if !rest.starts_with('}') {
+            return None;
+        }
+    }
+}
+
+fn parse_sample(line: &str) -> Option<Sample> {
+    let name_end = line
+        .find(|c: char| c == '{' || c.is_whitespace())
+        .unwrap_or_else(|| line.len());
+    let name = &line[..name_end];
+    if !valid_metric_name(name) {
+        return None;
+    }
+    let rest = line[name_end..].trim_start();
+    let (labels, rest) = if rest.starts_with('{') {
+        parse_labels(rest)?
+    } else {
+        (Vec::new(), rest)
+    };
+    let mut fields = rest.split_whitespace();
+    let value = parse_value(fields.next()?)?;
+    let timestamp_ms = match fields.next() {
+        Some(ts) => Some(i64::from_str(ts).ok()?),
+        None => None,
+    };
+    if fields.next().is_some() {
+        return None;
+    }
+    Some(Sample {
+        name: name.to_string(),
+        labels,
+        value,
+        timestamp_ms,
+    })
+}
+
+/// Determine whether `sample` belongs to the family `name` of type `kind`.
+fn belongs_to(sample: &str, name: &str, kind: MetricKind) -> bool {
+    if sample == name {
+        return kind != MetricKind::Histogram;
+    }
+    if !sample.starts_with(name) {
+        return false;
+    }
+    match (kind, &sample[name.len()..]) {
+        (MetricKind::Histogram, "_bucket") => true,
+        (MetricKind::Histogram, "_sum") | (MetricKind::Summary, "_sum") => true,
+        (MetricKind::Histogram, "_count") | (MetricKind::Summary, "_count") => true,
+        _ => false,
+    }
+}
+
+/// Parse a text exposition payload into its metric families.
+pub fn parse_text(source: &str) -> Result<Vec<MetricFamily>, Error> {
+    let mut families: Vec<MetricFamily> = Vec::new();
+    for (idx, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line.starts_with('#') {
+            let mut tokens = line[1..].split_whitespace();
+            if tokens.next() == Some("TYPE") {
+                let name = tokens.next().ok_or(Error::InvalidSample(idx + 1))?;
+                let kind = match tokens.next() {
+                    Some("counter") => MetricKind::Counter,
+                    Some("gauge") => MetricKind::Gauge,
+                    Some("histogram") => MetricKind::Histogram,
+                    Some("summary") => MetricKind::Summary,
+                    Some("untyped") => MetricKind::Untyped,
+                    Some(other) => return Err(Error::UnknownType(other.to_string())),
+                    None => return Err(Error::InvalidSample(idx + 1)),
+                };
+                families.push(MetricFamily {
+                    name: name.to_string(),
+                    kind,
+                    samples: Vec::new(),
+                });
+            }
+            continue;
+        }
+        let sample = parse_sample(line).ok_or(Error::InvalidSample(idx + 1))?;
+        let in_last = families
+            .last()
+            .map(|f| belongs_to(&sample.name, &f.name, f.kind))
+            .unwrap_or(false);
+        if in_last {
+            families.last_mut().unwrap().samples.push(sample);
+        } else {
+            families.push(MetricFamily {
+                name: sample.name.clone(),
+                kind: MetricKind::Untyped,
+                samples: vec![sample],
+            });
+        }
+    }
+    Ok(families)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_counter_and_gauge() {
+        let payload = "# HELP http_requests_total The total number of HTTP requests.
+# TYPE http_requests_total counter
+http_requests_total{method=\"post\",code=\"200\"} 1027 1395066363000
+http_requests_total{method=\"post\",code=\"400\"}    3 1395066363000
+
+# TYPE temperature gauge
+temperature -3.5
+";
+        let families = parse_text(payload).unwrap();
+        assert_eq!(families.len(), 2);
+
+        assert_eq!(families[0].name, "http_requests_total");
+        assert_eq!(families[0].kind, MetricKind::Counter);
+        assert_eq!(families[0].samples.len(), 2);
+        assert_eq!(
+            families[0].samples[0],
+            Sample {
+                name: "http_requests_total".into(),
+                labels: vec![
+                    ("method".into(), "post".into()),
+                    ("code".into(), "200".into())
+                ],
+                value: 1027.0,
+                timestamp_ms: Some(1_395_066_363_000),
+            }
+        );
+        assert_eq!(families[0].samples[1].value, 3.0);
+
+        assert_eq!(families[1].name, "temperature");
+        assert_eq!(families[1].kind, MetricKind::Gauge);
+        assert_eq!(families[1].samples[0].value, -3.5);
+        assert_eq!(families[1].samples[0].timestamp_ms, None);
+    }
+
+    #[test]
+    fn test_parse_histogram_and_summary() {
+        let payload = "# TYPE latency histogram
+latency_bucket{le=\"0.05\"} 24054
+latency_bucket{le=\"0.1\"} 33444
+latency_bucket{le=\"+Inf\"} 144320
+latency_sum 53423
+latency_count 144320
+# TYPE rpc summary
+rpc{quantile=\"0.5\"} 4773
+rpc{quantile=\"0.99\"} 76656
+rpc_sum 1.7560473e+07
+rpc_count 2693
+";
+        let families = parse_text(payload).unwrap();
+        assert_eq!(families.len(), 2);
+
+        assert_eq!(families[0].kind, MetricKind::Histogram);
+        assert_eq!(families[0].samples.len(), 5);
+        assert_eq!(families[0].samples[2].name, "latency_bucket");
+        assert_eq!(
+            families[0].samples[2].labels,
+            vec![("le".to_string(), "+Inf".to_string())]
+        );
+        assert_eq!(families[0].samples[4].name, "latency_count");
+
+        assert_eq!(families[1].kind, MetricKind::Summary);
+        assert_eq!(families[1].samples.len(), 4);
+        assert_eq!(families[1].samples[2].value, 17_560_473.0);
+    }
+
+    #[test]
+    fn test_parse_untyped_and_escapes() {
+        let payload = "up 1\nmsdos_file_access_time_seconds{path=\"C:\\\\DIR\\\\FILE.TXT\",error=\"Cannot find file:\\n\\\"FILE.TXT\\\"\"} 1.458255915e9\nvalue NaN\n";
+        let families = parse_text(payload).unwrap();
+        assert_eq!(families.len(), 3);
+        assert_eq!(families[0].kind, MetricKind::Untyped);
+        assert_eq!(families[0].name, "up");
+        assert_eq!(
+            families[1].samples[0].labels,
+            vec![
+                ("path".to_string(), "C:\\DIR\\FILE.TXT".to_string()),
+                (
+                    "error".to_string(),
+                    "Cannot find file:\n\"FILE.TXT\"".to_string()
+                ),
+            ]
+        );
+        assert!(families[2].samples[0].value.is_nan());
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert_eq!(
+            parse_text("# TYPE foo widget\n"),
+            Err(Error::UnknownType("widget".into()))
+        );
+        assert_eq!(parse_text("ok 1\nfoo\n"), Err(Error::InvalidSample(2)));
+        assert_eq!(
+            parse_text("foo{bar=\"baz} 1\n"),
+            Err(Error::InvalidSample(1))
+        );
+        assert_eq!(parse_text("foo{bar=baz} 1\n"), Err(Error::InvalidSample(1)));
+        assert_eq!(parse_text("foo 1 2 3\n"), Err(Error::InvalidSample(1)));
+        assert_eq!(parse_text("9foo 1\n"), Err(Error::InvalidSample(1)));
+    }
+}
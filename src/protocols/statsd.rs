@@ -16,7 +16,23 @@ use std::sync;
 /// - `<str:metric_name>:<f64:value>|c@<f64:sample_rate>`
 /// - `<str:metric_name>:<f64:value>|g|@<f64:sample_rate>`
 /// - `<str:metric_name>:<f64:value>|g@<f64:sample_rate>`
-/// p
+///
+/// - `<str:metric_name>:<str:member>|s`
+///
+/// The type may be one of `g`, `c`, `ms`, `h`, `d` or `s`. A set member is
+/// any non-empty string, numeric or not, see `SoftTelemetry::member`: a numeric
+/// member is counted by its value, `42` and `42.0` being one member. Any
+/// sample rate of a set is ignored.
+/// The DogStatsD
+/// extensions are also understood, appended as further `|` separated fields
+/// in any order after the type:
+///
+/// - `|#<str:key>:<str:value>,<str:key>` -- tags, overlaid onto the
+///   `Telemetry` tag map. A tag without a value is given the empty string.
+/// - `|c:<str:container_id>` -- the origin container, stored in the
+///   `container_id` tag.
+/// - `|T<i64:timestamp>` -- the time of the point, in epoch seconds.
+///
 /// Multiple metrics can be sent in a single UDP packet
 /// separated by newlines.
 pub fn parse_statsd(
@@ -26,138 +42,132 @@ pub fn parse_statsd(
     config: &sync::Arc<StatsdParseConfig>,
 ) -> bool {
     for src in source.lines() {
-        let mut offset = 0;
-        let len = src.len();
-        match (&src[offset..]).find(':') {
-            Some(colon_idx) => {
-                let name = &src[offset..(offset + colon_idx)];
-                if name.is_empty() {
-                    return false;
-                };
-                offset += colon_idx + 1;
-                if offset >= len {
+        let colon_idx = match src.find(':') {
+            Some(colon_idx) => colon_idx,
+            None => return false,
+        };
+        let name = &src[..colon_idx];
+        if name.is_empty() {
+            return false;
+        };
+        let mut fields = (&src[(colon_idx + 1)..]).split('|');
+        // `split` always yields at least one, possibly empty, field.
+        let raw_val = fields.next().unwrap();
+        let val = f64::from_str(raw_val).ok();
+        let signed = raw_val.starts_with('+') || raw_val.starts_with('-');
+        let (kind, mut sample) = match fields.next() {
+            Some(kind) => match kind.find('@') {
+                Some(sample_idx) => match f64::from_str(&kind[(sample_idx + 1)..]) {
+                    Ok(f) => (&kind[..sample_idx], Some(f)),
+                    Err(_) => return false,
+                },
+                None => (kind, None),
+            },
+            None => return false,
+        };
+        let mut timestamp = time::now();
+        let mut tags: Vec<(&str, &str)> = Vec::new();
+        for ext in fields {
+            if ext.starts_with('@') {
+                if sample.is_some() {
                     return false;
+                }
+                sample = match f64::from_str(&ext[1..]) {
+                    Ok(f) => Some(f),
+                    Err(_) => return false,
                 };
-                match (&src[offset..]).find('|') {
-                    Some(pipe_idx) => {
-                        let val =
-                            match f64::from_str(&src[offset..(offset + pipe_idx)]) {
-                                Ok(f) => f,
-                                Err(_) => return false,
-                            };
-                        let mut metric =
-                            sync::Arc::make_mut(&mut sync::Arc::clone(metric))
-                                .take()
-                                .unwrap()
-                                .thaw();
-                        metric = metric.name(name);
-                        metric = metric.value(val);
-                        metric = metric.timestamp(time::now());
-                        let signed = match &src[offset..=offset] {
-                            "+" | "-" => true,
-                            _ => false,
-                        };
-                        offset += pipe_idx + 1;
-                        if offset >= len {
-                            return false;
-                        };
-                        metric = match (&src[offset..]).find('@') {
-                            Some(sample_idx) => {
-                                match &src[offset..(offset + sample_idx)] {
-                                    "g|" | "g" => {
-                                        let sample = match f64::from_str(
-                                            &src[(offset + sample_idx + 1)..],
-                                        ) {
-                                            Ok(f) => f,
-                                            Err(_) => return false,
-                                        };
-                                        metric = metric.persist(true);
-                                        metric = if signed {
-                                            metric.kind(AggregationMethod::Sum)
-                                        } else {
-                                            metric.kind(AggregationMethod::Set)
-                                        };
-                                        metric.value(val * (1.0 / sample))
-                                    }
-                                    "c|" | "c" => {
-                                        let sample = match f64::from_str(
-                                            &src[(offset + sample_idx + 1)..],
-                                        ) {
-                                            Ok(f) => f,
-                                            Err(_) => return false,
-                                        };
-                                        metric = metric
-                                            .kind(AggregationMethod::Sum)
-                                            .persist(false);
-                                        metric.value(val * (1.0 / sample))
-                                    }
-                                    "ms" | "ms|" | "h" | "h|" => {
-                                        let sample = match f64::from_str(
-                                            &src[(offset + sample_idx + 1)..],
-                                        ) {
-                                            Ok(f) => f,
-                                            Err(_) => return false,
-                                        };
-                                        metric = metric.persist(false);
-                                        metric = metric
-                                            .kind(AggregationMethod::Summarize)
-                                            .error(config.summarize_error_bound);
-                                        for &(ref mask_re, ref bounds) in
-                                            &config.histogram_masks
-                                        {
-                                            if mask_re.is_match(name) {
-                                                metric = metric
-                                                    .kind(AggregationMethod::Histogram)
-                                                    .clear_error()
-                                                    .bounds(bounds.clone());
-                                                break;
-                                            }
-                                        }
-                                        metric.value(val * (1.0 / sample))
-                                    }
-                                    _ => return false,
-                                }
+            } else if ext.starts_with('#') {
+                for tag in (&ext[1..]).split(',') {
+                    if tag.is_empty() {
+                        continue;
+                    }
+                    match tag.find(':') {
+                        Some(tag_idx) => {
+                            if tag_idx == 0 {
+                                return false;
                             }
-                            None => match &src[offset..] {
-                                "g" => {
-                                    metric = metric.persist(true);
-                                    if signed {
-                                        metric.kind(AggregationMethod::Sum)
-                                    } else {
-                                        metric.kind(AggregationMethod::Set)
-                                    }
-                                }
-                                "ms" | "h" => {
-                                    metric = metric.persist(false);
-                                    metric = metric
-                                        .kind(AggregationMethod::Summarize)
-                                        .error(config.summarize_error_bound);
-                                    for &(ref mask_re, ref bounds) in
-                                        &config.histogram_masks
-                                    {
-                                        if mask_re.is_match(name) {
-                                            metric = metric
-                                                .kind(AggregationMethod::Histogram)
-                                                .clear_error()
-                                                .bounds(bounds.clone());
-                                            break;
-                                        }
-                                    }
-                                    metric
-                                }
-                                "c" => {
-                                    metric.kind(AggregationMethod::Sum).persist(false)
-                                }
-                                _ => return false,
-                            },
-                        };
-                        res.push(metric.harden().unwrap());
+                            tags.push((&tag[..tag_idx], &tag[(tag_idx + 1)..]))
+                        }
+                        None => tags.push((tag, "")),
+                    }
+                }
+            } else if ext.starts_with("c:") {
+                tags.push(("container_id", &ext[2..]));
+            } else if ext.starts_with('T') {
+                timestamp = match i64::from_str(&ext[1..]) {
+                    Ok(t) => t,
+                    Err(_) => return false,
+                };
+            } else {
+                return false;
+            }
+        }
+
+        let mut metric = sync::Arc::make_mut(&mut sync::Arc::clone(metric))
+            .take()
+            .unwrap()
+            .thaw();
+        metric = metric.name(name);
+        metric = metric.timestamp(timestamp);
+        metric = if kind == "s" {
+            if raw_val.is_empty() {
+                return false;
+            }
+            metric.member(raw_val)
+        } else {
+            match (val, sample) {
+                (Some(val), Some(sample)) => metric.value(val * (1.0 / sample)),
+                (Some(val), None) => metric.value(val),
+                (None, _) => return false,
+            }
+        };
+        metric = match kind {
+            "g" => {
+                metric = metric.persist(true);
+                if signed {
+                    metric.kind(AggregationMethod::Sum)
+                } else {
+                    metric.kind(AggregationMethod::Set)
+                }
+            }
+            "c" => metric.kind(AggregationMethod::Sum).persist(false),
+            "s" => metric.kind(AggregationMethod::Distinct).persist(false),
+            "ms" | "h" | "d" => {
+                metric = metric.persist(false);
+                metric = metric
+                    .kind(AggregationMethod::Summarize)
+                    .error(config.summarize_error_bound);
+                let mut masked = false;
+                for &(ref mask_re, ref bounds) in &config.histogram_masks {
+                    if mask_re.is_match(name) {
+                        metric = metric
+                            .kind(AggregationMethod::Histogram)
+                            .clear_error()
+                            .bounds(bounds.clone());
+                        masked = true;
+                        break;
+                    }
+                }
+                if !masked {
+                    for &(ref mask_re, accuracy) in &config.sketch_masks {
+                        if mask_re.is_match(name) {
+                            metric = metric
+                                .kind(AggregationMethod::Sketch)
+                                .clear_error()
+                                .error(accuracy);
+                            break;
+                        }
                     }
-                    None => return false,
                 }
+                metric
             }
-            None => return false,
+            _ => return false,
+        };
+        let mut metric = metric.harden().unwrap();
+        for (key, value) in tags {
+            metric = metric.overlay_tag(key, value);
         }
+        res.push(metric);
     }
     !res.is_empty()
 }
@@ -165,8 +175,9 @@ pub fn parse_statsd(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::metric::{AggregationMethod, Telemetry};
+    use crate::metric::{AggregationMethod, TagMap, Telemetry};
     use quickcheck::{Arbitrary, Gen, QuickCheck, TestResult};
+    use regex::Regex;
     use std::sync;
 
     #[derive(Clone, Debug)]
@@ -621,4 +632,178 @@ mod tests {
         assert_eq!(res[7].persist, true);
         assert_eq!(res[7].value(), Some(7.77));
     }
+
+    #[test]
+    fn test_dogstatsd_tags() {
+        let metric = sync::Arc::new(Some(Telemetry::default()));
+        let config = sync::Arc::new(StatsdParseConfig::default());
+        let mut res = Vec::new();
+        assert!(parse_statsd(
+            "a.b:1|c|#env:prod,endpoint:/v1/users,canary\nc.d:2|g|@0.5|#env:dev\n",
+            &mut res,
+            &metric,
+            &config,
+        ));
+        let defaults = TagMap::default();
+
+        assert_eq!(res[0].kind(), AggregationMethod::Sum);
+        assert_eq!(res[0].name, "a.b");
+        assert_eq!(Some(1.0), res[0].value());
+        let mut tags: Vec<(String, String)> = res[0]
+            .tags(&defaults)
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        tags.sort();
+        assert_eq!(
+            tags,
+            vec![
+                ("canary".to_string(), "".to_string()),
+                ("endpoint".to_string(), "/v1/users".to_string()),
+                ("env".to_string(), "prod".to_string()),
+            ]
+        );
+
+        assert_eq!(res[1].kind(), AggregationMethod::Set);
+        assert_eq!(res[1].name, "c.d");
+        assert_eq!(Some(4.0), res[1].value());
+        assert_eq!(
+            Some(&"dev".to_string()),
+            res[1].get_from_tags("env", &defaults)
+        );
+    }
+
+    #[test]
+    fn test_dogstatsd_distribution() {
+        let metric = sync::Arc::new(Some(Telemetry::default()));
+        let config = sync::Arc::new(StatsdParseConfig::default());
+        let mut res = Vec::new();
+        assert!(parse_statsd(
+            "lat:3.3|d|#route:home\n",
+            &mut res,
+            &metric,
+            &config
+        ));
+
+        assert_eq!(res[0].kind(), AggregationMethod::Summarize);
+        assert_eq!(res[0].name, "lat");
+        assert_eq!(res[0].persist, false);
+        assert_eq!(res[0].query(1.0), Some(3.3));
+    }
+
+    #[test]
+    fn test_dogstatsd_container_and_timestamp() {
+        let metric = sync::Arc::new(Some(Telemetry::default()));
+        let config = sync::Arc::new(StatsdParseConfig::default());
+        let mut res = Vec::new();
+        assert!(parse_statsd(
+            "hits:4|c|#env:prod|c:83d2f9a7|T1656581400\n",
+            &mut res,
+            &metric,
+            &config
+        ));
+        let defaults = TagMap::default();
+
+        assert_eq!(res[0].kind(), AggregationMethod::Sum);
+        assert_eq!(res[0].timestamp, 1_656_581_400);
+        assert_eq!(
+            Some(&"83d2f9a7".to_string()),
+            res[0].get_from_tags("container_id", &defaults)
+        );
+        assert_eq!(
+            Some(&"prod".to_string()),
+            res[0].get_from_tags("env", &defaults)
+        );
+    }
+
+    #[test]
+    fn test_dogstatsd_invalid_extensions() {
+        let invalid = vec![
+            "metric:1|c|x",
+            "metric:1|c|#:value",
+            "metric:1|c|Tnow",
+            "metric:1|c@0.1|@0.2",
+            "users:|s",
+            "users:bob|c",
+        ];
+        let metric = sync::Arc::new(Some(Telemetry::default()));
+        let config = sync::Arc::new(StatsdParseConfig::default());
+        for input in invalid.iter() {
+            assert!(!parse_statsd(
+                *input,
+                &mut Vec::new(),
+                &metric.clone(),
+                &config.clone()
+            ));
+        }
+    }
+
+    #[test]
+    fn test_set_members_counted_distinctly() {
+        let metric = sync::Arc::new(Some(Telemetry::default()));
+        let config = sync::Arc::new(StatsdParseConfig::default());
+        let mut res = Vec::new();
+        assert!(parse_statsd(
+            "users:alice|s\nusers:bob|s|@0.1\nusers:alice|s\nusers:42|s\n",
+            &mut res,
+            &metric,
+            &config
+        ));
+        assert_eq!(res.len(), 4);
+        assert!(res.iter().all(|m| m.kind() == AggregationMethod::Distinct));
+        assert!(res.iter().all(|m| !m.persist));
+
+        let mut users = res[0].clone();
+        for m in &res[1..] {
+            users += m.clone();
+        }
+        assert_eq!(users.distinct(), Some(3.0));
+
+        // Any string is a member, numeric members are counted by value.
+        let mut res = Vec::new();
+        assert!(parse_statsd(
+            "ids:42|s\nids:42.0|s\nids:4 2|s\nids:0x2a|s\n",
+            &mut res,
+            &metric,
+            &config
+        ));
+        let mut ids = res[0].clone();
+        for m in &res[1..] {
+            ids += m.clone();
+        }
+        assert_eq!(ids.distinct(), Some(3.0));
+
+        assert!(!parse_statsd(
+            "users:|s\n",
+            &mut Vec::new(),
+            &metric,
+            &config
+        ));
+    }
+
+    #[test]
+    fn test_sketch_masks() {
+        let metric = sync::Arc::new(Some(Telemetry::default()));
+        let mut config = StatsdParseConfig::default();
+        config.histogram_masks =
+            vec![(Regex::new("^api.*").unwrap(), vec![1.0, 10.0, 100.0])];
+        config.sketch_masks = vec![
+            (Regex::new("^api.*").unwrap(), 0.05),
+            (Regex::new("^db.*").unwrap(), 0.001),
+        ];
+        let config = sync::Arc::new(config);
+        let mut res = Vec::new();
+        assert!(parse_statsd(
+            "api.latency:12|ms\ndb.latency:1200|ms\ndb.size:3|h\nweb.latency:3|ms\n",
+            &mut res,
+            &metric,
+            &config
+        ));
+        // histogram masks take precedence over sketch masks
+        assert_eq!(res[0].kind(), AggregationMethod::Histogram);
+        assert_eq!(res[1].kind(), AggregationMethod::Sketch);
+        assert_eq!(res[1].sketch().unwrap().relative_accuracy(), 0.001);
+        assert!((res[1].query(0.99).unwrap() - 1200.0).abs() <= 1200.0 * 0.001);
+        assert_eq!(res[2].kind(), AggregationMethod::Sketch);
+        assert_eq!(res[3].kind(), AggregationMethod::Summarize);
+    }
 }
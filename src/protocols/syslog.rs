@@ -0,0 +1,405 @@
+//! Parsing for the syslog protocols, RFC 3164 and RFC 5424.
+//!
+//! The two protocols share a leading PRI, from which we derive the facility
+//! and severity of the message. RFC 5424 frames are recognized by their
+//! version, everything else is parsed as RFC 3164 as leniently as we can
+//! manage, as a great deal of software is loose with that format.
+use crate::metric::LogLine;
+use chrono::offset::Utc;
+use chrono::{DateTime, Datelike, NaiveDateTime};
+use std::str;
+
+const FACILITIES: [&str; 24] = [
+    "kern",
+    "user",
+    "mail",
+    "daemon",
+    "auth",
+    "syslog",
+    "lpr",
+    "news",
+    "uucp",
+    "cron",
+    "authpriv",
+    "ftp",
+    "ntp",
+    "security",
+    "console",
+    "solaris-cron",
+    "local0",
+    "local1",
+    "local2",
+    "local3",
+    "local4",
+    "local5",
+    "local6",
+    "local7",
+];
+
+const SEVERITIES: [&str; 8] = [
+    "emerg", "alert", "crit", "err", "warning", "notice", "info", "debug",
+];
+
+/// Find the next frame in a syslog stream.
+///
+/// Streams may use octet-counting framing, where each frame is preceded by its
+/// length in bytes and a space, or non-transparent framing, where frames are
+/// terminated by a newline. Both are accepted, frame by frame. On success the
+/// number of bytes to consume from `buf` and the frame itself are returned. If
+/// `buf` does not yet hold a complete frame `None` is returned.
+pub fn next_frame(buf: &[u8]) -> Option<(usize, &[u8])> {
+    let digits = buf.iter().take_while(|b| b.is_ascii_digit()).count();
+    if digits > 0 && digits < 10 && buf.get(digits) == Some(&b' ') {
+        // The digits are ASCII so this cannot fail.
+        let len: usize = str::from_utf8(&buf[..digits]).unwrap().parse().unwrap();
+        let start = digits + 1;
+        if buf.len() < start + len {
+            return None;
+        }
+        return Some((start + len, &buf[start..start + len]));
+    }
+    buf.iter().position(|b| *b == b'\n').map(|idx| {
+        let frame = &buf[..idx];
+        let frame = if frame.ends_with(b"\r") {
+            &frame[..idx - 1]
+        } else {
+            frame
+        };
+        (idx + 1, frame)
+    })
+}
+
+/// Parse a single syslog frame into a `LogLine`.
+///
+/// The message becomes the `LogLine` value. The facility, severity, hostname
+/// and app-name of the frame are set as tags. The process id, message id and
+/// any RFC 5424 structured data are set as fields, structured data parameters
+/// being keyed `<sd-id>.<param-name>`. Frames without a valid PRI are
+/// rejected.
+pub fn parse_syslog(path: &str, frame: &str) -> Option<LogLine> {
+    let frame = frame.trim_end_matches(|c: char| c == '\n' || c == '\r' || c == '\0');
+    if !frame.starts_with('<') {
+        return None;
+    }
+    let close = frame.find('>')?;
+    let pri_str = &frame[1..close];
+    if pri_str.is_empty()
+        || pri_str.len() > 3
+        || !pri_str.chars().all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+    let pri: usize = pri_str.parse().ok()?;
+    if pri > 191 {
+        return None;
+    }
+    let rest = &frame[close + 1..];
+
+    let line = if rest.starts_with("1 ") {
+        parse_rfc5424(path, &rest[2..])?
+    } else {
+        parse_rfc3164(path, rest)
+    };
+    Some(
+        line.overlay_tag("facility", FACILITIES[pri / 8])
+            .overlay_tag("severity", SEVERITIES[pri % 8]),
+    )
+}
+
+/// Split the next space delimited header field off of `s`.
+fn header_field(s: &str) -> Option<(&str, &str)> {
+    match s.find(' ') {
+        Some(idx) => Some((&s[..idx], &s[idx + 1..])),
+        None if !s.is_empty() => Some((s, "")),
+        None => None,
+    }
+}
+
+fn parse_rfc5424(path: &str, rest: &str) -> Option<LogLine> {
+    let (timestamp, rest) = header_field(rest)?;
+    let (hostname, rest) = header_field(rest)?;
+    let (app_name, rest) = header_field(rest)?;
+    let (proc_id, rest) = header_field(rest)?;
+    let (msg_id, rest) = header_field(rest)?;
+
+    let (sd, msg) = if rest.starts_with('-') {
+        (Vec::new(), &rest[1..])
+    } else {
+        parse_structured_data(rest)?
+    };
+    let msg = if msg.is_empty() {
+        msg
+    } else if msg.starts_with(' ') {
+        &msg[1..]
+    } else {
+        return None;
+    };
+    let msg = msg.trim_start_matches('\u{feff}');
+
+    let mut line = LogLine::new(path, msg);
+    if timestamp != "-" {
+        let time = DateTime::parse_from_rfc3339(timestamp).ok()?;
+        line = line.time(time.timestamp());
+        line.subsec_nanos = time.timestamp_subsec_nanos();
+    }
+    if hostname != "-" {
+        line = line.overlay_tag("hostname", hostname);
+    }
+    if app_name != "-" {
+        line = line.overlay_tag("app_name", app_name);
+    }
+    if proc_id != "-" {
+        line = line.insert_field("proc_id", proc_id);
+    }
+    if msg_id != "-" {
+        line = line.insert_field("msg_id", msg_id);
+    }
+    for (key, val) in sd {
+        line = line.insert_field(key, val);
+    }
+    Some(line)
+}
+
+/// Parse one or more RFC 5424 SD-ELEMENTs from the start of `s`, returning
+/// their parameters and whatever of `s` follows them.
+fn parse_structured_data(s: &str) -> Option<(Vec<(String, String)>, &str)> {
+    let mut params = Vec::new();
+    let mut rest = s;
+    while rest.starts_with('[') {
+        rest = &rest[1..];
+        let id_end = rest.find(|c: char| c == ' ' || c == ']')?;
+        let id = &rest[..id_end];
+        if id.is_empty() {
+            return None;
+        }
+        rest = &rest[id_end..];
+        loop {
+            if rest.starts_with(']') {
+                rest = &rest[1..];
+                break;
+            }
+            if !rest.starts_with(' ') {
+                return None;
+            }
+            rest = &rest[1..];
+            let eq = rest.find('=')?;
+            let name = &rest[..eq];
+            if name.is_empty()
+                || name.contains(|c: char| c == ' ' || c == ']' || c == '"')
+            {
+                return None;
+            }
+            rest = &rest[eq + 1..];
+            if !rest.starts_with('"') {
+                return None;
+            }
+            let mut value = String::new();
+            let mut escaped = false;
+            let mut end = None;
+            for (idx, c) in rest[1..].char_indices() {
+                if escaped {
+                    if c != '"' && c != '\\' && c != ']' {
+                        value.push('\\');
+                    }
+                    value.push(c);
+                    escaped = false;
+                } else if c == '\\' {
+                    escaped = true;
+                } else if c == '"' {
+                    end = Some(idx + 2);
+                    break;
+                } else {
+                    value.push(c);
+                }
+            }
+            rest = &rest[end?..];
+            params.push((format!("{}.{}", id, name), value));
+        }
+    }
+    if params.is_empty() && !s.starts_with('[') {
+        return None;
+    }
+    Some((params, rest))
+}
+
+fn parse_rfc3164(path: &str, rest: &str) -> LogLine {
+    // The RFC 3164 timestamp is 'Mmm dd hh:mm:ss', with no year. We assume
+    // the current one.
+    let mut time = None;
+    let mut rest = rest;
+    if rest.len() > 15 && rest.is_char_boundary(15) && rest[15..].starts_with(' ') {
+        let stamp = format!("{} {}", Utc::now().year(), &rest[..15]);
+        if let Ok(t) = NaiveDateTime::parse_from_str(&stamp, "%Y %b %e %H:%M:%S") {
+            time = Some(t.timestamp());
+            rest = &rest[16..];
+        }
+    }
+
+    // A hostname only follows a timestamp, and never ends in the ':' of a tag.
+    let mut hostname = None;
+    if time.is_some() {
+        if let Some((host, after)) = header_field(rest) {
+            if !host.ends_with(':') && !host.contains('[') {
+                hostname = Some(host);
+                rest = after;
+            }
+        }
+    }
+
+    // The tag is the name of the program, optionally followed by its pid in
+    // brackets, then a colon.
+    let mut app_name = None;
+    let mut proc_id = None;
+    let tag_end = rest
+        .find(|c: char| {
+            !(c.is_alphanumeric() || c == '-' || c == '_' || c == '.' || c == '/')
+        })
+        .unwrap_or_else(|| rest.len());
+    if tag_end > 0 && tag_end <= 48 {
+        let after = &rest[tag_end..];
+        if after.starts_with('[') {
+            if let Some(close) = after.find("]:") {
+                app_name = Some(&rest[..tag_end]);
+                proc_id = Some(&after[1..close]);
+                rest = &after[close + 2..];
+            }
+        } else if after.starts_with(':') {
+            app_name = Some(&rest[..tag_end]);
+            rest = &after[1..];
+        }
+    }
+    if app_name.is_some() && rest.starts_with(' ') {
+        rest = &rest[1..];
+    }
+
+    let mut line = LogLine::new(path, rest);
+    if let Some(time) = time {
+        line = line.time(time);
+    }
+    if let Some(hostname) = hostname {
+        line = line.overlay_tag("hostname", hostname);
+    }
+    if let Some(app_name) = app_name {
+        line = line.overlay_tag("app_name", app_name);
+    }
+    if let Some(proc_id) = proc_id {
+        line = line.insert_field("proc_id", proc_id);
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag(line: &LogLine, key: &str) -> Option<String> {
+        line.tags.as_ref().and_then(|t| t.get(key).cloned())
+    }
+
+    fn field(line: &LogLine, key: &str) -> Option<String> {
+        line.fields.get(key).cloned()
+    }
+
+    #[test]
+    fn test_next_frame_octet_counted() {
+        let buf = b"11 <13>1 - - -20 <13>1 - - - -";
+        let (consumed, frame) = next_frame(buf).unwrap();
+        assert_eq!(consumed, 14);
+        assert_eq!(frame, b"<13>1 - - -");
+        let buf = &buf[consumed..];
+        assert_eq!(next_frame(buf), None);
+    }
+
+    #[test]
+    fn test_next_frame_newline() {
+        let buf = b"<13>hello\r\n<14>world\n<15>partial";
+        let (consumed, frame) = next_frame(buf).unwrap();
+        assert_eq!(frame, b"<13>hello");
+        let buf = &buf[consumed..];
+        let (consumed, frame) = next_frame(buf).unwrap();
+        assert_eq!(frame, b"<14>world");
+        let buf = &buf[consumed..];
+        assert_eq!(next_frame(buf), None);
+    }
+
+    #[test]
+    fn test_parse_rfc5424() {
+        let frame = "<165>1 2003-10-11T22:14:15.003Z mymachine.example.com evntslog \
+                     - ID47 [exampleSDID@32473 iut=\"3\" eventSource=\"Appli\\\"cation\" \
+                     eventID=\"1011\"][examplePriority@32473 class=\"high\"] \
+                     \u{feff}An application event log entry...";
+        let line = parse_syslog("sources.syslog.test", frame).unwrap();
+        assert_eq!(line.path, "sources.syslog.test");
+        assert_eq!(line.value, "An application event log entry...");
+        assert_eq!(line.time, 1065910455);
+        assert_eq!(line.subsec_nanos, 3_000_000);
+        assert_eq!(tag(&line, "facility"), Some("local4".into()));
+        assert_eq!(tag(&line, "severity"), Some("notice".into()));
+        assert_eq!(tag(&line, "hostname"), Some("mymachine.example.com".into()));
+        assert_eq!(tag(&line, "app_name"), Some("evntslog".into()));
+        assert_eq!(field(&line, "proc_id"), None);
+        assert_eq!(field(&line, "msg_id"), Some("ID47".into()));
+        assert_eq!(field(&line, "exampleSDID@32473.iut"), Some("3".into()));
+        assert_eq!(
+            field(&line, "exampleSDID@32473.eventSource"),
+            Some("Appli\"cation".into())
+        );
+        assert_eq!(
+            field(&line, "exampleSDID@32473.eventID"),
+            Some("1011".into())
+        );
+        assert_eq!(
+            field(&line, "examplePriority@32473.class"),
+            Some("high".into())
+        );
+    }
+
+    #[test]
+    fn test_parse_rfc5424_nil_values() {
+        let line = parse_syslog("p", "<34>1 - - - - - -").unwrap();
+        assert_eq!(line.value, "");
+        assert_eq!(tag(&line, "facility"), Some("auth".into()));
+        assert_eq!(tag(&line, "severity"), Some("crit".into()));
+        assert_eq!(tag(&line, "hostname"), None);
+        assert!(line.fields.is_empty());
+    }
+
+    #[test]
+    fn test_parse_rfc3164() {
+        let line = parse_syslog(
+            "p",
+            "<34>Oct 11 22:14:15 mymachine su[2331]: 'su root' failed for lonvick\n",
+        )
+        .unwrap();
+        assert_eq!(line.value, "'su root' failed for lonvick");
+        assert_eq!(tag(&line, "facility"), Some("auth".into()));
+        assert_eq!(tag(&line, "severity"), Some("crit".into()));
+        assert_eq!(tag(&line, "hostname"), Some("mymachine".into()));
+        assert_eq!(tag(&line, "app_name"), Some("su".into()));
+        assert_eq!(field(&line, "proc_id"), Some("2331".into()));
+    }
+
+    #[test]
+    fn test_parse_rfc3164_no_header() {
+        let line = parse_syslog("p", "<13>sshd: connection closed").unwrap();
+        assert_eq!(line.value, "connection closed");
+        assert_eq!(tag(&line, "facility"), Some("user".into()));
+        assert_eq!(tag(&line, "severity"), Some("notice".into()));
+        assert_eq!(tag(&line, "hostname"), None);
+        assert_eq!(tag(&line, "app_name"), Some("sshd".into()));
+
+        let line = parse_syslog("p", "<13>just some text").unwrap();
+        assert_eq!(line.value, "just some text");
+        assert_eq!(tag(&line, "app_name"), None);
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(parse_syslog("p", "no pri").is_none());
+        assert!(parse_syslog("p", "<>hello").is_none());
+        assert!(parse_syslog("p", "<192>hello").is_none());
+        assert!(parse_syslog("p", "<1a>hello").is_none());
+        assert!(parse_syslog("p", "<13>1 not-a-time host app - - -").is_none());
+        assert!(parse_syslog("p", "<13>1 - host app - - [unterminated").is_none());
+    }
+}
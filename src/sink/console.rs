@@ -107,6 +107,7 @@ impl Sink<ConsoleConfig> for Console {
         let mut sets = String::new();
         let mut summaries = String::new();
         let mut histograms = String::new();
+        let mut distincts = String::new();
 
         for telem in self.aggrs.iter() {
             match telem.kind() {
@@ -158,7 +159,19 @@ impl Sink<ConsoleConfig> for Console {
                         tgt.push_str("\n");
                     }
                 }
-                AggregationMethod::Summarize => {
+                AggregationMethod::Distinct => {
+                    let tgt = &mut distincts;
+                    if let Some(f) = telem.distinct() {
+                        tgt.push_str("    ");
+                        tgt.push_str(&telem.name);
+                        tgt.push_str("(");
+                        tgt.push_str(&telem.timestamp.to_string());
+                        tgt.push_str("): ");
+                        tgt.push_str(&f.to_string());
+                        tgt.push_str("\n");
+                    }
+                }
+                AggregationMethod::Summarize | AggregationMethod::Sketch => {
                     let tgt = &mut summaries;
                     for tup in &[
                         ("min", 0.0),
@@ -191,6 +204,8 @@ impl Sink<ConsoleConfig> for Console {
         print!("{}", summaries);
         println!("  histograms:");
         print!("{}", histograms);
+        println!("  distincts:");
+        print!("{}", distincts);
 
         self.aggrs.reset();
     }
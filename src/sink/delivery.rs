@@ -0,0 +1,358 @@
+//! The delivery policy of network sinks
+//!
+//! Sinks that deliver over the network retry a failed delivery with
+//! exponential backoff, up to a limit of attempts and time, and then give up
+//! on that flush. A sink that gives up on `breaker_threshold` flushes in a row
+//! trips its circuit breaker: the breaker opens, the sink's valve closes and
+//! no delivery is attempted for `breaker_cooldown` seconds. The next delivery
+//! is then attempted once, closing the breaker on success or opening it again
+//! on failure.
+//!
+//! Attempts, failures, give-ups and flushes refused by an open breaker are
+//! counted across all sinks. Each time a breaker opens or closes
+//! `cernan.sinks.breaker.opened` or `cernan.sinks.breaker.closed` is reported,
+//! tagged with the sink.
+//!
+//! The defaults retry forever, waiting at most 512 milliseconds between
+//! attempts, and never trip the breaker.
+
+use crate::source::report_full_telemetry;
+use crate::time;
+use crate::util::Valve;
+use rand::random;
+use std::cmp;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Total delivery attempts made by network sinks
+pub static SINK_DELIVERY_ATTEMPTS: AtomicUsize = AtomicUsize::new(0);
+/// Total delivery attempts that failed
+pub static SINK_DELIVERY_FAILURES: AtomicUsize = AtomicUsize::new(0);
+/// Total flushes given up on after exhausting attempts or time
+pub static SINK_DELIVERY_GAVE_UP: AtomicUsize = AtomicUsize::new(0);
+/// Total flushes refused while a circuit breaker was open
+pub static SINK_BREAKER_REJECTED: AtomicUsize = AtomicUsize::new(0);
+
+/// Configuration of a sink's delivery policy, see the module documentation.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct DeliveryConfig {
+    /// The wait, in milliseconds, before the second attempt of a delivery.
+    /// Each later attempt waits twice as long as the one before.
+    pub initial_backoff: u64,
+    /// The longest wait, in milliseconds, between two attempts.
+    pub max_backoff: u64,
+    /// The fraction by which waits are randomly lengthened or shortened, so
+    /// that sinks do not retry in lockstep. Between 0 and 1.
+    pub jitter: f64,
+    /// The number of attempts made before giving up. Unlimited if None.
+    pub max_attempts: Option<u32>,
+    /// The milliseconds a delivery may take before giving up. Unlimited if
+    /// None.
+    pub max_elapsed: Option<u64>,
+    /// The number of flushes given up on in a row that open the circuit
+    /// breaker. The breaker never opens if None.
+    pub breaker_threshold: Option<u32>,
+    /// The seconds the breaker stays open before a delivery is tried again.
+    pub breaker_cooldown: u64,
+}
+
+impl Default for DeliveryConfig {
+    fn default() -> DeliveryConfig {
+        DeliveryConfig {
+            initial_backoff: 1,
+            max_backoff: 512,
+            jitter: 0.0,
+            max_attempts: None,
+            max_elapsed: None,
+            breaker_threshold: None,
+            breaker_cooldown: 30,
+        }
+    }
+}
+
+/// The state of a circuit breaker.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Breaker {
+    /// Deliveries are attempted as usual.
+    Closed,
+    /// Deliveries are refused, the sink's valve is closed.
+    Open,
+    /// The cooldown has passed, the next delivery is attempted once.
+    HalfOpen,
+}
+
+/// Why a delivery was given up on.
+#[derive(Debug, PartialEq)]
+pub enum GaveUp<E> {
+    /// Every attempt allowed failed, the last with the given error.
+    Exhausted {
+        /// The number of attempts made.
+        attempts: u32,
+        /// The error of the last attempt.
+        last: E,
+    },
+    /// The circuit breaker is open, no attempt was made.
+    BreakerOpen,
+}
+
+/// The delivery policy of a single sink.
+#[derive(Debug)]
+pub struct Delivery {
+    sink: String,
+    config: DeliveryConfig,
+    gave_up: u32,
+    opened_at: Option<i64>,
+}
+
+impl Delivery {
+    /// Create the delivery policy of the sink `sink`, its config path.
+    pub fn new(sink: &str, config: DeliveryConfig) -> Delivery {
+        Delivery {
+            sink: sink.to_string(),
+            config,
+            gave_up: 0,
+            opened_at: None,
+        }
+    }
+
+    /// The wait before the attempt following `attempts` failed ones.
+    pub fn backoff(&self, attempts: u32) -> Duration {
+        if attempts == 0 {
+            return Duration::from_millis(0);
+        }
+        let exp = cmp::min(attempts - 1, 63);
+        let ms = self
+            .config
+            .initial_backoff
+            .checked_mul(1 << exp)
+            .map_or(self.config.max_backoff, |ms| {
+                cmp::min(ms, self.config.max_backoff)
+            });
+        let jitter = self.config.jitter.max(0.0).min(1.0);
+        let scale = 1.0 + jitter * (2.0 * random::<f64>() - 1.0);
+        Duration::from_millis((ms as f64 * scale) as u64)
+    }
+
+    /// The state of the circuit breaker.
+    pub fn breaker(&self) -> Breaker {
+        match self.opened_at {
+            None => Breaker::Closed,
+            Some(at) if time::now() - at < self.config.breaker_cooldown as i64 => {
+                Breaker::Open
+            }
+            Some(_) => Breaker::HalfOpen,
+        }
+    }
+
+    /// End the cooldown of an open circuit breaker, so that the next delivery
+    /// is attempted once. Sinks do this for the last flush ahead of shutdown,
+    /// after which whatever the breaker held back would be lost.
+    pub fn half_open(&mut self) {
+        if self.breaker() == Breaker::Open {
+            self.opened_at = Some(time::now() - self.config.breaker_cooldown as i64);
+        }
+    }
+
+    /// Closed while the circuit breaker is open, else open.
+    pub fn valve_state(&self) -> Valve {
+        match self.breaker() {
+            Breaker::Open => Valve::Closed,
+            Breaker::Closed | Breaker::HalfOpen => Valve::Open,
+        }
+    }
+
+    /// Attempt a delivery with `op` until it succeeds or the policy gives up.
+    pub fn attempt<T, E, F>(&mut self, mut op: F) -> Result<T, GaveUp<E>>
+    where
+        F: FnMut() -> Result<T, E>,
+    {
+        let half_open = match self.breaker() {
+            Breaker::Open => {
+                SINK_BREAKER_REJECTED.fetch_add(1, Ordering::Relaxed);
+                return Err(GaveUp::BreakerOpen);
+            }
+            Breaker::HalfOpen => true,
+            Breaker::Closed => false,
+        };
+        let start = Instant::now();
+        let mut attempts = 0;
+        loop {
+            thread::sleep(self.backoff(attempts));
+            attempts += 1;
+            SINK_DELIVERY_ATTEMPTS.fetch_add(1, Ordering::Relaxed);
+            let last = match op() {
+                Ok(res) => {
+                    self.succeeded();
+                    return Ok(res);
+                }
+                Err(last) => last,
+            };
+            SINK_DELIVERY_FAILURES.fetch_add(1, Ordering::Relaxed);
+            let elapsed = start.elapsed() + self.backoff(attempts);
+            let exhausted = half_open
+                || self
+                    .config
+                    .max_attempts
+                    .map_or(false, |max| attempts >= max)
+                || self
+                    .config
+                    .max_elapsed
+                    .map_or(false, |max| elapsed > Duration::from_millis(max));
+            if exhausted {
+                self.failed(half_open);
+                return Err(GaveUp::Exhausted { attempts, last });
+            }
+        }
+    }
+
+    fn succeeded(&mut self) {
+        self.gave_up = 0;
+        if self.opened_at.take().is_some() {
+            info!("circuit breaker of {} closed", self.sink);
+            report_full_telemetry(
+                "cernan.sinks.breaker.closed",
+                1.0,
+                Some(vec![("sink", &self.sink)]),
+            );
+        }
+    }
+
+    fn failed(&mut self, half_open: bool) {
+        SINK_DELIVERY_GAVE_UP.fetch_add(1, Ordering::Relaxed);
+        self.gave_up = self.gave_up.saturating_add(1);
+        let trip = half_open
+            || self
+                .config
+                .breaker_threshold
+                .map_or(false, |threshold| self.gave_up >= threshold);
+        if trip {
+            warn!(
+                "circuit breaker of {} opened for {} seconds",
+                self.sink, self.config.breaker_cooldown
+            );
+            self.opened_at = Some(time::now());
+            report_full_telemetry(
+                "cernan.sinks.breaker.opened",
+                1.0,
+                Some(vec![("sink", &self.sink)]),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let delivery = Delivery::new("sinks.x", DeliveryConfig::default());
+        let waits: Vec<u64> = (0..12)
+            .map(|attempts| {
+                let wait = delivery.backoff(attempts);
+                wait.as_secs() * 1_000 + u64::from(wait.subsec_millis())
+            })
+            .collect();
+        assert_eq!(waits, vec![0, 1, 2, 4, 8, 16, 32, 64, 128, 256, 512, 512]);
+        assert_eq!(delivery.backoff(1_000), Duration::from_millis(512));
+
+        let jittered = Delivery::new(
+            "sinks.x",
+            DeliveryConfig {
+                initial_backoff: 100,
+                jitter: 0.5,
+                ..DeliveryConfig::default()
+            },
+        );
+        for _ in 0..100 {
+            let wait = jittered.backoff(1);
+            assert!(wait >= Duration::from_millis(50));
+            assert!(wait <= Duration::from_millis(150));
+        }
+    }
+
+    #[test]
+    fn test_attempt_gives_up() {
+        let mut delivery = Delivery::new(
+            "sinks.x",
+            DeliveryConfig {
+                initial_backoff: 0,
+                max_attempts: Some(3),
+                ..DeliveryConfig::default()
+            },
+        );
+        let mut calls = 0;
+        let res: Result<(), _> = delivery.attempt(|| {
+            calls += 1;
+            Err(calls)
+        });
+        assert_eq!(
+            res,
+            Err(GaveUp::Exhausted {
+                attempts: 3,
+                last: 3
+            })
+        );
+
+        let mut calls = 0;
+        let res: Result<u32, GaveUp<()>> = delivery.attempt(|| {
+            calls += 1;
+            if calls == 2 {
+                Ok(calls)
+            } else {
+                Err(())
+            }
+        });
+        assert_eq!(res, Ok(2));
+        assert_eq!(delivery.breaker(), Breaker::Closed);
+    }
+
+    #[test]
+    fn test_breaker() {
+        let mut delivery = Delivery::new(
+            "sinks.x",
+            DeliveryConfig {
+                initial_backoff: 0,
+                max_attempts: Some(1),
+                breaker_threshold: Some(2),
+                breaker_cooldown: 60,
+                ..DeliveryConfig::default()
+            },
+        );
+        let fail = || -> Result<(), ()> { Err(()) };
+        assert!(delivery.attempt(fail).is_err());
+        assert_eq!(delivery.breaker(), Breaker::Closed);
+        assert!(delivery.attempt(fail).is_err());
+        assert_eq!(delivery.breaker(), Breaker::Open);
+        assert_eq!(delivery.valve_state(), Valve::Closed);
+
+        let mut called = false;
+        let res = delivery.attempt(|| -> Result<(), ()> {
+            called = true;
+            Ok(())
+        });
+        assert_eq!(res, Err(GaveUp::BreakerOpen));
+        assert!(!called);
+
+        // Once the cooldown passes a single failure opens the breaker again.
+        delivery.opened_at = Some(time::now() - 60);
+        assert_eq!(delivery.breaker(), Breaker::HalfOpen);
+        assert_eq!(delivery.valve_state(), Valve::Open);
+        assert!(delivery.attempt(fail).is_err());
+        assert_eq!(delivery.breaker(), Breaker::Open);
+
+        // And a single success closes it.
+        delivery.opened_at = Some(time::now() - 60);
+        assert_eq!(delivery.attempt(|| -> Result<(), ()> { Ok(()) }), Ok(()));
+        assert_eq!(delivery.breaker(), Breaker::Closed);
+        // Ahead of shutdown the cooldown may be cut short.
+        assert!(delivery.attempt(fail).is_err());
+        assert!(delivery.attempt(fail).is_err());
+        assert_eq!(delivery.breaker(), Breaker::Open);
+        delivery.half_open();
+        assert_eq!(delivery.breaker(), Breaker::HalfOpen);
+        assert_eq!(delivery.attempt(|| -> Result<(), ()> { Ok(()) }), Ok(()));
+        assert_eq!(delivery.breaker(), Breaker::Closed);
+    }
+}
@@ -1,6 +1,7 @@
 //! `ElasticSearch` is a documentation indexing engine.
 
-use crate::metric::{LogLine, TagMap};
+use crate::metric::{Event, LogLine, TagMap};
+use crate::sink::delivery::{Breaker, Delivery, DeliveryConfig, GaveUp};
 use crate::sink::{Sink, Valve};
 use crate::source::flushes_per_second;
 use chrono::naive::NaiveDateTime;
@@ -8,10 +9,10 @@ use chrono::offset::Utc;
 use chrono::DateTime;
 use elastic::client::responses::bulk;
 use elastic::error;
-use elastic::error::Result;
 use elastic::prelude::*;
 use std::cmp;
 use std::error::Error;
+use std::mem;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use uuid;
 
@@ -83,6 +84,10 @@ pub struct ElasticsearchConfig {
     /// sink. These tags will overwrite any tags carried by the `metric::Event`
     /// itself.
     pub tags: TagMap,
+    /// The retry and circuit breaker policy of bulk requests. By default a
+    /// flush makes a single bulk request, records that fail are retried at
+    /// later flushes as set by `delivery_attempt_limit`.
+    pub delivery: DeliveryConfig,
 }
 
 impl Default for ElasticsearchConfig {
@@ -97,6 +102,10 @@ impl Default for ElasticsearchConfig {
             port: 9200,
             flush_interval: flushes_per_second(),
             tags: TagMap::default(),
+            delivery: DeliveryConfig {
+                max_attempts: Some(1),
+                ..DeliveryConfig::default()
+            },
         }
     }
 }
@@ -112,6 +121,8 @@ struct Line {
 /// Refer to the documentation on `ElasticsearchConfig` for more details.
 pub struct Elasticsearch {
     buffer: Vec<Line>,
+    dead_letters: Vec<(Event, String)>,
+    delivery: Delivery,
     secure: bool,
     host: String,
     port: usize,
@@ -142,7 +153,7 @@ impl Elasticsearch {
                 "uuid": uuid,
                 "path": line.path.clone(),
                 "payload": line.value.clone(),
-                "timestamp": format_time(line.time),
+                "timestamp": format_time(line.time, line.subsec_nanos),
             });
             let obj = payload.as_object_mut().unwrap();
             for (k, v) in line.tags(&self.tags) {
@@ -159,8 +170,13 @@ impl Elasticsearch {
 
 impl Sink<ElasticsearchConfig> for Elasticsearch {
     fn init(config: ElasticsearchConfig) -> Self {
+        let config_path = config
+            .config_path
+            .unwrap_or_else(|| "sinks.elasticsearch".to_string());
         Elasticsearch {
             buffer: Vec::new(),
+            dead_letters: Vec::new(),
+            delivery: Delivery::new(&config_path, config.delivery),
             secure: config.secure,
             host: config.host,
             port: config.port,
@@ -189,151 +205,177 @@ impl Sink<ElasticsearchConfig> for Elasticsearch {
 
         let mut buffer = String::with_capacity(4048);
         self.bulk_body(&mut buffer);
-        if let Ok(snd) = client.request(BulkRequest::new(buffer)).send() {
-            let bulk_resp: Result<BulkResponse> = snd.into_response::<BulkResponse>();
-            ELASTIC_INTERNAL_BUFFER_LEN.store(self.buffer.len(), Ordering::Relaxed);
-            match bulk_resp {
-                Ok(bulk) => {
-                    ELASTIC_RECORDS_DELIVERY.fetch_add(1, Ordering::Relaxed);
-                    for item in bulk.iter() {
-                        match item {
-                            Ok(item) => {
-                                let uuid = uuid::Uuid::parse_str(item.id())
-                                    .expect("catastrophic error, TID not a UUID");
-                                let mut idx = 0;
-                                for i in 0..self.buffer.len() {
-                                    match self.buffer[i].uuid.cmp(&uuid) {
-                                        cmp::Ordering::Equal => {
-                                            break;
-                                        }
-                                        _ => idx += 1,
+        let bulk_resp: Result<BulkResponse, GaveUp<error::Error>> =
+            self.delivery.attempt(|| {
+                client
+                    .request(BulkRequest::new(buffer.clone()))
+                    .send()
+                    .and_then(|snd| snd.into_response::<BulkResponse>())
+            });
+        ELASTIC_INTERNAL_BUFFER_LEN.store(self.buffer.len(), Ordering::Relaxed);
+        match bulk_resp {
+            Ok(bulk) => {
+                ELASTIC_RECORDS_DELIVERY.fetch_add(1, Ordering::Relaxed);
+                for item in bulk.iter() {
+                    match item {
+                        Ok(item) => {
+                            let uuid = uuid::Uuid::parse_str(item.id())
+                                .expect("catastrophic error, TID not a UUID");
+                            let mut idx = 0;
+                            for i in 0..self.buffer.len() {
+                                match self.buffer[i].uuid.cmp(&uuid) {
+                                    cmp::Ordering::Equal => {
+                                        break;
                                     }
+                                    _ => idx += 1,
                                 }
-                                self.buffer.remove(idx);
-                                ELASTIC_RECORDS_TOTAL_DELIVERED
-                                    .fetch_add(1, Ordering::Relaxed);
                             }
-                            Err(item) => {
-                                let uuid = uuid::Uuid::parse_str(item.id())
-                                    .expect("catastrophic error, TID not a UUID");
-                                let mut idx = 0;
-                                for i in 0..self.buffer.len() {
-                                    match self.buffer[i].uuid.cmp(&uuid) {
-                                        cmp::Ordering::Equal => {
-                                            break;
-                                        }
-                                        _ => idx += 1,
+                            self.buffer.remove(idx);
+                            ELASTIC_RECORDS_TOTAL_DELIVERED
+                                .fetch_add(1, Ordering::Relaxed);
+                        }
+                        Err(item) => {
+                            let uuid = uuid::Uuid::parse_str(item.id())
+                                .expect("catastrophic error, TID not a UUID");
+                            let mut idx = 0;
+                            for i in 0..self.buffer.len() {
+                                match self.buffer[i].uuid.cmp(&uuid) {
+                                    cmp::Ordering::Equal => {
+                                        break;
                                     }
+                                    _ => idx += 1,
                                 }
-                                self.buffer[idx].attempts += 1;
-                                if self.buffer[idx].attempts
-                                    > self.delivery_attempt_limit
-                                {
-                                    self.buffer.remove(idx);
-                                }
-                                ELASTIC_RECORDS_TOTAL_FAILED
-                                    .fetch_add(1, Ordering::Relaxed);
-                                if let Some(source) = item.source() {
-                                    debug!(
-                                        "Failed to write item with error {}, source {}",
-                                        item.description(),
-                                        source
-                                    );
-                                } else {
-                                    debug!(
-                                        "Failed to write item with error {}",
-                                        item.description()
-                                    );
-                                }
-                                match item.action() {
-                                    bulk::Action::Index => {
-                                        ELASTIC_BULK_ACTION_INDEX_ERR
-                                            .fetch_add(1, Ordering::Relaxed)
-                                    }
-                                    bulk::Action::Create => {
-                                        ELASTIC_BULK_ACTION_CREATE_ERR
-                                            .fetch_add(1, Ordering::Relaxed)
-                                    }
-                                    bulk::Action::Update => {
-                                        ELASTIC_BULK_ACTION_UPDATE_ERR
-                                            .fetch_add(1, Ordering::Relaxed)
-                                    }
-                                    bulk::Action::Delete => {
-                                        ELASTIC_BULK_ACTION_DELETE_ERR
-                                            .fetch_add(1, Ordering::Relaxed)
-                                    }
-                                };
                             }
+                            self.buffer[idx].attempts += 1;
+                            if self.buffer[idx].attempts > self.delivery_attempt_limit
+                            {
+                                let line = self.buffer.remove(idx);
+                                let reason = format!(
+                                    "rejected {} times, last with error {}",
+                                    line.attempts,
+                                    item.description()
+                                );
+                                self.dead_letters
+                                    .push((Event::new_log(line.line), reason));
+                            }
+                            ELASTIC_RECORDS_TOTAL_FAILED
+                                .fetch_add(1, Ordering::Relaxed);
+                            if let Some(source) = item.source() {
+                                debug!(
+                                    "Failed to write item with error {}, source {}",
+                                    item.description(),
+                                    source
+                                );
+                            } else {
+                                debug!(
+                                    "Failed to write item with error {}",
+                                    item.description()
+                                );
+                            }
+                            match item.action() {
+                                bulk::Action::Index => ELASTIC_BULK_ACTION_INDEX_ERR
+                                    .fetch_add(1, Ordering::Relaxed),
+                                bulk::Action::Create => ELASTIC_BULK_ACTION_CREATE_ERR
+                                    .fetch_add(1, Ordering::Relaxed),
+                                bulk::Action::Update => ELASTIC_BULK_ACTION_UPDATE_ERR
+                                    .fetch_add(1, Ordering::Relaxed),
+                                bulk::Action::Delete => ELASTIC_BULK_ACTION_DELETE_ERR
+                                    .fetch_add(1, Ordering::Relaxed),
+                            };
                         }
                     }
                 }
-                Err(err) => {
-                    match err {
-                        error::Error::Api(ref api_error) => {
-                            use elastic::error::ApiError;
-                            match *api_error {
-                                ApiError::IndexNotFound { ref index } => {
-                                    ELASTIC_ERROR_API_INDEX_NOT_FOUND
-                                        .fetch_add(1, Ordering::Relaxed);
-                                    debug!("Unable to write, API Error (Index Not Found): {}", index);
-                                }
-                                ApiError::Parsing { ref reason, .. } => {
-                                    ELASTIC_ERROR_API_PARSING
-                                        .fetch_add(1, Ordering::Relaxed);
-                                    debug!(
-                                        "Unable to write, API Error (Parsing): {}",
-                                        reason
-                                    );
-                                }
-                                ApiError::MapperParsing { ref reason, .. } => {
-                                    ELASTIC_ERROR_API_MAPPER_PARSING
-                                        .fetch_add(1, Ordering::Relaxed);
-                                    debug!("Unable to write, API Error (Mapper Parsing): {}", reason);
-                                }
-                                ApiError::ActionRequestValidation {
-                                    ref reason,
-                                    ..
-                                } => {
-                                    ELASTIC_ERROR_API_ACTION_REQUEST_VALIDATION
-                                        .fetch_add(1, Ordering::Relaxed);
-                                    debug!(
+            }
+            Err(GaveUp::Exhausted { last: err, .. }) => match err {
+                error::Error::Api(ref api_error) => {
+                    use elastic::error::ApiError;
+                    match *api_error {
+                        ApiError::IndexNotFound { ref index } => {
+                            ELASTIC_ERROR_API_INDEX_NOT_FOUND
+                                .fetch_add(1, Ordering::Relaxed);
+                            debug!(
+                                "Unable to write, API Error (Index Not Found): {}",
+                                index
+                            );
+                        }
+                        ApiError::Parsing { ref reason, .. } => {
+                            ELASTIC_ERROR_API_PARSING.fetch_add(1, Ordering::Relaxed);
+                            debug!("Unable to write, API Error (Parsing): {}", reason);
+                        }
+                        ApiError::MapperParsing { ref reason, .. } => {
+                            ELASTIC_ERROR_API_MAPPER_PARSING
+                                .fetch_add(1, Ordering::Relaxed);
+                            debug!(
+                                "Unable to write, API Error (Mapper Parsing): {}",
+                                reason
+                            );
+                        }
+                        ApiError::ActionRequestValidation { ref reason, .. } => {
+                            ELASTIC_ERROR_API_ACTION_REQUEST_VALIDATION
+                                .fetch_add(1, Ordering::Relaxed);
+                            debug!(
                                     "Unable to write, API Error (Action Request Validation): {}",
                                     reason
                                 );
-                                }
-                                ApiError::DocumentMissing { ref index, .. } => {
-                                    ELASTIC_ERROR_API_DOCUMENT_MISSING
-                                        .fetch_add(1, Ordering::Relaxed);
-                                    debug!("Unable to write, API Error (Document Missing): {}", index);
-                                }
-                                ApiError::IndexAlreadyExists { ref index, .. } => {
-                                    ELASTIC_ERROR_API_INDEX_ALREADY_EXISTS
-                                        .fetch_add(1, Ordering::Relaxed);
-                                    debug!(
+                        }
+                        ApiError::DocumentMissing { ref index, .. } => {
+                            ELASTIC_ERROR_API_DOCUMENT_MISSING
+                                .fetch_add(1, Ordering::Relaxed);
+                            debug!(
+                                "Unable to write, API Error (Document Missing): {}",
+                                index
+                            );
+                        }
+                        ApiError::IndexAlreadyExists { ref index, .. } => {
+                            ELASTIC_ERROR_API_INDEX_ALREADY_EXISTS
+                                .fetch_add(1, Ordering::Relaxed);
+                            debug!(
                                     "Unable to write, API Error (Index Already Exists): {}",
                                     index
                                 );
-                                }
-                                _ => {
-                                    ELASTIC_ERROR_API_UNKNOWN
-                                        .fetch_add(1, Ordering::Relaxed);
-                                    debug!("Unable to write, API Error (Unknown)");
-                                }
-                            }
                         }
-                        error::Error::Client(ref client_error) => {
-                            ELASTIC_ERROR_CLIENT.fetch_add(1, Ordering::Relaxed);
-                            debug!(
-                                "Unable to write, client error: {}",
-                                client_error.description()
-                            );
+                        _ => {
+                            ELASTIC_ERROR_API_UNKNOWN.fetch_add(1, Ordering::Relaxed);
+                            debug!("Unable to write, API Error (Unknown)");
                         }
                     }
                 }
-            }
+                error::Error::Client(ref client_error) => {
+                    ELASTIC_ERROR_CLIENT.fetch_add(1, Ordering::Relaxed);
+                    debug!(
+                        "Unable to write, client error: {}",
+                        client_error.description()
+                    );
+                }
+            },
+            // The buffer is kept for the breaker's cooldown, the sink's valve
+            // being closed meanwhile, or until the final flush.
+            Err(GaveUp::BreakerOpen) => {}
         }
     }
 
+    fn final_flush(&mut self) {
+        // Cut short the cooldown of an open breaker, so that what it held
+        // back is attempted once more.
+        self.delivery.half_open();
+        self.flush();
+        // Whatever is left would be lost with the sink.
+        let reason = match self.delivery.breaker() {
+            Breaker::Open => "circuit breaker open at shutdown",
+            Breaker::Closed | Breaker::HalfOpen => "undelivered at shutdown",
+        };
+        let buffer = mem::replace(&mut self.buffer, Vec::new());
+        self.dead_letters.extend(
+            buffer
+                .into_iter()
+                .map(|line| (Event::new_log(line.line), reason.to_string())),
+        );
+    }
+
+    fn dead_letters(&mut self) -> Vec<(Event, String)> {
+        mem::replace(&mut self.dead_letters, Vec::new())
+    }
+
     fn shutdown(mut self) {
         self.flush();
     }
@@ -351,14 +393,14 @@ impl Sink<ElasticsearchConfig> for Elasticsearch {
         if self.buffer.len() > 10_000 {
             Valve::Closed
         } else {
-            Valve::Open
+            self.delivery.valve_state()
         }
     }
 }
 
 #[inline]
-fn format_time(time: i64) -> String {
-    let naive_time = NaiveDateTime::from_timestamp(time, 0);
+fn format_time(time: i64, subsec_nanos: u32) -> String {
+    let naive_time = NaiveDateTime::from_timestamp(time, subsec_nanos);
     let utc_time: DateTime<Utc> = DateTime::from_utc(naive_time, Utc);
     format!("{}", utc_time.format("%+"))
 }
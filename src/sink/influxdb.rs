@@ -1,12 +1,13 @@
 //! `InfluxDB` is a telemetry database.
 
-use crate::metric::{TagIter, TagMap, Telemetry};
+use crate::metric::{Event, TagIter, TagMap, Telemetry};
+use crate::sink::delivery::{Delivery, DeliveryConfig, GaveUp};
 use crate::sink::{Sink, Valve};
 use crate::source::flushes_per_second;
-use crate::time;
 use quantiles::histogram::Bound;
 use reqwest;
 use std::cmp;
+use std::mem;
 use std::string;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use url::Url;
@@ -28,9 +29,10 @@ pub static INFLUX_FAILURE_SERVER: AtomicUsize = AtomicUsize::new(0);
 pub struct InfluxDB {
     /// The store of Telemetry to be reported
     aggrs: Vec<Telemetry>,
-    /// Number of failed delivery attempts by this sink. We keep track of this
-    /// across flushes to avoid stampeding between flushes.
-    delivery_attempts: u32,
+    /// The retry and circuit breaker policy of deliveries to the database.
+    delivery: Delivery,
+    /// Telemetry given up on, to be handed back as dead letters.
+    dead_letters: Vec<(Event, String)>,
     flush_interval: u64,
     client: reqwest::Client,
     uri: Url,
@@ -58,6 +60,8 @@ pub struct InfluxDBConfig {
     pub tags: TagMap,
     /// The interval, in seconds, on which the `InfluxDB` sink will report.
     pub flush_interval: u64,
+    /// The retry and circuit breaker policy of deliveries to the database.
+    pub delivery: DeliveryConfig,
 }
 
 impl Default for InfluxDBConfig {
@@ -70,6 +74,7 @@ impl Default for InfluxDBConfig {
             config_path: None,
             tags: Default::default(),
             flush_interval: 60 * flushes_per_second(),
+            delivery: DeliveryConfig::default(),
         }
     }
 }
@@ -120,7 +125,7 @@ impl InfluxDB {
                         buffer.push_str(" ");
                         buffer.push_str(get_from_cache(
                             &mut time_cache,
-                            telem.timestamp.saturating_mul(1_000_000_000) as u64,
+                            telem.epoch_ns() as u64,
                         ));
                         buffer.push_str("\n");
                         tag_buf.clear();
@@ -137,7 +142,24 @@ impl InfluxDB {
                         buffer.push_str(" ");
                         buffer.push_str(get_from_cache(
                             &mut time_cache,
-                            telem.timestamp.saturating_mul(1_000_000_000) as u64,
+                            telem.epoch_ns() as u64,
+                        ));
+                        buffer.push_str("\n");
+                        tag_buf.clear();
+                    }
+                }
+                AggregationMethod::Distinct => {
+                    if let Some(val) = telem.distinct() {
+                        buffer.push_str(&telem.name);
+                        fmt_tags(telem.tags(&self.tags), &mut tag_buf);
+                        buffer.push_str(&tag_buf);
+                        buffer.push_str(" ");
+                        buffer.push_str("value=");
+                        buffer.push_str(get_from_cache(&mut value_cache, val));
+                        buffer.push_str(" ");
+                        buffer.push_str(get_from_cache(
+                            &mut time_cache,
+                            telem.epoch_ns() as u64,
                         ));
                         buffer.push_str("\n");
                         tag_buf.clear();
@@ -163,14 +185,14 @@ impl InfluxDB {
                             buffer.push_str(" ");
                             buffer.push_str(get_from_cache(
                                 &mut time_cache,
-                                telem.timestamp.saturating_mul(1_000_000_000) as u64,
+                                telem.epoch_ns() as u64,
                             ));
                             buffer.push_str("\n");
                             tag_buf.clear();
                         }
                     }
                 }
-                AggregationMethod::Summarize => {
+                AggregationMethod::Summarize | AggregationMethod::Sketch => {
                     for percentile in &[0.25, 0.50, 0.75, 0.90, 0.99, 1.0] {
                         if let Some(val) = telem.query(*percentile) {
                             buffer
@@ -183,7 +205,7 @@ impl InfluxDB {
                             buffer.push_str(" ");
                             buffer.push_str(get_from_cache(
                                 &mut time_cache,
-                                telem.timestamp.saturating_mul(1_000_000_000) as u64,
+                                telem.epoch_ns() as u64,
                             ));
                             buffer.push_str("\n");
                             tag_buf.clear();
@@ -214,9 +236,14 @@ impl Sink<InfluxDBConfig> for InfluxDB {
             .build()
             .expect("could not create influxdb client");
 
+        let config_path = config
+            .config_path
+            .unwrap_or_else(|| "sinks.influxdb".to_string());
+
         InfluxDB {
             aggrs: Vec::with_capacity(4048),
-            delivery_attempts: 0,
+            delivery: Delivery::new(&config_path, config.delivery),
+            dead_letters: Vec::new(),
             flush_interval: config.flush_interval,
             client,
             uri,
@@ -229,53 +256,65 @@ impl Sink<InfluxDBConfig> for InfluxDB {
     }
 
     fn flush(&mut self) {
+        if self.aggrs.is_empty() {
+            return;
+        }
         let mut buffer = String::with_capacity(4048);
         self.format_stats(&mut buffer, &self.aggrs);
 
-        // report loop, infinite
-        loop {
-            // Report our delivery attempts and delay, potentially. As mentioned
-            // in the documentation for the InfluxDB struct we want to avoid
-            // stampeeding the database. If a flush fails X times then the next
-            // flush will have delivery_attempts_1 = (X - 1) +
-            // delivery_attempts_0 waits. The idea is that a failure that
-            // happens to succeed may succeed against a degraded system; we
-            // should not assume full health.
-            INFLUX_DELIVERY_ATTEMPTS
-                .fetch_add(self.delivery_attempts as usize, Ordering::Relaxed);
-            time::delay(self.delivery_attempts);
-
-            match self
-                .client
-                .post(self.uri.clone())
+        let client = &self.client;
+        let uri = &self.uri;
+        let res = self.delivery.attempt(|| {
+            INFLUX_DELIVERY_ATTEMPTS.fetch_add(1, Ordering::Relaxed);
+            let resp = client
+                .post(uri.clone())
                 .header(reqwest::header::Connection::keep_alive())
                 .body(buffer.clone())
                 .send()
-            {
-                Err(e) => debug!("hyper error doing POST: {:?}", e),
-                Ok(resp) => {
-                    // https://docs.influxdata.com/influxdb/v1.
-                    // 2/guides/writing_data/#http-response-summary
-                    if resp.status().is_success() {
-                        INFLUX_SUCCESS.fetch_add(1, Ordering::Relaxed);
-                        buffer.clear();
-                        self.delivery_attempts =
-                            self.delivery_attempts.saturating_sub(1);
-                        break;
-                    } else if resp.status().is_client_error() {
-                        self.delivery_attempts =
-                            self.delivery_attempts.saturating_add(1);
-                        INFLUX_FAILURE_CLIENT.fetch_add(1, Ordering::Relaxed);
-                    } else if resp.status().is_server_error() {
-                        self.delivery_attempts =
-                            self.delivery_attempts.saturating_add(1);
-                        INFLUX_FAILURE_SERVER.fetch_add(1, Ordering::Relaxed);
-                    }
-                }
+                .map_err(|e| format!("error doing POST: {}", e))?;
+            // https://docs.influxdata.com/influxdb/v1.
+            // 2/guides/writing_data/#http-response-summary
+            let status = resp.status();
+            if status.is_success() {
+                INFLUX_SUCCESS.fetch_add(1, Ordering::Relaxed);
+                return Ok(());
+            } else if status.is_client_error() {
+                INFLUX_FAILURE_CLIENT.fetch_add(1, Ordering::Relaxed);
+            } else if status.is_server_error() {
+                INFLUX_FAILURE_SERVER.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(format!("response status {}", status))
+        });
+        match res {
+            Ok(()) => self.aggrs.clear(),
+            Err(GaveUp::Exhausted { attempts, last }) => {
+                warn!(
+                    "Gave up delivering {} points to {} after {} attempts: {}",
+                    self.aggrs.len(),
+                    self.uri,
+                    attempts,
+                    last
+                );
+                let reason = format!("gave up after {} attempts: {}", attempts, last);
+                let aggrs = mem::replace(&mut self.aggrs, Vec::with_capacity(4048));
+                self.dead_letters.extend(
+                    aggrs
+                        .into_iter()
+                        .map(|telem| (Event::new_telemetry(telem), reason.clone())),
+                );
             }
+            // The points are kept for the breaker's cooldown, the sink's valve
+            // being closed meanwhile, or until the final flush. Should that
+            // fail too they are given up as above.
+            Err(GaveUp::BreakerOpen) => {}
         }
+    }
 
-        self.aggrs.clear();
+    fn final_flush(&mut self) {
+        // Cut short the cooldown of an open breaker, so that what it held
+        // back is attempted once more rather than lost.
+        self.delivery.half_open();
+        self.flush();
     }
 
     fn shutdown(mut self) {
@@ -290,9 +329,13 @@ impl Sink<InfluxDBConfig> for InfluxDB {
         if self.aggrs.len() > 100_000 {
             Valve::Closed
         } else {
-            Valve::Open
+            self.delivery.valve_state()
         }
     }
+
+    fn dead_letters(&mut self) -> Vec<(Event, String)> {
+        mem::replace(&mut self.dead_letters, Vec::new())
+    }
 }
 
 #[cfg(test)]
@@ -317,6 +360,7 @@ mod test {
             config_path: Some("sinks.influxdb".to_string()),
             tags: tags,
             flush_interval: 60 * flushes_per_second(),
+            delivery: DeliveryConfig::default(),
         };
         let mut influxdb = InfluxDB::init(config);
         let dt_0 = Utc.ymd(1990, 6, 12).and_hms_milli(9, 10, 11, 00);
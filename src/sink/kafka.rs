@@ -1,5 +1,5 @@
 //! Kafka sink for Raw events.
-use crate::metric::{global_ack_bag, Encoding, Metadata};
+use crate::metric::{global_ack_bag, Encoding, Event, Metadata};
 use crate::sink::Sink;
 use crate::source::flushes_per_second;
 use crate::util::Valve;
@@ -11,7 +11,8 @@ use rdkafka::message::{Headers, Message, OwnedHeaders, OwnedMessage};
 use rdkafka::producer::future_producer::DeliveryFuture;
 use rdkafka::producer::{FutureProducer, FutureRecord};
 use rdkafka::util::current_time_millis;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::mem;
 use std::str;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use uuid::Uuid;
@@ -81,7 +82,7 @@ pub struct KafkaConfig {
     /// Kafka brokers. This is a comma-separated list of host or host:port.
     pub brokers: Option<String>,
     /// Underlying librdkafka configuration.
-    pub rdkafka_config: Option<HashMap<String, String>>,
+    pub rdkafka_config: Option<BTreeMap<String, String>>,
     /// Maximum number of bytes that can be in-flight. Once we go over this, the
     /// valve closes. Default = 10Mb.
     pub max_message_bytes: usize,
@@ -231,21 +232,45 @@ impl FailedMessageWrapper {
     }
 
     pub fn metadata(&self) -> Option<Metadata> {
-        let headers = self.message.headers();
-        match headers {
-            Some(h) => {
-                let mut hash_map: Metadata = Metadata::default();
-                for idx in 0..h.count() {
-                    let (k, v) = h.get(idx).unwrap();
-                    hash_map.insert(k.as_bytes().to_vec(), v.to_vec());
-                }
-                Some(hash_map)
+        metadata(&self.message)
+    }
+}
+
+/// The metadata of a message, from its headers.
+fn metadata(message: &OwnedMessage) -> Option<Metadata> {
+    let headers = message.headers();
+    match headers {
+        Some(h) => {
+            let mut hash_map: Metadata = Metadata::default();
+            for idx in 0..h.count() {
+                let (k, v) = h.get(idx).unwrap();
+                hash_map.insert(k.as_bytes().to_vec(), v.to_vec());
             }
-            None => None,
+            Some(hash_map)
         }
+        None => None,
     }
 }
 
+/// The raw event a message was made from, to be dead lettered. Returns None
+/// if the message has lost its payload. The encoding of the original event
+/// is not kept by Kafka and is taken to be `Encoding::Raw`.
+fn dead_letter(message: &OwnedMessage) -> Option<Event> {
+    let bytes = message.payload()?.to_vec();
+    let order_by = message
+        .key()
+        .and_then(|key| str::from_utf8(key).ok())
+        .and_then(|key| u64::from_str_radix(key, 16).ok())
+        .unwrap_or(0);
+    Some(Event::Raw {
+        order_by,
+        encoding: Encoding::Raw,
+        bytes,
+        metadata: metadata(message),
+        connection_id: None,
+    })
+}
+
 type BoxedKafkaMessageSender = Box<KafkaMessageSender + Sync + Send>;
 
 /// Kafka sink internal state.
@@ -266,6 +291,8 @@ pub struct Kafka {
     flush_interval: u64,
     /// An object responsible for incrementing publication statistics.
     stats: Box<StatsCollector + Send + Sync>,
+    /// Messages given up on, as raw events, and the reason why.
+    dead_letters: Vec<(Event, String)>,
 }
 
 impl Sink<KafkaConfig> for Kafka {
@@ -297,6 +324,7 @@ impl Sink<KafkaConfig> for Kafka {
             max_message_bytes: config.max_message_bytes,
             flush_interval: config.flush_interval,
             stats: Box::new(DefaultStatsCollector),
+            dead_letters: Vec::new(),
         }
     }
 
@@ -368,6 +396,10 @@ impl Sink<KafkaConfig> for Kafka {
         Some(self.flush_interval)
     }
 
+    fn dead_letters(&mut self) -> Vec<(Event, String)> {
+        mem::replace(&mut self.dead_letters, Vec::new())
+    }
+
     fn shutdown(mut self) {
         self.flush();
     }
@@ -391,6 +423,7 @@ impl Kafka {
         let mut inc_retry = 0usize;
         let mut inc_fail = 0usize;
         let mut ack_ids: Vec<Option<Uuid>> = Vec::new();
+        let mut dead_letters = Vec::new();
         let result = self.messages
             .iter_mut()
             .filter_map(|future| {
@@ -431,6 +464,9 @@ impl Kafka {
                                     error!("Kafka broker returned an unrecoverable error: {:?}", err);
                                     inc_fail += 1;
                                     ack_ids.push(connection_id);
+                                    if let Some(event) = dead_letter(&message) {
+                                        dead_letters.push((event, format!("unrecoverable error: {:?}", err)));
+                                    }
                                     None
                                 }
                             },
@@ -439,6 +475,9 @@ impl Kafka {
                                 error!("Failed in send to kafka broker: {:?}", err);
                                 inc_fail += 1;
                                 ack_ids.push(connection_id);
+                                if let Some(event) = dead_letter(&message) {
+                                    dead_letters.push((event, format!("failed in send: {:?}", err)));
+                                }
                                 None
                             }
                         },
@@ -459,6 +498,7 @@ impl Kafka {
         for connection_id in ack_ids {
             self.acknowledge(connection_id)
         }
+        self.dead_letters.extend(dead_letters);
         result
     }
 }
@@ -570,6 +610,7 @@ mod tests {
             max_message_bytes: 10,
             flush_interval: 1,
             stats: Box::new(RecordingStatsCollector::new()),
+            dead_letters: Vec::new(),
         };
 
         assert_eq!(k.valve_state(), Valve::Open);
@@ -703,6 +744,7 @@ mod tests {
                 max_message_bytes: 1000,
                 flush_interval: 1,
                 stats: Box::new(RecordingStatsCollector::new()),
+                dead_letters: Vec::new(),
             };
 
             let mut metadata = Metadata::default();
@@ -760,6 +802,7 @@ mod tests {
             max_message_bytes: 1000,
             flush_interval: 1,
             stats: Box::new(RecordingStatsCollector::new()),
+            dead_letters: Vec::new(),
         };
 
         k.deliver_raw(
@@ -794,6 +837,7 @@ mod tests {
             max_message_bytes: 1000,
             flush_interval: 1,
             stats: Box::new(RecordingStatsCollector::new()),
+            dead_letters: Vec::new(),
         };
 
         k.deliver_raw(
@@ -810,6 +854,53 @@ mod tests {
         assert_eq!(k.stats.get_publish_failed(), 1);
     }
 
+    #[test]
+    fn test_unrecoverable_kafka_error_dead_letters() {
+        let producer = RetryOnceMockKafkaSender {
+            call_count: Arc::new(RwLock::new(0)),
+            send_entries: Arc::new(RwLock::new(Vec::new())),
+            error_type: KafkaError::MessageProduction(
+                RDKafkaError::InvalidReplicaAssignment,
+            ),
+            fail_retry: false,
+        };
+        let mut k = Kafka {
+            topic_name: String::from("test-topic"),
+            producer: Box::new(producer.clone()),
+            messages: Vec::new(),
+            message_bytes: 0,
+            max_message_bytes: 1000,
+            flush_interval: 1,
+            stats: Box::new(RecordingStatsCollector::new()),
+            dead_letters: Vec::new(),
+        };
+
+        let mut metadata = Metadata::default();
+        metadata.insert("k".as_bytes().to_vec(), vec![1, 2, 3]);
+        k.deliver_raw(
+            1024,
+            Encoding::Raw,
+            vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10],
+            Some(metadata.clone()),
+            None,
+        );
+        k.flush();
+
+        let dead_letters = k.dead_letters();
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(
+            dead_letters[0].0,
+            Event::Raw {
+                order_by: 1024,
+                encoding: Encoding::Raw,
+                bytes: vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10],
+                metadata: Some(metadata),
+                connection_id: None,
+            }
+        );
+        assert!(k.dead_letters().is_empty());
+    }
+
     #[test]
     fn test_unreachable_kafka_error() {
         let producer = RetryOnceMockKafkaSender {
@@ -826,6 +917,7 @@ mod tests {
             max_message_bytes: 1000,
             flush_interval: 1,
             stats: Box::new(RecordingStatsCollector::new()),
+            dead_letters: Vec::new(),
         };
 
         k.deliver_raw(
@@ -866,6 +958,7 @@ mod tests {
             max_message_bytes: 10,
             flush_interval: 1,
             stats: Box::new(RecordingStatsCollector::new()),
+            dead_letters: Vec::new(),
         };
 
         k.deliver_raw(
@@ -4,30 +4,38 @@
 //! and log lines it receives, other than to receive them. Individual sinks make
 //! different choices.
 
+use crate::dead_letter::{DeadLetters, DEAD_LETTER_LOST};
 use crate::metric::{Encoding, Event, LogLine, Metadata, Telemetry};
 use crate::thread;
 use crate::time;
-use crate::util::Valve;
+use crate::util::{SharedValve, Upstreams, Valve};
 use hopper;
 use std::marker::PhantomData;
+use std::sync::atomic::Ordering;
 use uuid::Uuid;
 
 mod console;
+pub mod delivery;
 pub mod elasticsearch;
 pub mod influxdb;
 pub mod kafka;
 mod native;
 mod null;
 pub mod prometheus;
+pub mod prometheus_remote_write;
 pub mod wavefront;
 
 pub use self::console::{Console, ConsoleConfig};
+pub use self::delivery::{Delivery, DeliveryConfig};
 pub use self::elasticsearch::{Elasticsearch, ElasticsearchConfig};
 pub use self::influxdb::{InfluxDB, InfluxDBConfig};
 pub use self::kafka::{Kafka, KafkaConfig};
 pub use self::native::{Native, NativeConfig};
 pub use self::null::{Null, NullConfig};
 pub use self::prometheus::{Prometheus, PrometheusConfig};
+pub use self::prometheus_remote_write::{
+    PrometheusRemoteWrite, PrometheusRemoteWriteConfig,
+};
 pub use self::wavefront::{Wavefront, WavefrontConfig};
 
 /// Generic interface used to capture global sink configuration
@@ -41,7 +49,9 @@ where
     SConfig: 'static + Send + Clone,
 {
     recv: hopper::Receiver<Event>,
-    sources: Vec<String>,
+    upstreams: Upstreams,
+    valve: SharedValve,
+    dead_letters: Option<DeadLetters>,
     state: S,
 
     // Yes, compiler, we know that we aren't storing
@@ -58,17 +68,31 @@ where
     /// implementing Sink.
     pub fn new(
         recv: hopper::Receiver<Event>,
-        sources: Vec<String>,
+        upstreams: Upstreams,
         config: SConfig,
     ) -> RunnableSink<S, SConfig> {
         RunnableSink {
             recv: recv,
-            sources: sources,
+            upstreams: upstreams,
+            valve: SharedValve::default(),
+            dead_letters: None,
             state: S::init(config),
             config: PhantomData,
         }
     }
 
+    /// The valve state of the sink, updated as the sink runs.
+    pub fn valve(&self) -> SharedValve {
+        self.valve.clone()
+    }
+
+    /// Write the events the sink gives up on to `queue`. Without a queue they
+    /// are counted in `dead_letter::DEAD_LETTER_LOST` and dropped.
+    pub fn dead_letter_queue(mut self, queue: DeadLetters) -> Self {
+        self.dead_letters = Some(queue);
+        self
+    }
+
     /// Spawns / consumes the given stateful sink, returning the corresponding
     /// thread.
     pub fn run(self) -> thread::ThreadHandle {
@@ -77,11 +101,38 @@ where
         })
     }
 
+    /// Flush `state` and write any events it gave up on to `dead_letters`.
+    /// The flush is recorded in `valve`, so that a sink stuck in it may be
+    /// told apart from one making progress. The `last` flush is the final one
+    /// ahead of shutdown.
+    fn flush(
+        state: &mut S,
+        valve: &SharedValve,
+        dead_letters: &Option<DeadLetters>,
+        last: bool,
+    ) {
+        valve.flushing(true);
+        if last {
+            state.final_flush();
+        } else {
+            state.flush();
+        }
+        valve.flushing(false);
+        let letters = state.dead_letters();
+        match *dead_letters {
+            Some(ref queue) => queue.write(letters),
+            None => {
+                DEAD_LETTER_LOST.fetch_add(letters.len(), Ordering::Relaxed);
+            }
+        }
+    }
+
     fn consume(mut self) {
         let mut attempts = 0;
         let mut recv = self.recv.into_iter();
         let mut last_flush_idx = 0;
         let mut total_shutdowns = 0;
+        let mut closed = 0;
         // The run-loop of a sink is two nested loops. The outer loop pulls a
         // value from the hopper queue. If that value is Some the inner loop
         // tries to do something with it, only discarding it at such time as
@@ -110,7 +161,12 @@ where
                 // then we flush. If the flush_interval is Some and DOES NOT
                 // match then we do not flush. If the flush_interval is NONE
                 // then we never flush.
-                match self.state.valve_state() {
+                let valve = self.state.valve_state();
+                self.valve.set(&valve);
+                if valve == Valve::Open {
+                    closed = 0;
+                }
+                match valve {
                     Valve::Open => match event {
                         Event::TimerFlush(idx) => {
                             // Flush timers are interesting. The timer thread
@@ -134,7 +190,12 @@ where
                                 {
                                     if flush_interval == 0 || idx % flush_interval == 0
                                     {
-                                        self.state.flush();
+                                        Self::flush(
+                                            &mut self.state,
+                                            &self.valve,
+                                            &self.dead_letters,
+                                            false,
+                                        );
                                     }
                                 }
                                 last_flush_idx = idx;
@@ -176,10 +237,19 @@ where
                             // from each of its
                             // upstream sources/filters.
                             total_shutdowns += 1;
-                            if total_shutdowns >= self.sources.len() {
+                            if total_shutdowns >= self.upstreams.expected() {
                                 trace!(
-                                    "Received shutdown from every configured source: {:?}",
-                                    self.sources
+                                    "Received shutdown from every configured source: {}",
+                                    total_shutdowns
+                                );
+                                // Flush ahead of the shutdown, which
+                                // consumes the sink, to keep the dead
+                                // letters of this last delivery attempt.
+                                Self::flush(
+                                    &mut self.state,
+                                    &self.valve,
+                                    &self.dead_letters,
+                                    true,
                                 );
                                 self.state.shutdown();
                                 return;
@@ -187,7 +257,17 @@ where
                         }
                     },
                     Valve::Closed => {
-                        self.state.flush();
+                        Self::flush(
+                            &mut self.state,
+                            &self.valve,
+                            &self.dead_letters,
+                            false,
+                        );
+                        // A sink whose circuit breaker is open stays closed
+                        // until the breaker's cooldown passes, back off
+                        // rather than spin on it.
+                        time::delay(closed);
+                        closed += 1;
                         continue;
                     }
                 }
@@ -205,10 +285,10 @@ where
     /// Generic constructor for sinks implementing this trait.
     fn new(
         recv: hopper::Receiver<Event>,
-        sources: Vec<String>,
+        upstreams: Upstreams,
         config: SConfig,
     ) -> RunnableSink<Self, SConfig> {
-        RunnableSink::<Self, SConfig>::new(recv, sources, config)
+        RunnableSink::<Self, SConfig>::new(recv, upstreams, config)
     }
 
     /// Constructs a new sink.
@@ -225,6 +305,13 @@ where
     /// interval. Pulses occur at a rate of once per second, subject to
     /// communication delays in the routing topology.
     fn flush(&mut self) -> ();
+    /// Perform the last flush, ahead of `shutdown`. Whatever the `Sink` still
+    /// holds after it is lost, so sinks which hold back events, as an open
+    /// circuit breaker does, should attempt them here or give them up as dead
+    /// letters. By default this is `flush`.
+    fn final_flush(&mut self) {
+        self.flush()
+    }
     /// Lookup the `Sink` valve state. See `Valve` documentation for more
     /// information.
     fn valve_state(&self) -> Valve {
@@ -252,6 +339,14 @@ where
     ) {
         // Not all sinks accept raw events.  By default, we do nothing.
     }
+    /// Hand back the events the `Sink` has permanently failed to deliver, each
+    /// with the reason it gave up, since last asked. These are asked for after
+    /// every flush and written to the sink's dead-letter queue, see
+    /// `dead_letter`.
+    fn dead_letters(&mut self) -> Vec<(Event, String)> {
+        // Most sinks never give up on an event.
+        Vec::new()
+    }
     /// Provide a hook to shutdown a sink. This is necessary for sinks which
     /// have their own long-running threads.
     fn shutdown(self) -> ();
@@ -1,17 +1,19 @@
 //! Sink for Cernan's native protocol.
 
 use crate::metric;
-use crate::protocols::native::{AggregationMethod, LogLine, Payload, Telemetry};
-use crate::sink::Sink;
+use crate::protocols::native::{
+    AggregationMethod, LogLine, Payload, Sketch, Telemetry,
+};
+use crate::sink::delivery::{Delivery, DeliveryConfig, GaveUp};
+use crate::sink::{Sink, Valve};
 use crate::source::flushes_per_second;
-use crate::time;
 use byteorder::{BigEndian, ByteOrder};
 use protobuf::repeated::RepeatedField;
 use protobuf::stream::CodedOutputStream;
-use protobuf::Message;
+use protobuf::{Message, ProtobufResult};
 use std::collections::HashMap;
 use std::io::BufWriter;
-use std::mem::replace;
+use std::mem;
 use std::net::{TcpStream, ToSocketAddrs};
 
 /// The native sink
@@ -26,7 +28,8 @@ pub struct Native {
     host: String,
     buffer: Vec<metric::Event>,
     flush_interval: u64,
-    delivery_attempts: u32,
+    delivery: Delivery,
+    dead_letters: Vec<(metric::Event, String)>,
     stream: Option<TcpStream>,
     tags: metric::TagMap,
 }
@@ -47,6 +50,8 @@ pub struct NativeConfig {
     /// sink. These tags will overwrite any tags carried by the `metric::Event`
     /// itself.
     pub tags: metric::TagMap,
+    /// The retry and circuit breaker policy of deliveries to the native host.
+    pub delivery: DeliveryConfig,
 }
 
 impl Default for NativeConfig {
@@ -57,6 +62,7 @@ impl Default for NativeConfig {
             config_path: None,
             flush_interval: 60 * flushes_per_second(),
             tags: metric::TagMap::default(),
+            delivery: DeliveryConfig::default(),
         }
     }
 }
@@ -88,15 +94,28 @@ fn connect(host: &str, port: u16) -> Option<TcpStream> {
     }
 }
 
+fn write_payload(stream: &mut TcpStream, pyld: &Payload) -> ProtobufResult<()> {
+    let mut bufwrite = BufWriter::new(stream);
+    let mut stream = CodedOutputStream::new(&mut bufwrite);
+    let mut sz_buf = [0; 4];
+    BigEndian::write_u32(&mut sz_buf, pyld.compute_size());
+    stream.write_raw_bytes(&sz_buf)?;
+    pyld.write_to_with_cached_sizes(&mut stream)
+}
+
 impl Sink<NativeConfig> for Native {
     fn init(config: NativeConfig) -> Self {
         let stream = connect(&config.host, config.port);
+        let config_path = config
+            .config_path
+            .unwrap_or_else(|| "sinks.native".to_string());
         Native {
             port: config.port,
             host: config.host,
             buffer: Vec::new(),
             flush_interval: config.flush_interval,
-            delivery_attempts: 0,
+            delivery: Delivery::new(&config_path, config.delivery),
+            dead_letters: Vec::new(),
             stream,
             tags: config.tags,
         }
@@ -118,11 +137,13 @@ impl Sink<NativeConfig> for Native {
         let mut points = Vec::with_capacity(1024);
         let mut lines = Vec::with_capacity(1024);
 
-        for ev in self.buffer.drain(..) {
-            match ev {
-                metric::Event::Telemetry(mut m) => {
+        // The buffer is kept until delivered, so that should delivery be given
+        // up on its events may be handed back as dead letters.
+        for ev in &self.buffer {
+            match *ev {
+                metric::Event::Telemetry(ref m) => {
                     let mut telem = Telemetry::new();
-                    telem.set_name(replace(&mut m.name, Default::default()));
+                    telem.set_name(m.name.clone());
                     let method = match m.kind() {
                         metric::AggregationMethod::Histogram => AggregationMethod::BIN,
                         metric::AggregationMethod::Sum => AggregationMethod::SUM,
@@ -130,6 +151,10 @@ impl Sink<NativeConfig> for Native {
                         metric::AggregationMethod::Summarize => {
                             AggregationMethod::SUMMARIZE
                         }
+                        metric::AggregationMethod::Distinct => {
+                            AggregationMethod::DISTINCT
+                        }
+                        metric::AggregationMethod::Sketch => AggregationMethod::SKETCH,
                     };
                     let persist = m.persist;
                     telem.set_persisted(persist);
@@ -143,14 +168,42 @@ impl Sink<NativeConfig> for Native {
                         meta.insert(k.to_string(), v.to_string());
                     }
                     telem.set_metadata(meta);
-                    telem.set_timestamp_ms(m.timestamp * 1000); // FIXME #166
+                    telem.set_timestamp_ms(m.epoch_ms());
                     telem.set_samples(m.samples());
+                    if let Some(hll) = m.hyperloglog() {
+                        telem.set_distinct_registers(hll.registers().to_vec());
+                    }
+                    if let Some(dds) = m.sketch() {
+                        let mut sketch = Sketch::new();
+                        sketch.set_relative_accuracy(dds.relative_accuracy());
+                        sketch.set_positive_indexes(
+                            dds.positive().keys().cloned().collect(),
+                        );
+                        sketch.set_positive_counts(
+                            dds.positive().values().cloned().collect(),
+                        );
+                        sketch.set_negative_indexes(
+                            dds.negative().keys().cloned().collect(),
+                        );
+                        sketch.set_negative_counts(
+                            dds.negative().values().cloned().collect(),
+                        );
+                        sketch.set_zero_count(dds.zero_count());
+                        sketch.set_sum(dds.sum());
+                        if let Some(min) = dds.min() {
+                            sketch.set_min(min);
+                        }
+                        if let Some(max) = dds.max() {
+                            sketch.set_max(max);
+                        }
+                        telem.set_sketch(sketch);
+                    }
                     // TODO set bin_bounds. What we do is set the counts for the
                     // bins as set_samples above, then bin_bounds comes from
                     // elsewhere
                     points.push(telem);
                 }
-                metric::Event::Log(l) => {
+                metric::Event::Log(ref l) => {
                     let mut ll = LogLine::new();
                     let mut meta = HashMap::new();
                     // TODO
@@ -160,10 +213,10 @@ impl Sink<NativeConfig> for Native {
                     for (k, v) in l.tags(&self.tags) {
                         meta.insert(k.clone(), v.clone());
                     }
-                    ll.set_path(l.path);
-                    ll.set_value(l.value);
+                    ll.set_path(l.path.clone());
+                    ll.set_value(l.value.clone());
                     ll.set_metadata(meta);
-                    ll.set_timestamp_ms(l.time * 1000); // FIXME #166
+                    ll.set_timestamp_ms(l.epoch_ms());
 
                     lines.push(ll);
                 }
@@ -175,33 +228,60 @@ impl Sink<NativeConfig> for Native {
         pyld.set_points(RepeatedField::from_vec(points));
         pyld.set_lines(RepeatedField::from_vec(lines));
 
-        loop {
-            let mut delivery_failure = false;
-            if let Some(ref mut stream) = self.stream {
-                let mut bufwrite = BufWriter::new(stream);
-                let mut stream = CodedOutputStream::new(&mut bufwrite);
-                let mut sz_buf = [0; 4];
-                let pyld_len = pyld.compute_size();
-                BigEndian::write_u32(&mut sz_buf, pyld_len);
-                stream.write_raw_bytes(&sz_buf).unwrap();
-                let res = pyld.write_to_with_cached_sizes(&mut stream);
-                if res.is_ok() {
-                    self.buffer.clear();
-                    return;
-                } else {
-                    self.delivery_attempts = self.delivery_attempts.saturating_add(1);
-                    delivery_failure = true;
-                }
-            } else {
-                time::delay(self.delivery_attempts);
-                self.stream = connect(&self.host, self.port);
+        let host = &self.host;
+        let port = self.port;
+        let stream = &mut self.stream;
+        let res = self.delivery.attempt(|| {
+            if stream.is_none() {
+                *stream = connect(host, port);
             }
-            if delivery_failure {
-                self.stream = None
+            let res = match *stream {
+                Some(ref mut stream) => write_payload(stream, &pyld),
+                None => return Err("unable to connect".to_string()),
+            };
+            res.map_err(|e| {
+                *stream = None;
+                e.to_string()
+            })
+        });
+        match res {
+            Ok(()) => self.buffer.clear(),
+            Err(GaveUp::Exhausted { attempts, last }) => {
+                warn!(
+                    "Gave up delivering {} events to {}:{} after {} attempts: {}",
+                    self.buffer.len(),
+                    self.host,
+                    self.port,
+                    attempts,
+                    last
+                );
+                let reason = format!("gave up after {} attempts: {}", attempts, last);
+                let buffer = mem::replace(&mut self.buffer, Vec::new());
+                self.dead_letters
+                    .extend(buffer.into_iter().map(|ev| (ev, reason.clone())));
             }
+            // The buffer is kept for the breaker's cooldown, the sink's valve
+            // being closed meanwhile, or until the final flush. Should that
+            // fail too it is given up as above.
+            Err(GaveUp::BreakerOpen) => {}
         }
     }
 
+    fn final_flush(&mut self) {
+        // Cut short the cooldown of an open breaker, so that what it held
+        // back is attempted once more rather than lost.
+        self.delivery.half_open();
+        self.flush();
+    }
+
+    fn valve_state(&self) -> Valve {
+        self.delivery.valve_state()
+    }
+
+    fn dead_letters(&mut self) -> Vec<(metric::Event, String)> {
+        mem::replace(&mut self.dead_letters, Vec::new())
+    }
+
     fn shutdown(mut self) {
         self.flush();
     }
@@ -7,15 +7,25 @@
 //!   - SUM -> counter
 //!   - QUANTILES -> summary
 //!   - HISTOGRAM -> histogram
+//!   - DISTINCT -> gauge, of the estimated number of distinct values
+//!   - SKETCH -> summary
 //!
-//! All points are retained indefinitely in their aggregation.
+//! SET, SUM and HISTOGRAM points are retained indefinitely in their
+//! aggregation. QUANTILES, DISTINCT and SKETCH points are kept in a sliding
+//! window of `capacity_in_seconds`, so that they describe recent values only.
+//!
+//! Scrapes are answered in the format requested by their `Accept` header: the
+//! text format, version 0.0.4, OpenMetrics text or the delimited protobuf
+//! `MetricFamily` encoding. The text format is the default.
 use crate::http::{Handler, Header, Request, Response, Server, StatusCode};
 use crate::metric::{AggregationMethod, TagIter, TagMap, Telemetry};
+use crate::protocols::prometheus as proto;
 use crate::sink::Sink;
 use crate::thread::Stoppable;
 use crate::time;
 use flate2::write::GzEncoder;
 use flate2::Compression;
+use protobuf::Message;
 use quantiles::histogram::Bound;
 use std::collections::hash_map::{Entry, HashMap, Values};
 use std::collections::HashSet;
@@ -65,7 +75,8 @@ pub struct PrometheusConfig {
     pub port: u16,
     /// The unique name of the sink in the routing topology.
     pub config_path: Option<String>,
-    /// The maximum size of the sample window for Summarize, in seconds.
+    /// The maximum size of the sample window for Summarize, Distinct and
+    /// Sketch, in seconds.
     pub capacity_in_seconds: usize,
     /// Determine the age at which a Telemetry point will be ejected. If the
     /// value is None no points will ever be rejected. Units are seconds.
@@ -96,6 +107,7 @@ impl Default for PrometheusConfig {
 enum Accumulator {
     Perpetual(Telemetry),
     Windowed {
+        kind: AggregationMethod,
         cap: usize,
         sum: f64,
         count: u64,
@@ -104,10 +116,33 @@ enum Accumulator {
 }
 
 impl Accumulator {
+    /// Create the accumulator for `telem`, windowed to `cap` seconds if its
+    /// kind is Summarize, Distinct or Sketch.
+    pub fn new(telem: Telemetry, cap: usize) -> Accumulator {
+        match telem.kind() {
+            AggregationMethod::Set
+            | AggregationMethod::Sum
+            | AggregationMethod::Histogram => Accumulator::Perpetual(telem),
+            kind @ AggregationMethod::Summarize
+            | kind @ AggregationMethod::Distinct
+            | kind @ AggregationMethod::Sketch => {
+                let mut accum = Accumulator::Windowed {
+                    kind,
+                    cap,
+                    sum: 0.0,
+                    count: 0,
+                    samples: Vec::new(),
+                };
+                accum.insert(telem);
+                accum
+            }
+        }
+    }
+
     pub fn kind(&self) -> AggregationMethod {
         match *self {
             Accumulator::Perpetual(ref t) => t.kind(),
-            Accumulator::Windowed { .. } => AggregationMethod::Summarize,
+            Accumulator::Windowed { kind, .. } => kind,
         }
     }
 
@@ -115,12 +150,7 @@ impl Accumulator {
     pub fn total_stored_samples(&self) -> usize {
         match *self {
             Accumulator::Perpetual(_) => 1,
-            Accumulator::Windowed {
-                cap: _,
-                ref samples,
-                sum: _,
-                count: _,
-            } => samples.len(),
+            Accumulator::Windowed { ref samples, .. } => samples.len(),
         }
     }
 
@@ -151,21 +181,32 @@ impl Accumulator {
         match *self {
             Accumulator::Perpetual(ref mut t) => *t += telem,
             Accumulator::Windowed {
+                kind,
                 cap,
                 ref mut sum,
                 ref mut count,
                 ref mut samples,
             } => {
                 use std::u64;
-                assert_eq!(telem.kind(), AggregationMethod::Summarize);
+                assert_eq!(telem.kind(), kind);
+                // The count and sum run over every sample ever inserted, not
+                // only those in the window. A Summarize is inserted one
+                // sample at a time, a Sketch may hold many. A Distinct has no
+                // count or sum to speak of.
+                let (samples_in, val) = match kind {
+                    AggregationMethod::Summarize => (1, telem.query(1.0).unwrap()),
+                    AggregationMethod::Sketch => {
+                        (telem.count() as u64, telem.samples_sum().unwrap_or(0.0))
+                    }
+                    _ => (0, 0.0),
+                };
                 // u64::wrapping_add makes a new u64. We need this to be
                 // in-place. Oops!
-                if (u64::MAX - *count) <= 1 {
-                    *count = 1;
+                if (u64::MAX - *count) <= samples_in {
+                    *count = samples_in;
                 } else {
-                    *count += 1;
+                    *count += samples_in;
                 }
-                let val = telem.query(1.0).unwrap();
                 // There's no wrapping_add for f64. Since it's rude to crash
                 // cernan because we've been summing for too long we knock
                 // together our own wrap.
@@ -226,8 +267,8 @@ struct PrometheusAggr {
     // according to this conversation:
     // https://github.com/postmates/cernan/pull/306#discussion_r139770087
     data: HashMap<String, Accumulator>,
-    // Summarize metrics are kept in a time-based sliding window. The
-    // capacity_in_seconds determines how wide this window is.
+    // Summarize, Distinct and Sketch metrics are kept in a time-based sliding
+    // window. The capacity_in_seconds determines how wide this window is.
     capacity_in_seconds: usize,
 }
 
@@ -239,12 +280,7 @@ impl PrometheusAggr {
         if let Some(accum) = self.data.get(&telem.name) {
             match *accum {
                 Accumulator::Perpetual(ref t) => Some(t.clone()),
-                Accumulator::Windowed {
-                    cap: _,
-                    ref samples,
-                    sum: _,
-                    count: _,
-                } => {
+                Accumulator::Windowed { ref samples, .. } => {
                     let mut start = samples[0].clone();
                     for t in &samples[1..] {
                         start += t.clone();
@@ -288,22 +324,7 @@ impl PrometheusAggr {
                 }
             }
             Entry::Vacant(ve) => {
-                ve.insert(match telem.kind() {
-                    AggregationMethod::Set
-                    | AggregationMethod::Sum
-                    | AggregationMethod::Histogram => Accumulator::Perpetual(telem),
-                    AggregationMethod::Summarize => {
-                        let mut samples = Vec::new();
-                        let sum = telem.query(1.0).unwrap();
-                        samples.push(telem);
-                        Accumulator::Windowed {
-                            cap: self.capacity_in_seconds,
-                            count: 1,
-                            sum,
-                            samples,
-                        }
-                    }
-                });
+                ve.insert(Accumulator::new(telem, self.capacity_in_seconds));
             }
         }
         true
@@ -390,8 +411,107 @@ struct PrometheusHandler {
     gzip: bool,
 }
 
+/// The exposition formats a scrape may be answered with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    /// The text format, version 0.0.4.
+    Text,
+    /// OpenMetrics text, version 1.0.0.
+    OpenMetrics,
+    /// Length delimited protobuf `MetricFamily` messages.
+    Protobuf,
+}
+
+impl Format {
+    fn content_type(self) -> &'static str {
+        match self {
+            Format::Text => "text/plain; version=0.0.4",
+            Format::OpenMetrics => {
+                "application/openmetrics-text; version=1.0.0; charset=utf-8"
+            }
+            Format::Protobuf => {
+                "application/vnd.google.protobuf; \
+                 proto=io.prometheus.client.MetricFamily; encoding=delimited"
+            }
+        }
+    }
+
+    /// Choose a format from the `Accept` header of a scrape
+    ///
+    /// The supported media range of highest quality wins, the earliest in the
+    /// header on a tie. Scrapes that send no `Accept` header, or accept
+    /// nothing we support, are answered with the text format.
+    fn negotiate(accept: Option<&str>) -> Format {
+        let mut best: Option<(Format, f64)> = None;
+        for range in accept.unwrap_or("").split(',') {
+            let mut parts = range.split(';').map(|p| p.trim());
+            let media_type = parts.next().unwrap_or("").to_lowercase();
+            let mut quality = 1.0;
+            let mut proto = None;
+            let mut encoding = None;
+            for param in parts {
+                let mut kv = param.splitn(2, '=');
+                let key = kv.next().unwrap_or("").trim().to_lowercase();
+                let val = kv.next().unwrap_or("").trim().trim_matches('"');
+                match key.as_str() {
+                    "q" => quality = val.parse::<f64>().unwrap_or(0.0),
+                    "proto" => proto = Some(val.to_string()),
+                    "encoding" => encoding = Some(val.to_string()),
+                    _ => {}
+                }
+            }
+            let format = match media_type.as_str() {
+                "application/vnd.google.protobuf" => {
+                    if proto.as_ref().map(|p| p.as_str())
+                        == Some("io.prometheus.client.MetricFamily")
+                        && encoding.as_ref().map(|e| e.as_str()) == Some("delimited")
+                    {
+                        Format::Protobuf
+                    } else {
+                        continue;
+                    }
+                }
+                "application/openmetrics-text" => Format::OpenMetrics,
+                "text/plain" | "text/*" | "*/*" => Format::Text,
+                _ => continue,
+            };
+            if quality <= 0.0 {
+                continue;
+            }
+            match best {
+                Some((_, q)) if q >= quality => {}
+                _ => best = Some((format, quality)),
+            }
+        }
+        best.map(|(format, _)| format).unwrap_or(Format::Text)
+    }
+}
+
+fn write_format<W>(
+    format: Format,
+    aggrs: Iter,
+    default: &TagMap,
+    w: W,
+) -> io::Result<W>
+where
+    W: Write,
+{
+    match format {
+        Format::Text => write_text(aggrs, default, w),
+        Format::OpenMetrics => write_openmetrics(aggrs, default, w),
+        Format::Protobuf => write_protobuf(aggrs, default, w),
+    }
+}
+
 impl Handler for PrometheusHandler {
     fn handle(&self, request: Request) {
+        let format = Format::negotiate(
+            request
+                .headers()
+                .iter()
+                .find(|h| h.field.equiv("Accept"))
+                .map(|h| h.value.as_str()),
+        );
         let mut buffer = Vec::with_capacity(2048);
         if let Ok(ref aggr) = self.aggr.try_lock() {
             PROMETHEUS_AGGR_REPORTABLE.store(aggr.count(), Ordering::Relaxed);
@@ -400,10 +520,10 @@ impl Handler for PrometheusHandler {
             buffer = match self.gzip {
                 true => {
                     let mut enc = GzEncoder::new(buffer, Compression::fast());
-                    enc = write_text(reportable, &self.tags, enc).unwrap();
+                    enc = write_format(format, reportable, &self.tags, enc).unwrap();
                     enc.finish().unwrap()
                 }
-                false => write_text(reportable, &self.tags, buffer).unwrap(),
+                false => write_format(format, reportable, &self.tags, buffer).unwrap(),
             };
             let elapsed = now.elapsed();
             let us = ((elapsed.as_secs() as f64) * 10_000.0)
@@ -411,7 +531,7 @@ impl Handler for PrometheusHandler {
             PROMETHEUS_RESPONSE_DELAY_SUM.fetch_add(us as usize, Ordering::Relaxed);
         }
         if !buffer.is_empty() {
-            let content_type = "text/plain; version=0.0.4";
+            let content_type = format.content_type();
             let mut headers =
                 vec![Header::from_bytes(&b"Content-Type"[..], content_type).unwrap()];
             if self.gzip {
@@ -520,6 +640,14 @@ where
                     write_metric(&sanitized_name, value.tags(default), v, &mut w)?;
                 }
             }
+            AggregationMethod::Distinct => {
+                if let Some(v) = value.distinct() {
+                    if seen.insert(value.name.clone()) {
+                        write_type(&sanitized_name, "gauge", &mut w)?;
+                    }
+                    write_metric(&sanitized_name, value.tags(default), v, &mut w)?;
+                }
+            }
             AggregationMethod::Histogram => {
                 if let Some(bin_iter) = value.bins() {
                     if seen.insert(value.name.clone()) {
@@ -562,11 +690,16 @@ where
                     )?;
                 }
             }
-            AggregationMethod::Summarize => {
+            AggregationMethod::Summarize | AggregationMethod::Sketch => {
                 if seen.insert(value.name.clone()) {
                     write_type(&sanitized_name, "summary", &mut w)?;
                 }
                 for q in &[0.0, 1.0, 0.25, 0.5, 0.75, 0.90, 0.95, 0.99, 0.999] {
+                    // A sketch of nothing but NaN is empty and has no quantiles.
+                    let quantile = match value.query(*q) {
+                        Some(quantile) => quantile,
+                        None => continue,
+                    };
                     w.write_all(sanitized_name.as_bytes())?;
 
                     // TODO(jpg): Could be combined with tags and processed with
@@ -579,7 +712,7 @@ where
                     }
                     w.write_all(b"} ")?;
 
-                    w.write_all(value.query(*q).unwrap().to_string().as_bytes())?;
+                    w.write_all(quantile.to_string().as_bytes())?;
                     w.write_all(b"\n")?;
                 }
                 let sum_value = value.samples_sum().unwrap_or(0.0);
@@ -604,6 +737,296 @@ where
     }
 }
 
+/// Group the reportable Telemetry into metric families by sanitized name, in
+/// order of first appearance.
+///
+/// The text format tolerates a family's samples being spread about but
+/// OpenMetrics and the protobuf encoding do not. Should sanitization bring
+/// together Telemetry of differing aggregations, only those of the first
+/// aggregation seen are kept in the family.
+fn families(aggrs: Iter) -> Vec<(String, Vec<Telemetry>)> {
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut families: Vec<(String, Vec<Telemetry>)> = Vec::new();
+    for value in aggrs {
+        let name = sanitize(&value.name);
+        match index.get(&name) {
+            Some(&idx) => {
+                if families[idx].1[0].kind() == value.kind() {
+                    families[idx].1.push(value);
+                }
+            }
+            None => {
+                index.insert(name.clone(), families.len());
+                families.push((name, vec![value]));
+            }
+        }
+    }
+    families
+}
+
+/// Format a sample value as OpenMetrics requires, which differs from Rust in
+/// its spelling of the infinities.
+fn fmt_float(v: f64) -> String {
+    if v.is_nan() {
+        "NaN".to_string()
+    } else if v == f64::INFINITY {
+        "+Inf".to_string()
+    } else if v == f64::NEG_INFINITY {
+        "-Inf".to_string()
+    } else {
+        v.to_string()
+    }
+}
+
+#[inline]
+fn write_openmetrics_sample<W>(
+    name: &str,
+    extra: Option<(&str, &str)>,
+    tags: TagIter,
+    v: f64,
+    w: &mut W,
+) -> io::Result<()>
+where
+    W: Write,
+{
+    w.write_all(name.as_bytes())?;
+    let mut first = true;
+    if let Some((ek, ev)) = extra {
+        w.write_all(b"{")?;
+        write_kv(ek, ev, w)?;
+        first = false;
+    }
+    for (tk, tv) in tags {
+        w.write_all(if first { b"{" } else { b"," })?;
+        write_kv(&sanitize(tk), tv, w)?;
+        first = false;
+    }
+    if !first {
+        w.write_all(b"}")?;
+    }
+    w.write_all(b" ")?;
+    w.write_all(fmt_float(v).as_bytes())?;
+    w.write_all(b"\n")
+}
+
+/// Write the reportable Telemetry as OpenMetrics text
+///
+/// Unlike the text format, counters carry the `_total` suffix and histogram
+/// buckets the `_bucket` suffix that OpenMetrics requires. Label names are
+/// sanitized as metric names are.
+fn write_openmetrics<W>(aggrs: Iter, default: &TagMap, mut w: W) -> io::Result<W>
+where
+    W: Write,
+{
+    for (name, values) in families(aggrs) {
+        let tname = match values[0].kind() {
+            AggregationMethod::Sum => "counter",
+            AggregationMethod::Set | AggregationMethod::Distinct => "gauge",
+            AggregationMethod::Histogram => "histogram",
+            AggregationMethod::Summarize | AggregationMethod::Sketch => "summary",
+        };
+        write_type(&name, tname, &mut w)?;
+        for value in values {
+            match value.kind() {
+                AggregationMethod::Sum => {
+                    if let Some(v) = value.sum() {
+                        write_openmetrics_sample(
+                            &format!("{}_total", name),
+                            None,
+                            value.tags(default),
+                            v,
+                            &mut w,
+                        )?;
+                    }
+                }
+                AggregationMethod::Set => {
+                    if let Some(v) = value.set() {
+                        write_openmetrics_sample(
+                            &name,
+                            None,
+                            value.tags(default),
+                            v,
+                            &mut w,
+                        )?;
+                    }
+                }
+                AggregationMethod::Distinct => {
+                    if let Some(v) = value.distinct() {
+                        write_openmetrics_sample(
+                            &name,
+                            None,
+                            value.tags(default),
+                            v,
+                            &mut w,
+                        )?;
+                    }
+                }
+                AggregationMethod::Histogram => {
+                    if let Some(bin_iter) = value.bins() {
+                        let bucket_name = format!("{}_bucket", name);
+                        let mut running_sum = 0;
+                        for &(bound, val) in bin_iter {
+                            running_sum += val;
+                            let le = match bound {
+                                Bound::Finite(bnd) => fmt_float(bnd),
+                                Bound::PosInf => "+Inf".to_string(),
+                            };
+                            write_openmetrics_sample(
+                                &bucket_name,
+                                Some(("le", &le)),
+                                value.tags(default),
+                                running_sum as f64,
+                                &mut w,
+                            )?;
+                        }
+                        write_openmetrics_sum_count(&name, &value, default, &mut w)?;
+                    }
+                }
+                AggregationMethod::Summarize | AggregationMethod::Sketch => {
+                    for q in &[0.0, 1.0, 0.25, 0.5, 0.75, 0.90, 0.95, 0.99, 0.999] {
+                        if let Some(v) = value.query(*q) {
+                            write_openmetrics_sample(
+                                &name,
+                                Some(("quantile", &q.to_string())),
+                                value.tags(default),
+                                v,
+                                &mut w,
+                            )?;
+                        }
+                    }
+                    write_openmetrics_sum_count(&name, &value, default, &mut w)?;
+                }
+            }
+        }
+    }
+    w.write_all(b"# EOF\n")?;
+    w.flush()?;
+    Ok(w)
+}
+
+#[inline]
+fn write_openmetrics_sum_count<W>(
+    name: &str,
+    value: &Telemetry,
+    default: &TagMap,
+    w: &mut W,
+) -> io::Result<()>
+where
+    W: Write,
+{
+    write_openmetrics_sample(
+        &format!("{}_sum", name),
+        None,
+        value.tags(default),
+        value.samples_sum().unwrap_or(0.0),
+        w,
+    )?;
+    write_openmetrics_sample(
+        &format!("{}_count", name),
+        None,
+        value.tags(default),
+        value.count() as f64,
+        w,
+    )
+}
+
+/// Write the reportable Telemetry as length delimited protobuf
+/// `MetricFamily` messages, see `protocols::prometheus`.
+fn write_protobuf<W>(aggrs: Iter, default: &TagMap, mut w: W) -> io::Result<W>
+where
+    W: Write,
+{
+    for (name, values) in families(aggrs) {
+        let mut family = proto::MetricFamily::new();
+        family.set_name(name);
+        family.set_field_type(match values[0].kind() {
+            AggregationMethod::Sum => proto::MetricType::COUNTER,
+            AggregationMethod::Set | AggregationMethod::Distinct => {
+                proto::MetricType::GAUGE
+            }
+            AggregationMethod::Histogram => proto::MetricType::HISTOGRAM,
+            AggregationMethod::Summarize | AggregationMethod::Sketch => {
+                proto::MetricType::SUMMARY
+            }
+        });
+        for value in values {
+            let mut metric = proto::Metric::new();
+            for (k, v) in value.tags(default) {
+                let mut label = proto::LabelPair::new();
+                label.set_name(sanitize(k));
+                label.set_value(v.clone());
+                metric.mut_label().push(label);
+            }
+            match value.kind() {
+                AggregationMethod::Sum => match value.sum() {
+                    Some(v) => {
+                        let mut counter = proto::Counter::new();
+                        counter.set_value(v);
+                        metric.set_counter(counter);
+                    }
+                    None => continue,
+                },
+                AggregationMethod::Set => match value.set() {
+                    Some(v) => {
+                        let mut gauge = proto::Gauge::new();
+                        gauge.set_value(v);
+                        metric.set_gauge(gauge);
+                    }
+                    None => continue,
+                },
+                AggregationMethod::Distinct => match value.distinct() {
+                    Some(v) => {
+                        let mut gauge = proto::Gauge::new();
+                        gauge.set_value(v);
+                        metric.set_gauge(gauge);
+                    }
+                    None => continue,
+                },
+                AggregationMethod::Histogram => match value.bins() {
+                    Some(bin_iter) => {
+                        let mut histogram = proto::Histogram::new();
+                        histogram.set_sample_count(value.count() as u64);
+                        histogram.set_sample_sum(value.samples_sum().unwrap_or(0.0));
+                        let mut running_sum = 0;
+                        for &(bound, val) in bin_iter {
+                            running_sum += val;
+                            let mut bucket = proto::Bucket::new();
+                            bucket.set_cumulative_count(running_sum as u64);
+                            bucket.set_upper_bound(match bound {
+                                Bound::Finite(bnd) => bnd,
+                                Bound::PosInf => f64::INFINITY,
+                            });
+                            histogram.mut_bucket().push(bucket);
+                        }
+                        metric.set_histogram(histogram);
+                    }
+                    None => continue,
+                },
+                AggregationMethod::Summarize | AggregationMethod::Sketch => {
+                    let mut summary = proto::Summary::new();
+                    summary.set_sample_count(value.count() as u64);
+                    summary.set_sample_sum(value.samples_sum().unwrap_or(0.0));
+                    for q in &[0.0, 1.0, 0.25, 0.5, 0.75, 0.90, 0.95, 0.99, 0.999] {
+                        if let Some(v) = value.query(*q) {
+                            let mut quantile = proto::Quantile::new();
+                            quantile.set_quantile(*q);
+                            quantile.set_value(v);
+                            summary.mut_quantile().push(quantile);
+                        }
+                    }
+                    metric.set_summary(summary);
+                }
+            }
+            family.mut_metric().push(metric);
+        }
+        family
+            .write_length_delimited_to_writer(&mut w)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("{:?}", e)))?;
+    }
+    w.flush()?;
+    Ok(w)
+}
+
 /// Sanitize cernan Telemetry into prometheus' notion
 ///
 /// Prometheus is pretty strict about the names of its ingested metrics.
@@ -620,7 +1043,7 @@ fn sanitize(name: &str) -> String {
     let mut new_name: Vec<u8> = Vec::with_capacity(128);
     for c in name.as_bytes() {
         match *c {
-            b'a'...b'z' | b'A'...b'Z' | b'0'...b'9' | b':' | b'_' => new_name.push(*c),
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b':' | b'_' => new_name.push(*c),
             _ => new_name.push(b'_'),
         }
     }
@@ -703,24 +1126,7 @@ mod test {
                         }
                     }
                     Entry::Vacant(ve) => {
-                        ve.insert(match telem.kind() {
-                            AggregationMethod::Set
-                            | AggregationMethod::Sum
-                            | AggregationMethod::Histogram => {
-                                Accumulator::Perpetual(telem)
-                            }
-                            AggregationMethod::Summarize => {
-                                let mut samples = Vec::new();
-                                let sum = telem.query(1.0).unwrap();
-                                samples.push(telem);
-                                Accumulator::Windowed {
-                                    cap: capacity_in_seconds,
-                                    count: 1,
-                                    sum: sum,
-                                    samples: samples,
-                                }
-                            }
-                        });
+                        ve.insert(Accumulator::new(telem, capacity_in_seconds));
                     }
                 }
             }
@@ -731,10 +1137,64 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_distinct_windowed() {
+        let member = |member: &str, timestamp: i64| {
+            Telemetry::new()
+                .name("users")
+                .member(member)
+                .kind(AggregationMethod::Distinct)
+                .timestamp(timestamp)
+                .harden()
+                .unwrap()
+        };
+        let mut aggr = PrometheusAggr::new(10);
+        assert!(aggr.insert(member("alice", time::now() - 100)));
+        assert!(aggr.insert(member("bob", time::now())));
+        assert!(aggr.insert(member("carol", time::now())));
+        assert_eq!(aggr.reportable().next().unwrap().distinct(), Some(3.0));
+
+        assert_eq!(aggr.purge(), 1);
+        let users: Vec<Telemetry> = aggr.reportable().collect();
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0].kind(), AggregationMethod::Distinct);
+        assert_eq!(users[0].distinct(), Some(2.0));
+    }
+
+    #[test]
+    fn test_sketch_windowed() {
+        let sample = |value: f64, timestamp: i64| {
+            Telemetry::new()
+                .name("latency")
+                .value(value)
+                .kind(AggregationMethod::Sketch)
+                .timestamp(timestamp)
+                .harden()
+                .unwrap()
+        };
+        let mut aggr = PrometheusAggr::new(10);
+        assert!(aggr.insert(sample(100.0, time::now() - 100)));
+        assert!(aggr.insert(sample(1.0, time::now())));
+        assert!(aggr.insert(sample(2.0, time::now())));
+        let latency = aggr.reportable().next().unwrap();
+        assert_eq!(latency.count(), 3);
+        assert_eq!(latency.samples_sum(), Some(103.0));
+
+        assert_eq!(aggr.purge(), 1);
+        let latencies: Vec<Telemetry> = aggr.reportable().collect();
+        assert_eq!(latencies.len(), 1);
+        assert_eq!(latencies[0].kind(), AggregationMethod::Sketch);
+        // The count and sum are cumulative, the quantiles are windowed.
+        assert_eq!(latencies[0].count(), 3);
+        assert_eq!(latencies[0].samples_sum(), Some(103.0));
+        assert!(latencies[0].query(1.0).unwrap() < 3.0);
+    }
+
     #[test]
     fn test_accumlator_window_boundary_obeyed() {
         fn inner(cap: usize, telems: Vec<Telemetry>) -> TestResult {
             let mut windowed = Accumulator::Windowed {
+                kind: AggregationMethod::Summarize,
                 cap: cap,
                 samples: Vec::new(),
                 sum: 0.0,
@@ -833,7 +1293,7 @@ mod test {
             let name: String = sanitize(&metric.name);
             for c in name.chars() {
                 match c {
-                    'a'...'z' | 'A'...'Z' | '0'...'9' | ':' | '_' => continue,
+                    'a'..='z' | 'A'..='Z' | '0'..='9' | ':' | '_' => continue,
                     other => {
                         println!("OTHER: {}", other);
                         return TestResult::failed();
@@ -1026,4 +1486,149 @@ mod test {
             ],
         );
     }
+
+    #[test]
+    fn test_negotiate() {
+        let delimited = "application/vnd.google.protobuf;\
+                         proto=io.prometheus.client.MetricFamily;encoding=delimited";
+        assert_eq!(Format::negotiate(None), Format::Text);
+        assert_eq!(Format::negotiate(Some("")), Format::Text);
+        assert_eq!(Format::negotiate(Some("*/*")), Format::Text);
+        assert_eq!(Format::negotiate(Some("application/json")), Format::Text);
+        assert_eq!(Format::negotiate(Some(delimited)), Format::Protobuf);
+        // Prometheus' own scrape header, protobuf preferred
+        assert_eq!(
+            Format::negotiate(Some(&format!(
+                "{};q=0.7,text/plain;version=0.0.4;q=0.3,*/*;q=0.1",
+                delimited
+            ))),
+            Format::Protobuf
+        );
+        assert_eq!(
+            Format::negotiate(Some(
+                "application/openmetrics-text; version=1.0.0,text/plain;q=0.5"
+            )),
+            Format::OpenMetrics
+        );
+        assert_eq!(
+            Format::negotiate(Some(&format!(
+                "text/plain, {}, application/openmetrics-text",
+                delimited
+            ))),
+            Format::Text
+        );
+        assert_eq!(
+            Format::negotiate(Some("application/openmetrics-text;q=0")),
+            Format::Text
+        );
+        // Only the delimited MetricFamily encoding is served
+        assert_eq!(
+            Format::negotiate(Some(
+                "application/vnd.google.protobuf;\
+                 proto=io.prometheus.client.MetricFamily;encoding=text"
+            )),
+            Format::Text
+        );
+    }
+
+    #[test]
+    fn test_write_openmetrics() {
+        let mut defaults = TagMap::default();
+        defaults.insert("source".into(), "test-src".into());
+
+        let mut aggr = PrometheusAggr::new(1);
+        aggr.insert(
+            Telemetry::new()
+                .name("test.counter")
+                .value(2.0)
+                .kind(AggregationMethod::Sum)
+                .harden()
+                .unwrap()
+                .overlay_tag("custom-tag", "custom-value"),
+        );
+        aggr.insert(
+            Telemetry::new()
+                .name("test.histogram")
+                .value(0.1)
+                .kind(AggregationMethod::Histogram)
+                .bounds(vec![1.0, 10.0])
+                .harden()
+                .unwrap()
+                .insert(3.0)
+                .insert(11.0),
+        );
+
+        let buffer =
+            write_openmetrics(aggr.reportable(), &defaults, Vec::new()).unwrap();
+        let string = String::from_utf8(buffer).unwrap();
+        let lines: Vec<&str> = string.lines().collect();
+        assert_eq!(lines.last(), Some(&"# EOF"));
+        let mut lines: Vec<&str> = lines[..lines.len() - 1].to_vec();
+        lines.sort();
+        let mut expected = vec![
+            "# TYPE test_counter counter",
+            r#"test_counter_total{custom_tag="custom-value",source="test-src"} 2"#,
+            "# TYPE test_histogram histogram",
+            r#"test_histogram_bucket{le="1",source="test-src"} 1"#,
+            r#"test_histogram_bucket{le="10",source="test-src"} 2"#,
+            r#"test_histogram_bucket{le="+Inf",source="test-src"} 3"#,
+            r#"test_histogram_sum{source="test-src"} 14.1"#,
+            r#"test_histogram_count{source="test-src"} 3"#,
+        ];
+        expected.sort();
+        assert_eq!(expected, lines);
+    }
+
+    #[test]
+    fn test_write_protobuf() {
+        let defaults = TagMap::default();
+        let mut aggr = PrometheusAggr::new(1);
+        aggr.insert(
+            Telemetry::new()
+                .name("test.gauge")
+                .value(3.211)
+                .kind(AggregationMethod::Set)
+                .harden()
+                .unwrap()
+                .overlay_tag("host", "a"),
+        );
+        aggr.insert(
+            Telemetry::new()
+                .name("test.histogram")
+                .value(0.1)
+                .kind(AggregationMethod::Histogram)
+                .bounds(vec![1.0, 10.0])
+                .harden()
+                .unwrap()
+                .insert(3.0),
+        );
+
+        let buffer = write_protobuf(aggr.reportable(), &defaults, Vec::new()).unwrap();
+        let mut input = protobuf::CodedInputStream::from_bytes(&buffer);
+        let mut families = Vec::new();
+        while !input.eof().unwrap() {
+            families.push(input.read_message::<proto::MetricFamily>().unwrap());
+        }
+        families.sort_by(|a, b| a.get_name().cmp(b.get_name()));
+        assert_eq!(families.len(), 2);
+
+        assert_eq!(families[0].get_name(), "test_gauge");
+        assert_eq!(families[0].get_field_type(), proto::MetricType::GAUGE);
+        let gauge = &families[0].get_metric()[0];
+        assert_eq!(gauge.get_gauge().get_value(), 3.211);
+        assert_eq!(gauge.get_label()[0].get_name(), "host");
+        assert_eq!(gauge.get_label()[0].get_value(), "a");
+
+        assert_eq!(families[1].get_name(), "test_histogram");
+        assert_eq!(families[1].get_field_type(), proto::MetricType::HISTOGRAM);
+        let histogram = families[1].get_metric()[0].get_histogram();
+        assert_eq!(histogram.get_sample_count(), 2);
+        assert_eq!(histogram.get_sample_sum(), 3.1);
+        let buckets: Vec<(f64, u64)> = histogram
+            .get_bucket()
+            .iter()
+            .map(|b| (b.get_upper_bound(), b.get_cumulative_count()))
+            .collect();
+        assert_eq!(buckets, vec![(1.0, 1), (10.0, 2), (f64::INFINITY, 2)]);
+    }
 }
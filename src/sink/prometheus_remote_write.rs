@@ -0,0 +1,715 @@
+//! Prometheus remote-write is a push protocol for Prometheus compatible
+//! long-term storage
+//!
+//! This sink batches `Telemetry` into snappy compressed remote-write
+//! `WriteRequest`s and POSTs them to a configured URL. Cortex, Thanos receive,
+//! VictoriaMetrics and Prometheus itself, with its receiver enabled, all accept
+//! remote-write. Aggregations are mapped as in `sink::Prometheus`:
+//!
+//!   - SET -> gauge
+//!   - SUM -> counter
+//!   - QUANTILES -> summary
+//!   - HISTOGRAM -> histogram
+//!   - DISTINCT -> gauge, of the estimated number of distinct values
+//!   - SKETCH -> summary
+//!
+//! Prometheus counters are cumulative while cernan delivers the sum of each
+//! flush interval. This sink keeps a running total for every counter series,
+//! including the `_bucket`, `_sum` and `_count` series of histograms and
+//! summaries, so that `rate` and friends work as they would against a scrape.
+//!
+//! Receivers reject a series whose samples are duplicated or out of order, and
+//! with it the whole request. Points alike by name, tags and aggregation are
+//! therefore merged by the second before they are converted, and each series
+//! is sent in time order.
+use crate::metric::{AggregationMethod, TagMap, Telemetry};
+use crate::protocols::prometheus_remote::{Label, Sample, TimeSeries, WriteRequest};
+use crate::sink::delivery::{Breaker, Delivery, DeliveryConfig, GaveUp};
+use crate::sink::{Sink, Valve};
+use crate::source::flushes_per_second;
+use protobuf::{Message, RepeatedField};
+use quantiles::histogram::Bound;
+use reqwest;
+use snap;
+use std::cmp;
+use std::collections::HashMap;
+use std::mem;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use url::Url;
+
+/// Total delivery attempts made by this sink
+pub static PROMETHEUS_REMOTE_WRITE_DELIVERY_ATTEMPTS: AtomicUsize =
+    AtomicUsize::new(0);
+/// Total successful deliveries made by this sink
+pub static PROMETHEUS_REMOTE_WRITE_SUCCESS: AtomicUsize = AtomicUsize::new(0);
+/// Total failed delivery attempts because of client error
+pub static PROMETHEUS_REMOTE_WRITE_FAILURE_CLIENT: AtomicUsize = AtomicUsize::new(0);
+/// Total failed delivery attempts because of server error
+pub static PROMETHEUS_REMOTE_WRITE_FAILURE_SERVER: AtomicUsize = AtomicUsize::new(0);
+/// Total samples dropped after a request could not be delivered
+pub static PROMETHEUS_REMOTE_WRITE_DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+/// The quantiles reported for `AggregationMethod::Summarize` and
+/// `AggregationMethod::Sketch`, as in `sink::Prometheus`.
+const QUANTILES: [f64; 9] = [0.0, 1.0, 0.25, 0.5, 0.75, 0.90, 0.95, 0.99, 0.999];
+
+/// Running totals not updated for this many seconds, measured against the
+/// newest point flushed, are forgotten. The next sample of such a series
+/// starts again from zero, which receivers take as a counter reset.
+const TOTAL_TTL_SECS: i64 = 60 * 60;
+
+/// The Prometheus remote-write sink
+///
+/// See the module documentation for how `Telemetry` are mapped onto
+/// Prometheus series and `PrometheusRemoteWriteConfig` for configurable
+/// parameters.
+pub struct PrometheusRemoteWrite {
+    /// The store of Telemetry to be reported
+    aggrs: Vec<Telemetry>,
+    /// The index into `aggrs` of each Telemetry, keyed by its hash and second
+    bins: HashMap<(u64, i64), usize>,
+    /// Running totals of counter series, keyed by their sorted labels, with
+    /// the second they were last updated in
+    totals: HashMap<Vec<(String, String)>, (f64, i64)>,
+    /// The retry and circuit breaker policy of deliveries to the receiver.
+    delivery: Delivery,
+    max_samples_per_send: usize,
+    flush_interval: u64,
+    client: reqwest::Client,
+    url: Url,
+    tags: TagMap,
+}
+
+/// Configuration for the Prometheus remote-write sink
+#[derive(Clone, Debug, Deserialize)]
+pub struct PrometheusRemoteWriteConfig {
+    /// The URL to POST `WriteRequest`s to. For Prometheus itself this is
+    /// `http://host:9090/api/v1/write`.
+    pub url: String,
+    /// The maximum number of samples sent in a single `WriteRequest`.
+    pub max_samples_per_send: usize,
+    /// The timeout of each delivery attempt, in seconds.
+    pub timeout: u64,
+    /// The unique name of the sink in the routing topology.
+    pub config_path: Option<String>,
+    /// The tags to be applied to all `metric::Event`s streaming through this
+    /// sink. These tags will overwrite any tags carried by the `metric::Event`
+    /// itself.
+    pub tags: TagMap,
+    /// The interval, in seconds, on which the sink will report.
+    pub flush_interval: u64,
+    /// The retry and circuit breaker policy of deliveries to the receiver.
+    /// Requests rejected with a client error, other than 429 Too Many
+    /// Requests, are not retried.
+    pub delivery: DeliveryConfig,
+}
+
+impl Default for PrometheusRemoteWriteConfig {
+    fn default() -> Self {
+        PrometheusRemoteWriteConfig {
+            url: "http://localhost:9090/api/v1/write".to_string(),
+            max_samples_per_send: 500,
+            timeout: 30,
+            config_path: None,
+            tags: TagMap::default(),
+            flush_interval: 60 * flushes_per_second(),
+            delivery: DeliveryConfig::default(),
+        }
+    }
+}
+
+/// Sanitize cernan Telemetry names into Prometheus' notion. See
+/// `sink::Prometheus` for discussion.
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | ':' | '_' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+/// The series of a Prometheus remote-write request, in order of first
+/// appearance, with the samples of each series.
+///
+/// Samples must be pushed in time order. A sample sharing the timestamp of
+/// the one before it replaces it.
+#[derive(Default)]
+struct Series {
+    index: HashMap<Vec<(String, String)>, usize>,
+    series: Vec<(Vec<(String, String)>, Vec<(f64, i64)>)>,
+}
+
+impl Series {
+    fn push(&mut self, labels: Vec<(String, String)>, value: f64, timestamp_ms: i64) {
+        let series = &mut self.series;
+        let idx = *self.index.entry(labels.clone()).or_insert_with(|| {
+            series.push((labels, Vec::new()));
+            series.len() - 1
+        });
+        let samples = &mut self.series[idx].1;
+        match samples.last_mut() {
+            Some(last) if last.1 == timestamp_ms => last.0 = value,
+            _ => samples.push((value, timestamp_ms)),
+        }
+    }
+}
+
+impl PrometheusRemoteWrite {
+    /// Create the sorted label set of a series. Remote-write receivers require
+    /// labels sorted by name, `__name__` included.
+    fn labels(
+        &self,
+        name: &str,
+        telem: &Telemetry,
+        extra: Option<(&str, String)>,
+    ) -> Vec<(String, String)> {
+        let reserved = extra.as_ref().map(|&(k, _)| k);
+        let mut labels: Vec<(String, String)> = telem
+            .tags(&self.tags)
+            .map(|(k, v)| (sanitize(k), v.clone()))
+            .filter(|&(ref k, _)| k != "__name__" && Some(k.as_str()) != reserved)
+            .collect();
+        if let Some((k, v)) = extra {
+            labels.push((k.to_string(), v));
+        }
+        labels.push(("__name__".to_string(), name.to_string()));
+        labels.sort();
+        labels
+    }
+
+    /// Add `delta` to the running total of the counter series `labels` as of
+    /// the second `timestamp`, returning the new total.
+    fn accumulate(
+        &mut self,
+        labels: &[(String, String)],
+        delta: f64,
+        timestamp: i64,
+    ) -> f64 {
+        let total = self
+            .totals
+            .entry(labels.to_vec())
+            .or_insert((0.0, timestamp));
+        total.0 += delta;
+        total.1 = cmp::max(total.1, timestamp);
+        total.0
+    }
+
+    /// Convert the stored `Telemetry` into series
+    fn series(&mut self) -> Series {
+        let mut series = Series::default();
+        let mut aggrs = mem::replace(&mut self.aggrs, Vec::new());
+        self.bins.clear();
+        aggrs.sort_by_key(|telem| telem.epoch_ns());
+        for telem in &aggrs {
+            let name = sanitize(&telem.name);
+            let timestamp = telem.timestamp;
+            let timestamp_ms = telem.epoch_ms();
+            match telem.kind() {
+                AggregationMethod::Set => {
+                    if let Some(v) = telem.set() {
+                        series.push(self.labels(&name, telem, None), v, timestamp_ms);
+                    }
+                }
+                AggregationMethod::Distinct => {
+                    if let Some(v) = telem.distinct() {
+                        series.push(self.labels(&name, telem, None), v, timestamp_ms);
+                    }
+                }
+                AggregationMethod::Sum => {
+                    if let Some(v) = telem.sum() {
+                        let labels = self.labels(&name, telem, None);
+                        let total = self.accumulate(&labels, v, timestamp);
+                        series.push(labels, total, timestamp_ms);
+                    }
+                }
+                AggregationMethod::Histogram => {
+                    if let Some(bins) = telem.bins() {
+                        let bucket = format!("{}_bucket", name);
+                        let mut running_sum = 0;
+                        for &(bound, count) in bins {
+                            running_sum += count;
+                            let le = match bound {
+                                Bound::Finite(bnd) => bnd.to_string(),
+                                Bound::PosInf => "+Inf".to_string(),
+                            };
+                            let labels = self.labels(&bucket, telem, Some(("le", le)));
+                            let total = self.accumulate(
+                                &labels,
+                                running_sum as f64,
+                                timestamp,
+                            );
+                            series.push(labels, total, timestamp_ms);
+                        }
+                        self.push_sum_count(&mut series, &name, telem, timestamp_ms);
+                    }
+                }
+                AggregationMethod::Summarize | AggregationMethod::Sketch => {
+                    for q in &QUANTILES {
+                        if let Some(v) = telem.query(*q) {
+                            let labels = self.labels(
+                                &name,
+                                telem,
+                                Some(("quantile", q.to_string())),
+                            );
+                            series.push(labels, v, timestamp_ms);
+                        }
+                    }
+                    self.push_sum_count(&mut series, &name, telem, timestamp_ms);
+                }
+            }
+        }
+        if let Some(newest) = aggrs.iter().map(|telem| telem.timestamp).max() {
+            self.totals
+                .retain(|_, &mut (_, updated)| newest - updated <= TOTAL_TTL_SECS);
+        }
+        series
+    }
+
+    fn push_sum_count(
+        &mut self,
+        series: &mut Series,
+        name: &str,
+        telem: &Telemetry,
+        timestamp_ms: i64,
+    ) {
+        let timestamp = telem.timestamp;
+        let labels = self.labels(&format!("{}_sum", name), telem, None);
+        let sum = telem.samples_sum().unwrap_or(0.0);
+        let total = self.accumulate(&labels, sum, timestamp);
+        series.push(labels, total, timestamp_ms);
+        let labels = self.labels(&format!("{}_count", name), telem, None);
+        let total = self.accumulate(&labels, telem.count() as f64, timestamp);
+        series.push(labels, total, timestamp_ms);
+    }
+
+    /// Split `series` into `WriteRequest`s of no more than
+    /// `max_samples_per_send` samples each.
+    fn write_requests(&self, series: Series) -> Vec<(WriteRequest, usize)> {
+        let mut requests = Vec::new();
+        let mut timeseries = Vec::new();
+        let mut total_samples = 0;
+        for (labels, samples) in series.series {
+            for chunk in samples.chunks(self.max_samples_per_send) {
+                if total_samples + chunk.len() > self.max_samples_per_send {
+                    let mut req = WriteRequest::new();
+                    req.set_timeseries(RepeatedField::from_vec(timeseries));
+                    requests.push((req, total_samples));
+                    timeseries = Vec::new();
+                    total_samples = 0;
+                }
+                let mut ts = TimeSeries::new();
+                for &(ref name, ref value) in &labels {
+                    let mut label = Label::new();
+                    label.set_name(name.clone());
+                    label.set_value(value.clone());
+                    ts.mut_labels().push(label);
+                }
+                for &(value, timestamp_ms) in chunk {
+                    let mut sample = Sample::new();
+                    sample.set_value(value);
+                    sample.set_timestamp(timestamp_ms);
+                    ts.mut_samples().push(sample);
+                }
+                total_samples += chunk.len();
+                timeseries.push(ts);
+            }
+        }
+        if !timeseries.is_empty() {
+            let mut req = WriteRequest::new();
+            req.set_timeseries(RepeatedField::from_vec(timeseries));
+            requests.push((req, total_samples));
+        }
+        requests
+    }
+
+    /// POST a single snappy compressed `WriteRequest` under the sink's
+    /// delivery policy. Returns whether the request was delivered.
+    fn send(&mut self, body: &[u8]) -> bool {
+        let client = &self.client;
+        let url = &self.url;
+        let res = self.delivery.attempt(|| {
+            PROMETHEUS_REMOTE_WRITE_DELIVERY_ATTEMPTS.fetch_add(1, Ordering::Relaxed);
+            let mut headers = reqwest::header::Headers::new();
+            headers.set_raw("Content-Encoding", "snappy");
+            headers.set_raw("Content-Type", "application/x-protobuf");
+            headers.set_raw("X-Prometheus-Remote-Write-Version", "0.1.0");
+            let resp = client
+                .post(url.clone())
+                .headers(headers)
+                .body(body.to_vec())
+                .send()
+                .map_err(|e| format!("error doing POST: {}", e))?;
+            let status = resp.status();
+            if status.is_success() {
+                PROMETHEUS_REMOTE_WRITE_SUCCESS.fetch_add(1, Ordering::Relaxed);
+                return Ok(true);
+            } else if status.is_client_error() {
+                PROMETHEUS_REMOTE_WRITE_FAILURE_CLIENT.fetch_add(1, Ordering::Relaxed);
+                // Per the remote-write specification a 4xx, save for rate
+                // limiting, will not succeed on retry.
+                if status != reqwest::StatusCode::TooManyRequests {
+                    warn!("remote-write request rejected: {}", status);
+                    return Ok(false);
+                }
+            } else if status.is_server_error() {
+                PROMETHEUS_REMOTE_WRITE_FAILURE_SERVER.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(format!("response status {}", status))
+        });
+        match res {
+            Ok(delivered) => delivered,
+            Err(GaveUp::Exhausted { attempts, last }) => {
+                warn!(
+                    "Gave up delivering remote-write request to {} after {} \
+                     attempts: {}",
+                    self.url, attempts, last
+                );
+                false
+            }
+            Err(GaveUp::BreakerOpen) => false,
+        }
+    }
+}
+
+impl Sink<PrometheusRemoteWriteConfig> for PrometheusRemoteWrite {
+    fn init(config: PrometheusRemoteWriteConfig) -> Self {
+        let url = Url::parse(&config.url).expect("malformed url");
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout))
+            .build()
+            .expect("could not create prometheus remote-write client");
+        let config_path = config
+            .config_path
+            .unwrap_or_else(|| "sinks.prometheus_remote_write".to_string());
+
+        PrometheusRemoteWrite {
+            aggrs: Vec::with_capacity(4048),
+            bins: HashMap::default(),
+            totals: HashMap::default(),
+            delivery: Delivery::new(&config_path, config.delivery),
+            max_samples_per_send: config.max_samples_per_send,
+            flush_interval: config.flush_interval,
+            client,
+            url,
+            tags: config.tags,
+        }
+    }
+
+    fn flush_interval(&self) -> Option<u64> {
+        Some(self.flush_interval)
+    }
+
+    fn flush(&mut self) {
+        // The points are kept for the breaker's cooldown, the sink's valve
+        // being closed meanwhile.
+        if self.delivery.breaker() == Breaker::Open {
+            return;
+        }
+        let series = self.series();
+        for (req, samples) in self.write_requests(series) {
+            let body = match req.write_to_bytes() {
+                Ok(buf) => snap::Encoder::new()
+                    .compress_vec(&buf)
+                    .expect("could not snappy compress write request"),
+                Err(e) => {
+                    error!("could not encode write request: {:?}", e);
+                    PROMETHEUS_REMOTE_WRITE_DROPPED
+                        .fetch_add(samples, Ordering::Relaxed);
+                    continue;
+                }
+            };
+            if !self.send(&body) {
+                PROMETHEUS_REMOTE_WRITE_DROPPED.fetch_add(samples, Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn shutdown(mut self) {
+        self.flush();
+    }
+
+    fn deliver(&mut self, point: Telemetry) {
+        let key = (point.hash(), point.timestamp);
+        if let Some(&idx) = self.bins.get(&key) {
+            let prev = &mut self.aggrs[idx];
+            if prev.kind() == point.kind()
+                && prev.within(1, &point) == cmp::Ordering::Equal
+            {
+                *prev += point;
+                return;
+            }
+        }
+        self.bins.insert(key, self.aggrs.len());
+        self.aggrs.push(point);
+    }
+
+    fn valve_state(&self) -> Valve {
+        if self.aggrs.len() > 100_000 {
+            Valve::Closed
+        } else {
+            self.delivery.valve_state()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::time;
+
+    fn sink(max_samples_per_send: usize) -> PrometheusRemoteWrite {
+        let mut tags = TagMap::default();
+        tags.insert("source".into(), "test-src".into());
+        let mut config = PrometheusRemoteWriteConfig::default();
+        config.tags = tags;
+        config.max_samples_per_send = max_samples_per_send;
+        PrometheusRemoteWrite::init(config)
+    }
+
+    fn labels(ts: &TimeSeries) -> Vec<(&str, &str)> {
+        ts.get_labels()
+            .iter()
+            .map(|l| (l.get_name(), l.get_value()))
+            .collect()
+    }
+
+    #[test]
+    fn test_gauges_and_counters() {
+        let mut sink = sink(500);
+        for &(v, ts) in &[(1.0, 10), (2.0, 11)] {
+            sink.deliver(
+                Telemetry::new()
+                    .name("test.counter")
+                    .value(v)
+                    .timestamp(ts)
+                    .kind(AggregationMethod::Sum)
+                    .harden()
+                    .unwrap(),
+            );
+        }
+        sink.deliver(
+            Telemetry::new()
+                .name("test.gauge")
+                .value(3.5)
+                .timestamp(10)
+                .kind(AggregationMethod::Set)
+                .harden()
+                .unwrap()
+                .overlay_tag("host", "a"),
+        );
+        let series = sink.series();
+        let reqs = sink.write_requests(series);
+        assert_eq!(reqs.len(), 1);
+        let (ref req, samples) = reqs[0];
+        assert_eq!(samples, 3);
+        let series = req.get_timeseries();
+        assert_eq!(series.len(), 2);
+
+        assert_eq!(
+            labels(&series[0]),
+            vec![("__name__", "test_counter"), ("source", "test-src")]
+        );
+        let counter: Vec<(f64, i64)> = series[0]
+            .get_samples()
+            .iter()
+            .map(|s| (s.get_value(), s.get_timestamp()))
+            .collect();
+        assert_eq!(counter, vec![(1.0, 10_000), (3.0, 11_000)]);
+
+        assert_eq!(
+            labels(&series[1]),
+            vec![
+                ("__name__", "test_gauge"),
+                ("host", "a"),
+                ("source", "test-src")
+            ]
+        );
+        assert_eq!(series[1].get_samples()[0].get_value(), 3.5);
+
+        // Counters keep their running total across flushes.
+        sink.deliver(
+            Telemetry::new()
+                .name("test.counter")
+                .value(4.0)
+                .timestamp(12)
+                .kind(AggregationMethod::Sum)
+                .harden()
+                .unwrap(),
+        );
+        let series = sink.series();
+        let reqs = sink.write_requests(series);
+        let series = reqs[0].0.get_timeseries();
+        assert_eq!(series[0].get_samples()[0].get_value(), 7.0);
+    }
+
+    #[test]
+    fn test_histogram() {
+        let mut sink = sink(500);
+        let telem = Telemetry::new()
+            .name("latency")
+            .value(1.0)
+            .timestamp(10)
+            .kind(AggregationMethod::Histogram)
+            .bounds(vec![2.0, 4.0])
+            .harden()
+            .unwrap()
+            .insert(3.0)
+            .insert(10.0);
+        sink.deliver(telem);
+        let series = sink.series();
+        let reqs = sink.write_requests(series);
+        let series = reqs[0].0.get_timeseries();
+        let got: Vec<(Vec<(&str, &str)>, f64)> = series
+            .iter()
+            .map(|ts| (labels(ts), ts.get_samples()[0].get_value()))
+            .collect();
+        assert_eq!(
+            got,
+            vec![
+                (
+                    vec![
+                        ("__name__", "latency_bucket"),
+                        ("le", "2"),
+                        ("source", "test-src")
+                    ],
+                    1.0
+                ),
+                (
+                    vec![
+                        ("__name__", "latency_bucket"),
+                        ("le", "4"),
+                        ("source", "test-src")
+                    ],
+                    2.0
+                ),
+                (
+                    vec![
+                        ("__name__", "latency_bucket"),
+                        ("le", "+Inf"),
+                        ("source", "test-src")
+                    ],
+                    3.0
+                ),
+                (
+                    vec![("__name__", "latency_sum"), ("source", "test-src")],
+                    14.0
+                ),
+                (
+                    vec![("__name__", "latency_count"), ("source", "test-src")],
+                    3.0
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_max_samples_per_send() {
+        let mut sink = sink(2);
+        for ts in 0..5 {
+            sink.deliver(
+                Telemetry::new()
+                    .name("test.gauge")
+                    .value(ts as f64)
+                    .timestamp(ts)
+                    .kind(AggregationMethod::Set)
+                    .harden()
+                    .unwrap(),
+            );
+        }
+        let series = sink.series();
+        let reqs = sink.write_requests(series);
+        let sizes: Vec<usize> = reqs.iter().map(|&(_, samples)| samples).collect();
+        assert_eq!(sizes, vec![2, 2, 1]);
+        for &(ref req, samples) in &reqs {
+            let total: usize = req
+                .get_timeseries()
+                .iter()
+                .map(|ts| ts.get_samples().len())
+                .sum();
+            assert_eq!(total, samples);
+        }
+    }
+
+    #[test]
+    fn test_write_request_round_trip() {
+        let mut sink = sink(500);
+        sink.deliver(
+            Telemetry::new()
+                .name("test.gauge")
+                .value(1.5)
+                .timestamp(10)
+                .kind(AggregationMethod::Set)
+                .harden()
+                .unwrap(),
+        );
+        let series = sink.series();
+        let reqs = sink.write_requests(series);
+        let buf = reqs[0].0.write_to_bytes().unwrap();
+        let compressed = snap::Encoder::new().compress_vec(&buf).unwrap();
+        let decompressed = snap::Decoder::new().decompress_vec(&compressed).unwrap();
+        let req: WriteRequest = protobuf::parse_from_bytes(&decompressed).unwrap();
+        assert_eq!(req, reqs[0].0);
+    }
+
+    #[test]
+    fn test_same_second_points_merged() {
+        let mut sink = sink(500);
+        for &(v, ts, ns) in &[(2.0, 11, 0), (1.0, 10, 0), (4.0, 10, 500_000_000)] {
+            sink.deliver(
+                Telemetry::new()
+                    .name("test.counter")
+                    .value(v)
+                    .timestamp_ns(ts * time::NANOS_PER_SEC + ns)
+                    .kind(AggregationMethod::Sum)
+                    .harden()
+                    .unwrap(),
+            );
+        }
+        for &(v, ns) in &[(1.0, 0), (9.0, 250_000_000)] {
+            sink.deliver(
+                Telemetry::new()
+                    .name("test.gauge")
+                    .value(v)
+                    .timestamp_ns(10 * time::NANOS_PER_SEC + ns)
+                    .kind(AggregationMethod::Set)
+                    .harden()
+                    .unwrap(),
+            );
+        }
+        let series = sink.series();
+        let reqs = sink.write_requests(series);
+        let series = reqs[0].0.get_timeseries();
+        let samples = |ts: &TimeSeries| -> Vec<(f64, i64)> {
+            ts.get_samples()
+                .iter()
+                .map(|s| (s.get_value(), s.get_timestamp()))
+                .collect()
+        };
+        assert_eq!(series.len(), 2);
+        assert_eq!(samples(&series[0]), vec![(5.0, 10_000), (7.0, 11_000)]);
+        assert_eq!(samples(&series[1]), vec![(9.0, 10_000)]);
+    }
+
+    #[test]
+    fn test_stale_totals_evicted() {
+        let mut sink = sink(500);
+        for &(name, ts) in &[("test.old", 10), ("test.new", 10 + TOTAL_TTL_SECS + 1)] {
+            sink.deliver(
+                Telemetry::new()
+                    .name(name)
+                    .value(1.0)
+                    .timestamp(ts)
+                    .kind(AggregationMethod::Sum)
+                    .harden()
+                    .unwrap(),
+            );
+        }
+        let _ = sink.series();
+        assert_eq!(sink.totals.len(), 1);
+        assert!(sink.totals.keys().all(|labels| labels
+            .iter()
+            .any(|&(ref k, ref v)| k == "__name__" && v == "test_new")));
+    }
+}
@@ -2,6 +2,7 @@
 
 use crate::buckets;
 use crate::metric::{AggregationMethod, TagIter, TagMap, Telemetry};
+use crate::sink::delivery::{Delivery, DeliveryConfig, GaveUp};
 use crate::sink::{Sink, Valve};
 use crate::source::flushes_per_second;
 use crate::time;
@@ -46,14 +47,16 @@ pub struct PadControl {
     /// DO pad SUM Telemetry if the value is true, DO NOT pad if the value is
     /// false.
     pub sum: bool,
-    /// DO pad SUMMARIZE Telemetry if the value is true, DO NOT pad if the
-    /// value is
-    /// false.
+    /// DO pad SUMMARIZE and SKETCH Telemetry if the value is true, DO NOT pad
+    /// if the value is false.
     pub summarize: bool,
     /// DO pad HISTOGRAM Telemetry if the value is true, DO NOT pad if the
     /// value is
     /// false.
     pub histogram: bool,
+    /// DO pad DISTINCT Telemetry if the value is true, DO NOT pad if the
+    /// value is false.
+    pub distinct: bool,
 }
 
 /// The `wavefront` sink emits into [Wavefront](http://wavefront.com), a
@@ -63,7 +66,7 @@ pub struct Wavefront {
     port: u16,
     bin_width: i64,
     aggrs: buckets::Buckets,
-    delivery_attempts: u32,
+    delivery: Delivery,
     percentiles: Vec<(String, f64)>,
     stats: String,
     flush_interval: u64,
@@ -105,6 +108,8 @@ pub struct WavefrontConfig {
     /// Determine the age at which a Telemetry point will be ejected. If the
     /// value is None no points will ever be rejected. Units are seconds.
     pub age_threshold: Option<u64>,
+    /// The retry and circuit breaker policy of deliveries to the proxy.
+    pub delivery: DeliveryConfig,
 }
 
 impl Default for WavefrontConfig {
@@ -134,6 +139,7 @@ impl Default for WavefrontConfig {
             flush_interval: 60 * flushes_per_second(),
             pad_control: PadControl::default(),
             age_threshold: None,
+            delivery: DeliveryConfig::default(),
         }
     }
 }
@@ -187,7 +193,10 @@ impl<'a> Pad<'a> {
                 AggregationMethod::Histogram => !pad_control.histogram,
                 AggregationMethod::Set => !pad_control.set,
                 AggregationMethod::Sum => !pad_control.sum,
-                AggregationMethod::Summarize => !pad_control.summarize,
+                AggregationMethod::Summarize | AggregationMethod::Sketch => {
+                    !pad_control.summarize
+                }
+                AggregationMethod::Distinct => !pad_control.distinct,
             },
         }
     }
@@ -429,10 +438,12 @@ impl Wavefront {
                         AggregationMethod::Histogram => {
                             WAVEFRONT_AGGR_HISTO.fetch_add(1, Ordering::Relaxed)
                         }
-                        AggregationMethod::Sum | AggregationMethod::Set => {
+                        AggregationMethod::Sum
+                        | AggregationMethod::Set
+                        | AggregationMethod::Distinct => {
                             WAVEFRONT_AGGR_SUM.fetch_add(1, Ordering::Relaxed)
                         }
-                        AggregationMethod::Summarize => {
+                        AggregationMethod::Summarize | AggregationMethod::Sketch => {
                             WAVEFRONT_AGGR_SUMMARIZE.fetch_add(1, Ordering::Relaxed);
                             WAVEFRONT_AGGR_TOT_PERCENT
                                 .fetch_add(self.percentiles.len(), Ordering::Relaxed)
@@ -512,8 +523,8 @@ impl Wavefront {
                     tag_buf.clear();
                 }
             }
-            AggregationMethod::Set => {
-                if let Some(v) = value.set() {
+            AggregationMethod::Set | AggregationMethod::Distinct => {
+                if let Some(v) = value.set().or_else(|| value.distinct()) {
                     self.stats.push_str(&value.name);
                     self.stats.push_str(" ");
                     self.stats.push_str(get_from_cache(&mut value_cache, v));
@@ -528,19 +539,20 @@ impl Wavefront {
                     tag_buf.clear();
                 }
             }
-            AggregationMethod::Summarize => {
+            AggregationMethod::Summarize | AggregationMethod::Sketch => {
                 fmt_tags(value.tags(&self.tags), &mut tag_buf);
                 for tup in &self.percentiles {
                     let stat: &String = &tup.0;
                     let quant: f64 = tup.1;
+                    let v = match value.query(quant) {
+                        Some(v) => v,
+                        None => continue,
+                    };
                     self.stats.push_str(&value.name);
                     self.stats.push_str(".");
                     self.stats.push_str(stat);
                     self.stats.push_str(" ");
-                    self.stats.push_str(get_from_cache(
-                        &mut value_cache,
-                        value.query(quant).unwrap(),
-                    ));
+                    self.stats.push_str(get_from_cache(&mut value_cache, v));
                     self.stats.push_str(" ");
                     self.stats
                         .push_str(get_from_cache(&mut time_cache, value.timestamp));
@@ -584,12 +596,15 @@ impl Sink<WavefrontConfig> for Wavefront {
             panic!("Host can not be empty".to_string());
         }
         let stream = connect(&config.host, config.port);
+        let config_path = config
+            .config_path
+            .unwrap_or_else(|| "sinks.wavefront".to_string());
         Wavefront {
             host: config.host,
             port: config.port,
             bin_width: config.bin_width,
             aggrs: buckets::Buckets::new(config.bin_width),
-            delivery_attempts: 0,
+            delivery: Delivery::new(&config_path, config.delivery),
             percentiles: config.percentiles,
             stats: String::with_capacity(0x2000),
             stream: stream,
@@ -607,35 +622,53 @@ impl Sink<WavefrontConfig> for Wavefront {
 
     fn flush(&mut self) {
         self.format_stats();
-        loop {
-            if self.delivery_attempts > 0 {
-                debug!("delivery attempts: {}", self.delivery_attempts);
-            }
-            let mut delivery_failure = false;
-            if let Some(ref mut stream) = self.stream {
-                let res = stream.write_all(self.stats.as_bytes());
-                if res.is_ok() {
-                    self.aggrs.reset();
-                    self.stats.clear();
-                    self.delivery_attempts = 0;
-                    WAVEFRONT_DELIVERY_SUCCESS.fetch_add(1, Ordering::Relaxed);
-                    return;
-                } else {
-                    WAVEFRONT_DELIVERY_FAILURE.fetch_add(1, Ordering::Relaxed);
-                    self.delivery_attempts = self.delivery_attempts.saturating_add(1);
-                    delivery_failure = true;
-                }
-            } else {
-                time::delay(self.delivery_attempts);
+        if self.stats.is_empty() {
+            return;
+        }
+        let host = &self.host;
+        let port = self.port;
+        let stats = &self.stats;
+        let stream = &mut self.stream;
+        let res = self.delivery.attempt(|| {
+            if stream.is_none() {
                 WAVEFRONT_CONNECT_ATTEMPTS.fetch_add(1, Ordering::Relaxed);
-                self.stream = connect(&self.host, self.port);
+                *stream = connect(host, port);
             }
-            if delivery_failure {
-                self.stream = None
+            let res = match *stream {
+                Some(ref mut stream) => stream.write_all(stats.as_bytes()),
+                None => return Err("unable to connect".to_string()),
+            };
+            res.map_err(|e| {
+                WAVEFRONT_DELIVERY_FAILURE.fetch_add(1, Ordering::Relaxed);
+                *stream = None;
+                e.to_string()
+            })
+        });
+        match res {
+            Ok(()) => {
+                self.aggrs.reset();
+                self.stats.clear();
+                WAVEFRONT_DELIVERY_SUCCESS.fetch_add(1, Ordering::Relaxed);
             }
+            // The formatted stats are kept and delivery tried again, along
+            // with whatever has been aggregated since, at the next flush.
+            Err(GaveUp::Exhausted { attempts, last }) => warn!(
+                "Gave up delivering to wavefront after {} attempts: {}",
+                attempts, last
+            ),
+            // The stats are kept for the breaker's cooldown, the sink's valve
+            // being closed meanwhile, or until the final flush.
+            Err(GaveUp::BreakerOpen) => {}
         }
     }
 
+    fn final_flush(&mut self) {
+        // Cut short the cooldown of an open breaker, so that what it held
+        // back is attempted once more rather than lost.
+        self.delivery.half_open();
+        self.flush();
+    }
+
     fn shutdown(mut self) {
         self.flush();
     }
@@ -653,7 +686,7 @@ impl Sink<WavefrontConfig> for Wavefront {
     fn valve_state(&self) -> Valve {
         let total_values = self.aggrs.count();
         WAVEFRONT_AGGR_STORED_VALUES.store(total_values, Ordering::Relaxed);
-        if total_values > 10_000 {
+        if total_values > 10_000 || self.delivery.valve_state() == Valve::Closed {
             WAVEFRONT_VALVE_CLOSED.fetch_add(1, Ordering::Relaxed);
             Valve::Closed
         } else {
@@ -692,6 +725,7 @@ mod test {
             sum: true,
             summarize: true,
             histogram: true,
+            distinct: true,
         };
 
         bucket.add(m0);
@@ -723,6 +757,7 @@ mod test {
             sum: false,
             summarize: false,
             histogram: false,
+            distinct: false,
         };
 
         bucket.add(m0);
@@ -778,6 +813,7 @@ mod test {
             sum: true,
             summarize: true,
             histogram: true,
+            distinct: true,
         };
 
         let mut telems = padding(bucket.iter(), bin_width, &last_seen, pad_control);
@@ -810,6 +846,7 @@ mod test {
                 sum: false,
                 summarize: false,
                 histogram: false,
+                distinct: false,
             };
             let expected = bucket.count();
             let mut padding =
@@ -842,6 +879,7 @@ mod test {
                 sum: true,
                 summarize: true,
                 histogram: true,
+                distinct: true,
             };
             let mut padding =
                 padding(bucket.iter(), bin_width as i64, &last_seen, pad_control)
@@ -903,6 +941,7 @@ mod test {
                 sum: true,
                 summarize: true,
                 histogram: true,
+                distinct: true,
             };
             let padding =
                 padding(bucket.iter(), bin_width as i64, &last_seen, pad_control);
@@ -945,6 +984,7 @@ mod test {
                 sum: true,
                 summarize: true,
                 histogram: true,
+                distinct: true,
             };
             let padding =
                 padding(bucket.iter(), bin_width as i64, &last_seen, pad_control);
@@ -985,6 +1025,7 @@ mod test {
             sum: true,
             summarize: true,
             histogram: true,
+            distinct: true,
         };
         let config = WavefrontConfig {
             bin_width: 1024,
@@ -996,6 +1037,7 @@ mod test {
             flush_interval: 60,
             pad_control: pad_control,
             age_threshold: None,
+            delivery: DeliveryConfig::default(),
         };
         let mut wavefront = Wavefront::init(config);
         wavefront.format_stats();
@@ -1029,6 +1071,7 @@ mod test {
             sum: true,
             summarize: true,
             histogram: true,
+            distinct: true,
         };
         let config = WavefrontConfig {
             bin_width: 1,
@@ -1040,6 +1083,7 @@ mod test {
             flush_interval: 60,
             pad_control: pad_control,
             age_threshold: None,
+            delivery: DeliveryConfig::default(),
         };
         let mut wavefront = Wavefront::init(config);
         let dt_0 = Utc
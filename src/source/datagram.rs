@@ -0,0 +1,201 @@
+//! Helpers for sources which receive datagrams at high rates.
+//!
+//! `bind_reuseport` opens a UDP socket with `SO_REUSEPORT` set so that several
+//! sockets, each serviced by their own thread, may share a single host and
+//! port. The kernel spreads incoming datagrams across them. `RecvBatch` reads
+//! many datagrams off a socket per system call, using `recvmmsg` where the
+//! platform has it.
+use libc;
+use std::io;
+use std::mem;
+use std::net::{SocketAddr, UdpSocket};
+use std::os::unix::io::{FromRawFd, RawFd};
+
+/// The maximum number of datagrams read by a single `RecvBatch::recv`.
+pub const BATCH_SIZE: usize = 32;
+
+fn cvt(res: libc::c_int) -> io::Result<libc::c_int> {
+    if res < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(res)
+    }
+}
+
+/// Bind a non-blocking UDP socket to `addr` with `SO_REUSEPORT` set.
+pub fn bind_reuseport(addr: &SocketAddr) -> io::Result<UdpSocket> {
+    let domain = match *addr {
+        SocketAddr::V4(_) => libc::AF_INET,
+        SocketAddr::V6(_) => libc::AF_INET6,
+    };
+    unsafe {
+        let fd = cvt(libc::socket(domain, libc::SOCK_DGRAM, 0))?;
+        // Take ownership of the descriptor straight away so that it is closed
+        // should any of the calls below fail.
+        let socket = UdpSocket::from_raw_fd(fd);
+        let one: libc::c_int = 1;
+        cvt(libc::setsockopt(
+            fd,
+            libc::SOL_SOCKET,
+            libc::SO_REUSEPORT,
+            &one as *const libc::c_int as *const libc::c_void,
+            mem::size_of::<libc::c_int>() as libc::socklen_t,
+        ))?;
+        match *addr {
+            SocketAddr::V4(ref a) => {
+                let mut sin: libc::sockaddr_in = mem::zeroed();
+                sin.sin_family = libc::AF_INET as libc::sa_family_t;
+                sin.sin_port = a.port().to_be();
+                sin.sin_addr = libc::in_addr {
+                    s_addr: u32::from(*a.ip()).to_be(),
+                };
+                cvt(libc::bind(
+                    fd,
+                    &sin as *const libc::sockaddr_in as *const libc::sockaddr,
+                    mem::size_of::<libc::sockaddr_in>() as libc::socklen_t,
+                ))?;
+            }
+            SocketAddr::V6(ref a) => {
+                let mut sin6: libc::sockaddr_in6 = mem::zeroed();
+                sin6.sin6_family = libc::AF_INET6 as libc::sa_family_t;
+                sin6.sin6_port = a.port().to_be();
+                sin6.sin6_addr.s6_addr = a.ip().octets();
+                sin6.sin6_flowinfo = a.flowinfo();
+                sin6.sin6_scope_id = a.scope_id();
+                cvt(libc::bind(
+                    fd,
+                    &sin6 as *const libc::sockaddr_in6 as *const libc::sockaddr,
+                    mem::size_of::<libc::sockaddr_in6>() as libc::socklen_t,
+                ))?;
+            }
+        }
+        socket.set_nonblocking(true)?;
+        Ok(socket)
+    }
+}
+
+/// Reusable receive buffers for reading datagrams in batches.
+pub struct RecvBatch {
+    bufs: Vec<Vec<u8>>,
+    lens: Vec<usize>,
+}
+
+impl RecvBatch {
+    /// Create a new `RecvBatch` able to hold `BATCH_SIZE` datagrams of up to
+    /// `max_datagram` bytes each. Longer datagrams are truncated.
+    pub fn new(max_datagram: usize) -> RecvBatch {
+        RecvBatch {
+            bufs: vec![vec![0; max_datagram]; BATCH_SIZE],
+            lens: vec![0; BATCH_SIZE],
+        }
+    }
+
+    /// Return the `idx`th datagram read by the last call to `recv`.
+    pub fn get(&self, idx: usize) -> &[u8] {
+        &self.bufs[idx][..self.lens[idx]]
+    }
+
+    /// Read as many datagrams as are ready on the non-blocking socket `fd`, up
+    /// to `BATCH_SIZE`, returning how many were read. Fails with
+    /// `io::ErrorKind::WouldBlock` if none are ready.
+    #[cfg(target_os = "linux")]
+    pub fn recv(&mut self, fd: RawFd) -> io::Result<usize> {
+        let mut iovecs: Vec<libc::iovec> = self
+            .bufs
+            .iter_mut()
+            .map(|buf| libc::iovec {
+                iov_base: buf.as_mut_ptr() as *mut libc::c_void,
+                iov_len: buf.len(),
+            })
+            .collect();
+        let mut msgs: Vec<libc::mmsghdr> = iovecs
+            .iter_mut()
+            .map(|iov| {
+                let mut msg: libc::mmsghdr = unsafe { mem::zeroed() };
+                msg.msg_hdr.msg_iov = iov as *mut libc::iovec;
+                msg.msg_hdr.msg_iovlen = 1;
+                msg
+            })
+            .collect();
+        let received = unsafe {
+            cvt(libc::recvmmsg(
+                fd,
+                msgs.as_mut_ptr(),
+                msgs.len() as libc::c_uint,
+                0,
+                ::std::ptr::null_mut(),
+            ))?
+        } as usize;
+        for (len, msg) in self.lens.iter_mut().zip(msgs.iter()).take(received) {
+            *len = msg.msg_len as usize;
+        }
+        Ok(received)
+    }
+
+    /// Read a single datagram off of the non-blocking socket `fd`, returning
+    /// how many were read. Fails with `io::ErrorKind::WouldBlock` if none are
+    /// ready.
+    #[cfg(not(target_os = "linux"))]
+    pub fn recv(&mut self, fd: RawFd) -> io::Result<usize> {
+        let buf = &mut self.bufs[0];
+        let len = unsafe {
+            libc::recv(fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len(), 0)
+        };
+        if len < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        self.lens[0] = len as usize;
+        Ok(1)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::os::unix::io::AsRawFd;
+
+    #[test]
+    fn test_reuseport_shares_address() {
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let first = bind_reuseport(&addr).unwrap();
+        let bound = first.local_addr().unwrap();
+        let second = bind_reuseport(&bound).unwrap();
+        assert_eq!(bound, second.local_addr().unwrap());
+    }
+
+    #[test]
+    fn test_recv_batch() {
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let socket = bind_reuseport(&addr).unwrap();
+        let bound = socket.local_addr().unwrap();
+
+        let mut batch = RecvBatch::new(64);
+        match batch.recv(socket.as_raw_fd()) {
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {}
+            other => panic!("expected WouldBlock, got {:?}", other),
+        }
+
+        let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let payloads: Vec<&[u8]> =
+            vec![&b"foo:1|c"[..], &b"bar:2|g"[..], &b"baz:3|ms"[..]];
+        for payload in &payloads {
+            client.send_to(payload, bound).unwrap();
+        }
+
+        let mut received = Vec::new();
+        while received.len() < payloads.len() {
+            match batch.recv(socket.as_raw_fd()) {
+                Ok(n) => {
+                    assert!(n <= BATCH_SIZE);
+                    for idx in 0..n {
+                        received.push(batch.get(idx).to_vec());
+                    }
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => continue,
+                Err(e) => panic!("recv failed: {:?}", e),
+            }
+        }
+        let received: Vec<&[u8]> = received.iter().map(|r| r.as_slice()).collect();
+        assert_eq!(payloads, received);
+    }
+}
@@ -1,11 +1,17 @@
+use crate::constants;
 use crate::metric;
 use crate::source;
 use crate::util;
 use crate::util::send;
 use mio;
-use std::thread::sleep;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Duration;
 
+/// The index of the last flush pulse sent. A restarted `FlushTimer` resumes
+/// from here, else the filters and sinks that saw its predecessor's pulses
+/// would ignore its own until it caught up.
+static LAST_FLUSH_IDX: AtomicUsize = AtomicUsize::new(0);
+
 /// The source of all flush pulses.
 pub struct FlushTimer;
 
@@ -28,7 +34,9 @@ impl source::Source<FlushTimerConfig> for FlushTimer {
         FlushTimer {}
     }
 
-    fn run(self, mut chans: util::Channel, _poller: mio::Poll) {
+    /// Unlike other sources the `FlushTimer` sends no `Shutdown` when it is
+    /// itself shut down, it being no upstream of any filter or sink.
+    fn run(self, mut chans: util::Channel, poller: mio::Poll) {
         let flush_duration = Duration::from_millis(1000 / flushes_per_second());
         // idx will _always_ increase. If it's kept at u64 or greater it will
         // overflow long past the collapse of our industrial civilization even
@@ -36,14 +44,25 @@ impl source::Source<FlushTimerConfig> for FlushTimer {
         //
         // Point being, there's a theoretical overflow problem here but it's not
         // going to be hit in practice.
-        let mut idx: u64 = 0;
+        let mut idx: u64 = LAST_FLUSH_IDX.load(Ordering::Relaxed) as u64;
+        let mut events = mio::Events::with_capacity(1);
         loop {
             // We start with TimerFlush(1) as receivers start with
             // TimerFlush(0). This will update their last_flush_idx seen at
             // system boot.
             idx += 1;
-            sleep(flush_duration);
+            match poller.poll(&mut events, Some(flush_duration)) {
+                Ok(_num_events) => {
+                    for event in &events {
+                        if event.token() == constants::SYSTEM {
+                            return;
+                        }
+                    }
+                }
+                Err(e) => panic!(format!("Failed during poll {:?}", e)),
+            }
             send(&mut chans, metric::Event::TimerFlush(idx));
+            LAST_FLUSH_IDX.store(idx as usize, Ordering::Relaxed);
         }
     }
 }
@@ -1,12 +1,13 @@
 use crate::constants;
 use crate::metric;
-use crate::protocols::graphite::parse_graphite;
-use crate::source::{TCPConfig, TCPStreamHandler, TCP};
+use crate::protocols::graphite::{parse_graphite, parse_graphite_pickle};
+use crate::source::{BufferedPayload, PayloadErr, TCPConfig, TCPStreamHandler, TCP};
 use crate::util;
 use crate::util::send;
 use mio;
 use std::io::prelude::*;
 use std::io::BufReader;
+use std::net;
 use std::str;
 use std::sync;
 use std::sync::atomic::{AtomicUsize, Ordering};
@@ -16,6 +17,16 @@ pub static GRAPHITE_GOOD_PACKET: AtomicUsize = AtomicUsize::new(0);
 pub static GRAPHITE_TELEM: AtomicUsize = AtomicUsize::new(0);
 pub static GRAPHITE_BAD_PACKET: AtomicUsize = AtomicUsize::new(0);
 
+/// The wire protocols a graphite source may speak.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+pub enum GraphiteProtocol {
+    /// Newline delimited `name value timestamp` lines, conventionally on
+    /// port 2003.
+    Plaintext,
+    /// Carbon's length-prefixed pickle batches, conventionally on port 2004.
+    Pickle,
+}
+
 /// Configured for the `metric::Telemetry` source.
 #[derive(Debug, Deserialize, Clone)]
 pub struct GraphiteConfig {
@@ -28,6 +39,8 @@ pub struct GraphiteConfig {
     pub forwards: Vec<String>,
     /// The unique name of the source in the routing topology.
     pub config_path: Option<String>,
+    /// The wire protocol the source will accept.
+    pub protocol: GraphiteProtocol,
 }
 
 impl Default for GraphiteConfig {
@@ -37,6 +50,7 @@ impl Default for GraphiteConfig {
             port: 2003,
             forwards: Vec::new(),
             config_path: Some("sources.graphite".to_string()),
+            protocol: GraphiteProtocol::Plaintext,
         }
     }
 }
@@ -118,3 +132,96 @@ impl TCPStreamHandler for GraphiteStreamHandler {
 ///
 /// This source produces `metric::Telemetry` from the graphite protocol.
 pub type Graphite = TCP<GraphiteStreamHandler>;
+
+#[derive(Default, Debug, Clone, Deserialize)]
+pub struct GraphitePickleStreamHandler;
+
+impl TCPStreamHandler for GraphitePickleStreamHandler {
+    fn handle_stream(
+        &mut self,
+        mut chans: util::Channel,
+        poller: &mio::Poll,
+        stream: mio::net::TcpStream,
+    ) {
+        let mut res = Vec::new();
+        let basic_metric = sync::Arc::new(Some(metric::Telemetry::default()));
+        let mut streaming = true;
+        let mut reader = BufferedPayload::new(stream.try_clone().unwrap(), 1_048_576);
+        while streaming {
+            let mut events = mio::Events::with_capacity(1024);
+            match poller.poll(&mut events, None) {
+                Err(e) => panic!("Failed during poll {:?}", e),
+                Ok(_num_events) => {
+                    for event in events {
+                        match event.token() {
+                            constants::SYSTEM => {
+                                streaming = false;
+                                break;
+                            }
+                            _stream_token => {
+                                while streaming {
+                                    match reader.read() {
+                                        Ok(payload) => {
+                                            if parse_graphite_pickle(
+                                                &payload,
+                                                &mut res,
+                                                &basic_metric,
+                                            ) {
+                                                GRAPHITE_GOOD_PACKET
+                                                    .fetch_add(1, Ordering::Relaxed);
+                                                GRAPHITE_TELEM.fetch_add(
+                                                    res.len(),
+                                                    Ordering::Relaxed,
+                                                );
+                                                for m in res.drain(..) {
+                                                    send(
+                                                        &mut chans,
+                                                        metric::Event::Telemetry(m),
+                                                    );
+                                                }
+                                            } else {
+                                                GRAPHITE_BAD_PACKET
+                                                    .fetch_add(1, Ordering::Relaxed);
+                                                error!(
+                                                    "bad pickle payload of {} bytes",
+                                                    payload.len()
+                                                );
+                                                res.clear();
+                                            }
+                                        }
+                                        Err(PayloadErr::WouldBlock) => {
+                                            // Not enough data yet.  Try again.
+                                            break;
+                                        }
+                                        Err(PayloadErr::EOF) => {
+                                            trace!("TCP stream closed.");
+                                            streaming = false;
+                                            break;
+                                        }
+                                        Err(e) => {
+                                            error!(
+                                                "Failed to read pickle payload! {:?}",
+                                                e
+                                            );
+                                            streaming = false;
+                                            break;
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // On some systems shutting down an already closed connection (client or
+        // otherwise) results in an Err. We ignore it.
+        let _shutdown_result = stream.shutdown(net::Shutdown::Both);
+    }
+}
+
+/// Graphite pickle protocol source
+///
+/// This source produces `metric::Telemetry` from Carbon's pickle protocol.
+pub type GraphitePickle = TCP<GraphitePickleStreamHandler>;
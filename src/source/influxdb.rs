@@ -0,0 +1,377 @@
+use crate::constants;
+use crate::http::{self, Handler, Header, Method, Request, Response, StatusCode};
+use crate::metric;
+use crate::protocols::influxdb::{parse_influxdb, Precision};
+use crate::source;
+use crate::source::listener::{
+    LineBuffer, Listener, ListenerConfig, ListenerHandler, ListenerTransport,
+};
+use crate::thread::Stoppable;
+use crate::util;
+use crate::util::send;
+use flate2::read::GzDecoder;
+use mio;
+use std::io::prelude::*;
+use std::mem;
+use std::str;
+use std::sync;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+pub static INFLUXDB_GOOD_PACKET: AtomicUsize = AtomicUsize::new(0);
+pub static INFLUXDB_TELEM: AtomicUsize = AtomicUsize::new(0);
+pub static INFLUXDB_BAD_PACKET: AtomicUsize = AtomicUsize::new(0);
+
+/// The largest HTTP body we will accept, after any decompression. This is
+/// InfluxDB's own default `max-body-size`. Larger bodies are answered 413.
+const MAX_BODY: usize = 25_000_000;
+
+/// The InfluxDB line protocol source
+///
+/// Accepts the InfluxDB line protocol as written by Telegraf and the InfluxDB
+/// client libraries, so that they may point at cernan in place of InfluxDB.
+/// See `protocols::influxdb` for how points become `Telemetry`.
+///
+/// The UDP and TCP transports are served by a `Listener`, the HTTP transport
+/// by an HTTP server of its own.
+pub struct InfluxDBServer {
+    listener: Option<Listener<InfluxDBHandler>>,
+    http_host_port: Option<String>,
+}
+
+/// The transports over which the InfluxDB source may receive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InfluxDBTransport {
+    /// UDP datagrams, each holding one or more lines.
+    Udp,
+    /// Newline delimited lines over TCP.
+    Tcp,
+    /// HTTP POSTs to `/write`, as accepted by InfluxDB itself.
+    Http,
+}
+
+/// Configuration for the InfluxDB source.
+#[derive(Debug, Clone)]
+pub struct InfluxDBServerConfig {
+    /// The transport the source will receive over.
+    pub transport: InfluxDBTransport,
+    /// The host for the source to bind to.
+    pub host: String,
+    /// The port for the source to listen on.
+    pub port: u16,
+    /// The number of threads receiving on the udp transport. When greater
+    /// than one each thread binds its own `SO_REUSEPORT` socket to `host` and
+    /// `port`.
+    pub workers: usize,
+    /// The precision of timestamps received over the UDP and TCP transports.
+    /// Over HTTP the `precision` query parameter is used instead, defaulting
+    /// to nanoseconds as InfluxDB does.
+    pub precision: Precision,
+    /// The forwards that the source will send its telemetry on to.
+    pub forwards: Vec<String>,
+    /// The unique name for the source in the routing topology.
+    pub config_path: Option<String>,
+}
+
+impl Default for InfluxDBServerConfig {
+    fn default() -> InfluxDBServerConfig {
+        InfluxDBServerConfig {
+            transport: InfluxDBTransport::Http,
+            host: "localhost".to_string(),
+            port: 8086,
+            workers: 1,
+            precision: Precision::default(),
+            forwards: Vec::new(),
+            config_path: None,
+        }
+    }
+}
+
+impl From<InfluxDBServerConfig> for ListenerConfig {
+    fn from(item: InfluxDBServerConfig) -> Self {
+        let transport = match item.transport {
+            InfluxDBTransport::Udp => ListenerTransport::Udp,
+            InfluxDBTransport::Tcp => ListenerTransport::Tcp,
+            InfluxDBTransport::Http => {
+                unreachable!("the http transport is not served by a listener")
+            }
+        };
+        ListenerConfig {
+            transport: transport,
+            host: item.host,
+            port: item.port,
+            path: None,
+            workers: item.workers,
+            forwards: item.forwards,
+            config_path: item.config_path,
+        }
+    }
+}
+
+/// Parse a payload of line protocol and send the resulting `Telemetry` on to
+/// `chans`, keeping the packet counters. Returns whether the payload parsed.
+///
+/// Points without a timestamp are stamped with the time the payload arrived.
+fn handle_payload(
+    chans: &mut util::Channel,
+    payload: &str,
+    precision: Precision,
+    metrics: &mut Vec<metric::Telemetry>,
+) -> bool {
+    let basic_metric = sync::Arc::new(Some(metric::Telemetry::default()));
+    if parse_influxdb(payload, precision, metrics, &basic_metric) {
+        INFLUXDB_GOOD_PACKET.fetch_add(1, Ordering::Relaxed);
+        INFLUXDB_TELEM.fetch_add(metrics.len(), Ordering::Relaxed);
+        for m in metrics.drain(..) {
+            send(chans, metric::Event::new_telemetry(m));
+        }
+        true
+    } else {
+        metrics.clear();
+        INFLUXDB_BAD_PACKET.fetch_add(1, Ordering::Relaxed);
+        error!("BAD PACKET: {:?}", payload);
+        false
+    }
+}
+
+/// Handler for line protocol datagrams and newline delimited streams.
+#[derive(Default, Debug, Clone)]
+pub struct InfluxDBHandler {
+    precision: Precision,
+    metrics: Vec<metric::Telemetry>,
+    lines: LineBuffer,
+}
+
+impl InfluxDBHandler {
+    fn handle_payload(&mut self, chans: &mut util::Channel, payload: &[u8]) {
+        match str::from_utf8(payload) {
+            Ok(payload) => {
+                handle_payload(chans, payload, self.precision, &mut self.metrics);
+            }
+            Err(e) => {
+                INFLUXDB_BAD_PACKET.fetch_add(1, Ordering::Relaxed);
+                error!("Payload not valid UTF-8: {:?}", e);
+            }
+        }
+    }
+}
+
+impl ListenerHandler for InfluxDBHandler {
+    /// The largest datagram we will read. Stream lines are held to the same
+    /// length.
+    const MAX_DATAGRAM: usize = 65_536;
+
+    fn handle_datagram(&mut self, chans: &mut util::Channel, datagram: &[u8]) {
+        self.handle_payload(chans, datagram);
+    }
+
+    fn handle_bytes(&mut self, chans: &mut util::Channel, bytes: &[u8]) -> bool {
+        let mut lines = mem::replace(&mut self.lines, LineBuffer::default());
+        let fits = lines.push(bytes, Self::MAX_DATAGRAM, |line| {
+            self.handle_payload(chans, line)
+        });
+        self.lines = lines;
+        if !fits {
+            INFLUXDB_BAD_PACKET.fetch_add(1, Ordering::Relaxed);
+            error!(
+                "influxdb line exceeds {} bytes, closing stream",
+                Self::MAX_DATAGRAM
+            );
+        }
+        fits
+    }
+
+    fn handle_close(&mut self, chans: &mut util::Channel) {
+        if let Some(line) = self.lines.take() {
+            self.handle_payload(chans, &line);
+        }
+    }
+}
+
+/// HTTP handler for the InfluxDB `/write` endpoint.
+struct InfluxDBWriteHandler {
+    chans: sync::Mutex<util::Channel>,
+}
+
+fn respond(request: Request, status: u16, body: &str) {
+    let headers =
+        vec![Header::from_bytes(&b"Content-Type"[..], "text/plain").unwrap()];
+    let response = Response::new(
+        StatusCode::from(status),
+        headers,
+        body.as_bytes(),
+        Some(body.len()),
+        None,
+    );
+    if let Err(e) = request.respond(response) {
+        warn!("Failed to send influxdb response! {:?}", e);
+    }
+}
+
+impl Handler for InfluxDBWriteHandler {
+    fn handle(&self, mut request: Request) {
+        let (path, query) = {
+            let url = request.url();
+            match url.find('?') {
+                Some(idx) => (url[..idx].to_string(), url[idx + 1..].to_string()),
+                None => (url.to_string(), String::new()),
+            }
+        };
+        if path != "/write" {
+            return respond(request, 404, "not found\n");
+        }
+        if *request.method() != Method::Post {
+            return respond(request, 405, "method not allowed\n");
+        }
+
+        let mut precision = Precision::default();
+        for param in query.split('&') {
+            if param.starts_with("precision=") {
+                match Precision::parse(&param["precision=".len()..]) {
+                    Some(p) => precision = p,
+                    None => return respond(request, 400, "invalid precision\n"),
+                }
+            }
+        }
+
+        let gzipped = request
+            .headers()
+            .iter()
+            .any(|h| h.field.equiv("Content-Encoding") && h.value.as_str() == "gzip");
+        if request.body_length().map_or(false, |len| len > MAX_BODY) {
+            INFLUXDB_BAD_PACKET.fetch_add(1, Ordering::Relaxed);
+            return respond(request, 413, "request body too large\n");
+        }
+        // One byte past the limit is read to tell a body at the limit from
+        // one over it.
+        let limit = MAX_BODY as u64 + 1;
+        let mut body = Vec::new();
+        let read = if gzipped {
+            GzDecoder::new(request.as_reader())
+                .take(limit)
+                .read_to_end(&mut body)
+        } else {
+            request.as_reader().take(limit).read_to_end(&mut body)
+        };
+        if read.is_err() {
+            INFLUXDB_BAD_PACKET.fetch_add(1, Ordering::Relaxed);
+            return respond(request, 400, "unable to read body\n");
+        }
+        if body.len() > MAX_BODY {
+            INFLUXDB_BAD_PACKET.fetch_add(1, Ordering::Relaxed);
+            return respond(request, 413, "request body too large\n");
+        }
+        let body = match String::from_utf8(body) {
+            Ok(body) => body,
+            Err(_) => {
+                INFLUXDB_BAD_PACKET.fetch_add(1, Ordering::Relaxed);
+                return respond(request, 400, "body is not valid UTF-8\n");
+            }
+        };
+
+        let mut metrics = Vec::new();
+        let parsed = {
+            let mut chans = self.chans.lock().unwrap();
+            handle_payload(&mut chans, &body, precision, &mut metrics)
+        };
+        if parsed {
+            respond(request, 204, "")
+        } else {
+            respond(request, 400, "unable to parse points\n")
+        }
+    }
+}
+
+impl source::Source<InfluxDBServerConfig> for InfluxDBServer {
+    /// Create and spawn a new InfluxDB source
+    fn init(config: InfluxDBServerConfig) -> Self {
+        match config.transport {
+            InfluxDBTransport::Udp | InfluxDBTransport::Tcp => {
+                let handler = InfluxDBHandler {
+                    precision: config.precision,
+                    metrics: Vec::new(),
+                    lines: LineBuffer::default(),
+                };
+                InfluxDBServer {
+                    listener: Some(Listener::bind(config.into(), handler)),
+                    http_host_port: None,
+                }
+            }
+            InfluxDBTransport::Http => InfluxDBServer {
+                listener: None,
+                http_host_port: Some(format!("{}:{}", config.host, config.port)),
+            },
+        }
+    }
+
+    fn run(mut self, mut chans: util::Channel, poller: mio::Poll) {
+        if let Some(listener) = self.listener.take() {
+            return listener.run(chans, poller);
+        }
+
+        let http_srv = self.http_host_port.take().map(|host_port| {
+            http::Server::new(
+                host_port,
+                InfluxDBWriteHandler {
+                    chans: sync::Mutex::new(chans.clone()),
+                },
+            )
+        });
+
+        loop {
+            let mut events = mio::Events::with_capacity(1024);
+            match poller.poll(&mut events, None) {
+                Ok(_num_events) => {
+                    for event in events {
+                        if event.token() == constants::SYSTEM {
+                            if let Some(http_srv) = http_srv {
+                                http_srv.shutdown();
+                            }
+                            send(&mut chans, metric::Event::Shutdown);
+                            return;
+                        }
+                    }
+                }
+                Err(e) => panic!("Failed during poll {:?}", e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use hopper;
+    use tempdir;
+
+    #[test]
+    fn test_stream_lines_span_reads() {
+        let dir = tempdir::TempDir::new("cernan_influxdb").unwrap();
+        let (sender, receiver) = hopper::channel("influxdb", dir.path()).unwrap();
+        let mut chans = util::Channel::new(vec![sender]);
+        let mut handler = InfluxDBHandler::default();
+        assert!(handler.handle_bytes(&mut chans, b"cpu value=1\nmem "));
+        assert!(handler.handle_bytes(&mut chans, b"value=2\ndisk value=3"));
+        handler.handle_close(&mut chans);
+
+        let names: Vec<String> = receiver
+            .into_iter()
+            .take(3)
+            .map(|event| match event {
+                metric::Event::Telemetry(telem) => telem.name,
+                _ => panic!("expected telemetry"),
+            })
+            .collect();
+        assert_eq!(names, vec!["cpu.value", "mem.value", "disk.value"]);
+    }
+
+    #[test]
+    fn test_stream_oversized_line_closes() {
+        let dir = tempdir::TempDir::new("cernan_influxdb").unwrap();
+        let (sender, _receiver) = hopper::channel("influxdb", dir.path()).unwrap();
+        let mut chans = util::Channel::new(vec![sender]);
+        let mut handler = InfluxDBHandler::default();
+        let chunk = vec![b'a'; InfluxDBHandler::MAX_DATAGRAM];
+        assert!(handler.handle_bytes(&mut chans, &chunk));
+        assert!(!handler.handle_bytes(&mut chans, b"a"));
+    }
+}
@@ -1,3 +1,4 @@
+use crate::dead_letter;
 use crate::filter;
 use crate::metric;
 use crate::metric::{AggregationMethod, Telemetry};
@@ -8,7 +9,7 @@ use crate::util;
 use coco::Stack;
 use mio;
 use std;
-use std::sync::atomic::Ordering;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 lazy_static! {
     static ref Q: Stack<metric::Telemetry> = Stack::new();
@@ -18,7 +19,22 @@ lazy_static! {
 /// self-telemeter. This is an improvement over past methods as an explicit
 /// Source gives operators the ability to define a filter topology for such
 /// telemetry and makes it easier for modules to report on themeselves.
-pub struct Internal;
+pub struct Internal {
+    /// The value of each of `COUNTERS` when last reported.
+    last: Vec<usize>,
+    /// The value of each counter of dropped events when last reported.
+    last_dropped: util::HashMap<(String, String), usize>,
+}
+
+/// The increase of a counter from `last` to `value`. A counter that went
+/// down has been created anew, its whole value is the increase.
+fn increase(last: usize, value: usize) -> usize {
+    if value < last {
+        value
+    } else {
+        value - last
+    }
+}
 
 /// The configuration struct for 'Internal'
 #[derive(Debug, Deserialize, Clone)]
@@ -62,39 +78,435 @@ pub fn report_full_telemetry(
     Q.push(telem);
 }
 
-macro_rules! atom_telem {
-    ($name:expr, $atom:expr, $chans:expr) => {
-        let now = time::now();
-        let value = $atom.swap(0, Ordering::Relaxed);
-        if value != 0 {
-            let telem = Telemetry::new()
-                .name($name)
-                .value(value as f64)
-                .timestamp(now)
-                .kind(AggregationMethod::Sum)
-                .harden()
-                .unwrap();
-            util::send(&mut $chans, metric::Event::new_telemetry(telem));
-        }
-    };
-}
-
-macro_rules! atom_set_telem {
-    ($name:expr, $atom:expr, $chans:expr) => {
-        let now = time::now();
-        let value = $atom.swap(0, Ordering::Relaxed);
-        if value != 0 {
-            let telem = Telemetry::new()
-                .name($name)
-                .value(value as f64)
-                .timestamp(now)
-                .kind(AggregationMethod::Set)
-                .harden()
-                .unwrap();
-            util::send(&mut $chans, metric::Event::new_telemetry(telem));
-        }
-    };
-}
+/// Every counter cernan keeps, by the name it is reported under and the
+/// aggregation it is reported with. Counters reported as
+/// `AggregationMethod::Sum` only ever grow, the `Internal` source reports
+/// their increase since it last reported them.
+pub static COUNTERS: &[(&str, &AtomicUsize, AggregationMethod)] = &[
+    (
+        "cernan.util.hopper.error.full",
+        &util::UTIL_SEND_HOPPER_ERROR_FULL,
+        AggregationMethod::Sum,
+    ),
+    // dead_letter
+    (
+        "cernan.dead_letter.written",
+        &dead_letter::DEAD_LETTER_WRITTEN,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.dead_letter.lost",
+        &dead_letter::DEAD_LETTER_LOST,
+        AggregationMethod::Sum,
+    ),
+    // source::graphite
+    (
+        "cernan.graphite.new_peer",
+        &source::graphite::GRAPHITE_NEW_PEER,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.graphite.packet",
+        &source::graphite::GRAPHITE_GOOD_PACKET,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.graphite.telemetry.received",
+        &source::graphite::GRAPHITE_TELEM,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.graphite.bad_packet",
+        &source::graphite::GRAPHITE_BAD_PACKET,
+        AggregationMethod::Sum,
+    ),
+    // source::influxdb
+    (
+        "cernan.influxdb.packet",
+        &source::influxdb::INFLUXDB_GOOD_PACKET,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.influxdb.telemetry.received",
+        &source::influxdb::INFLUXDB_TELEM,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.influxdb.bad_packet",
+        &source::influxdb::INFLUXDB_BAD_PACKET,
+        AggregationMethod::Sum,
+    ),
+    // source::prometheus
+    (
+        "cernan.prometheus_scrape.success",
+        &source::prometheus::PROMETHEUS_SCRAPE_SUCCESS,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.prometheus_scrape.failure",
+        &source::prometheus::PROMETHEUS_SCRAPE_FAILURE,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.prometheus_scrape.telemetry.received",
+        &source::prometheus::PROMETHEUS_SCRAPE_TELEM,
+        AggregationMethod::Sum,
+    ),
+    // source::prometheus_remote_write
+    (
+        "cernan.prometheus_remote_write.request",
+        &source::prometheus_remote_write::PROMETHEUS_REMOTE_WRITE_GOOD_REQUEST,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.prometheus_remote_write.bad_request",
+        &source::prometheus_remote_write::PROMETHEUS_REMOTE_WRITE_BAD_REQUEST,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.prometheus_remote_write.telemetry.received",
+        &source::prometheus_remote_write::PROMETHEUS_REMOTE_WRITE_TELEM,
+        AggregationMethod::Sum,
+    ),
+    // source::statsd
+    (
+        "cernan.statsd.packet",
+        &source::statsd::STATSD_GOOD_PACKET,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.statsd.bad_packet",
+        &source::statsd::STATSD_BAD_PACKET,
+        AggregationMethod::Sum,
+    ),
+    // source::syslog
+    (
+        "cernan.syslog.packet",
+        &source::syslog::SYSLOG_GOOD_PACKET,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.syslog.bad_packet",
+        &source::syslog::SYSLOG_BAD_PACKET,
+        AggregationMethod::Sum,
+    ),
+    // source::native
+    (
+        "cernan.native.payload.success",
+        &source::native::NATIVE_PAYLOAD_SUCCESS_SUM,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.native.payload.parse.failure",
+        &source::native::NATIVE_PAYLOAD_PARSE_FAILURE_SUM,
+        AggregationMethod::Sum,
+    ),
+    // source::avro
+    (
+        "cernan.avro.payload.success",
+        &source::avro::AVRO_PAYLOAD_SUCCESS_SUM,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.avro.payload.parse.failure",
+        &source::avro::AVRO_PAYLOAD_PARSE_FAILURE_SUM,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.avro.payload.io.failure",
+        &source::avro::AVRO_PAYLOAD_IO_FAILURE_SUM,
+        AggregationMethod::Sum,
+    ),
+    // sink::delivery
+    (
+        "cernan.sinks.delivery.attempts",
+        &sink::delivery::SINK_DELIVERY_ATTEMPTS,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.delivery.failures",
+        &sink::delivery::SINK_DELIVERY_FAILURES,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.delivery.gave_up",
+        &sink::delivery::SINK_DELIVERY_GAVE_UP,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.breaker.rejected",
+        &sink::delivery::SINK_BREAKER_REJECTED,
+        AggregationMethod::Sum,
+    ),
+    // sink::elasticsearch
+    (
+        "cernan.sinks.elasticsearch.records.delivery",
+        &sink::elasticsearch::ELASTIC_RECORDS_DELIVERY,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.elasticsearch.internal.buffer_len",
+        &sink::elasticsearch::ELASTIC_INTERNAL_BUFFER_LEN,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.elasticsearch.records.total_delivered",
+        &sink::elasticsearch::ELASTIC_RECORDS_TOTAL_DELIVERED,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.elasticsearch.records.total_failed",
+        &sink::elasticsearch::ELASTIC_RECORDS_TOTAL_FAILED,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.elasticsearch.error.unknown",
+        &sink::elasticsearch::ELASTIC_ERROR_UNKNOWN,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.elasticsearch.error.bulk_action.index",
+        &sink::elasticsearch::ELASTIC_BULK_ACTION_INDEX_ERR,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.elasticsearch.error.bulk_action.create",
+        &sink::elasticsearch::ELASTIC_BULK_ACTION_CREATE_ERR,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.elasticsearch.error.bulk_action.update",
+        &sink::elasticsearch::ELASTIC_BULK_ACTION_UPDATE_ERR,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.elasticsearch.error.bulk_action.delete",
+        &sink::elasticsearch::ELASTIC_BULK_ACTION_DELETE_ERR,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.elasticsearch.error.api.index_not_found",
+        &sink::elasticsearch::ELASTIC_ERROR_API_INDEX_NOT_FOUND,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.elasticsearch.error.api.parsing",
+        &sink::elasticsearch::ELASTIC_ERROR_API_PARSING,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.elasticsearch.error.api.mapper_parsing",
+        &sink::elasticsearch::ELASTIC_ERROR_API_MAPPER_PARSING,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.elasticsearch.error.api.action_request_validation",
+        &sink::elasticsearch::ELASTIC_ERROR_API_ACTION_REQUEST_VALIDATION,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.elasticsearch.error.api.action_document_missing",
+        &sink::elasticsearch::ELASTIC_ERROR_API_DOCUMENT_MISSING,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.elasticsearch.error.api.index_already_exists",
+        &sink::elasticsearch::ELASTIC_ERROR_API_INDEX_ALREADY_EXISTS,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.elasticsearch.error.api.unknown",
+        &sink::elasticsearch::ELASTIC_ERROR_API_UNKNOWN,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.elasticsearch.error.client",
+        &sink::elasticsearch::ELASTIC_ERROR_CLIENT,
+        AggregationMethod::Sum,
+    ),
+    // sink::wavefront
+    (
+        "cernan.sinks.wavefront.proxy.connection_attempts",
+        &sink::wavefront::WAVEFRONT_CONNECT_ATTEMPTS,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.wavefront.aggregations.total_stored",
+        &sink::wavefront::WAVEFRONT_AGGR_STORED_VALUES,
+        AggregationMethod::Set,
+    ),
+    (
+        "cernan.sinks.wavefront.aggregation.histogram",
+        &sink::wavefront::WAVEFRONT_AGGR_HISTO,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.wavefront.aggregation.sum",
+        &sink::wavefront::WAVEFRONT_AGGR_SUM,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.wavefront.aggregation.set",
+        &sink::wavefront::WAVEFRONT_AGGR_SET,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.wavefront.aggregation.summarize",
+        &sink::wavefront::WAVEFRONT_AGGR_SUMMARIZE,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.wavefront.aggregation.summarize.total_percentiles",
+        &sink::wavefront::WAVEFRONT_AGGR_TOT_PERCENT,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.wavefront.delivery.success",
+        &sink::wavefront::WAVEFRONT_DELIVERY_SUCCESS,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.wavefront.delivery.failure",
+        &sink::wavefront::WAVEFRONT_DELIVERY_FAILURE,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.wavefront.valve.closed",
+        &sink::wavefront::WAVEFRONT_VALVE_CLOSED,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.wavefront.valve.open",
+        &sink::wavefront::WAVEFRONT_VALVE_OPEN,
+        AggregationMethod::Sum,
+    ),
+    // sink::prometheus
+    (
+        "cernan.sinks.prometheus.aggregation.inside_baseball.windowed.purged",
+        &sink::prometheus::PROMETHEUS_AGGR_WINDOWED_PURGED,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.prometheus.exposition.inside_baseball.delay.sum",
+        &sink::prometheus::PROMETHEUS_RESPONSE_DELAY_SUM,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.prometheus.aggregation.reportable",
+        &sink::prometheus::PROMETHEUS_AGGR_REPORTABLE,
+        AggregationMethod::Set,
+    ),
+    (
+        "cernan.sinks.prometheus.report.success",
+        &sink::prometheus::PROMETHEUS_REPORT_SUCCESS,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.prometheus.report.error",
+        &sink::prometheus::PROMETHEUS_REPORT_ERROR,
+        AggregationMethod::Sum,
+    ),
+    // sink::prometheus_remote_write
+    (
+        "cernan.sinks.prometheus_remote_write.delivery_attempts",
+        &sink::prometheus_remote_write::PROMETHEUS_REMOTE_WRITE_DELIVERY_ATTEMPTS,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.prometheus_remote_write.success",
+        &sink::prometheus_remote_write::PROMETHEUS_REMOTE_WRITE_SUCCESS,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.prometheus_remote_write.failure.client_error",
+        &sink::prometheus_remote_write::PROMETHEUS_REMOTE_WRITE_FAILURE_CLIENT,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.prometheus_remote_write.failure.server_error",
+        &sink::prometheus_remote_write::PROMETHEUS_REMOTE_WRITE_FAILURE_SERVER,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.prometheus_remote_write.dropped",
+        &sink::prometheus_remote_write::PROMETHEUS_REMOTE_WRITE_DROPPED,
+        AggregationMethod::Sum,
+    ),
+    // sink::influxdb
+    (
+        "cernan.sinks.influxdb.delivery_attempts",
+        &sink::influxdb::INFLUX_DELIVERY_ATTEMPTS,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.influxdb.success",
+        &sink::influxdb::INFLUX_SUCCESS,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.influxdb.failure.client_error",
+        &sink::influxdb::INFLUX_FAILURE_CLIENT,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.influxdb.failure.server_error",
+        &sink::influxdb::INFLUX_FAILURE_SERVER,
+        AggregationMethod::Sum,
+    ),
+    // sink::kafka
+    (
+        "cernan.sinks.kafka.publish.success",
+        &sink::kafka::KAFKA_PUBLISH_SUCCESS_SUM,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.kafka.publish.retry",
+        &sink::kafka::KAFKA_PUBLISH_RETRY_SUM,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.kafka.publish.failure",
+        &sink::kafka::KAFKA_PUBLISH_FAILURE_SUM,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.sinks.kafka.publish.retry.failure",
+        &sink::kafka::KAFKA_PUBLISH_RETRY_FAILURE_SUM,
+        AggregationMethod::Sum,
+    ),
+    // filter::delay_filter
+    (
+        "cernan.filters.delay.telemetry.accept",
+        &filter::delay_filter::DELAY_TELEM_ACCEPT,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.filters.delay.telemetry.reject",
+        &filter::delay_filter::DELAY_TELEM_REJECT,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.filters.delay.log.reject",
+        &filter::delay_filter::DELAY_LOG_REJECT,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.filters.delay.log.accept",
+        &filter::delay_filter::DELAY_LOG_ACCEPT,
+        AggregationMethod::Sum,
+    ),
+    // filter::json_encode_filter
+    (
+        "cernan.filters.json_encode.log.processed",
+        &filter::json_encode_filter::JSON_ENCODE_LOG_PROCESSED,
+        AggregationMethod::Sum,
+    ),
+    (
+        "cernan.filters.json_encode.log.parsed",
+        &filter::json_encode_filter::JSON_ENCODE_LOG_PARSED,
+        AggregationMethod::Sum,
+    ),
+];
 
 /// Internal as Source
 ///
@@ -105,11 +517,13 @@ macro_rules! atom_set_telem {
 impl source::Source<InternalConfig> for Internal {
     /// Create a new Internal
     fn init(_config: InternalConfig) -> Self {
-        Internal {}
+        Internal {
+            last: vec![0; COUNTERS.len()],
+            last_dropped: util::HashMap::default(),
+        }
     }
 
-    #[allow(clippy::cyclomatic_complexity)]
-    fn run(self, mut chans: util::Channel, poller: mio::Poll) {
+    fn run(mut self, mut chans: util::Channel, poller: mio::Poll) {
         let slp = std::time::Duration::from_millis(1_000);
         loop {
             let mut events = mio::Events::with_capacity(1024);
@@ -124,312 +538,54 @@ impl source::Source<InternalConfig> for Internal {
                 }
                 Ok(_) => {
                     if !chans.is_empty() {
-                        atom_telem!(
-                            "cernan.util.hopper.error.full",
-                            util::UTIL_SEND_HOPPER_ERROR_FULL,
-                            chans
-                        );
-                        // source::graphite
-                        atom_telem!(
-                            "cernan.graphite.new_peer",
-                            source::graphite::GRAPHITE_NEW_PEER,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.graphite.packet",
-                            source::graphite::GRAPHITE_GOOD_PACKET,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.graphite.telemetry.received",
-                            source::graphite::GRAPHITE_TELEM,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.graphite.bad_packet",
-                            source::graphite::GRAPHITE_BAD_PACKET,
-                            chans
-                        );
-                        // source::statsd
-                        atom_telem!(
-                            "cernan.statsd.packet",
-                            source::statsd::STATSD_GOOD_PACKET,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.statsd.bad_packet",
-                            source::statsd::STATSD_BAD_PACKET,
-                            chans
-                        );
-                        // source::native
-                        atom_telem!(
-                            "cernan.native.payload.success",
-                            source::native::NATIVE_PAYLOAD_SUCCESS_SUM,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.native.payload.parse.failure",
-                            source::native::NATIVE_PAYLOAD_PARSE_FAILURE_SUM,
-                            chans
-                        );
-                        // source::avro
-                        atom_telem!(
-                            "cernan.avro.payload.success",
-                            source::avro::AVRO_PAYLOAD_SUCCESS_SUM,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.avro.payload.parse.failure",
-                            source::avro::AVRO_PAYLOAD_PARSE_FAILURE_SUM,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.avro.payload.io.failure",
-                            source::avro::AVRO_PAYLOAD_IO_FAILURE_SUM,
-                            chans
-                        );
-                        // sink::elasticsearch
-                        atom_telem!(
-                            "cernan.sinks.elasticsearch.records.delivery",
-                            sink::elasticsearch::ELASTIC_RECORDS_DELIVERY,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.sinks.elasticsearch.internal.buffer_len",
-                            sink::elasticsearch::ELASTIC_INTERNAL_BUFFER_LEN,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.sinks.elasticsearch.records.total_delivered",
-                            sink::elasticsearch::ELASTIC_RECORDS_TOTAL_DELIVERED,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.sinks.elasticsearch.records.total_failed",
-                            sink::elasticsearch::ELASTIC_RECORDS_TOTAL_FAILED,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.sinks.elasticsearch.error.unknown",
-                            sink::elasticsearch::ELASTIC_ERROR_UNKNOWN,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.sinks.elasticsearch.error.bulk_action.index",
-                            sink::elasticsearch::ELASTIC_BULK_ACTION_INDEX_ERR,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.sinks.elasticsearch.error.bulk_action.create",
-                            sink::elasticsearch::ELASTIC_BULK_ACTION_CREATE_ERR,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.sinks.elasticsearch.error.bulk_action.update",
-                            sink::elasticsearch::ELASTIC_BULK_ACTION_UPDATE_ERR,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.sinks.elasticsearch.error.bulk_action.delete",
-                            sink::elasticsearch::ELASTIC_BULK_ACTION_DELETE_ERR,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.sinks.elasticsearch.error.api.index_not_found",
-                            sink::elasticsearch::ELASTIC_ERROR_API_INDEX_NOT_FOUND,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.sinks.elasticsearch.error.api.parsing",
-                            sink::elasticsearch::ELASTIC_ERROR_API_PARSING,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.sinks.elasticsearch.error.api.mapper_parsing",
-                            sink::elasticsearch::ELASTIC_ERROR_API_MAPPER_PARSING,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.sinks.elasticsearch.error.api.action_request_validation",
-                            sink::elasticsearch::ELASTIC_ERROR_API_ACTION_REQUEST_VALIDATION,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.sinks.elasticsearch.error.api.action_document_missing",
-                            sink::elasticsearch::ELASTIC_ERROR_API_DOCUMENT_MISSING,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.sinks.elasticsearch.error.api.index_already_exists",
-                            sink::elasticsearch::ELASTIC_ERROR_API_INDEX_ALREADY_EXISTS,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.sinks.elasticsearch.error.api.unknown",
-                            sink::elasticsearch::ELASTIC_ERROR_API_UNKNOWN,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.sinks.elasticsearch.error.client",
-                            sink::elasticsearch::ELASTIC_ERROR_CLIENT,
-                            chans
-                        );
-                        // sink::wavefront
-                        atom_telem!(
-                            "cernan.sinks.wavefront.proxy.connection_attempts",
-                            sink::wavefront::WAVEFRONT_CONNECT_ATTEMPTS,
-                            chans
-                        );
-                        atom_set_telem!(
-                            "cernan.sinks.wavefront.aggregations.total_stored",
-                            sink::wavefront::WAVEFRONT_AGGR_STORED_VALUES,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.sinks.wavefront.aggregation.histogram",
-                            sink::wavefront::WAVEFRONT_AGGR_HISTO,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.sinks.wavefront.aggregation.sum",
-                            sink::wavefront::WAVEFRONT_AGGR_SUM,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.sinks.wavefront.aggregation.set",
-                            sink::wavefront::WAVEFRONT_AGGR_SET,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.sinks.wavefront.aggregation.summarize",
-                            sink::wavefront::WAVEFRONT_AGGR_SUMMARIZE,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.sinks.wavefront.aggregation.summarize.total_percentiles",
-                            sink::wavefront::WAVEFRONT_AGGR_TOT_PERCENT,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.sinks.wavefront.delivery.success",
-                            sink::wavefront::WAVEFRONT_DELIVERY_SUCCESS,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.sinks.wavefront.delivery.failure",
-                            sink::wavefront::WAVEFRONT_DELIVERY_FAILURE,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.sinks.wavefront.valve.closed",
-                            sink::wavefront::WAVEFRONT_VALVE_CLOSED,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.sinks.wavefront.valve.open",
-                            sink::wavefront::WAVEFRONT_VALVE_OPEN,
-                            chans
-                        );
-                        // sink::prometheus
-                        atom_telem!(
-                            "cernan.sinks.prometheus.aggregation.inside_baseball.windowed.purged",
-                            sink::prometheus::PROMETHEUS_AGGR_WINDOWED_PURGED,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.sinks.prometheus.exposition.inside_baseball.delay.sum",
-                            sink::prometheus::PROMETHEUS_RESPONSE_DELAY_SUM,
-                            chans
-                        );
-                        atom_set_telem!(
-                            "cernan.sinks.prometheus.aggregation.reportable",
-                            sink::prometheus::PROMETHEUS_AGGR_REPORTABLE,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.sinks.prometheus.report.success",
-                            sink::prometheus::PROMETHEUS_REPORT_SUCCESS,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.sinks.prometheus.report.error",
-                            sink::prometheus::PROMETHEUS_REPORT_ERROR,
-                            chans
-                        );
-                        // sink::influxdb
-                        atom_telem!(
-                            "cernan.sinks.influxdb.delivery_attempts",
-                            sink::influxdb::INFLUX_DELIVERY_ATTEMPTS,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.sinks.influxdb.success",
-                            sink::influxdb::INFLUX_SUCCESS,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.sinks.influxdb.failure.client_error",
-                            sink::influxdb::INFLUX_FAILURE_CLIENT,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.sinks.influxdb.failure.server_error",
-                            sink::influxdb::INFLUX_FAILURE_SERVER,
-                            chans
-                        );
-                        // sink::kafka
-                        atom_telem!(
-                            "cernan.sinks.kafka.publish.success",
-                            sink::kafka::KAFKA_PUBLISH_SUCCESS_SUM,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.sinks.kafka.publish.retry",
-                            sink::kafka::KAFKA_PUBLISH_RETRY_SUM,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.sinks.kafka.publish.failure",
-                            sink::kafka::KAFKA_PUBLISH_FAILURE_SUM,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.sinks.kafka.publish.retry.failure",
-                            sink::kafka::KAFKA_PUBLISH_RETRY_FAILURE_SUM,
-                            chans
-                        );
-                        // filter::delay_filter
-                        atom_telem!(
-                            "cernan.filters.delay.telemetry.accept",
-                            filter::delay_filter::DELAY_TELEM_ACCEPT,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.filters.delay.telemetry.reject",
-                            filter::delay_filter::DELAY_TELEM_REJECT,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.filters.delay.log.reject",
-                            filter::delay_filter::DELAY_LOG_REJECT,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.filters.delay.log.accept",
-                            filter::delay_filter::DELAY_LOG_ACCEPT,
-                            chans
-                        );
-                        // filter::json_encode_filter
-                        atom_telem!(
-                            "cernan.filters.json_encode.log.processed",
-                            filter::json_encode_filter::JSON_ENCODE_LOG_PROCESSED,
-                            chans
-                        );
-                        atom_telem!(
-                            "cernan.filters.json_encode.log.parsed",
-                            filter::json_encode_filter::JSON_ENCODE_LOG_PARSED,
-                            chans
-                        );
+                        let now = time::now();
+                        for (idx, &(name, atom, kind)) in COUNTERS.iter().enumerate() {
+                            let mut value = atom.load(Ordering::Relaxed);
+                            if kind == AggregationMethod::Sum {
+                                let total = value;
+                                value = increase(self.last[idx], total);
+                                self.last[idx] = total;
+                            }
+                            if value != 0 {
+                                let telem = Telemetry::new()
+                                    .name(name)
+                                    .value(value as f64)
+                                    .timestamp(now)
+                                    .kind(kind)
+                                    .harden()
+                                    .unwrap();
+                                util::send(
+                                    &mut chans,
+                                    metric::Event::new_telemetry(telem),
+                                );
+                            }
+                        }
+                        // Counters of nodes no longer running are forgotten.
+                        let mut last_dropped = util::HashMap::default();
+                        for (from, to, atom) in util::dropped_counters() {
+                            let total = atom.load(Ordering::Relaxed);
+                            let key = (from, to);
+                            let last = self.last_dropped.get(&key).cloned();
+                            let value = increase(last.unwrap_or(0), total);
+                            last_dropped.insert(key.clone(), total);
+                            let (from, to) = key;
+                            if value != 0 {
+                                let telem = Telemetry::new()
+                                    .name("cernan.util.hopper.dropped")
+                                    .value(value as f64)
+                                    .timestamp(now)
+                                    .kind(AggregationMethod::Sum)
+                                    .harden()
+                                    .unwrap()
+                                    .overlay_tag("source", from.as_str())
+                                    .overlay_tag("destination", to.as_str());
+                                util::send(
+                                    &mut chans,
+                                    metric::Event::new_telemetry(telem),
+                                );
+                            }
+                        }
+                        self.last_dropped = last_dropped;
                         while let Some(telem) = Q.pop() {
                             if !chans.is_empty() {
                                 util::send(
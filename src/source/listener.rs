@@ -0,0 +1,530 @@
+//! Receive loop shared by sources which take both datagrams and streams.
+//!
+//! A `Listener` binds a single `ListenerTransport`: UDP or unix datagrams, or
+//! TCP or unix streams. Datagrams are read on the source's own thread, and on
+//! those of any additional UDP workers. Each stream is served on a thread of
+//! its own. What becomes of the bytes received is left to a
+//! `ListenerHandler`.
+use crate::constants;
+use crate::metric;
+use crate::source::datagram::{self, RecvBatch};
+use crate::source::{Source, TCPConfig, TCPStreamHandler, TCP};
+use crate::thread;
+use crate::thread::Stoppable;
+use crate::util;
+use crate::util::send;
+use mio;
+use mio::unix::EventedFd;
+use std::fs;
+use std::io::prelude::*;
+use std::io::ErrorKind;
+use std::mem;
+use std::net::ToSocketAddrs;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::os::unix::net::{UnixDatagram, UnixListener, UnixStream};
+use std::path::PathBuf;
+
+/// The transports over which a `Listener` may receive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ListenerTransport {
+    /// UDP datagrams on `host` and `port`.
+    Udp,
+    /// Streams over TCP on `host` and `port`.
+    Tcp,
+    /// Unix datagrams on the socket at `path`.
+    UnixDatagram,
+    /// Streams over the unix socket at `path`.
+    UnixStream,
+}
+
+/// Configuration for a `Listener`.
+#[derive(Debug, Clone)]
+pub struct ListenerConfig {
+    /// The transport the listener will receive over.
+    pub transport: ListenerTransport,
+    /// The host to bind to. Used by the network transports.
+    pub host: String,
+    /// The port to listen on. Used by the network transports.
+    pub port: u16,
+    /// The filesystem path of the socket to bind. Used by the unix
+    /// transports. Any existing file at this path will be removed.
+    pub path: Option<PathBuf>,
+    /// The number of threads receiving on the udp transport. When greater
+    /// than one each thread binds its own `SO_REUSEPORT` socket to `host` and
+    /// `port`.
+    pub workers: usize,
+    /// The forwards that the source will send its events on to.
+    pub forwards: Vec<String>,
+    /// The unique name for the source in the routing topology.
+    pub config_path: Option<String>,
+}
+
+impl From<ListenerConfig> for TCPConfig {
+    fn from(item: ListenerConfig) -> Self {
+        TCPConfig {
+            host: item.host,
+            port: item.port,
+            forwards: item.forwards,
+            config_path: item.config_path,
+        }
+    }
+}
+
+/// Handler for the datagrams and streams received by a `Listener`.
+///
+/// Every stream is served by a clone of the handler, so any state a handler
+/// keeps between reads, such as a partial frame, is the stream's own.
+pub trait ListenerHandler: 'static + Default + Clone + Sync + Send {
+    /// The largest datagram the handler will read. Longer datagrams are
+    /// truncated.
+    const MAX_DATAGRAM: usize;
+
+    /// Handle a single datagram.
+    fn handle_datagram(&mut self, chans: &mut util::Channel, datagram: &[u8]);
+
+    /// Handle the next bytes read off a stream. The stream is closed if this
+    /// returns false.
+    fn handle_bytes(&mut self, chans: &mut util::Channel, bytes: &[u8]) -> bool;
+
+    /// Handle the close of a stream by its client. Whatever the handler has
+    /// left buffered is an unterminated final message.
+    fn handle_close(&mut self, chans: &mut util::Channel);
+}
+
+/// The partial line of a newline delimited stream.
+///
+/// Each byte is searched for a newline only once, however many reads a line
+/// spans.
+#[derive(Default, Debug, Clone)]
+pub struct LineBuffer {
+    buf: Vec<u8>,
+    // The leading bytes of `buf` already searched for a newline.
+    scanned: usize,
+}
+
+impl LineBuffer {
+    /// Append `bytes`, calling `handle` on every line they complete, newline
+    /// included. Returns false if the line left incomplete is longer than
+    /// `max`.
+    pub fn push<F>(&mut self, bytes: &[u8], max: usize, mut handle: F) -> bool
+    where
+        F: FnMut(&[u8]),
+    {
+        self.buf.extend_from_slice(bytes);
+        let mut consumed = 0;
+        while let Some(len) = self.buf[self.scanned..].iter().position(|b| *b == b'\n')
+        {
+            let end = self.scanned + len + 1;
+            handle(&self.buf[consumed..end]);
+            consumed = end;
+            self.scanned = end;
+        }
+        self.buf.drain(..consumed);
+        self.scanned = self.buf.len();
+        self.buf.len() <= max
+    }
+
+    /// Take the line left incomplete, if any.
+    pub fn take(&mut self) -> Option<Vec<u8>> {
+        self.scanned = 0;
+        if self.buf.is_empty() {
+            None
+        } else {
+            Some(mem::replace(&mut self.buf, Vec::new()))
+        }
+    }
+}
+
+/// Adapts a `ListenerHandler` to serve the streams of a `TCP` source.
+#[derive(Default, Debug, Clone)]
+pub struct ListenerStreams<H>(H);
+
+impl<H> TCPStreamHandler for ListenerStreams<H>
+where
+    H: ListenerHandler,
+{
+    fn handle_stream(
+        &mut self,
+        chans: util::Channel,
+        poller: &mio::Poll,
+        stream: mio::net::TcpStream,
+    ) {
+        handle_stream(self.0.clone(), chans, poller, stream);
+    }
+}
+
+/// Read off of a non-blocking stream until the stream closes or the SYSTEM
+/// token signals shutdown.
+///
+/// The stream must already be registered with `poller`.
+fn handle_stream<H: ListenerHandler, R: Read>(
+    mut handler: H,
+    mut chans: util::Channel,
+    poller: &mio::Poll,
+    mut stream: R,
+) {
+    let mut read_buf = vec![0; 8192];
+    loop {
+        let mut events = mio::Events::with_capacity(1024);
+        match poller.poll(&mut events, None) {
+            Err(e) => panic!("Failed during poll {:?}", e),
+            Ok(_num_events) => {
+                for event in events {
+                    match event.token() {
+                        constants::SYSTEM => return,
+                        _stream_token => loop {
+                            match stream.read(&mut read_buf) {
+                                Ok(0) => {
+                                    handler.handle_close(&mut chans);
+                                    trace!("stream closed.");
+                                    return;
+                                }
+                                Ok(len) => {
+                                    if !handler
+                                        .handle_bytes(&mut chans, &read_buf[..len])
+                                    {
+                                        return;
+                                    }
+                                }
+                                Err(ref e) if e.kind() == ErrorKind::WouldBlock => {
+                                    break;
+                                }
+                                Err(e) => {
+                                    error!("Could not read stream: {:?}", e);
+                                    return;
+                                }
+                            }
+                        },
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Read every datagram ready on the non-blocking socket `fd`.
+fn handle_datagrams<H: ListenerHandler>(
+    handler: &mut H,
+    chans: &mut util::Channel,
+    fd: RawFd,
+    batch: &mut RecvBatch,
+) -> Result<(), ()> {
+    loop {
+        match batch.recv(fd) {
+            Ok(received) => {
+                for idx in 0..received {
+                    handler.handle_datagram(chans, batch.get(idx));
+                }
+            }
+            Err(e) => match e.kind() {
+                ErrorKind::WouldBlock => return Ok(()),
+                _ => {
+                    error!("Could not read socket with error {:?}", e);
+                    return Err(());
+                }
+            },
+        }
+    }
+}
+
+fn register_udp(poller: &mio::Poll, conns: &util::TokenSlab<mio::net::UdpSocket>) {
+    for (idx, socket) in conns.iter() {
+        if let Err(e) = poller.register(
+            socket,
+            mio::Token::from(idx),
+            mio::Ready::readable(),
+            mio::PollOpt::edge(),
+        ) {
+            error!("Failed to register {:?} - {:?}!", socket, e);
+        }
+    }
+}
+
+fn handle_udp<H: ListenerHandler>(
+    handler: &mut H,
+    chans: &mut util::Channel,
+    conns: &util::TokenSlab<mio::net::UdpSocket>,
+    token: mio::Token,
+    batch: &mut RecvBatch,
+) {
+    let socket = &conns[token];
+    if let Err(_e) = handle_datagrams(handler, chans, socket.as_raw_fd(), batch) {
+        error!("Deregistering {:?} due to unrecoverable error!", *socket);
+    }
+}
+
+/// Service the UDP sockets of an additional worker until the SYSTEM token
+/// signals shutdown.
+fn run_worker<H: ListenerHandler>(
+    mut handler: H,
+    mut chans: util::Channel,
+    poller: &mio::Poll,
+    conns: &util::TokenSlab<mio::net::UdpSocket>,
+) {
+    register_udp(poller, conns);
+    let mut batch = RecvBatch::new(H::MAX_DATAGRAM);
+    loop {
+        let mut events = mio::Events::with_capacity(1024);
+        match poller.poll(&mut events, None) {
+            Ok(_num_events) => {
+                for event in events {
+                    match event.token() {
+                        constants::SYSTEM => return,
+                        token => handle_udp(
+                            &mut handler,
+                            &mut chans,
+                            conns,
+                            token,
+                            &mut batch,
+                        ),
+                    }
+                }
+            }
+            Err(e) => panic!("Failed during poll {:?}", e),
+        }
+    }
+}
+
+fn bind_unix_path(config: &ListenerConfig) -> PathBuf {
+    let path = config
+        .path
+        .clone()
+        .expect("unix transports must have a path");
+    // A socket left over from a previous run would make bind fail.
+    let _ = fs::remove_file(&path);
+    path
+}
+
+/// The sockets of a source bound to a single `ListenerTransport`.
+///
+/// The UDP transport may be split across several workers, each with its own
+/// thread and its own `SO_REUSEPORT` sockets bound to the same address. The
+/// first worker runs on the source's thread.
+pub struct Listener<H> {
+    conns: util::TokenSlab<mio::net::UdpSocket>,
+    workers: Vec<util::TokenSlab<mio::net::UdpSocket>>,
+    unix_datagram: Option<UnixDatagram>,
+    unix_listener: Option<UnixListener>,
+    tcp: Option<TCP<ListenerStreams<H>>>,
+    handler: H,
+}
+
+impl<H> Listener<H>
+where
+    H: ListenerHandler,
+{
+    /// Bind the sockets of `config`, whose datagrams and streams are each
+    /// handled by a clone of `handler`.
+    pub fn bind(config: ListenerConfig, handler: H) -> Self {
+        let mut conns = util::TokenSlab::<mio::net::UdpSocket>::new();
+        let mut workers = Vec::new();
+        let mut unix_datagram = None;
+        let mut unix_listener = None;
+        let mut tcp = None;
+        match config.transport {
+            ListenerTransport::Udp => {
+                let addrs = (config.host.as_str(), config.port).to_socket_addrs();
+                match addrs {
+                    Ok(ips) => {
+                        let ips: Vec<_> = ips.collect();
+                        for worker in 0..config.workers {
+                            let mut slab = util::TokenSlab::new();
+                            for addr in &ips {
+                                let socket = if config.workers > 1 {
+                                    let socket = datagram::bind_reuseport(addr)
+                                        .expect("Unable to bind to UDP socket");
+                                    mio::net::UdpSocket::from_socket(socket)
+                                        .expect("Unable to bind to UDP socket")
+                                } else {
+                                    mio::net::UdpSocket::bind(addr)
+                                        .expect("Unable to bind to UDP socket")
+                                };
+                                slab.insert(socket);
+                            }
+                            if worker == 0 {
+                                conns = slab;
+                            } else {
+                                workers.push(slab);
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        info!(
+                            "Unable to perform DNS lookup on host {} with error {}",
+                            config.host, e
+                        );
+                    }
+                };
+            }
+            ListenerTransport::Tcp => {
+                let streams = ListenerStreams(handler.clone());
+                tcp = Some(TCP::with_handler(config.into(), streams));
+            }
+            ListenerTransport::UnixDatagram => {
+                let path = bind_unix_path(&config);
+                let socket = UnixDatagram::bind(&path)
+                    .expect("Unable to bind to unix datagram socket");
+                socket
+                    .set_nonblocking(true)
+                    .expect("Unable to set unix datagram socket non-blocking");
+                unix_datagram = Some(socket);
+            }
+            ListenerTransport::UnixStream => {
+                let path = bind_unix_path(&config);
+                let listener = UnixListener::bind(&path)
+                    .expect("Unable to bind to unix stream socket");
+                listener
+                    .set_nonblocking(true)
+                    .expect("Unable to set unix stream socket non-blocking");
+                unix_listener = Some(listener);
+            }
+        }
+
+        Listener {
+            conns: conns,
+            workers: workers,
+            unix_datagram: unix_datagram,
+            unix_listener: unix_listener,
+            tcp: tcp,
+            handler: handler,
+        }
+    }
+
+    fn accept_streams(
+        &self,
+        chans: &util::Channel,
+        listener: &UnixListener,
+        handlers: &mut thread::ThreadPool,
+    ) {
+        // Reap any stream handlers whose clients have gone away.
+        let _ = handlers.join_ready();
+        loop {
+            match listener.accept() {
+                Ok((stream, _addr)) => {
+                    if let Err(e) = stream.set_nonblocking(true) {
+                        error!("Could not set unix stream non-blocking: {:?}", e);
+                        continue;
+                    }
+                    let rchans = chans.clone();
+                    let handler = self.handler.clone();
+                    handlers.spawn(move |poller| {
+                        poller
+                            .register(
+                                &EventedFd(&stream.as_raw_fd()),
+                                mio::Token(0),
+                                mio::Ready::readable(),
+                                mio::PollOpt::edge(),
+                            )
+                            .unwrap();
+                        handle_stream::<H, &UnixStream>(
+                            handler, rchans, &poller, &stream,
+                        );
+                    });
+                }
+                Err(e) => match e.kind() {
+                    ErrorKind::Interrupted | ErrorKind::ConnectionAborted => continue,
+                    ErrorKind::WouldBlock => return,
+                    _ => {
+                        error!("Could not accept unix stream: {:?}", e);
+                        return;
+                    }
+                },
+            }
+        }
+    }
+
+    /// Receive until the SYSTEM token signals shutdown.
+    pub fn run(mut self, mut chans: util::Channel, poller: mio::Poll) {
+        if let Some(tcp) = self.tcp.take() {
+            return Source::run(tcp, chans, poller);
+        }
+
+        let mut worker_handles = Vec::new();
+        for conns in self.workers.drain(..) {
+            let wchans = chans.clone();
+            let handler = self.handler.clone();
+            worker_handles.push(thread::spawn(move |poller| {
+                run_worker(handler, wchans, &poller, &conns)
+            }));
+        }
+
+        register_udp(&poller, &self.conns);
+        // The unix sockets take the tokens after those of the UDP sockets.
+        let unix_token = mio::Token::from(self.conns.count());
+        if let Some(ref socket) = self.unix_datagram {
+            if let Err(e) = poller.register(
+                &EventedFd(&socket.as_raw_fd()),
+                unix_token,
+                mio::Ready::readable(),
+                mio::PollOpt::edge(),
+            ) {
+                error!("Failed to register {:?} - {:?}!", socket, e);
+            }
+        }
+        if let Some(ref listener) = self.unix_listener {
+            if let Err(e) = poller.register(
+                &EventedFd(&listener.as_raw_fd()),
+                unix_token,
+                mio::Ready::readable(),
+                mio::PollOpt::edge(),
+            ) {
+                error!("Failed to register {:?} - {:?}!", listener, e);
+            }
+        }
+        let mut handlers = thread::ThreadPool::new(None);
+
+        let mut handler = self.handler.clone();
+        let mut batch = RecvBatch::new(H::MAX_DATAGRAM);
+        loop {
+            let mut events = mio::Events::with_capacity(1024);
+            match poller.poll(&mut events, None) {
+                Ok(_num_events) => {
+                    for event in events {
+                        match event.token() {
+                            constants::SYSTEM => {
+                                handlers.shutdown();
+                                for handle in worker_handles.drain(..) {
+                                    handle.shutdown();
+                                }
+                                send(&mut chans, metric::Event::Shutdown);
+                                return;
+                            }
+
+                            token if token == unix_token => {
+                                if let Some(ref socket) = self.unix_datagram {
+                                    if let Err(_e) = handle_datagrams(
+                                        &mut handler,
+                                        &mut chans,
+                                        socket.as_raw_fd(),
+                                        &mut batch,
+                                    ) {
+                                        error!(
+                                            "Deregistering {:?} due to unrecoverable error!",
+                                            *socket
+                                        );
+                                    }
+                                }
+                                if let Some(ref listener) = self.unix_listener {
+                                    self.accept_streams(
+                                        &chans,
+                                        listener,
+                                        &mut handlers,
+                                    );
+                                }
+                            }
+
+                            token => handle_udp(
+                                &mut handler,
+                                &mut chans,
+                                &self.conns,
+                                token,
+                                &mut batch,
+                            ),
+                        }
+                    }
+                }
+                Err(e) => panic!("Failed during poll {:?}", e),
+            }
+        } // loop
+    } // run
+}
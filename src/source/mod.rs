@@ -9,23 +9,43 @@ use mio;
 use std::marker::PhantomData;
 
 mod avro;
+mod datagram;
 mod file;
 mod flush;
 mod graphite;
+mod influxdb;
 mod internal;
+mod listener;
 mod native;
 mod nonblocking;
+mod prometheus;
+mod prometheus_remote_write;
 mod statsd;
+mod syslog;
 mod tcp;
 
 pub use self::avro::Avro;
 pub use self::file::{FileServer, FileServerConfig};
 pub use self::flush::{flushes_per_second, FlushTimer, FlushTimerConfig};
-pub use self::graphite::{Graphite, GraphiteConfig};
-pub use self::internal::{report_full_telemetry, Internal, InternalConfig};
+pub use self::graphite::{Graphite, GraphiteConfig, GraphitePickle, GraphiteProtocol};
+pub use self::influxdb::{
+    InfluxDBHandler, InfluxDBServer, InfluxDBServerConfig, InfluxDBTransport,
+};
+pub use self::internal::{report_full_telemetry, Internal, InternalConfig, COUNTERS};
+pub use self::listener::{
+    LineBuffer, Listener, ListenerConfig, ListenerHandler, ListenerStreams,
+    ListenerTransport,
+};
 pub use self::native::{NativeServer, NativeServerConfig};
 use self::nonblocking::{BufferedPayload, PayloadErr};
-pub use self::statsd::{Statsd, StatsdConfig, StatsdParseConfig};
+pub use self::prometheus::{PrometheusScrape, PrometheusScrapeConfig};
+pub use self::prometheus_remote_write::{
+    PrometheusRemoteWriteServer, PrometheusRemoteWriteServerConfig,
+};
+pub use self::statsd::{
+    Statsd, StatsdConfig, StatsdHandler, StatsdParseConfig, StatsdTransport,
+};
+pub use self::syslog::{Syslog, SyslogConfig, SyslogHandler, SyslogTransport};
 pub use self::tcp::{TCPConfig, TCPStreamHandler, TCP};
 
 /// Generic interface used to capture global source configuration
@@ -2,6 +2,7 @@ use crate::constants;
 use crate::metric;
 use crate::protocols::native::{AggregationMethod, Payload};
 use crate::source::{BufferedPayload, PayloadErr, TCPConfig, TCPStreamHandler, TCP};
+use crate::time;
 use crate::util;
 use mio;
 use protobuf;
@@ -142,17 +143,59 @@ impl NativeStreamHandler {
             Ok(mut pyld) => {
                 for mut point in pyld.take_points().into_iter() {
                     let name: String = point.take_name();
-                    let smpls: Vec<f64> = point.take_samples();
+                    let mut smpls: Vec<f64> = point.take_samples();
                     let aggr_type: AggregationMethod = point.get_method();
                     let mut meta = point.take_metadata();
-                    // FIXME #166
-                    let ts: i64 = (point.get_timestamp_ms() as f64 * 0.001) as i64;
+                    let ts: i64 = point
+                        .get_timestamp_ms()
+                        .saturating_mul(time::NANOS_PER_MILLI);
 
-                    if smpls.is_empty() {
-                        continue;
-                    }
                     let mut metric = metric::Telemetry::new().name(name);
-                    metric = metric.value(smpls[0]);
+                    if aggr_type == AggregationMethod::DISTINCT {
+                        // The samples of a DISTINCT hold only its estimate. The
+                        // sketch itself travels in the registers.
+                        let registers = point.take_distinct_registers();
+                        match metric::HyperLogLog::from_registers(registers) {
+                            Some(hll) => metric = metric.hyperloglog(hll),
+                            None => continue,
+                        }
+                        smpls.clear();
+                    } else if aggr_type == AggregationMethod::SKETCH
+                        && point.has_sketch()
+                    {
+                        // Likewise the buckets of a SKETCH travel whole, so
+                        // that its quantiles keep their relative accuracy no
+                        // matter how many hops it is merged over.
+                        let mut sk = point.take_sketch();
+                        let positive = sk
+                            .take_positive_indexes()
+                            .into_iter()
+                            .zip(sk.take_positive_counts().into_iter())
+                            .collect();
+                        let negative = sk
+                            .take_negative_indexes()
+                            .into_iter()
+                            .zip(sk.take_negative_counts().into_iter())
+                            .collect();
+                        match metric::DDSketch::from_buckets(
+                            sk.get_relative_accuracy(),
+                            positive,
+                            negative,
+                            sk.get_zero_count(),
+                            sk.get_sum(),
+                            sk.get_min(),
+                            sk.get_max(),
+                        ) {
+                            Some(dds) => metric = metric.sketch(dds),
+                            None => continue,
+                        }
+                        smpls.clear();
+                    } else {
+                        if smpls.is_empty() {
+                            continue;
+                        }
+                        metric = metric.value(smpls[0]);
+                    }
                     metric = match aggr_type {
                         AggregationMethod::SET => {
                             metric.kind(metric::AggregationMethod::Set)
@@ -166,14 +209,18 @@ impl NativeStreamHandler {
                         AggregationMethod::BIN => {
                             metric.kind(metric::AggregationMethod::Histogram)
                         }
+                        AggregationMethod::DISTINCT => metric,
+                        AggregationMethod::SKETCH => {
+                            metric.kind(metric::AggregationMethod::Sketch)
+                        }
                     };
                     metric = metric.persist(point.get_persisted());
-                    metric = metric.timestamp(ts);
+                    metric = metric.timestamp_ns(ts);
                     let mut metric = metric.harden().unwrap(); // todo don't unwrap
                     for (key, value) in meta.drain() {
                         metric = metric.overlay_tag(key, value);
                     }
-                    for smpl in &smpls[1..] {
+                    for smpl in smpls.iter().skip(1) {
                         metric = metric.insert(*smpl);
                     }
                     util::send(&mut chans, metric::Event::new_telemetry(metric));
@@ -182,11 +229,12 @@ impl NativeStreamHandler {
                     let path: String = line.take_path();
                     let value: String = line.take_value();
                     let mut meta = line.take_metadata();
-                    // FIXME #166
-                    let ts: i64 = (line.get_timestamp_ms() as f64 * 0.001) as i64;
+                    let ts: i64 = line
+                        .get_timestamp_ms()
+                        .saturating_mul(time::NANOS_PER_MILLI);
 
                     let mut logline = metric::LogLine::new(path, value);
-                    logline = logline.time(ts);
+                    logline = logline.time_ns(ts);
                     for (key, value) in meta.drain() {
                         logline = logline.overlay_tag(key, value);
                     }
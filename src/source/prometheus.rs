@@ -0,0 +1,623 @@
+use crate::constants;
+use crate::metric;
+use crate::metric::{AggregationMethod, Telemetry};
+use crate::protocols::prometheus_text::{
+    parse_text, MetricFamily, MetricKind, Sample,
+};
+use crate::source;
+use crate::time;
+use crate::util;
+use crate::util::send;
+use mio;
+use quantiles::histogram::Histogram;
+use reqwest;
+use std::collections::HashMap;
+use std::io::Read;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use url::Url;
+
+pub static PROMETHEUS_SCRAPE_SUCCESS: AtomicUsize = AtomicUsize::new(0);
+pub static PROMETHEUS_SCRAPE_FAILURE: AtomicUsize = AtomicUsize::new(0);
+pub static PROMETHEUS_SCRAPE_TELEM: AtomicUsize = AtomicUsize::new(0);
+
+/// The Prometheus scrape source
+///
+/// Periodically scrapes a list of targets serving the Prometheus text
+/// exposition format, converting what it finds into `Telemetry`:
+///
+/// * gauges and untyped metrics become `AggregationMethod::Set`,
+/// * counters become `AggregationMethod::Sum` of their increase since the
+///   previous scrape,
+/// * the quantiles of summaries become `AggregationMethod::Set`, tagged with
+///   their `quantile`, and their `_sum` and `_count` are taken as counters,
+/// * the buckets of histograms become `AggregationMethod::Histogram` over
+///   their bounds, holding the observations made since the previous scrape,
+///   and their `_sum` and `_count` are taken as counters.
+///
+/// Counters, histograms and the sums and counts of summaries are cumulative
+/// in Prometheus, so nothing is emitted for them until a target has been
+/// scraped twice. A decrease is taken to be a reset of the target.
+/// Prometheus histograms do not record where in a bucket an observation
+/// fell, so each is inserted at its bucket's upper bound, and sums of the
+/// resulting `Telemetry` are approximate.
+///
+/// Every `Telemetry` is tagged with the `instance`, the host and port of the
+/// target it was scraped from, unless the target set that label itself.
+pub struct PrometheusScrape {
+    client: reqwest::Client,
+    targets: Vec<(Url, String)>,
+    scrape_interval: Duration,
+    previous: HashMap<String, HashMap<String, f64>>,
+}
+
+/// Configuration for the Prometheus scrape source.
+#[derive(Debug, Clone)]
+pub struct PrometheusScrapeConfig {
+    /// The URLs to scrape, for instance `http://localhost:9100/metrics`.
+    pub targets: Vec<String>,
+    /// The number of seconds between scrapes of the targets.
+    pub scrape_interval: u64,
+    /// The number of seconds to wait on a target before abandoning a scrape.
+    pub scrape_timeout: u64,
+    /// The forwards that the source will send its telemetry on to.
+    pub forwards: Vec<String>,
+    /// The unique name for the source in the routing topology.
+    pub config_path: Option<String>,
+}
+
+impl Default for PrometheusScrapeConfig {
+    fn default() -> PrometheusScrapeConfig {
+        PrometheusScrapeConfig {
+            targets: Vec::new(),
+            scrape_interval: 60,
+            scrape_timeout: 10,
+            forwards: Vec::new(),
+            config_path: None,
+        }
+    }
+}
+
+/// Build the key identifying a series, ignoring the label `skip`.
+fn series_key(name: &str, labels: &[(String, String)], skip: &str) -> String {
+    let mut labels: Vec<&(String, String)> =
+        labels.iter().filter(|&&(ref k, _)| k != skip).collect();
+    labels.sort();
+    let mut key = name.to_string();
+    for &&(ref k, ref v) in &labels {
+        key.push_str(&format!("\u{0}{}={}", k, v));
+    }
+    key
+}
+
+/// Compute the increase of a cumulative value, recording it in `current`.
+/// Returns None if the series has not been seen before.
+fn increase(
+    key: String,
+    value: f64,
+    previous: &HashMap<String, f64>,
+    current: &mut HashMap<String, f64>,
+) -> Option<f64> {
+    let res = previous.get(&key).map(
+        |prev| {
+            if value < *prev {
+                value
+            } else {
+                value - prev
+            }
+        },
+    );
+    current.insert(key, value);
+    res
+}
+
+/// Set the timestamp and tags of `telem` from those of `sample`.
+fn finish(
+    mut telem: Telemetry,
+    sample: &Sample,
+    skip: &str,
+    instance: &str,
+) -> Telemetry {
+    if let Some(ms) = sample.timestamp_ms {
+        let (secs, subsec_nanos) = time::from_millis(ms);
+        telem.timestamp = secs;
+        telem.subsec_nanos = subsec_nanos;
+    }
+    let mut has_instance = false;
+    for &(ref k, ref v) in &sample.labels {
+        if k != skip {
+            has_instance |= k == "instance";
+            telem = telem.overlay_tag(k.as_str(), v.as_str());
+        }
+    }
+    if !has_instance {
+        telem = telem.overlay_tag("instance", instance);
+    }
+    telem
+}
+
+/// Group the samples of a family by their labels, ignoring `skip`.
+fn group<'a>(family: &'a MetricFamily, skip: &str) -> Vec<Vec<&'a Sample>> {
+    let mut keys: Vec<String> = Vec::new();
+    let mut groups: Vec<Vec<&Sample>> = Vec::new();
+    for sample in &family.samples {
+        let key = series_key(&family.name, &sample.labels, skip);
+        match keys.iter().position(|k| *k == key) {
+            Some(idx) => groups[idx].push(sample),
+            None => {
+                keys.push(key);
+                groups.push(vec![sample]);
+            }
+        }
+    }
+    groups
+}
+
+fn label<'a>(sample: &'a Sample, name: &str) -> Option<&'a str> {
+    sample
+        .labels
+        .iter()
+        .find(|&&(ref k, _)| k == name)
+        .map(|&(_, ref v)| v.as_str())
+}
+
+/// Convert a summary into a gauge per reported quantile, tagged with the
+/// `quantile`, and its `_sum` and `_count` into counters. Quantiles computed
+/// by the target cannot be merged, so they are not fed into a summary of our
+/// own.
+fn convert_summary(
+    family: &MetricFamily,
+    instance: &str,
+    previous: &HashMap<String, f64>,
+    current: &mut HashMap<String, f64>,
+    res: &mut Vec<Telemetry>,
+) {
+    for sample in &family.samples {
+        if sample.name == family.name {
+            if sample.value.is_nan() {
+                continue;
+            }
+            let telem = Telemetry::new()
+                .name(sample.name.as_str())
+                .value(sample.value)
+                .kind(AggregationMethod::Set)
+                .harden()
+                .unwrap();
+            res.push(finish(telem, sample, "", instance));
+        } else {
+            convert_counter(sample, instance, previous, current, res);
+        }
+    }
+}
+
+/// Convert a cumulative sample into the `AggregationMethod::Sum` of its
+/// increase since the previous scrape.
+fn convert_counter(
+    sample: &Sample,
+    instance: &str,
+    previous: &HashMap<String, f64>,
+    current: &mut HashMap<String, f64>,
+    res: &mut Vec<Telemetry>,
+) {
+    let key = series_key(&sample.name, &sample.labels, "");
+    if let Some(inc) = increase(key, sample.value, previous, current) {
+        let telem = Telemetry::new()
+            .name(sample.name.as_str())
+            .value(inc)
+            .kind(AggregationMethod::Sum)
+            .harden()
+            .unwrap();
+        res.push(finish(telem, sample, "", instance));
+    }
+}
+
+/// A histogram over `bounds` holding `count` observations of `point`.
+///
+/// The histogram is built by doubling, so that a bucket's count costs
+/// logarithmic rather than linear time.
+fn repeated(bounds: &[f64], point: f64, mut count: u64) -> Histogram<f64> {
+    let mut res = Histogram::new(bounds.to_vec()).unwrap();
+    let mut power = Histogram::new(bounds.to_vec()).unwrap();
+    power.insert(point);
+    while count > 0 {
+        if count & 1 == 1 {
+            res += power.clone();
+        }
+        count >>= 1;
+        if count > 0 {
+            let double = power.clone();
+            power += double;
+        }
+    }
+    res
+}
+
+fn convert_histogram(
+    family: &MetricFamily,
+    instance: &str,
+    previous: &HashMap<String, f64>,
+    current: &mut HashMap<String, f64>,
+    res: &mut Vec<Telemetry>,
+) {
+    for samples in group(family, "le") {
+        // (upper bound, increase of the cumulative count)
+        let mut buckets: Vec<(f64, f64)> = Vec::new();
+        let mut template = None;
+        let mut unseen = false;
+        for sample in samples {
+            if sample.name.ends_with("_sum") || sample.name.ends_with("_count") {
+                convert_counter(sample, instance, previous, current, res);
+                continue;
+            }
+            if !sample.name.ends_with("_bucket") {
+                continue;
+            }
+            let bound = match label(sample, "le").and_then(|le| {
+                if le == "+Inf" {
+                    Some(::std::f64::INFINITY)
+                } else {
+                    f64::from_str(le).ok()
+                }
+            }) {
+                Some(bound) if !bound.is_nan() => bound,
+                _ => continue,
+            };
+            let key = series_key(&sample.name, &sample.labels, "");
+            match increase(key, sample.value, previous, current) {
+                Some(inc) => buckets.push((bound, inc)),
+                None => unseen = true,
+            }
+            template = Some(sample);
+        }
+        let template = match template {
+            Some(t) if !unseen => t,
+            _ => continue,
+        };
+        buckets.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        let bounds: Vec<f64> = buckets
+            .iter()
+            .map(|&(b, _)| b)
+            .filter(|b| b.is_finite())
+            .collect();
+        if bounds.is_empty() {
+            continue;
+        }
+        let overflow = bounds[bounds.len() - 1] + 1.0;
+
+        // The bucket counts are cumulative, observations in each bin are the
+        // difference from the bin below.
+        let mut histo = Histogram::new(bounds.clone()).unwrap();
+        let mut below = 0.0;
+        for &(bound, inc) in &buckets {
+            let point = if bound.is_finite() { bound } else { overflow };
+            let in_bin = (inc - below).max(0.0) as u64;
+            if in_bin > 0 {
+                histo += repeated(&bounds, point, in_bin);
+            }
+            below = inc;
+        }
+        if histo.count() == 0 {
+            continue;
+        }
+
+        let telem = Telemetry::new()
+            .name(family.name.as_str())
+            .histogram(histo)
+            .harden()
+            .unwrap();
+        res.push(finish(telem, template, "le", instance));
+    }
+}
+
+/// Convert scraped metric families into `Telemetry`.
+///
+/// `previous` holds the cumulative values of the last scrape of the target,
+/// `current` is filled with those of this one.
+fn convert(
+    families: &[MetricFamily],
+    instance: &str,
+    previous: &HashMap<String, f64>,
+    current: &mut HashMap<String, f64>,
+    res: &mut Vec<Telemetry>,
+) {
+    for family in families {
+        match family.kind {
+            MetricKind::Gauge | MetricKind::Untyped => {
+                for sample in &family.samples {
+                    let telem = Telemetry::new()
+                        .name(sample.name.as_str())
+                        .value(sample.value)
+                        .kind(AggregationMethod::Set)
+                        .harden()
+                        .unwrap();
+                    res.push(finish(telem, sample, "", instance));
+                }
+            }
+            MetricKind::Counter => {
+                for sample in &family.samples {
+                    convert_counter(sample, instance, previous, current, res);
+                }
+            }
+            MetricKind::Summary => {
+                convert_summary(family, instance, previous, current, res)
+            }
+            MetricKind::Histogram => {
+                convert_histogram(family, instance, previous, current, res)
+            }
+        }
+    }
+}
+
+impl PrometheusScrape {
+    fn scrape(&mut self, chans: &mut util::Channel) {
+        let mut res = Vec::new();
+        for &(ref url, ref instance) in &self.targets {
+            let body = self
+                .client
+                .get(url.clone())
+                .send()
+                .map_err(|e| format!("{:?}", e))
+                .and_then(|mut resp| {
+                    if !resp.status().is_success() {
+                        return Err(format!("status {}", resp.status()));
+                    }
+                    let mut body = String::new();
+                    resp.read_to_string(&mut body)
+                        .map_err(|e| format!("{:?}", e))?;
+                    Ok(body)
+                })
+                .and_then(|body| parse_text(&body).map_err(|e| format!("{:?}", e)));
+            let families = match body {
+                Ok(families) => families,
+                Err(e) => {
+                    PROMETHEUS_SCRAPE_FAILURE.fetch_add(1, Ordering::Relaxed);
+                    error!("Failed to scrape {}: {}", url, e);
+                    continue;
+                }
+            };
+            PROMETHEUS_SCRAPE_SUCCESS.fetch_add(1, Ordering::Relaxed);
+
+            let mut current = HashMap::new();
+            {
+                let previous = self
+                    .previous
+                    .entry(url.to_string())
+                    .or_insert_with(HashMap::new);
+                convert(&families, instance, previous, &mut current, &mut res);
+            }
+            self.previous.insert(url.to_string(), current);
+        }
+        PROMETHEUS_SCRAPE_TELEM.fetch_add(res.len(), Ordering::Relaxed);
+        for telem in res {
+            send(chans, metric::Event::new_telemetry(telem));
+        }
+    }
+}
+
+impl source::Source<PrometheusScrapeConfig> for PrometheusScrape {
+    /// Create and spawn a new Prometheus scrape source
+    fn init(config: PrometheusScrapeConfig) -> Self {
+        let targets = config
+            .targets
+            .iter()
+            .map(|target| {
+                let url = Url::parse(target).expect("invalid prometheus target");
+                let instance = format!(
+                    "{}:{}",
+                    url.host_str().unwrap_or(""),
+                    url.port_or_known_default().unwrap_or(0)
+                );
+                (url, instance)
+            })
+            .collect();
+        let client = reqwest::Client::builder()
+            .gzip(true)
+            .timeout(Duration::from_secs(config.scrape_timeout))
+            .build()
+            .expect("could not create prometheus scrape client");
+        PrometheusScrape {
+            client,
+            targets,
+            scrape_interval: Duration::from_secs(config.scrape_interval),
+            previous: HashMap::new(),
+        }
+    }
+
+    fn run(mut self, mut chans: util::Channel, poller: mio::Poll) {
+        loop {
+            let started = Instant::now();
+            self.scrape(&mut chans);
+
+            // Sleep out the remainder of the interval, waking for shutdown.
+            loop {
+                let elapsed = started.elapsed();
+                if elapsed >= self.scrape_interval {
+                    break;
+                }
+                let mut events = mio::Events::with_capacity(1024);
+                match poller.poll(&mut events, Some(self.scrape_interval - elapsed)) {
+                    Ok(_num_events) => {
+                        for event in events {
+                            if event.token() == constants::SYSTEM {
+                                send(&mut chans, metric::Event::Shutdown);
+                                return;
+                            }
+                        }
+                    }
+                    Err(e) => panic!(format!("Failed during poll {:?}", e)),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::metric::TagMap;
+    use crate::protocols::prometheus_text::parse_text;
+
+    fn scrape(
+        payload: &str,
+        previous: &HashMap<String, f64>,
+        current: &mut HashMap<String, f64>,
+    ) -> Vec<Telemetry> {
+        let families = parse_text(payload).unwrap();
+        let mut res = Vec::new();
+        convert(&families, "app:9100", previous, current, &mut res);
+        res
+    }
+
+    #[test]
+    fn test_gauge_and_counter() {
+        let first = "# TYPE reqs counter\nreqs{code=\"200\"} 10\n# TYPE temp gauge\ntemp 21.5\n";
+        let second =
+            "# TYPE reqs counter\nreqs{code=\"200\"} 15\n# TYPE temp gauge\ntemp 22\n";
+        let third = "# TYPE reqs counter\nreqs{code=\"200\"} 3\n";
+
+        let mut state = HashMap::new();
+        let mut next = HashMap::new();
+        let mut res = scrape(first, &state, &mut next);
+        // The counter has no baseline yet.
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].name, "temp");
+        assert_eq!(res[0].kind(), AggregationMethod::Set);
+        assert_eq!(res[0].set(), Some(21.5));
+        assert_eq!(
+            res[0].get_from_tags("instance", &TagMap::default()),
+            Some(&"app:9100".to_string())
+        );
+
+        state = next;
+        next = HashMap::new();
+        let mut res = scrape(second, &state, &mut next);
+        assert_eq!(res.len(), 2);
+        assert_eq!(res[0].name, "reqs");
+        assert_eq!(res[0].kind(), AggregationMethod::Sum);
+        assert_eq!(res[0].sum(), Some(5.0));
+        assert_eq!(
+            res[0].get_from_tags("code", &TagMap::default()),
+            Some(&"200".to_string())
+        );
+
+        // A decrease is a reset of the target.
+        state = next;
+        next = HashMap::new();
+        let res = scrape(third, &state, &mut next);
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].sum(), Some(3.0));
+    }
+
+    #[test]
+    fn test_histogram() {
+        let first = "# TYPE lat histogram
+lat_bucket{le=\"0.1\"} 1
+lat_bucket{le=\"1\"} 2
+lat_bucket{le=\"+Inf\"} 2
+lat_sum 0.6
+lat_count 2
+";
+        let second = "# TYPE lat histogram
+lat_bucket{le=\"0.1\"} 3
+lat_bucket{le=\"1\"} 5
+lat_bucket{le=\"+Inf\"} 6
+lat_sum 7.0
+lat_count 6
+";
+        let mut state = HashMap::new();
+        let mut next = HashMap::new();
+        assert!(scrape(first, &state, &mut next).is_empty());
+
+        state = next;
+        next = HashMap::new();
+        let mut res = scrape(second, &state, &mut next);
+        assert_eq!(res.len(), 3);
+        let idx = res.iter().position(|t| t.name == "lat").unwrap();
+        let mut histo = res.remove(idx);
+        assert_eq!(histo.kind(), AggregationMethod::Histogram);
+        assert_eq!(histo.count(), 4);
+        let bins: Vec<usize> = histo.bins().unwrap().map(|&(_, c)| c).collect();
+        assert_eq!(bins, vec![2, 1, 1]);
+        assert_eq!(histo.get_from_tags("le", &TagMap::default()), None);
+
+        // The sum and count are counters of their increase.
+        res.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(res[0].name, "lat_count");
+        assert_eq!(res[0].kind(), AggregationMethod::Sum);
+        assert_eq!(res[0].sum(), Some(4.0));
+        assert_eq!(res[1].name, "lat_sum");
+        assert_eq!(res[1].kind(), AggregationMethod::Sum);
+        assert_eq!(res[1].sum(), Some(6.4));
+    }
+
+    #[test]
+    fn test_summary() {
+        let first = "# TYPE rpc summary
+rpc{quantile=\"0.5\",svc=\"a\"} 4
+rpc{quantile=\"0.99\",svc=\"a\"} 70
+rpc_sum{svc=\"a\"} 120
+rpc_count{svc=\"a\"} 12
+";
+        let second = "# TYPE rpc summary
+rpc{quantile=\"0.5\",svc=\"a\"} 5
+rpc{quantile=\"0.99\",svc=\"a\"} NaN
+rpc_sum{svc=\"a\"} 150
+rpc_count{svc=\"a\"} 15
+";
+        let mut state = HashMap::new();
+        let mut next = HashMap::new();
+        let mut res = scrape(first, &state, &mut next);
+        // The sum and count have no baseline yet.
+        assert_eq!(res.len(), 2);
+        for telem in res.iter_mut() {
+            assert_eq!(telem.name, "rpc");
+            assert_eq!(telem.kind(), AggregationMethod::Set);
+            assert_eq!(
+                telem.get_from_tags("svc", &TagMap::default()),
+                Some(&"a".to_string())
+            );
+        }
+        assert_eq!(res[0].set(), Some(4.0));
+        assert_eq!(
+            res[0].get_from_tags("quantile", &TagMap::default()),
+            Some(&"0.5".to_string())
+        );
+        assert_eq!(res[1].set(), Some(70.0));
+        assert_eq!(
+            res[1].get_from_tags("quantile", &TagMap::default()),
+            Some(&"0.99".to_string())
+        );
+
+        state = next;
+        next = HashMap::new();
+        let res = scrape(second, &state, &mut next);
+        assert_eq!(res.len(), 3);
+        assert_eq!(res[0].set(), Some(5.0));
+        assert_eq!(res[1].name, "rpc_sum");
+        assert_eq!(res[1].kind(), AggregationMethod::Sum);
+        assert_eq!(res[1].sum(), Some(30.0));
+        assert_eq!(res[2].name, "rpc_count");
+        assert_eq!(res[2].sum(), Some(3.0));
+    }
+
+    #[test]
+    fn test_histogram_large_counts() {
+        let first = "# TYPE lat histogram
+lat_bucket{le=\"1\"} 0
+lat_bucket{le=\"+Inf\"} 0
+";
+        let second = "# TYPE lat histogram
+lat_bucket{le=\"1\"} 1000000000
+lat_bucket{le=\"+Inf\"} 1000000001
+";
+        let mut next = HashMap::new();
+        assert!(scrape(first, &HashMap::new(), &mut next).is_empty());
+
+        let res = scrape(second, &next, &mut HashMap::new());
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].count(), 1_000_000_001);
+        let bins: Vec<usize> = res[0].bins().unwrap().map(|&(_, c)| c).collect();
+        assert_eq!(bins, vec![1_000_000_000, 1]);
+    }
+}
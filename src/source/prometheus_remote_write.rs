@@ -0,0 +1,309 @@
+use crate::constants;
+use crate::http::{self, Handler, Header, Method, Request, Response, StatusCode};
+use crate::metric;
+use crate::protocols::prometheus_remote::WriteRequest;
+use crate::source;
+use crate::thread::Stoppable;
+use crate::time;
+use crate::util;
+use crate::util::send;
+use mio;
+use protobuf;
+use snap;
+use std::io::Read;
+use std::sync;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+pub static PROMETHEUS_REMOTE_WRITE_GOOD_REQUEST: AtomicUsize = AtomicUsize::new(0);
+pub static PROMETHEUS_REMOTE_WRITE_BAD_REQUEST: AtomicUsize = AtomicUsize::new(0);
+pub static PROMETHEUS_REMOTE_WRITE_TELEM: AtomicUsize = AtomicUsize::new(0);
+
+/// The largest body we will accept, snappy compressed. Larger bodies are
+/// answered 413.
+const MAX_BODY: usize = 16 * 1024 * 1024;
+
+/// The largest `WriteRequest` we will decompress a body into. Bodies that
+/// would decompress to more are answered 413.
+const MAX_DECOMPRESSED: usize = 64 * 1024 * 1024;
+
+/// The Prometheus remote-write source
+///
+/// Accepts the snappy compressed `WriteRequest`s that Prometheus servers, and
+/// anything else speaking remote-write, push. Every sample becomes a
+/// `Telemetry` of `AggregationMethod::Set`, named by the `__name__` label and
+/// tagged with the remaining labels. Remote-write carries no type information
+/// so counters arrive as their cumulative value, as they would be scraped.
+pub struct PrometheusRemoteWriteServer {
+    host_port: String,
+    path: String,
+}
+
+/// Configuration for the Prometheus remote-write source.
+#[derive(Debug, Clone)]
+pub struct PrometheusRemoteWriteServerConfig {
+    /// The host for the source to bind to.
+    pub host: String,
+    /// The port for the source to listen on.
+    pub port: u16,
+    /// The path to accept `WriteRequest`s on.
+    pub path: String,
+    /// The forwards that the source will send its telemetry on to.
+    pub forwards: Vec<String>,
+    /// The unique name for the source in the routing topology.
+    pub config_path: Option<String>,
+}
+
+impl Default for PrometheusRemoteWriteServerConfig {
+    fn default() -> PrometheusRemoteWriteServerConfig {
+        PrometheusRemoteWriteServerConfig {
+            host: "localhost".to_string(),
+            port: 9201,
+            path: "/api/v1/write".to_string(),
+            forwards: Vec::new(),
+            config_path: None,
+        }
+    }
+}
+
+/// Convert the samples of a `WriteRequest` into `Telemetry`. Series without
+/// a `__name__` label are skipped. Returns the number of samples skipped.
+fn convert(req: &WriteRequest, res: &mut Vec<metric::Telemetry>) -> usize {
+    let mut skipped = 0;
+    for ts in req.get_timeseries() {
+        let name = match ts.get_labels().iter().find(|l| l.get_name() == "__name__") {
+            Some(label) if !label.get_value().is_empty() => label.get_value(),
+            _ => {
+                skipped += ts.get_samples().len();
+                continue;
+            }
+        };
+        for sample in ts.get_samples() {
+            let mut telem = metric::Telemetry::new()
+                .name(name)
+                .value(sample.get_value())
+                .kind(metric::AggregationMethod::Set)
+                .harden()
+                .unwrap();
+            if sample.has_timestamp() {
+                let (secs, subsec_nanos) = time::from_millis(sample.get_timestamp());
+                telem.timestamp = secs;
+                telem.subsec_nanos = subsec_nanos;
+            }
+            for label in ts.get_labels() {
+                if label.get_name() != "__name__" {
+                    telem = telem.overlay_tag(label.get_name(), label.get_value());
+                }
+            }
+            res.push(telem);
+        }
+    }
+    skipped
+}
+
+/// Decode a snappy compressed `WriteRequest` body. The body must already be
+/// known to decompress to no more than `MAX_DECOMPRESSED`, see `handle`.
+fn decode(body: &[u8]) -> Result<WriteRequest, String> {
+    let buf = snap::Decoder::new()
+        .decompress_vec(body)
+        .map_err(|e| format!("invalid snappy body: {}", e))?;
+    protobuf::parse_from_bytes::<WriteRequest>(&buf)
+        .map_err(|e| format!("invalid write request: {}", e))
+}
+
+/// HTTP handler for the remote-write endpoint.
+struct RemoteWriteHandler {
+    chans: sync::Mutex<util::Channel>,
+    path: String,
+}
+
+fn respond(request: Request, status: u16, body: &str) {
+    let headers =
+        vec![Header::from_bytes(&b"Content-Type"[..], "text/plain").unwrap()];
+    let response = Response::new(
+        StatusCode::from(status),
+        headers,
+        body.as_bytes(),
+        Some(body.len()),
+        None,
+    );
+    if let Err(e) = request.respond(response) {
+        warn!("Failed to send prometheus remote-write response! {:?}", e);
+    }
+}
+
+impl Handler for RemoteWriteHandler {
+    fn handle(&self, mut request: Request) {
+        let matches = {
+            let url = request.url();
+            url.split('?').next() == Some(self.path.as_str())
+        };
+        if !matches {
+            return respond(request, 404, "not found\n");
+        }
+        if *request.method() != Method::Post {
+            return respond(request, 405, "method not allowed\n");
+        }
+
+        if request.body_length().map_or(false, |len| len > MAX_BODY) {
+            PROMETHEUS_REMOTE_WRITE_BAD_REQUEST.fetch_add(1, Ordering::Relaxed);
+            return respond(request, 413, "request body too large\n");
+        }
+        // One byte past the limit is read to tell a body at the limit from
+        // one over it.
+        let mut body = Vec::new();
+        if request
+            .as_reader()
+            .take(MAX_BODY as u64 + 1)
+            .read_to_end(&mut body)
+            .is_err()
+        {
+            PROMETHEUS_REMOTE_WRITE_BAD_REQUEST.fetch_add(1, Ordering::Relaxed);
+            return respond(request, 400, "unable to read body\n");
+        }
+        if body.len() > MAX_BODY {
+            PROMETHEUS_REMOTE_WRITE_BAD_REQUEST.fetch_add(1, Ordering::Relaxed);
+            return respond(request, 413, "request body too large\n");
+        }
+        // Snappy gives the decompressed length up front, checked before any
+        // memory is given over to it. A malformed length is left to `decode`
+        // to report.
+        if snap::decompress_len(&body).map_or(false, |len| len > MAX_DECOMPRESSED) {
+            PROMETHEUS_REMOTE_WRITE_BAD_REQUEST.fetch_add(1, Ordering::Relaxed);
+            return respond(request, 413, "decompressed request too large\n");
+        }
+        let req = match decode(&body) {
+            Ok(req) => req,
+            Err(e) => {
+                PROMETHEUS_REMOTE_WRITE_BAD_REQUEST.fetch_add(1, Ordering::Relaxed);
+                error!("BAD REQUEST: {}", e);
+                return respond(request, 400, &format!("{}\n", e));
+            }
+        };
+
+        let mut metrics = Vec::new();
+        let skipped = convert(&req, &mut metrics);
+        if skipped > 0 {
+            warn!("skipped {} samples without a __name__ label", skipped);
+        }
+        PROMETHEUS_REMOTE_WRITE_GOOD_REQUEST.fetch_add(1, Ordering::Relaxed);
+        PROMETHEUS_REMOTE_WRITE_TELEM.fetch_add(metrics.len(), Ordering::Relaxed);
+        {
+            let mut chans = self.chans.lock().unwrap();
+            for m in metrics {
+                send(&mut chans, metric::Event::new_telemetry(m));
+            }
+        }
+        respond(request, 204, "")
+    }
+}
+
+impl source::Source<PrometheusRemoteWriteServerConfig>
+    for PrometheusRemoteWriteServer
+{
+    /// Create and spawn a new Prometheus remote-write source
+    fn init(config: PrometheusRemoteWriteServerConfig) -> Self {
+        PrometheusRemoteWriteServer {
+            host_port: format!("{}:{}", config.host, config.port),
+            path: config.path,
+        }
+    }
+
+    fn run(self, mut chans: util::Channel, poller: mio::Poll) {
+        let http_srv = http::Server::new(
+            self.host_port,
+            RemoteWriteHandler {
+                chans: sync::Mutex::new(chans.clone()),
+                path: self.path,
+            },
+        );
+
+        loop {
+            let mut events = mio::Events::with_capacity(1024);
+            match poller.poll(&mut events, None) {
+                Ok(_num_events) => {
+                    for event in events {
+                        if let constants::SYSTEM = event.token() {
+                            http_srv.shutdown();
+                            send(&mut chans, metric::Event::Shutdown);
+                            return;
+                        }
+                    }
+                }
+                Err(e) => panic!(format!("Failed during poll {:?}", e)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::metric::TagMap;
+    use crate::protocols::prometheus_remote::{Label, Sample, TimeSeries};
+    use protobuf::Message;
+
+    fn series(labels: &[(&str, &str)], samples: &[(f64, i64)]) -> TimeSeries {
+        let mut ts = TimeSeries::new();
+        for &(name, value) in labels {
+            let mut label = Label::new();
+            label.set_name(name.to_string());
+            label.set_value(value.to_string());
+            ts.mut_labels().push(label);
+        }
+        for &(value, timestamp) in samples {
+            let mut sample = Sample::new();
+            sample.set_value(value);
+            sample.set_timestamp(timestamp);
+            ts.mut_samples().push(sample);
+        }
+        ts
+    }
+
+    #[test]
+    fn test_decode_and_convert() {
+        let mut req = WriteRequest::new();
+        req.mut_timeseries().push(series(
+            &[
+                ("__name__", "http_requests_total"),
+                ("code", "200"),
+                ("job", "api"),
+            ],
+            &[(1027.0, 1_395_066_363_000), (1030.0, 1_395_066_378_000)],
+        ));
+        req.mut_timeseries()
+            .push(series(&[("job", "api")], &[(1.0, 1_395_066_363_000)]));
+        let body = snap::Encoder::new()
+            .compress_vec(&req.write_to_bytes().unwrap())
+            .unwrap();
+
+        let decoded = decode(&body).unwrap();
+        assert_eq!(decoded, req);
+
+        let mut res = Vec::new();
+        assert_eq!(convert(&decoded, &mut res), 1);
+        assert_eq!(res.len(), 2);
+        let defaults = TagMap::default();
+        assert_eq!(res[0].name, "http_requests_total");
+        assert_eq!(res[0].kind(), metric::AggregationMethod::Set);
+        assert_eq!(res[0].set(), Some(1027.0));
+        assert_eq!(res[0].timestamp, 1_395_066_363);
+        assert_eq!(
+            res[0].get_from_tags("code", &defaults),
+            Some(&"200".to_string())
+        );
+        assert_eq!(
+            res[0].get_from_tags("job", &defaults),
+            Some(&"api".to_string())
+        );
+        assert_eq!(res[0].get_from_tags("__name__", &defaults), None);
+        assert_eq!(res[1].set(), Some(1030.0));
+        assert_eq!(res[1].timestamp, 1_395_066_378);
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        assert!(decode(b"not snappy").is_err());
+        let body = snap::Encoder::new().compress_vec(b"\xff\xff\xff").unwrap();
+        assert!(decode(&body).is_err());
+    }
+}
@@ -1,13 +1,15 @@
-use crate::constants;
 use crate::metric;
 use crate::protocols::statsd::parse_statsd;
 use crate::source;
+use crate::source::listener::{
+    LineBuffer, Listener, ListenerConfig, ListenerHandler, ListenerTransport,
+};
 use crate::util;
 use crate::util::send;
 use mio;
 use regex::Regex;
-use std::io::ErrorKind;
-use std::net::ToSocketAddrs;
+use std::mem;
+use std::path::PathBuf;
 use std::str;
 use std::sync;
 use std::sync::atomic::{AtomicUsize, Ordering};
@@ -20,9 +22,13 @@ pub static STATSD_BAD_PACKET: AtomicUsize = AtomicUsize::new(0);
 /// Statsd is a collection of protocols, originally spawned by the telemetering
 /// work done out of Etsy. Cernan tries to support a cow-path subset of the
 /// statsd protocol family.
+///
+/// Statsd is received over one `StatsdTransport` per source. Datagram
+/// transports carry one or more newline separated lines per packet, stream
+/// transports carry newline delimited lines. See `Listener` for how the
+/// transports are served.
 pub struct Statsd {
-    conns: util::TokenSlab<mio::net::UdpSocket>,
-    parse_config: sync::Arc<StatsdParseConfig>,
+    listener: Listener<StatsdHandler>,
 }
 
 /// The mask type for metrics in `StatsdParseConfig`.
@@ -39,6 +45,12 @@ pub struct StatsdParseConfig {
     /// 'foo.*'. In this case all metrics prefixed by 'foo.' which are timer or
     /// histogram will be interpreted as a histogram.
     pub histogram_masks: Vec<(Mask, Bounds)>,
+    /// Set specific sketch masks for timeseries according to their name, each
+    /// with the relative accuracy of the sketch. Timers and histograms matching
+    /// a mask are aggregated into a DDSketch, whose quantiles stay within that
+    /// accuracy however many times it is merged. Histogram masks take
+    /// precedence.
+    pub sketch_masks: Vec<(Mask, f64)>,
     /// Configure the error bound for a statsd timer or histogram. Cernan does
     /// not compute precise quantiles but approximations with a guaranteed upper
     /// bound on the error of approximation. This allows the end-user to set
@@ -50,18 +62,33 @@ impl Default for StatsdParseConfig {
     fn default() -> StatsdParseConfig {
         StatsdParseConfig {
             histogram_masks: vec![],
+            sketch_masks: vec![],
             summarize_error_bound: 0.01,
         }
     }
 }
 
+/// The transports over which the statsd source may receive.
+pub type StatsdTransport = ListenerTransport;
+
 /// Configuration for the statsd source.
 #[derive(Debug, Clone)]
 pub struct StatsdConfig {
-    /// The host for the statsd protocol to bind to.
+    /// The transport the statsd source will receive over.
+    pub transport: StatsdTransport,
+    /// The host for the statsd protocol to bind to. Used by the network
+    /// transports.
     pub host: String,
-    /// The port for the statsd source to listen on.
+    /// The port for the statsd source to listen on. Used by the network
+    /// transports.
     pub port: u16,
+    /// The filesystem path of the socket to bind. Used by the unix
+    /// transports. Any existing file at this path will be removed.
+    pub path: Option<PathBuf>,
+    /// The number of threads receiving on the udp transport. When greater
+    /// than one each thread binds its own `SO_REUSEPORT` socket to `host` and
+    /// `port`.
+    pub workers: usize,
     /// The forwards that statsd will send its telemetry on to.
     pub forwards: Vec<String>,
     /// The unique name for the source in the routing topology.
@@ -73,8 +100,11 @@ pub struct StatsdConfig {
 impl Default for StatsdConfig {
     fn default() -> StatsdConfig {
         StatsdConfig {
+            transport: StatsdTransport::Udp,
             host: "localhost".to_string(),
             port: 8125,
+            path: None,
+            workers: 1,
             forwards: Vec::new(),
             config_path: None,
             parse_config: StatsdParseConfig::default(),
@@ -82,124 +112,156 @@ impl Default for StatsdConfig {
     }
 }
 
-enum StatsdHandlerErr {
-    Fatal,
+impl From<StatsdConfig> for ListenerConfig {
+    fn from(item: StatsdConfig) -> Self {
+        ListenerConfig {
+            transport: item.transport,
+            host: item.host,
+            port: item.port,
+            path: item.path,
+            workers: item.workers,
+            forwards: item.forwards,
+            config_path: item.config_path,
+        }
+    }
+}
+
+/// Handler for statsd datagrams and newline delimited streams.
+#[derive(Debug, Clone)]
+pub struct StatsdHandler {
+    parse_config: sync::Arc<StatsdParseConfig>,
+    basic_metric: sync::Arc<Option<metric::Telemetry>>,
+    metrics: Vec<metric::Telemetry>,
+    lines: LineBuffer,
+}
+
+impl Default for StatsdHandler {
+    fn default() -> StatsdHandler {
+        StatsdHandler::new(sync::Arc::new(StatsdParseConfig::default()))
+    }
 }
 
-impl Statsd {
-    fn handle_datagrams(
-        &self,
-        mut chans: &mut util::Channel,
-        socket: &mio::net::UdpSocket,
-        mut buf: &mut Vec<u8>,
-    ) -> Result<(), StatsdHandlerErr> {
-        let mut metrics = Vec::new();
-        let basic_metric = sync::Arc::new(Some(metric::Telemetry::default()));
-        loop {
-            match socket.recv_from(&mut buf) {
-                Ok((len, _)) => match str::from_utf8(&buf[..len]) {
-                    Ok(val) => {
-                        if parse_statsd(
-                            val,
-                            &mut metrics,
-                            &basic_metric,
-                            &self.parse_config,
-                        ) {
-                            for m in metrics.drain(..) {
-                                send(&mut chans, metric::Event::new_telemetry(m));
-                            }
-                            STATSD_GOOD_PACKET.fetch_add(1, Ordering::Relaxed);
-                        } else {
-                            STATSD_BAD_PACKET.fetch_add(1, Ordering::Relaxed);
-                            error!("BAD PACKET: {:?}", val);
-                        }
-                    }
-                    Err(e) => {
-                        error!("Payload not valid UTF-8: {:?}", e);
-                    }
-                },
-                Err(e) => match e.kind() {
-                    ErrorKind::WouldBlock => {
-                        break;
-                    }
-                    _ => {
-                        error!("Could not read UDP socket with error {:?}", e);
-                        return Err(StatsdHandlerErr::Fatal);
-                    }
-                },
+impl StatsdHandler {
+    fn new(parse_config: sync::Arc<StatsdParseConfig>) -> StatsdHandler {
+        StatsdHandler {
+            parse_config: parse_config,
+            basic_metric: sync::Arc::new(Some(metric::Telemetry::default())),
+            metrics: Vec::new(),
+            lines: LineBuffer::default(),
+        }
+    }
+
+    /// Parse a single statsd payload and send any resulting `Telemetry` on to
+    /// `chans`, keeping the packet counters.
+    fn handle_payload(&mut self, chans: &mut util::Channel, payload: &[u8]) {
+        let payload = match str::from_utf8(payload) {
+            Ok(payload) => payload,
+            Err(e) => {
+                error!("Payload not valid UTF-8: {:?}", e);
+                return;
             }
+        };
+        if parse_statsd(
+            payload,
+            &mut self.metrics,
+            &self.basic_metric,
+            &self.parse_config,
+        ) {
+            for m in self.metrics.drain(..) {
+                send(chans, metric::Event::new_telemetry(m));
+            }
+            STATSD_GOOD_PACKET.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.metrics.clear();
+            STATSD_BAD_PACKET.fetch_add(1, Ordering::Relaxed);
+            error!("BAD PACKET: {:?}", payload);
+        }
+    }
+}
+
+impl ListenerHandler for StatsdHandler {
+    /// The largest statsd datagram we will read. Stream lines are held to
+    /// the same length.
+    const MAX_DATAGRAM: usize = 16_250;
+
+    fn handle_datagram(&mut self, chans: &mut util::Channel, datagram: &[u8]) {
+        self.handle_payload(chans, datagram);
+    }
+
+    fn handle_bytes(&mut self, chans: &mut util::Channel, bytes: &[u8]) -> bool {
+        let mut lines = mem::replace(&mut self.lines, LineBuffer::default());
+        let fits = lines.push(bytes, Self::MAX_DATAGRAM, |line| {
+            self.handle_payload(chans, line)
+        });
+        self.lines = lines;
+        if !fits {
+            STATSD_BAD_PACKET.fetch_add(1, Ordering::Relaxed);
+            error!(
+                "statsd line exceeds {} bytes, closing stream",
+                Self::MAX_DATAGRAM
+            );
+        }
+        fits
+    }
+
+    fn handle_close(&mut self, chans: &mut util::Channel) {
+        if let Some(line) = self.lines.take() {
+            self.handle_payload(chans, &line);
         }
-        Ok(())
     }
 }
 
 impl source::Source<StatsdConfig> for Statsd {
     /// Create and spawn a new statsd source
     fn init(config: StatsdConfig) -> Self {
-        let mut conns = util::TokenSlab::<mio::net::UdpSocket>::new();
-        let addrs = (config.host.as_str(), config.port).to_socket_addrs();
-        match addrs {
-            Ok(ips) => {
-                for addr in ips {
-                    let socket = mio::net::UdpSocket::bind(&addr)
-                        .expect("Unable to bind to UDP socket");
-                    conns.insert(socket);
-                }
-            }
-            Err(e) => {
-                info!(
-                    "Unable to perform DNS lookup on host {} with error {}",
-                    config.host, e
-                );
-            }
-        };
-
+        let parse_config = sync::Arc::new(config.parse_config.clone());
+        let handler = StatsdHandler::new(parse_config);
         Statsd {
-            conns: conns,
-            parse_config: sync::Arc::new(config.parse_config),
+            listener: Listener::bind(config.into(), handler),
         }
     }
 
-    fn run(self, mut chans: util::Channel, poller: mio::Poll) {
-        for (idx, socket) in self.conns.iter() {
-            if let Err(e) = poller.register(
-                socket,
-                mio::Token::from(idx),
-                mio::Ready::readable(),
-                mio::PollOpt::edge(),
-            ) {
-                error!("Failed to register {:?} - {:?}!", socket, e);
-            }
-        }
+    fn run(self, chans: util::Channel, poller: mio::Poll) {
+        self.listener.run(chans, poller)
+    }
+}
 
-        let mut buf = vec![0; 16_250];
-        loop {
-            let mut events = mio::Events::with_capacity(1024);
-            match poller.poll(&mut events, None) {
-                Ok(_num_events) => {
-                    for event in events {
-                        match event.token() {
-                            constants::SYSTEM => {
-                                send(&mut chans, metric::Event::Shutdown);
-                                return;
-                            }
-
-                            token => {
-                                let socket = &self.conns[token];
-                                if let Err(_e) =
-                                    self.handle_datagrams(&mut chans, socket, &mut buf)
-                                {
-                                    error!(
-                                        "Deregistering {:?} due to unrecoverable error!",
-                                        *socket
-                                    );
-                                }
-                            }
-                        }
-                    }
-                }
-                Err(e) => panic!(format!("Failed during poll {:?}", e)),
-            }
-        } // loop
-    } // run
+#[cfg(test)]
+mod test {
+    use super::*;
+    use hopper;
+    use tempdir;
+
+    #[test]
+    fn test_stream_lines_span_reads() {
+        let dir = tempdir::TempDir::new("cernan_statsd").unwrap();
+        let (sender, receiver) = hopper::channel("statsd", dir.path()).unwrap();
+        let mut chans = util::Channel::new(vec![sender]);
+        let mut handler = StatsdHandler::default();
+        assert!(handler.handle_bytes(&mut chans, b"a:1|c\nb:"));
+        assert!(handler.handle_bytes(&mut chans, b"2|c\nc:3|g"));
+        handler.handle_close(&mut chans);
+
+        let names: Vec<String> = receiver
+            .into_iter()
+            .take(3)
+            .map(|event| match event {
+                metric::Event::Telemetry(telem) => telem.name,
+                _ => panic!("expected telemetry"),
+            })
+            .collect();
+        assert_eq!(names, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_stream_oversized_line_closes() {
+        let dir = tempdir::TempDir::new("cernan_statsd").unwrap();
+        let (sender, _receiver) = hopper::channel("statsd", dir.path()).unwrap();
+        let mut chans = util::Channel::new(vec![sender]);
+        let mut handler = StatsdHandler::default();
+        let chunk = vec![b'a'; StatsdHandler::MAX_DATAGRAM / 2];
+        assert!(handler.handle_bytes(&mut chans, &chunk));
+        assert!(handler.handle_bytes(&mut chans, &chunk));
+        assert!(!handler.handle_bytes(&mut chans, b"aa"));
+    }
 }
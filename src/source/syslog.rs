@@ -0,0 +1,168 @@
+use crate::metric;
+use crate::protocols::syslog::{next_frame, parse_syslog};
+use crate::source;
+use crate::source::listener::{
+    Listener, ListenerConfig, ListenerHandler, ListenerTransport,
+};
+use crate::util;
+use crate::util::send;
+use mio;
+use std::path::PathBuf;
+use std::str;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+pub static SYSLOG_GOOD_PACKET: AtomicUsize = AtomicUsize::new(0);
+pub static SYSLOG_BAD_PACKET: AtomicUsize = AtomicUsize::new(0);
+
+/// The largest syslog frame we will accept. RFC 5425 requires receivers to
+/// handle frames of at least 2048 octets and recommends 8192.
+const MAX_FRAME: usize = 65_536;
+
+/// The syslog source
+///
+/// Syslog frames, in either RFC 3164 or RFC 5424 format, are parsed into
+/// `LogLine`s. See `protocols::syslog::parse_syslog` for how the frame is
+/// represented. Datagram transports carry one frame per packet, stream
+/// transports may use octet-counting or newline delimited framing. See
+/// `Listener` for how the transports are served.
+pub struct Syslog {
+    listener: Listener<SyslogHandler>,
+}
+
+/// The transports over which the syslog source may receive.
+pub type SyslogTransport = ListenerTransport;
+
+/// Configuration for the syslog source.
+#[derive(Debug, Clone)]
+pub struct SyslogConfig {
+    /// The transport the syslog source will receive over.
+    pub transport: SyslogTransport,
+    /// The host for the syslog source to bind to. Used by the network
+    /// transports.
+    pub host: String,
+    /// The port for the syslog source to listen on. Used by the network
+    /// transports.
+    pub port: u16,
+    /// The filesystem path of the socket to bind. Used by the unix
+    /// transports. Any existing file at this path will be removed.
+    pub path: Option<PathBuf>,
+    /// The forwards that syslog will send its `LogLine`s on to.
+    pub forwards: Vec<String>,
+    /// The unique name for the source in the routing topology.
+    pub config_path: Option<String>,
+}
+
+impl Default for SyslogConfig {
+    fn default() -> SyslogConfig {
+        SyslogConfig {
+            transport: SyslogTransport::Udp,
+            host: "localhost".to_string(),
+            port: 514,
+            path: None,
+            forwards: Vec::new(),
+            config_path: None,
+        }
+    }
+}
+
+impl From<SyslogConfig> for ListenerConfig {
+    fn from(item: SyslogConfig) -> Self {
+        ListenerConfig {
+            transport: item.transport,
+            host: item.host,
+            port: item.port,
+            path: item.path,
+            workers: 1,
+            forwards: item.forwards,
+            config_path: item.config_path,
+        }
+    }
+}
+
+/// Parse a single syslog frame and send the resulting `LogLine` on to `chans`,
+/// keeping the packet counters.
+fn handle_frame(chans: &mut util::Channel, path: &str, frame: &[u8]) {
+    let line = str::from_utf8(frame)
+        .ok()
+        .and_then(|frame| parse_syslog(path, frame));
+    match line {
+        Some(line) => {
+            send(chans, metric::Event::new_log(line));
+            SYSLOG_GOOD_PACKET.fetch_add(1, Ordering::Relaxed);
+        }
+        None => {
+            SYSLOG_BAD_PACKET.fetch_add(1, Ordering::Relaxed);
+            error!("BAD PACKET: {:?}", String::from_utf8_lossy(frame));
+        }
+    }
+}
+
+/// Handler for syslog datagrams and framed streams.
+#[derive(Default, Debug, Clone)]
+pub struct SyslogHandler {
+    path: String,
+    buf: Vec<u8>,
+}
+
+impl ListenerHandler for SyslogHandler {
+    const MAX_DATAGRAM: usize = MAX_FRAME;
+
+    fn handle_datagram(&mut self, chans: &mut util::Channel, datagram: &[u8]) {
+        handle_frame(chans, &self.path, datagram);
+    }
+
+    fn handle_bytes(&mut self, chans: &mut util::Channel, bytes: &[u8]) -> bool {
+        self.buf.extend_from_slice(bytes);
+        let mut consumed = 0;
+        while let Some((frame_len, frame)) = next_frame(&self.buf[consumed..]) {
+            if !frame.is_empty() {
+                handle_frame(chans, &self.path, frame);
+            }
+            consumed += frame_len;
+        }
+        self.buf.drain(..consumed);
+        if self.buf.len() > MAX_FRAME {
+            SYSLOG_BAD_PACKET.fetch_add(1, Ordering::Relaxed);
+            error!("syslog frame exceeds {} bytes, closing stream", MAX_FRAME);
+            return false;
+        }
+        true
+    }
+
+    fn handle_close(&mut self, chans: &mut util::Channel) {
+        if !self.buf.is_empty() {
+            handle_frame(chans, &self.path, &self.buf);
+            self.buf.clear();
+        }
+    }
+}
+
+impl source::Source<SyslogConfig> for Syslog {
+    /// Create and spawn a new syslog source
+    fn init(config: SyslogConfig) -> Self {
+        // The `LogLine` path is the address or socket path that the source
+        // listens on.
+        let path = match config.transport {
+            SyslogTransport::Udp | SyslogTransport::Tcp => {
+                format!("{}:{}", config.host, config.port)
+            }
+            SyslogTransport::UnixDatagram | SyslogTransport::UnixStream => config
+                .path
+                .as_ref()
+                .expect("unix syslog transports must have a path")
+                .display()
+                .to_string(),
+        };
+        let handler = SyslogHandler {
+            path: path,
+            buf: Vec::new(),
+        };
+        Syslog {
+            listener: Listener::bind(config.into(), handler),
+        }
+    }
+
+    fn run(self, chans: util::Channel, poller: mio::Poll) {
+        self.listener.run(chans, poller)
+    }
+}
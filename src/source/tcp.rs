@@ -3,11 +3,9 @@ use crate::metric;
 use crate::source::Source;
 use crate::thread;
 use crate::util;
-use hopper;
 use mio;
 use std;
 use std::io::ErrorKind;
-use std::marker::PhantomData;
 use std::net::ToSocketAddrs;
 
 /// Configured for the `metric::Telemetry` source.
@@ -57,7 +55,7 @@ pub struct TCP<H> {
     stream_events: mio::Registration,
     stream_events_token: mio::Token,
     handlers: thread::ThreadPool,
-    phantom: PhantomData<H>,
+    handler: H,
 }
 
 impl<H> Source<TCPConfig> for TCP<H>
@@ -66,6 +64,45 @@ where
 {
     /// Constructs and starts a new TCP source.
     fn init(config: TCPConfig) -> Self {
+        TCP::with_handler(config, H::new())
+    }
+
+    /// Starts the accept loop.
+    fn run(self, chans: util::Channel, poller: mio::Poll) {
+        for (idx, listener) in self.listeners.iter() {
+            if let Err(e) = poller.register(
+                listener,
+                mio::Token::from(idx),
+                mio::Ready::readable(),
+                mio::PollOpt::edge(),
+            ) {
+                error!("Failed to register {:?} - {:?}!", listener, e);
+            }
+        }
+
+        if let Err(e) = poller.register(
+            &self.stream_events,
+            self.stream_events_token,
+            mio::Ready::readable(),
+            mio::PollOpt::edge(),
+        ) {
+            error!("Failed to register stream events - {:?}!", e);
+        };
+
+        self.accept_loop(chans, &poller)
+    }
+}
+
+impl<H> TCP<H>
+where
+    H: TCPStreamHandler,
+{
+    /// Constructs a new TCP source whose streams are each served by a clone
+    /// of `handler`.
+    ///
+    /// This is useful when a handler carries configuration of its own. Where
+    /// it does not, `Source::init` will construct the handler by default.
+    pub fn with_handler(config: TCPConfig, handler: H) -> Self {
         // Create registrations and for all TCP interfaces and stream handlers.
         //
         // Note - Due to restrictions in mio, we must construct these registrations
@@ -103,40 +140,10 @@ where
             stream_events: stream_events,
             stream_events_token: stream_events_token,
             handlers: thread_pool,
-            phantom: PhantomData,
+            handler: handler,
         }
     }
 
-    /// Starts the accept loop.
-    fn run(self, chans: util::Channel, poller: mio::Poll) {
-        for (idx, listener) in self.listeners.iter() {
-            if let Err(e) = poller.register(
-                listener,
-                mio::Token::from(idx),
-                mio::Ready::readable(),
-                mio::PollOpt::edge(),
-            ) {
-                error!("Failed to register {:?} - {:?}!", listener, e);
-            }
-        }
-
-        if let Err(e) = poller.register(
-            &self.stream_events,
-            self.stream_events_token,
-            mio::Ready::readable(),
-            mio::PollOpt::edge(),
-        ) {
-            error!("Failed to register stream events - {:?}!", e);
-        };
-
-        self.accept_loop(chans, &poller)
-    }
-}
-
-impl<H> TCP<H>
-where
-    H: TCPStreamHandler,
-{
     fn accept_loop(mut self, mut chans: util::Channel, poll: &mio::Poll) {
         loop {
             let mut events = mio::Events::with_capacity(1024);
@@ -178,15 +185,19 @@ where
 
     fn spawn_stream_handlers(
         &mut self,
-        chans: &[hopper::Sender<metric::Event>],
+        chans: &util::Channel,
         listener_token: mio::Token,
     ) -> Result<(), std::io::Error> {
         let listener = &self.listeners[listener_token];
         loop {
             match listener.accept() {
                 Ok((stream, _addr)) => {
-                    // Actually spawn the stream handler
-                    let rchans = chans.to_owned();
+                    // Actually spawn the stream handler. The handler sends
+                    // through a clone of this node's channel, so that its
+                    // events reach the node's tap and obey its overflow
+                    // policies.
+                    let rchans = chans.clone();
+                    let mut handler = self.handler.clone();
                     self.handlers.spawn(move |poller| {
                         // Note - Stream handlers are allowed to crash without
                         // compromising Cernan's ability to gracefully shutdown.
@@ -199,7 +210,6 @@ where
                             )
                             .unwrap();
 
-                        let mut handler = H::new();
                         handler.handle_stream(rchans, &poller, stream);
                     });
                 }
@@ -0,0 +1,227 @@
+//! Live taps on the output of a node
+//!
+//! Every source and filter sends its events through a `util::Channel` which
+//! carries the node's `TapPoint`. A tap attaches to a point by config path and
+//! receives a sampled copy of the node's events, as JSON, optionally only
+//! those of a given name or tag. Copies are offered to taps without blocking
+//! and dropped if a tap does not keep up, delivery to the node's forwards is
+//! never held up by a tap.
+
+use crate::metric::{Event, TagMap};
+use serde_json;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+
+/// The number of JSON events a tap may have waiting before further events are
+/// dropped.
+const TAP_BUFFER: usize = 1024;
+
+lazy_static! {
+    static ref POINTS: Mutex<HashMap<String, Arc<TapPoint>>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Which events of a node a tap receives.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TapFilter {
+    /// Only events of this name, a `Telemetry` name or `LogLine` path.
+    pub name: Option<String>,
+    /// Only events with this tag key and value.
+    pub tag: Option<(String, String)>,
+    /// Only one in every `sample` events that match the above.
+    pub sample: usize,
+}
+
+impl Default for TapFilter {
+    fn default() -> TapFilter {
+        TapFilter {
+            name: None,
+            tag: None,
+            sample: 1,
+        }
+    }
+}
+
+impl TapFilter {
+    /// Whether `event` matches the name and tag of the filter. Only
+    /// `Telemetry` and `LogLine`s are considered, flush and shutdown markers
+    /// never match and raw events only match a filter without name or tag.
+    pub fn matches(&self, event: &Event) -> bool {
+        let empty = TagMap::default();
+        match *event {
+            Event::Telemetry(ref telem) => {
+                self.name.as_ref().map_or(true, |name| *name == telem.name)
+                    && self.tag.as_ref().map_or(true, |&(ref k, ref v)| {
+                        telem.tags(&empty).any(|(tk, tv)| tk == k && tv == v)
+                    })
+            }
+            Event::Log(ref line) => {
+                self.name.as_ref().map_or(true, |name| *name == line.path)
+                    && self.tag.as_ref().map_or(true, |&(ref k, ref v)| {
+                        line.tags.as_ref().and_then(|tags| tags.get(k)) == Some(v)
+                    })
+            }
+            Event::Raw { .. } => self.name.is_none() && self.tag.is_none(),
+            Event::TimerFlush(_) | Event::Shutdown => false,
+        }
+    }
+}
+
+struct Tap {
+    filter: TapFilter,
+    matched: usize,
+    sender: mpsc::SyncSender<String>,
+}
+
+impl Tap {
+    /// Send `event` to the tap if it matches and is sampled. `json` holds the
+    /// event's encoding once made, to be shared between taps. Returns false
+    /// if the tap has hung up.
+    fn offer(&mut self, event: &Event, json: &mut Option<String>) -> bool {
+        if !self.filter.matches(event) {
+            return true;
+        }
+        self.matched += 1;
+        if (self.matched - 1) % self.filter.sample.max(1) != 0 {
+            return true;
+        }
+        if json.is_none() {
+            *json = serde_json::to_string(event).ok();
+        }
+        match *json {
+            Some(ref json) => match self.sender.try_send(json.clone()) {
+                Ok(()) | Err(mpsc::TrySendError::Full(_)) => true,
+                Err(mpsc::TrySendError::Disconnected(_)) => false,
+            },
+            None => true,
+        }
+    }
+}
+
+/// The point at which taps attach to a node.
+#[derive(Default)]
+pub struct TapPoint {
+    /// The number of taps attached, checked before taking the lock.
+    attached: AtomicUsize,
+    taps: Mutex<Vec<Tap>>,
+}
+
+impl TapPoint {
+    /// Offer a copy of `event` to every tap attached. Taps whose receiver
+    /// has hung up are detached, as they are next sent an event.
+    pub fn offer(&self, event: &Event) {
+        if self.attached.load(Ordering::Relaxed) == 0 {
+            return;
+        }
+        let mut taps = self.taps.lock().unwrap();
+        let mut json = None;
+        let mut i = 0;
+        while i < taps.len() {
+            if taps[i].offer(event, &mut json) {
+                i += 1;
+            } else {
+                taps.swap_remove(i);
+            }
+        }
+        self.attached.store(taps.len(), Ordering::Relaxed);
+    }
+
+    fn attach(&self, filter: TapFilter) -> mpsc::Receiver<String> {
+        let (sender, receiver) = mpsc::sync_channel(TAP_BUFFER);
+        let mut taps = self.taps.lock().unwrap();
+        taps.push(Tap {
+            filter,
+            matched: 0,
+            sender,
+        });
+        self.attached.store(taps.len(), Ordering::Relaxed);
+        receiver
+    }
+}
+
+/// The tap point of the node `config_path`, created if need be.
+pub fn point(config_path: &str) -> Arc<TapPoint> {
+    let mut points = POINTS.lock().unwrap();
+    Arc::clone(
+        points
+            .entry(config_path.to_string())
+            .or_insert_with(|| Arc::new(TapPoint::default())),
+    )
+}
+
+/// Attach a tap to the output of the node `config_path`. The events the tap
+/// receives, as JSON, are read from the returned receiver. The tap detaches
+/// once the receiver is dropped.
+pub fn attach(config_path: &str, filter: TapFilter) -> mpsc::Receiver<String> {
+    point(config_path).attach(filter)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::metric::{LogLine, Telemetry};
+
+    fn telem(name: &str, tag: (&str, &str)) -> Event {
+        Event::new_telemetry(
+            Telemetry::new()
+                .name(name)
+                .value(1.0)
+                .harden()
+                .unwrap()
+                .overlay_tag(tag.0, tag.1),
+        )
+    }
+
+    #[test]
+    fn test_filter_matches() {
+        let all = TapFilter::default();
+        let by_name = TapFilter {
+            name: Some("a".into()),
+            ..TapFilter::default()
+        };
+        let by_tag = TapFilter {
+            tag: Some(("host".into(), "x".into())),
+            ..TapFilter::default()
+        };
+        let a = telem("a", ("host", "y"));
+        let b = telem("b", ("host", "x"));
+        let line = Event::new_log(LogLine::new("a", "hello"));
+
+        assert!(all.matches(&a) && all.matches(&b) && all.matches(&line));
+        assert!(by_name.matches(&a) && !by_name.matches(&b) && by_name.matches(&line));
+        assert!(!by_tag.matches(&a) && by_tag.matches(&b) && !by_tag.matches(&line));
+        assert!(!all.matches(&Event::Shutdown));
+        assert!(!all.matches(&Event::TimerFlush(1)));
+    }
+
+    #[test]
+    fn test_offer_samples_and_detaches() {
+        let point = TapPoint::default();
+        // Nothing attached, nothing to do.
+        point.offer(&telem("a", ("host", "x")));
+
+        let every_other = point.attach(TapFilter {
+            sample: 2,
+            ..TapFilter::default()
+        });
+        let named = point.attach(TapFilter {
+            name: Some("b".into()),
+            ..TapFilter::default()
+        });
+        for name in &["a", "b", "a", "b"] {
+            point.offer(&telem(name, ("host", "x")));
+        }
+        let sampled: Vec<String> = every_other.try_iter().collect();
+        assert_eq!(sampled.len(), 2);
+        assert!(sampled.iter().all(|json| json.contains("\"a\"")));
+        assert_eq!(named.try_iter().count(), 2);
+
+        drop(every_other);
+        point.offer(&telem("a", ("host", "x")));
+        assert_eq!(point.attached.load(Ordering::Relaxed), 1);
+        drop(named);
+        point.offer(&telem("b", ("host", "x")));
+        assert_eq!(point.attached.load(Ordering::Relaxed), 0);
+    }
+}
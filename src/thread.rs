@@ -2,8 +2,10 @@
 use crate::constants;
 use crate::util;
 use mio;
+use std::cell::RefCell;
 use std::option;
 use std::sync;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
 
 /// Event polling structure. Alias of `mio::Poll`.
@@ -11,6 +13,18 @@ pub type Poll = mio::Poll;
 /// Events buffer type. Alias of `mio::Events`.
 pub type Events = mio::Events;
 
+thread_local! {
+    /// Whether the current thread has been asked to shut down, see `stopping`.
+    static STOPPING: RefCell<sync::Arc<AtomicBool>> =
+        RefCell::new(sync::Arc::new(AtomicBool::new(false)));
+}
+
+/// Whether the current thread has been asked to shut down through its
+/// `ThreadHandle`. Always false for threads not started by `spawn`.
+pub fn stopping() -> bool {
+    STOPPING.with(|stopping| stopping.borrow().load(Ordering::Relaxed))
+}
+
 /// Mio enabled thread state.
 pub struct ThreadHandle {
     /// JoinHandle for the executing thread.
@@ -19,6 +33,34 @@ pub struct ThreadHandle {
     /// Readiness signal used to notify the given thread when an event is ready
     /// to be consumed on the SYSTEM channel.
     shutdown_event: mio::SetReadiness,
+
+    /// Whether the thread has been asked to shut down.
+    stopping: sync::Arc<AtomicBool>,
+
+    /// Whether the thread is still executing.
+    alive: sync::Arc<AtomicBool>,
+}
+
+impl ThreadHandle {
+    /// Whether the thread is still executing. A thread that has returned or
+    /// panicked is not.
+    pub fn is_alive(&self) -> bool {
+        self.alive.load(Ordering::Relaxed)
+    }
+
+    /// A liveness flag for the thread, which may outlive the handle.
+    pub fn liveness(&self) -> sync::Arc<AtomicBool> {
+        sync::Arc::clone(&self.alive)
+    }
+}
+
+/// Clears a thread's liveness flag when dropped, on return or unwind.
+struct Alive(sync::Arc<AtomicBool>);
+
+impl Drop for Alive {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
 }
 
 /// Trait for stoppable processes.
@@ -41,6 +83,7 @@ impl Stoppable for ThreadHandle {
     /// Note - It is the responsability of the developer to ensure
     /// that thread logic polls for events occuring on the SYSTEM token.
     fn shutdown(self) {
+        self.stopping.store(true, Ordering::Relaxed);
         self.shutdown_event
             .set_readiness(mio::Ready::readable())
             .expect("Failed to notify child thread of shutdown!");
@@ -55,9 +98,17 @@ where
 {
     let child_poller = mio::Poll::new().unwrap();
     let (shutdown_event_registration, shutdown_event) = mio::Registration::new2();
+    let stopping = sync::Arc::new(AtomicBool::new(false));
+    let flag = sync::Arc::clone(&stopping);
+    let alive = sync::Arc::new(AtomicBool::new(true));
+    let guard = Alive(sync::Arc::clone(&alive));
     ThreadHandle {
         shutdown_event: shutdown_event,
+        stopping: stopping,
+        alive: alive,
         handle: thread::spawn(move || {
+            let _guard = guard;
+            STOPPING.with(|stopping| *stopping.borrow_mut() = flag);
             child_poller
                 .register(
                     &shutdown_event_registration,
@@ -2,7 +2,8 @@
 //!
 //! Time in cernan is not based strictly on wall-clock. We keep a global clock
 //! for cernan and update it ourselves periodically. See `update_time` in this
-//! module for more details.
+//! module for more details. Sub-second precision is not tracked by the global
+//! clock; callers that need it read the system clock through `now_parts`.
 
 use chrono::offset::Utc;
 use std::sync::atomic::{AtomicUsize, Ordering};
@@ -12,11 +13,61 @@ lazy_static! {
     static ref NOW: AtomicUsize = AtomicUsize::new(Utc::now().timestamp() as usize);
 }
 
+/// The number of nanoseconds in one second
+pub const NANOS_PER_SEC: i64 = 1_000_000_000;
+
+/// The number of nanoseconds in one millisecond
+pub const NANOS_PER_MILLI: i64 = 1_000_000;
+
 /// Return the current time in epoch seconds
 pub fn now() -> i64 {
     NOW.load(Ordering::Relaxed) as i64
 }
 
+/// Return the current time as epoch seconds and the nanoseconds past that
+/// second
+///
+/// Unlike `now` this reads the system clock directly, as cernan's global clock
+/// only advances every 500ms and would collapse the ordering of anything that
+/// happens in between.
+pub fn now_parts() -> (i64, u32) {
+    let now = Utc::now();
+    (now.timestamp(), now.timestamp_subsec_nanos())
+}
+
+/// Split epoch nanoseconds into epoch seconds and the nanoseconds past that
+/// second
+///
+/// The nanosecond part is always positive, so instants before the epoch
+/// borrow from the seconds part.
+pub fn from_nanos(nanos: i64) -> (i64, u32) {
+    let secs = nanos.div_euclid(NANOS_PER_SEC);
+    let subsec = nanos.rem_euclid(NANOS_PER_SEC);
+    (secs, subsec as u32)
+}
+
+/// Split epoch milliseconds into epoch seconds and the nanoseconds past that
+/// second
+pub fn from_millis(millis: i64) -> (i64, u32) {
+    let secs = millis.div_euclid(1_000);
+    let subsec = millis.rem_euclid(1_000) * NANOS_PER_MILLI;
+    (secs, subsec as u32)
+}
+
+/// Join epoch seconds and sub-second nanoseconds into epoch nanoseconds
+///
+/// Instants past the year 2262 do not fit and saturate.
+pub fn to_nanos(secs: i64, subsec_nanos: u32) -> i64 {
+    secs.saturating_mul(NANOS_PER_SEC)
+        .saturating_add(i64::from(subsec_nanos))
+}
+
+/// Join epoch seconds and sub-second nanoseconds into epoch milliseconds
+pub fn to_millis(secs: i64, subsec_nanos: u32) -> i64 {
+    secs.saturating_mul(1_000)
+        .saturating_add(i64::from(subsec_nanos) / NANOS_PER_MILLI)
+}
+
 /// Update cernan's view of time every 500ms. Time is in UTC.
 pub fn update_time() {
     let dur = time::Duration::from_millis(500);
@@ -55,3 +106,34 @@ pub fn delay(attempts: u32) {
     let sleep_time = time::Duration::from_millis(delay as u64);
     thread::sleep(sleep_time);
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use quickcheck::{QuickCheck, TestResult};
+
+    #[test]
+    fn test_nanos_round_trip() {
+        fn inner(secs: i32, subsec_nanos: u32) -> TestResult {
+            if i64::from(subsec_nanos) >= NANOS_PER_SEC {
+                return TestResult::discard();
+            }
+            let secs = i64::from(secs);
+            let nanos = to_nanos(secs, subsec_nanos);
+            assert_eq!((secs, subsec_nanos), from_nanos(nanos));
+            TestResult::passed()
+        }
+        QuickCheck::new()
+            .tests(1000)
+            .max_tests(10000)
+            .quickcheck(inner as fn(i32, u32) -> TestResult);
+    }
+
+    #[test]
+    fn test_millis_before_epoch() {
+        assert_eq!((-1, 500_000_000), from_millis(-500));
+        assert_eq!(-500, to_millis(-1, 500_000_000));
+        assert_eq!((1, 1_000_000), from_millis(1_001));
+        assert_eq!(1_001, to_millis(1, 1_000_000));
+    }
+}
@@ -0,0 +1,756 @@
+//! The routing topology of a cernan run
+//!
+//! Every source, filter and sink is a node of the topology, named by its
+//! config path and holding the forwards it sends its events on to. When the
+//! configuration is reloaded the topology it describes is compared against
+//! the running one, see `Topology::diff`. Nodes added are started, nodes
+//! removed drain and shut down and nodes whose configuration changed do both.
+//! Everything else keeps running, hopper queues and all, nodes forwarding
+//! into a restarted node having their sender into it swapped for one into its
+//! replacement.
+
+use crate::config::Args;
+use crate::util::Overflow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::{self, Debug, Write};
+
+/// The kinds of node in a routing topology.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    /// An originator of events, see `source::Source`.
+    Source,
+    /// A transformer of events, see `filter::Filter`.
+    Filter,
+    /// A final destination of events, see `sink::Sink`.
+    Sink,
+}
+
+/// A single source, filter or sink of a routing topology.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    /// The kind of the node.
+    pub kind: Kind,
+    /// The config paths of the filters and sinks the node sends to.
+    pub forwards: Vec<String>,
+    /// The `Overflow` policy of each forward, where not `DropNewest`.
+    pub overflow: BTreeMap<String, Overflow>,
+    /// The node's configuration, compared to decide if it has changed.
+    fingerprint: String,
+}
+
+impl Node {
+    /// The node's configuration, as its `Debug` representation.
+    pub fn config(&self) -> &str {
+        &self.fingerprint
+    }
+}
+
+/// Errors that may arise while validating a topology.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// A node forwards to something that is not a filter or sink of the
+    /// topology. The node, the index of the forward and the forward are
+    /// given.
+    UnknownForward(String, usize, String),
+    /// A cycle of filters, each forwarding to the next and the last to the
+    /// first. Events would circle forever and the filters never shut down.
+    Cycle(Vec<String>),
+    /// A filter or sink that nothing forwards to.
+    Orphaned(String),
+}
+
+impl Error {
+    /// Whether cernan refuses to run a topology with this error. Orphaned
+    /// filters and sinks are harmless, if likely a mistake.
+    pub fn is_fatal(&self) -> bool {
+        match *self {
+            Error::Orphaned(_) => false,
+            _ => true,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::UnknownForward(ref config_path, idx, ref forward) => write!(
+                f,
+                "{}.forwards[{}]: no filter or sink named \"{}\"",
+                config_path, idx, forward
+            ),
+            Error::Cycle(ref config_paths) => write!(
+                f,
+                "{}.forwards: cycle {} -> {}",
+                config_paths[0],
+                config_paths.join(" -> "),
+                config_paths[0]
+            ),
+            Error::Orphaned(ref config_path) => {
+                write!(f, "{}: nothing forwards to it", config_path)
+            }
+        }
+    }
+}
+
+/// The changes needed to move from one topology to another
+///
+/// A node of the running topology that keeps running but forwards into a
+/// node being restarted is rewired: its sends are held up while the node
+/// restarts and then go into the hopper queue of the replacement. The
+/// rewiring is done before anything is shut down, so that the replaced node
+/// sees nothing from the rewired nodes as it drains.
+///
+/// Filters and sinks exit once they have received a `metric::Event::Shutdown`
+/// for every one their upstreams will send, see `util::Upstreams`. Nodes
+/// being shut down send `Shutdown`s into the filters and sinks that keep
+/// running and so these must expect more, as must the replacements of
+/// restarted filters and sinks that rewired filters pass `Shutdown`s on to.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Diff {
+    /// Nodes of the running topology to drain and shut down.
+    pub stop: BTreeSet<String>,
+    /// Nodes of the new topology to start.
+    pub start: BTreeSet<String>,
+    /// Nodes that keep running but forward into nodes being restarted.
+    pub rewire: BTreeSet<String>,
+    /// Adjustments to the `Shutdown`s expected by the filters and sinks that
+    /// keep running, and by those started in addition to what the new
+    /// topology has them expect, by config path.
+    pub upstreams: BTreeMap<String, isize>,
+    /// The number of `Shutdown`s that must be sent directly to filters and
+    /// sinks being shut down, which would otherwise not see enough from
+    /// their upstreams to exit.
+    pub inject: BTreeMap<String, usize>,
+}
+
+/// A routing topology, the nodes of a cernan run.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Topology {
+    nodes: HashMap<String, Node>,
+}
+
+impl Topology {
+    /// Create a new, empty topology.
+    pub fn new() -> Topology {
+        Topology::default()
+    }
+
+    /// Build the topology of the given configuration.
+    pub fn from_args(args: &Args) -> Topology {
+        let mut topology = Topology::new();
+
+        // SINKS
+        macro_rules! sinks {
+            ($configs:expr) => {
+                if let Some(ref configs) = $configs {
+                    for (config_path, config) in configs {
+                        topology.insert(config_path, Kind::Sink, &[], config);
+                    }
+                }
+            };
+        }
+        sinks!(args.null);
+        sinks!(args.console);
+        sinks!(args.wavefront);
+        sinks!(args.prometheus);
+        sinks!(args.prometheus_remote_write);
+        sinks!(args.influxdb);
+        sinks!(args.native_sink_config);
+        sinks!(args.elasticsearch);
+        if let Some(ref configs) = args.kafkas {
+            for config in configs {
+                if let Some(ref config_path) = config.config_path {
+                    topology.insert(config_path, Kind::Sink, &[], config);
+                }
+            }
+        }
+
+        // FILTERS and SOURCES
+        macro_rules! forwarding {
+            ($configs:expr, $kind:expr) => {
+                if let Some(ref configs) = $configs {
+                    for (config_path, config) in configs {
+                        topology.insert(config_path, $kind, &config.forwards, config);
+                    }
+                }
+            };
+        }
+        forwarding!(args.programmable_filters, Kind::Filter);
+        forwarding!(args.delay_filters, Kind::Filter);
+        forwarding!(args.json_encode_filters, Kind::Filter);
+        forwarding!(args.flush_boundary_filters, Kind::Filter);
+        forwarding!(args.native_server_config, Kind::Source);
+        forwarding!(args.statsds, Kind::Source);
+        forwarding!(args.syslogs, Kind::Source);
+        forwarding!(args.graphites, Kind::Source);
+        forwarding!(args.influxdbs, Kind::Source);
+        forwarding!(args.prometheus_scrapes, Kind::Source);
+        forwarding!(args.prometheus_remote_writes, Kind::Source);
+        forwarding!(args.avros, Kind::Source);
+        if let Some(ref configs) = args.files {
+            for config in configs {
+                if let Some(ref config_path) = config.config_path {
+                    topology.insert(
+                        config_path,
+                        Kind::Source,
+                        &config.forwards,
+                        config,
+                    );
+                }
+            }
+        }
+        if let Some(ref config_path) = args.internal.config_path {
+            topology.insert(
+                config_path,
+                Kind::Source,
+                &args.internal.forwards,
+                &args.internal,
+            );
+        }
+
+        for (config_path, policies) in &args.overflow {
+            if let Some(node) = topology.nodes.get_mut(config_path) {
+                node.overflow = policies.clone();
+            }
+        }
+
+        topology
+    }
+
+    /// Insert a node into the topology, replacing any of the same name.
+    pub fn insert<C>(
+        &mut self,
+        config_path: &str,
+        kind: Kind,
+        forwards: &[String],
+        config: &C,
+    ) where
+        C: Debug,
+    {
+        self.nodes.insert(
+            config_path.to_string(),
+            Node {
+                kind,
+                forwards: forwards.to_vec(),
+                overflow: BTreeMap::new(),
+                fingerprint: format!("{:?}", config),
+            },
+        );
+    }
+
+    /// Look up a node by its config path.
+    pub fn get(&self, config_path: &str) -> Option<&Node> {
+        self.nodes.get(config_path)
+    }
+
+    /// Iterate over the config paths and nodes of the topology.
+    pub fn iter(&self) -> ::std::collections::hash_map::Iter<String, Node> {
+        self.nodes.iter()
+    }
+
+    /// Assert that every forward of the topology is one of its filters or
+    /// sinks and that no filters forward in a cycle. Every fatal error is
+    /// returned, see `Topology::errors`.
+    pub fn validate(&self) -> Result<(), Vec<Error>> {
+        let errors: Vec<Error> =
+            self.errors().into_iter().filter(Error::is_fatal).collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Every error of the topology, fatal or otherwise, ordered by config
+    /// path.
+    pub fn errors(&self) -> Vec<Error> {
+        let mut errors = Vec::new();
+        for config_path in self.config_paths() {
+            let node = &self.nodes[config_path];
+            for (idx, forward) in node.forwards.iter().enumerate() {
+                match self.nodes.get(forward) {
+                    Some(node) if node.kind != Kind::Source => {}
+                    _ => errors.push(Error::UnknownForward(
+                        config_path.clone(),
+                        idx,
+                        forward.clone(),
+                    )),
+                }
+            }
+            if node.kind != Kind::Source && self.upstreams(config_path).is_empty() {
+                errors.push(Error::Orphaned(config_path.clone()));
+            }
+        }
+
+        let mut finished = HashSet::new();
+        let mut cycles = BTreeSet::new();
+        for config_path in self.config_paths() {
+            self.find_cycles(config_path, &mut Vec::new(), &mut finished, &mut cycles);
+        }
+        errors.extend(cycles.into_iter().map(Error::Cycle));
+        errors.sort_by(|a, b| error_path(a).cmp(error_path(b)));
+        errors
+    }
+
+    /// Depth-first search for cycles from `config_path`. Each cycle is
+    /// rotated to start from its least config path so that it is recorded
+    /// only once.
+    fn find_cycles(
+        &self,
+        config_path: &str,
+        path: &mut Vec<String>,
+        finished: &mut HashSet<String>,
+        cycles: &mut BTreeSet<Vec<String>>,
+    ) {
+        if let Some(start) = path.iter().position(|p| p == config_path) {
+            let mut cycle = path[start..].to_vec();
+            let least = (0..cycle.len()).min_by_key(|&i| &cycle[i]).unwrap();
+            cycle.rotate_left(least);
+            cycles.insert(cycle);
+            return;
+        }
+        if finished.contains(config_path) {
+            return;
+        }
+        if let Some(node) = self.nodes.get(config_path) {
+            path.push(config_path.to_string());
+            for forward in &node.forwards {
+                self.find_cycles(forward, path, finished, cycles);
+            }
+            path.pop();
+        }
+        finished.insert(config_path.to_string());
+    }
+
+    fn config_paths(&self) -> Vec<&String> {
+        let mut config_paths: Vec<&String> = self.nodes.keys().collect();
+        config_paths.sort();
+        config_paths
+    }
+
+    /// Render the topology as a Graphviz digraph, sources on the left to
+    /// sinks on the right.
+    pub fn dot(&self) -> String {
+        let mut dot = String::new();
+        let _ = writeln!(dot, "digraph cernan {{");
+        let _ = writeln!(dot, "    rankdir=LR;");
+        for config_path in self.config_paths() {
+            let shape = match self.nodes[config_path].kind {
+                Kind::Source => "invhouse",
+                Kind::Filter => "box",
+                Kind::Sink => "house",
+            };
+            let _ = writeln!(dot, "    {:?} [shape={}];", config_path, shape);
+        }
+        for config_path in self.config_paths() {
+            for forward in &self.nodes[config_path].forwards {
+                let _ = writeln!(dot, "    {:?} -> {:?};", config_path, forward);
+            }
+        }
+        let _ = writeln!(dot, "}}");
+        dot
+    }
+
+    /// The config paths of the nodes forwarding to `config_path`, sorted.
+    pub fn upstreams(&self, config_path: &str) -> Vec<String> {
+        let mut upstreams: Vec<String> = self
+            .nodes
+            .iter()
+            .filter(|&(_, node)| node.forwards.iter().any(|f| f == config_path))
+            .map(|(k, _)| k.clone())
+            .collect();
+        upstreams.sort();
+        upstreams
+    }
+
+    /// The number of `metric::Event::Shutdown` that the filter or sink
+    /// `config_path` will receive when every source of the topology shuts
+    /// down. Filters pass on every `Shutdown` they receive.
+    fn shutdowns(&self, config_path: &str, visiting: &mut HashSet<String>) -> usize {
+        if !visiting.insert(config_path.to_string()) {
+            // A cycle of filters, which will never shut down.
+            return 0;
+        }
+        let mut total = 0;
+        for upstream in self.upstreams(config_path) {
+            total += match self.nodes[&upstream].kind {
+                Kind::Source => 1,
+                _ => self.shutdowns(&upstream, visiting),
+            };
+        }
+        visiting.remove(config_path);
+        total
+    }
+
+    /// The number of `metric::Event::Shutdown` the filter or sink
+    /// `config_path` should wait on before it exits. This is never less than
+    /// one, else the filter or sink would exit on the first `Shutdown` sent
+    /// by a node being reloaded away.
+    pub fn expected_shutdowns(&self, config_path: &str) -> usize {
+        ::std::cmp::max(self.shutdowns(config_path, &mut HashSet::new()), 1)
+    }
+
+    /// Compute the changes needed to move from this topology to `next`.
+    pub fn diff(&self, next: &Topology) -> Diff {
+        let mut diff = Diff::default();
+
+        for (config_path, node) in &self.nodes {
+            if next.nodes.get(config_path) != Some(node) {
+                diff.stop.insert(config_path.clone());
+            }
+        }
+        for config_path in next.nodes.keys() {
+            if !self.nodes.contains_key(config_path) || diff.stop.contains(config_path)
+            {
+                diff.start.insert(config_path.clone());
+            }
+        }
+        for (config_path, node) in &self.nodes {
+            if !diff.stop.contains(config_path)
+                && node.forwards.iter().any(|f| diff.stop.contains(f))
+            {
+                diff.rewire.insert(config_path.clone());
+            }
+        }
+
+        let mut emitted = HashMap::new();
+        for (config_path, node) in &self.nodes {
+            if node.kind == Kind::Source {
+                continue;
+            }
+            let injected = self.injected(config_path, &diff.stop, &mut emitted);
+            let expected = self.expected_shutdowns(config_path);
+            if diff.stop.contains(config_path) {
+                if injected < expected {
+                    diff.inject.insert(config_path.clone(), expected - injected);
+                }
+                if diff.start.contains(config_path) {
+                    // Rewired filters pass on to the replacement what they
+                    // would have to the node itself.
+                    let passed: usize = self
+                        .upstreams(config_path)
+                        .iter()
+                        .filter(|u| diff.rewire.contains(*u))
+                        .filter(|u| self.nodes[*u].kind == Kind::Filter)
+                        .map(|u| self.emitted(u, &diff.stop, &mut emitted))
+                        .sum();
+                    if passed > 0 {
+                        diff.upstreams.insert(config_path.clone(), passed as isize);
+                    }
+                }
+            } else {
+                let delta = (injected + next.expected_shutdowns(config_path)) as isize
+                    - expected as isize;
+                if delta != 0 {
+                    diff.upstreams.insert(config_path.clone(), delta);
+                }
+            }
+        }
+
+        diff
+    }
+
+    /// The number of `metric::Event::Shutdown` the filter or sink
+    /// `config_path` will receive from the nodes in `stop` as they shut
+    /// down. `emitted` memoizes the number each filter passes on.
+    ///
+    /// A filter or sink being stopped receives nothing from the upstreams
+    /// that keep running, these having been rewired to its replacement.
+    fn injected(
+        &self,
+        config_path: &str,
+        stop: &BTreeSet<String>,
+        emitted: &mut HashMap<String, usize>,
+    ) -> usize {
+        let stopping = stop.contains(config_path);
+        let mut total = 0;
+        for upstream in self.upstreams(config_path) {
+            total += match self.nodes[&upstream].kind {
+                Kind::Source => stop.contains(&upstream) as usize,
+                _ if stopping && !stop.contains(&upstream) => 0,
+                _ => self.emitted(&upstream, stop, emitted),
+            };
+        }
+        total
+    }
+
+    fn emitted(
+        &self,
+        config_path: &str,
+        stop: &BTreeSet<String>,
+        emitted: &mut HashMap<String, usize>,
+    ) -> usize {
+        if let Some(n) = emitted.get(config_path) {
+            return *n;
+        }
+        // Guard against cycles of filters while we recurse.
+        emitted.insert(config_path.to_string(), 0);
+        // A filter being stopped exits once it has seen, and passed on, the
+        // shutdowns it expects. One that keeps running passes on all it sees.
+        let n = if stop.contains(config_path) {
+            self.expected_shutdowns(config_path)
+        } else {
+            self.injected(config_path, stop, emitted)
+        };
+        emitted.insert(config_path.to_string(), n);
+        n
+    }
+}
+
+fn error_path(error: &Error) -> &str {
+    match *error {
+        Error::UnknownForward(ref config_path, _, _)
+        | Error::Orphaned(ref config_path) => config_path,
+        Error::Cycle(ref config_paths) => &config_paths[0],
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn topology(nodes: &[(&str, Kind, &[&str], &str)]) -> Topology {
+        let mut topology = Topology::new();
+        for &(config_path, kind, forwards, config) in nodes {
+            let forwards: Vec<String> =
+                forwards.iter().map(|f| f.to_string()).collect();
+            topology.insert(config_path, kind, &forwards, &config);
+        }
+        topology
+    }
+
+    fn set(items: &[&str]) -> BTreeSet<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_validate() {
+        let good = topology(&[
+            ("sources.statsd.a", Kind::Source, &["filters.f"], ""),
+            ("filters.f", Kind::Filter, &["sinks.console"], ""),
+            ("sinks.console", Kind::Sink, &[], ""),
+        ]);
+        assert_eq!(good.validate(), Ok(()));
+        assert!(good.errors().is_empty());
+
+        let missing = topology(&[
+            ("sources.statsd.a", Kind::Source, &["sinks.null"], ""),
+            ("sinks.console", Kind::Sink, &[], ""),
+        ]);
+        assert_eq!(
+            missing.validate(),
+            Err(vec![Error::UnknownForward(
+                "sources.statsd.a".into(),
+                0,
+                "sinks.null".into()
+            )])
+        );
+
+        let to_source = topology(&[
+            ("sources.statsd.a", Kind::Source, &["sources.statsd.b"], ""),
+            ("sources.statsd.b", Kind::Source, &[], ""),
+        ]);
+        assert!(to_source.validate().is_err());
+    }
+
+    #[test]
+    fn test_errors() {
+        let topology = topology(&[
+            (
+                "sources.statsd.a",
+                Kind::Source,
+                &["filters.a", "sinks.nope"],
+                "",
+            ),
+            ("filters.a", Kind::Filter, &["filters.b"], ""),
+            (
+                "filters.b",
+                Kind::Filter,
+                &["filters.a", "sinks.console"],
+                "",
+            ),
+            ("sinks.console", Kind::Sink, &[], ""),
+            ("sinks.null", Kind::Sink, &[], ""),
+        ]);
+        let errors = topology.errors();
+        assert_eq!(
+            errors,
+            vec![
+                Error::Cycle(vec!["filters.a".into(), "filters.b".into()]),
+                Error::Orphaned("sinks.null".into()),
+                Error::UnknownForward(
+                    "sources.statsd.a".into(),
+                    1,
+                    "sinks.nope".into()
+                ),
+            ]
+        );
+        assert_eq!(topology.validate().unwrap_err().len(), 2);
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "filters.a.forwards: cycle filters.a -> filters.b -> filters.a",
+                "sinks.null: nothing forwards to it",
+                "sources.statsd.a.forwards[1]: no filter or sink named \"sinks.nope\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dot() {
+        let topology = topology(&[
+            ("sources.statsd.a", Kind::Source, &["filters.f"], ""),
+            ("filters.f", Kind::Filter, &["sinks.console"], ""),
+            ("sinks.console", Kind::Sink, &[], ""),
+        ]);
+        assert_eq!(
+            topology.dot(),
+            "digraph cernan {
+    rankdir=LR;
+    \"filters.f\" [shape=box];
+    \"sinks.console\" [shape=house];
+    \"sources.statsd.a\" [shape=invhouse];
+    \"filters.f\" -> \"sinks.console\";
+    \"sources.statsd.a\" -> \"filters.f\";
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_expected_shutdowns() {
+        let topology = topology(&[
+            ("sources.statsd.a", Kind::Source, &["filters.f"], ""),
+            (
+                "sources.statsd.b",
+                Kind::Source,
+                &["filters.f", "sinks.console"],
+                "",
+            ),
+            ("filters.f", Kind::Filter, &["sinks.console"], ""),
+            ("sinks.console", Kind::Sink, &[], ""),
+            ("sinks.null", Kind::Sink, &[], ""),
+        ]);
+        assert_eq!(topology.expected_shutdowns("filters.f"), 2);
+        // One from statsd.b, two passed on by the filter.
+        assert_eq!(topology.expected_shutdowns("sinks.console"), 3);
+        assert_eq!(topology.expected_shutdowns("sinks.null"), 1);
+    }
+
+    #[test]
+    fn test_diff_from_empty() {
+        let next = topology(&[
+            ("sources.statsd.a", Kind::Source, &["sinks.console"], ""),
+            ("sinks.console", Kind::Sink, &[], ""),
+        ]);
+        let diff = Topology::new().diff(&next);
+        assert!(diff.stop.is_empty());
+        assert_eq!(diff.start, set(&["sinks.console", "sources.statsd.a"]));
+        assert!(diff.upstreams.is_empty());
+        assert!(diff.inject.is_empty());
+    }
+
+    #[test]
+    fn test_diff_unchanged() {
+        let running = topology(&[
+            ("sources.statsd.a", Kind::Source, &["sinks.console"], "a"),
+            ("sinks.console", Kind::Sink, &[], "console"),
+        ]);
+        assert_eq!(running.diff(&running.clone()), Diff::default());
+    }
+
+    #[test]
+    fn test_diff_add_sink() {
+        let running = topology(&[
+            ("sources.statsd.a", Kind::Source, &["sinks.console"], "a"),
+            ("sources.statsd.b", Kind::Source, &["sinks.console"], "b"),
+            ("sinks.console", Kind::Sink, &[], "console"),
+        ]);
+        let next = topology(&[
+            ("sources.statsd.a", Kind::Source, &["sinks.console"], "a"),
+            (
+                "sources.statsd.b",
+                Kind::Source,
+                &["sinks.console", "sinks.null"],
+                "b+null",
+            ),
+            ("sinks.console", Kind::Sink, &[], "console"),
+            ("sinks.null", Kind::Sink, &[], "null"),
+        ]);
+        let diff = running.diff(&next);
+        assert_eq!(diff.stop, set(&["sources.statsd.b"]));
+        assert_eq!(diff.start, set(&["sinks.null", "sources.statsd.b"]));
+        // The console sink sees statsd.b's shutdown on restart and must wait
+        // on one more as a result.
+        assert_eq!(diff.upstreams.get("sinks.console"), Some(&1));
+        assert!(diff.inject.is_empty());
+    }
+
+    #[test]
+    fn test_diff_changed_sink_rewires_upstreams() {
+        let running = topology(&[
+            ("sources.statsd.a", Kind::Source, &["filters.f"], "a"),
+            ("sources.statsd.b", Kind::Source, &["sinks.null"], "b"),
+            ("filters.f", Kind::Filter, &["sinks.console"], "f"),
+            ("sinks.console", Kind::Sink, &[], "console"),
+            ("sinks.null", Kind::Sink, &[], "null"),
+        ]);
+        let mut next = running.clone();
+        next.insert("sinks.console", Kind::Sink, &[], &"console, changed");
+        let diff = running.diff(&next);
+        assert_eq!(diff.stop, set(&["sinks.console"]));
+        assert_eq!(diff.start, set(&["sinks.console"]));
+        assert_eq!(diff.rewire, set(&["filters.f"]));
+        assert!(diff.upstreams.is_empty());
+        // The filter keeps running, the old sink must be told to exit.
+        assert_eq!(diff.inject.get("sinks.console"), Some(&1));
+    }
+
+    #[test]
+    fn test_diff_changed_sink_and_source() {
+        let running = topology(&[
+            ("sources.statsd.a", Kind::Source, &["filters.f"], "a"),
+            ("sources.statsd.b", Kind::Source, &["filters.f"], "b"),
+            ("filters.f", Kind::Filter, &["sinks.console"], "f"),
+            ("sinks.console", Kind::Sink, &[], "console"),
+        ]);
+        let mut next = running.clone();
+        let forwards = vec!["filters.f".to_string()];
+        next.insert("sources.statsd.a", Kind::Source, &forwards, &"a, changed");
+        next.insert("sinks.console", Kind::Sink, &[], &"console, changed");
+        let diff = running.diff(&next);
+        assert_eq!(diff.stop, set(&["sinks.console", "sources.statsd.a"]));
+        assert_eq!(diff.start, set(&["sinks.console", "sources.statsd.a"]));
+        assert_eq!(diff.rewire, set(&["filters.f"]));
+        // The filter sees statsd.a's shutdown and passes it on to the new
+        // sink, which must wait on it besides those of the new topology.
+        assert_eq!(diff.upstreams.get("filters.f"), Some(&1));
+        assert_eq!(diff.upstreams.get("sinks.console"), Some(&1));
+        assert_eq!(diff.inject.get("sinks.console"), Some(&2));
+    }
+
+    #[test]
+    fn test_diff_remove() {
+        let running = topology(&[
+            ("sources.statsd.a", Kind::Source, &["filters.f"], "a"),
+            ("sources.statsd.b", Kind::Source, &["filters.f"], "b"),
+            ("filters.f", Kind::Filter, &["sinks.console"], "f"),
+            ("sinks.console", Kind::Sink, &[], "console"),
+            ("sinks.null", Kind::Sink, &[], "null"),
+        ]);
+        let next = topology(&[
+            ("sources.statsd.a", Kind::Source, &["filters.f"], "a"),
+            ("filters.f", Kind::Filter, &["sinks.console"], "f"),
+            ("sinks.console", Kind::Sink, &[], "console"),
+        ]);
+        let diff = running.diff(&next);
+        assert_eq!(diff.stop, set(&["sinks.null", "sources.statsd.b"]));
+        assert!(diff.start.is_empty());
+        // The filter sees statsd.b's shutdown and passes it on, one fewer
+        // source remains to shut down through either.
+        assert!(diff.upstreams.is_empty());
+        // Nothing forwards to the null sink, it must be told to exit.
+        assert_eq!(diff.inject.get("sinks.null"), Some(&1));
+    }
+}
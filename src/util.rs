@@ -1,14 +1,21 @@
 //! Utility module, a grab-bag of functionality
 use crate::constants;
 use crate::metric;
+use crate::tap;
+use crate::thread;
+use crate::time;
 use hopper;
 use mio;
 use seahash::SeaHasher;
 use slab;
 use std::collections;
+use std::collections::BTreeMap;
 use std::hash;
-use std::ops::{Index, IndexMut};
+use std::iter::FromIterator;
+use std::ops::{Deref, Index, IndexMut};
+use std::str::FromStr;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock, RwLockWriteGuard};
 
 /// Number of dropped events due to channel being totally full
 pub static UTIL_SEND_HOPPER_ERROR_FULL: AtomicUsize = AtomicUsize::new(0);
@@ -21,47 +28,357 @@ pub static UTIL_SEND_HOPPER_ERROR_FULL: AtomicUsize = AtomicUsize::new(0);
 pub type HashMap<K, V> =
     collections::HashMap<K, V, hash::BuildHasherDefault<SeaHasher>>;
 
-/// A vector of `hopper::Sender`s.
-pub type Channel = Vec<hopper::Sender<metric::Event>>;
+lazy_static! {
+    static ref DROPPED: Mutex<BTreeMap<(String, String), Arc<AtomicUsize>>> =
+        Mutex::new(BTreeMap::new());
+}
+
+/// The classes of `metric::Event` an `Overflow::Shed` may drop.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EventClass {
+    /// `metric::Event::Telemetry`
+    Telemetry,
+    /// `metric::Event::Log`
+    Log,
+    /// `metric::Event::Raw`
+    Raw,
+}
+
+impl EventClass {
+    /// The class of `event`, if any. Flush and shutdown markers have none and
+    /// are never shed.
+    pub fn of(event: &metric::Event) -> Option<EventClass> {
+        match *event {
+            metric::Event::Telemetry(_) => Some(EventClass::Telemetry),
+            metric::Event::Log(_) => Some(EventClass::Log),
+            metric::Event::Raw { .. } => Some(EventClass::Raw),
+            metric::Event::TimerFlush(_) | metric::Event::Shutdown => None,
+        }
+    }
+}
+
+/// What a node does with an event when the hopper queue of one of its
+/// forwards is full, the queue's disk and memory space all consumed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Overflow {
+    /// Drop the event. This is the default.
+    DropNewest,
+    /// Wait for the queue to make room, holding up the node and so applying
+    /// backpressure to whatever feeds it. A node asked to shut down waits
+    /// only on `metric::Event::Shutdown`, dropping anything else.
+    Block,
+    /// Drop events of the given classes, wait on the rest.
+    Shed(Vec<EventClass>),
+}
+
+impl Default for Overflow {
+    fn default() -> Overflow {
+        Overflow::DropNewest
+    }
+}
+
+impl FromStr for Overflow {
+    type Err = String;
+
+    /// Parse an `Overflow` from "drop-newest", "block" or "shed:" followed
+    /// by a comma separated list of the classes to shed, for instance
+    /// "shed:log,raw".
+    fn from_str(s: &str) -> Result<Overflow, String> {
+        match s {
+            "drop-newest" => Ok(Overflow::DropNewest),
+            "block" => Ok(Overflow::Block),
+            _ if s.starts_with("shed:") => s[5..]
+                .split(',')
+                .map(|class| match class.trim() {
+                    "telemetry" => Ok(EventClass::Telemetry),
+                    "log" => Ok(EventClass::Log),
+                    "raw" => Ok(EventClass::Raw),
+                    other => Err(format!("unknown event class \"{}\"", other)),
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map(Overflow::Shed),
+            _ => Err(format!("unknown overflow policy \"{}\"", s)),
+        }
+    }
+}
+
+impl Overflow {
+    /// Whether `event` is dropped, rather than waited on, when the queue is
+    /// full.
+    pub fn drops(&self, event: &metric::Event) -> bool {
+        match *self {
+            Overflow::DropNewest => true,
+            Overflow::Block => false,
+            Overflow::Shed(ref classes) => {
+                EventClass::of(event).map_or(false, |class| classes.contains(&class))
+            }
+        }
+    }
+}
+
+/// The counter of events dropped by the node `from` on its way to the node
+/// `to`, created if need be.
+pub fn dropped(from: &str, to: &str) -> Arc<AtomicUsize> {
+    let mut dropped = DROPPED.lock().unwrap();
+    Arc::clone(
+        dropped
+            .entry((from.to_string(), to.to_string()))
+            .or_insert_with(|| Arc::new(AtomicUsize::new(0))),
+    )
+}
+
+/// Forget the counters of dropped events from or to any node for which `keep`
+/// is false, nodes no longer in the routing topology.
+pub fn forget_dropped<F>(keep: F)
+where
+    F: Fn(&str) -> bool,
+{
+    DROPPED
+        .lock()
+        .unwrap()
+        .retain(|&(ref from, ref to), _| keep(from) && keep(to));
+}
+
+/// Every counter of dropped events, by the node dropping and the node dropped
+/// for. Like `UTIL_SEND_HOPPER_ERROR_FULL` these only ever grow.
+pub fn dropped_counters() -> Vec<(String, String, Arc<AtomicUsize>)> {
+    DROPPED
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(&(ref from, ref to), count)| {
+            (from.clone(), to.clone(), Arc::clone(count))
+        })
+        .collect()
+}
+
+/// The policy and drop counter of a single forward of a `Channel`.
+#[derive(Clone, Default)]
+struct Edge {
+    overflow: Overflow,
+    dropped: Arc<AtomicUsize>,
+}
+
+/// The senders of a `Channel` shared by all its clones, numbered by the times
+/// they have been replaced.
+#[derive(Default)]
+struct Senders {
+    generation: usize,
+    senders: Vec<hopper::Sender<metric::Event>>,
+}
+
+/// A vector of `hopper::Sender`s, the forwards of a source / filter.
+///
+/// A `Channel` may carry the `tap::TapPoint` of the node sending into it, in
+/// which case every event sent is first offered to the node's taps. Every
+/// forward has an `Overflow` policy, `Overflow::DropNewest` unless set with
+/// `Channel::overflow`.
+///
+/// The senders of a `Channel` and its clones may be swapped for others while
+/// the node runs, see `Channel::forwards`.
+#[derive(Clone, Default)]
+pub struct Channel {
+    senders: Vec<hopper::Sender<metric::Event>>,
+    generation: usize,
+    shared: Arc<RwLock<Senders>>,
+    edges: Vec<Edge>,
+    tap: Option<Arc<tap::TapPoint>>,
+}
+
+/// A handle on the senders of a `Channel` and all its clones.
+#[derive(Clone)]
+pub struct Forwards {
+    shared: Arc<RwLock<Senders>>,
+}
+
+impl Forwards {
+    /// Hold up every send into the `Channel` until the returned `Hold` is
+    /// dropped. Sends under way are waited on.
+    pub fn hold(&self) -> Hold {
+        Hold {
+            senders: self.shared.write().unwrap(),
+        }
+    }
+}
+
+/// Sends into a `Channel` held up, see `Forwards::hold`.
+pub struct Hold<'a> {
+    senders: RwLockWriteGuard<'a, Senders>,
+}
+
+impl<'a> Hold<'a> {
+    /// Replace the sender of the forward at `idx`. Every later send into the
+    /// forward goes to `sender`.
+    pub fn replace(&mut self, idx: usize, sender: hopper::Sender<metric::Event>) {
+        self.senders.senders[idx] = sender;
+        self.senders.generation += 1;
+    }
+}
+
+impl Channel {
+    /// Create a new `Channel` sending into `senders`.
+    pub fn new(senders: Vec<hopper::Sender<metric::Event>>) -> Channel {
+        let edges = vec![Edge::default(); senders.len()];
+        let shared = Arc::new(RwLock::new(Senders {
+            generation: 0,
+            senders: senders.clone(),
+        }));
+        Channel {
+            senders,
+            generation: 0,
+            shared,
+            edges,
+            tap: None,
+        }
+    }
+
+    /// A handle on the senders of this `Channel` and its clones, by which
+    /// they may be replaced.
+    pub fn forwards(&self) -> Forwards {
+        Forwards {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+
+    /// Offer every event sent into this `Channel` to the taps of the node
+    /// `config_path`.
+    pub fn tapped(mut self, config_path: &str) -> Channel {
+        self.tap = Some(tap::point(config_path));
+        self
+    }
+
+    /// Set the `Overflow` policy of the forwards of the node `config_path`,
+    /// one per sender and in the same order, and count the events dropped
+    /// into each under `dropped(config_path, forward)`. Forwards missing from
+    /// `policies` drop newest.
+    pub fn overflow(
+        mut self,
+        config_path: &str,
+        forwards: &[String],
+        policies: &BTreeMap<String, Overflow>,
+    ) -> Channel {
+        assert_eq!(forwards.len(), self.senders.len());
+        self.edges = forwards
+            .iter()
+            .map(|forward| Edge {
+                overflow: policies.get(forward).cloned().unwrap_or_default(),
+                dropped: dropped(config_path, forward),
+            })
+            .collect();
+        self
+    }
+}
+
+impl Deref for Channel {
+    type Target = Vec<hopper::Sender<metric::Event>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.senders
+    }
+}
+
+impl FromIterator<hopper::Sender<metric::Event>> for Channel {
+    fn from_iter<I>(iter: I) -> Channel
+    where
+        I: IntoIterator<Item = hopper::Sender<metric::Event>>,
+    {
+        Channel::new(iter.into_iter().collect())
+    }
+}
 
 /// Send a `metric::Event` into a `Channel`.
-pub fn send(chans: &mut Channel, mut event: metric::Event) {
+pub fn send(chans: &mut Channel, event: metric::Event) {
+    if let Some(ref tap) = chans.tap {
+        tap.offer(&event);
+    }
     if chans.is_empty() {
         // Nothing to send to.
         return;
     }
 
+    // The shared senders are read locked for the length of the send, so that
+    // once a sender has been replaced nothing more goes into the old one.
+    let shared = chans.shared.read().unwrap();
+    if shared.generation != chans.generation {
+        chans.senders = shared.senders.clone();
+        chans.generation = shared.generation;
+    }
     let max: usize = chans.len().saturating_sub(1);
-    if max != 0 {
-        for chan in &mut chans[1..] {
-            let mut snd_event = event.clone();
-            while let Err(res) = chan.send(snd_event) {
-                // The are a variety of errors that hopper will signal back up
-                // when we do a send. The only one we care about is
-                // `Error::Full`, meaning that all disk and memory buffer space
-                // is consumed. We drop the event on the floor in that case.
-                match res.1 {
-                    hopper::Error::Full => {
-                        UTIL_SEND_HOPPER_ERROR_FULL.fetch_add(1, Ordering::Relaxed);
-                        break;
-                    }
-                    _ => {
-                        snd_event = res.0;
-                        continue;
-                    }
-                }
-            }
-        }
+    for idx in 1..=max {
+        send_edge(&mut chans.senders[idx], &chans.edges[idx], event.clone());
     }
-    while let Err(res) = chans[0].send(event) {
-        match res.1 {
-            hopper::Error::Full => {
+    send_edge(&mut chans.senders[0], &chans.edges[0], event);
+}
+
+fn send_edge(
+    chan: &mut hopper::Sender<metric::Event>,
+    edge: &Edge,
+    mut event: metric::Event,
+) {
+    let mut attempts = 0;
+    while let Err(res) = chan.send(event) {
+        // The are a variety of errors that hopper will signal back up when we
+        // do a send. The only one we care about is `Error::Full`, meaning that
+        // all disk and memory buffer space is consumed. The edge's `Overflow`
+        // decides whether we drop the event on the floor or wait for room.
+        event = res.0;
+        if let hopper::Error::Full = res.1 {
+            let stopping = match event {
+                metric::Event::Shutdown => false,
+                _ => thread::stopping(),
+            };
+            if stopping || edge.overflow.drops(&event) {
                 UTIL_SEND_HOPPER_ERROR_FULL.fetch_add(1, Ordering::Relaxed);
-                break;
+                edge.dropped.fetch_add(1, Ordering::Relaxed);
+                return;
             }
-            _ => {
-                event = res.0;
-                continue;
+            attempts += 1;
+            time::delay(attempts);
+        }
+    }
+}
+
+/// The number of `metric::Event::Shutdown` a filter / sink waits on
+///
+/// Every upstream of a filter / sink sends it a `Shutdown` when it exits, and
+/// the filter / sink exits once it has received as many as it expects. The
+/// expectation is shared so that it may be adjusted while the filter / sink
+/// runs, as upstreams come and go when the routing topology is reloaded.
+#[derive(Clone, Debug, Default)]
+pub struct Upstreams {
+    expected: Arc<AtomicUsize>,
+}
+
+impl Upstreams {
+    /// Create a new `Upstreams` expecting `expected` shutdowns.
+    pub fn new(expected: usize) -> Upstreams {
+        Upstreams {
+            expected: Arc::new(AtomicUsize::new(expected)),
+        }
+    }
+
+    /// The number of shutdowns expected.
+    pub fn expected(&self) -> usize {
+        self.expected.load(Ordering::Acquire)
+    }
+
+    /// Adjust the number of shutdowns expected by `delta`, saturating at zero.
+    pub fn adjust(&self, delta: isize) {
+        let mut current = self.expected.load(Ordering::Acquire);
+        loop {
+            let next = if delta < 0 {
+                current.saturating_sub(delta.wrapping_neg() as usize)
+            } else {
+                current.saturating_add(delta as usize)
+            };
+            match self.expected.compare_exchange_weak(
+                current,
+                next,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return,
+                Err(actual) => current = actual,
             }
         }
     }
@@ -84,6 +401,70 @@ pub enum Valve {
     Closed,
 }
 
+/// The last `Valve` state a filter / sink declared, shared so that it may be
+/// inspected from other threads.
+///
+/// A sink's valve is only declared between events, so a sink stuck in a
+/// flush never declares it closed. The time the sink last entered or left a
+/// flush is kept alongside, see `SharedValve::flushing_since`.
+#[derive(Clone, Debug, Default)]
+pub struct SharedValve {
+    /// The time, in epoch seconds, the valve closed. Zero while it is open.
+    closed_since: Arc<AtomicUsize>,
+    /// The time, in epoch seconds, the sink entered the flush it is in. Zero
+    /// while it is not flushing.
+    flushing_since: Arc<AtomicUsize>,
+}
+
+impl SharedValve {
+    /// The last state declared.
+    pub fn get(&self) -> Valve {
+        match self.closed_since() {
+            Some(_) => Valve::Closed,
+            None => Valve::Open,
+        }
+    }
+
+    /// The time, in epoch seconds, the valve closed if it is closed.
+    pub fn closed_since(&self) -> Option<i64> {
+        match self.closed_since.load(Ordering::Relaxed) {
+            0 => None,
+            since => Some(since as i64),
+        }
+    }
+
+    /// The time, in epoch seconds, the sink entered the flush it is in, if it
+    /// is flushing.
+    pub fn flushing_since(&self) -> Option<i64> {
+        match self.flushing_since.load(Ordering::Relaxed) {
+            0 => None,
+            since => Some(since as i64),
+        }
+    }
+
+    /// Record that the sink entered, or left, a flush.
+    pub fn flushing(&self, flushing: bool) {
+        let since = if flushing { time::now() as usize } else { 0 };
+        self.flushing_since.store(since, Ordering::Relaxed);
+    }
+
+    /// Record a newly declared state.
+    pub fn set(&self, valve: &Valve) {
+        match *valve {
+            Valve::Open => self.closed_since.store(0, Ordering::Relaxed),
+            Valve::Closed => {
+                // Only the first of consecutive closes marks the time.
+                let _ = self.closed_since.compare_exchange(
+                    0,
+                    time::now() as usize,
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                );
+            }
+        }
+    }
+}
+
 #[inline]
 fn token_to_idx(token: mio::Token) -> usize {
     match token {
@@ -126,6 +126,7 @@ mod integration {
                 "identity",
                 "i am the very model of the modern major general",
             )
+            .time(1)
             .overlay_tag("foo", "bar")
             .overlay_tag("bizz", "bazz");
             let expected_log = metric::LogLine::new(
@@ -133,6 +134,7 @@ mod integration {
                 "i am the very model of the modern major \
                  general",
             )
+            .time(1)
             .overlay_tag("foo", "bar");
             let orig_event = metric::Event::new_log(orig_log);
             let expected_event = metric::Event::new_log(expected_log);
@@ -164,12 +166,14 @@ mod integration {
             let orig_log = metric::LogLine::new(
                 "identity",
                 "i am the very model of the modern major general",
-            );
+            )
+            .time(1);
             let expected_log = metric::LogLine::new(
                 "identity",
                 "i am the very model of the modern major \
                  general",
             )
+            .time(1)
             .insert_field("foo", "identity");
             let orig_event = metric::Event::new_log(orig_log);
             let expected_event = metric::Event::new_log(expected_log);
@@ -201,8 +205,9 @@ mod integration {
             let orig_log = metric::LogLine::new(
                 "identity",
                 "i am the very model of the modern major general",
-            );
-            let expected_log = metric::LogLine::new("identity", "foo");
+            )
+            .time(1);
+            let expected_log = metric::LogLine::new("identity", "foo").time(1);
             let orig_event = metric::Event::new_log(orig_log);
             let expected_event = metric::Event::new_log(expected_log);
 
@@ -371,12 +376,14 @@ mod integration {
                 "i am the very model of the modern major \
                  general",
             )
+            .time(1)
             .overlay_tag("foo", "bar")
             .overlay_tag("bizz", "bazz");
             let orig_log = metric::LogLine::new(
                 "identity",
                 "i am the very model of the modern major general",
             )
+            .time(1)
             .overlay_tag("foo", "bar");
             let orig_event = metric::Event::new_log(orig_log);
             let expected_event = metric::Event::new_log(expected_log);
@@ -410,12 +417,14 @@ mod integration {
                 "i am the very model of the modern major \
                  general",
             )
+            .time(1)
             .overlay_tag("foo", "bar")
             .overlay_tag("bizz", "bazz");
             let orig_log = metric::LogLine::new(
                 "identity",
                 "i am the very model of the modern major general",
             )
+            .time(1)
             .overlay_tag("foo", "bar");
             let orig_event = metric::Event::new_log(orig_log);
             let expected_event = metric::Event::new_log(expected_log);
@@ -537,13 +546,15 @@ mod integration {
                     assert!(false);
                 }
             }
-            assert_eq!(
-                events[0],
-                metric::Event::new_log(metric::LogLine::new(
-                    "filters.keep_count",
-                    "count_per_tick: 5"
-                ))
-            );
+            match events[0] {
+                metric::Event::Log(ref l) => {
+                    assert_eq!(l.path, "filters.keep_count");
+                    assert_eq!(l.value, "count_per_tick: 5");
+                }
+                _ => {
+                    assert!(false);
+                }
+            }
 
             events.clear();
             for ev in &[log2, log3] {
@@ -566,13 +577,15 @@ mod integration {
                     assert!(false);
                 }
             }
-            assert_eq!(
-                events[0],
-                metric::Event::new_log(metric::LogLine::new(
-                    "filters.keep_count",
-                    "count_per_tick: 2"
-                ))
-            );
+            match events[0] {
+                metric::Event::Log(ref l) => {
+                    assert_eq!(l.path, "filters.keep_count");
+                    assert_eq!(l.value, "count_per_tick: 2");
+                }
+                _ => {
+                    assert!(false);
+                }
+            }
         }
 
         #[test]
@@ -684,8 +697,10 @@ mod integration {
             let mut cs = ProgrammableFilter::new(config);
 
             let expected_log = metric::LogLine::new("identity", "{\"foo\": \"bar\"}")
+                .time(1)
                 .insert_field("foo", "bar");
-            let orig_log = metric::LogLine::new("identity", "{\"foo\": \"bar\"}");
+            let orig_log =
+                metric::LogLine::new("identity", "{\"foo\": \"bar\"}").time(1);
 
             let orig_event = metric::Event::new_log(orig_log);
             let expected_event = metric::Event::new_log(expected_log);
@@ -697,6 +712,5 @@ mod integration {
             assert_eq!(events.len(), 1);
             assert_eq!(events[0], expected_event);
         }
-
     }
 }